@@ -0,0 +1,168 @@
+use nom::error::{VerboseError, VerboseErrorKind};
+use std::error;
+use std::fmt;
+
+/// Maximum number of characters of the remaining input kept in [`ParseError::near`]
+const NEAR_MAX_CHARS: usize = 32;
+
+/// Owned parser error which carries the position of the failure,
+/// what the parser expected there and the chain of contexts it was in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    offset: Option<usize>,
+    near: String,
+    expected: Option<String>,
+    context: Vec<&'static str>,
+}
+
+impl ParseError {
+    /// Create a parse error from the `VerboseError` returned by a parser which was given `input`.
+    ///
+    /// The offset is only available if the error points into `input`.
+    pub fn new(input: &str, error: VerboseError<&str>) -> Self {
+        let mut offset = None;
+        let mut near = String::new();
+        let mut expected = None;
+        let mut context = vec![];
+
+        // The first entry is the innermost error, it marks the position of the failure
+        if let Some((i, _)) = error.errors.first() {
+            offset = offset_in(input, i);
+            near = i.chars().take(NEAR_MAX_CHARS).collect();
+        }
+
+        for (_, kind) in error.errors {
+            match kind {
+                VerboseErrorKind::Context(ctx) => context.push(ctx),
+                VerboseErrorKind::Char(c) => {
+                    expected.get_or_insert_with(|| format!("{c:?}"));
+                }
+                VerboseErrorKind::Nom(kind) => {
+                    expected.get_or_insert_with(|| kind.description().to_string());
+                }
+            }
+        }
+
+        // Contexts are collected innermost first, store them outermost first
+        context.reverse();
+
+        Self {
+            offset,
+            near,
+            expected,
+            context,
+        }
+    }
+
+    /// Byte offset into the parsed input where parsing failed
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// The (truncated) remaining input at the position parsing failed
+    pub fn near(&self) -> &str {
+        &self.near
+    }
+
+    /// Description of the element the parser expected at the failing position
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    /// Contexts the parser was in when it failed, outermost first
+    pub fn context(&self) -> &[&'static str] {
+        &self.context
+    }
+}
+
+/// Returns the byte offset of `part` inside `input`, if `part` is a subslice of `input`
+fn offset_in(input: &str, part: &str) -> Option<usize> {
+    let start = input.as_ptr() as usize;
+    let part_start = part.as_ptr() as usize;
+
+    if part_start >= start && part_start + part.len() <= start + input.len() {
+        Some(part_start - start)
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to parse")?;
+
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+
+        if let Some(expected) = &self.expected {
+            write!(f, ", expected {expected}")?;
+        }
+
+        if self.near.is_empty() {
+            write!(f, " at end of input")?;
+        } else {
+            write!(f, " near {:?}", self.near)?;
+        }
+
+        if !self.context.is_empty() {
+            write!(f, " in {}", self.context.join(" > "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nom::bytes::complete::tag;
+    use nom::character::complete::char;
+    use nom::error::context;
+    use nom::sequence::preceded;
+    use nom::Finish;
+
+    fn parse(i: &str) -> crate::IResult<&str, &str> {
+        context("outer", preceded(tag("ab"), context("inner", tag("cd"))))(i)
+    }
+
+    #[test]
+    fn position_and_context() {
+        let input = "abxyz";
+        let err = parse(input).finish().unwrap_err();
+        let err = ParseError::new(input, err);
+
+        assert_eq!(err.offset(), Some(2));
+        assert_eq!(err.near(), "xyz");
+        assert_eq!(err.expected(), Some("Tag"));
+        assert_eq!(err.context(), &["outer", "inner"]);
+        assert_eq!(
+            err.to_string(),
+            r#"failed to parse at offset 2, expected Tag near "xyz" in outer > inner"#
+        );
+    }
+
+    #[test]
+    fn expected_char() {
+        let input = "a";
+        let err = char::<_, VerboseError<&str>>('b')(input)
+            .finish()
+            .unwrap_err();
+        let err = ParseError::new(input, err);
+
+        assert_eq!(err.offset(), Some(0));
+        assert_eq!(err.expected(), Some("'b'"));
+        assert!(err.context().is_empty());
+    }
+
+    #[test]
+    fn foreign_input_has_no_offset() {
+        let err = parse("abxyz").finish().unwrap_err();
+        let err = ParseError::new("unrelated", err);
+
+        assert_eq!(err.offset(), None);
+        assert_eq!(err.near(), "xyz");
+    }
+}
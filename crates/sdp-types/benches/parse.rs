@@ -0,0 +1,149 @@
+//! Parsing and printing of large WebRTC offers, which contain many media sections, codecs and candidates
+
+use bytesstr::BytesStr;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ezk_sdp_types::SessionDescription;
+use std::fmt::Write;
+
+/// Create a bundled WebRTC offer with one audio and `video_sections` video media sections
+fn webrtc_offer(video_sections: usize) -> String {
+    let mut sdp = String::new();
+
+    let mids: Vec<String> = (0..=video_sections).map(|mid| mid.to_string()).collect();
+
+    sdp.push_str(
+        "v=0\r\n\
+         o=- 4611731400430051336 2 IN IP4 127.0.0.1\r\n\
+         s=-\r\n\
+         t=0 0\r\n",
+    );
+    writeln!(sdp, "a=group:BUNDLE {}\r", mids.join(" ")).unwrap();
+    sdp.push_str(
+        "a=extmap-allow-mixed\r\n\
+         a=msid-semantic: WMS stream\r\n",
+    );
+
+    media_section(
+        &mut sdp,
+        "audio",
+        0,
+        &[
+            (111, "opus/48000/2"),
+            (63, "red/48000/2"),
+            (9, "G722/8000"),
+            (0, "PCMU/8000"),
+            (8, "PCMA/8000"),
+            (126, "telephone-event/8000"),
+        ],
+    );
+
+    let video_codecs = [
+        (96, "VP8/90000"),
+        (97, "rtx/90000"),
+        (98, "VP9/90000"),
+        (99, "rtx/90000"),
+        (100, "H264/90000"),
+        (101, "rtx/90000"),
+        (102, "H264/90000"),
+        (103, "rtx/90000"),
+        (104, "AV1/90000"),
+        (105, "rtx/90000"),
+        (106, "red/90000"),
+        (107, "ulpfec/90000"),
+    ];
+
+    for mid in 1..=video_sections {
+        media_section(&mut sdp, "video", mid, &video_codecs);
+    }
+
+    sdp
+}
+
+fn media_section(sdp: &mut String, media: &str, mid: usize, codecs: &[(u8, &str)]) {
+    let fmts: Vec<String> = codecs.iter().map(|(pt, _)| pt.to_string()).collect();
+
+    writeln!(sdp, "m={media} 9 UDP/TLS/RTP/SAVPF {}\r", fmts.join(" ")).unwrap();
+    sdp.push_str(
+        "c=IN IP4 0.0.0.0\r\n\
+         a=rtcp:9 IN IP4 0.0.0.0\r\n\
+         a=ice-ufrag:EsAw\r\n\
+         a=ice-pwd:bP+XJMM09aR8AiX1jdukzR6Y\r\n\
+         a=ice-options:trickle\r\n\
+         a=fingerprint:sha-256 DA:39:A3:EE:5E:6B:4B:0D:32:55:BF:EF:95:60:18:90:AF:D8:07:09:DA:39:A3:EE:5E:6B:4B:0D:32:55:BF:EF\r\n\
+         a=setup:actpass\r\n",
+    );
+    writeln!(sdp, "a=mid:{mid}\r").unwrap();
+
+    for (id, uri) in [
+        "urn:ietf:params:rtp-hdrext:ssrc-audio-level",
+        "http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time",
+        "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01",
+        "urn:ietf:params:rtp-hdrext:sdes:mid",
+    ]
+    .iter()
+    .enumerate()
+    {
+        writeln!(sdp, "a=extmap:{} {uri}\r", id + 1).unwrap();
+    }
+
+    sdp.push_str("a=sendrecv\r\n");
+    writeln!(sdp, "a=msid:stream track{mid}\r").unwrap();
+    sdp.push_str("a=rtcp-mux\r\na=rtcp-rsize\r\n");
+
+    for (pt, codec) in codecs {
+        writeln!(sdp, "a=rtpmap:{pt} {codec}\r").unwrap();
+        writeln!(sdp, "a=rtcp-fb:{pt} transport-cc\r").unwrap();
+        writeln!(sdp, "a=rtcp-fb:{pt} nack\r").unwrap();
+        writeln!(sdp, "a=rtcp-fb:{pt} nack pli\r").unwrap();
+
+        if codec.starts_with("rtx") {
+            writeln!(sdp, "a=fmtp:{pt} apt={}\r", pt - 1).unwrap();
+        } else if codec.starts_with("H264") {
+            writeln!(
+                sdp,
+                "a=fmtp:{pt} level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f\r"
+            )
+            .unwrap();
+        }
+    }
+
+    let ssrc = 1000 + mid as u32 * 2;
+    writeln!(sdp, "a=ssrc-group:FID {ssrc} {}\r", ssrc + 1).unwrap();
+
+    for ssrc in [ssrc, ssrc + 1] {
+        writeln!(sdp, "a=ssrc:{ssrc} cname:4TOk42mSjXCkVIa6\r").unwrap();
+        writeln!(sdp, "a=ssrc:{ssrc} msid:stream track{mid}\r").unwrap();
+    }
+
+    for i in 0..8 {
+        writeln!(
+            sdp,
+            "a=candidate:{i} 1 udp {} 192.0.2.{} {} typ host generation 0 network-id {i}\r",
+            2122260223 - i,
+            i + 1,
+            50000 + i
+        )
+        .unwrap();
+    }
+}
+
+fn parse(c: &mut Criterion) {
+    for video_sections in [4, 16] {
+        let offer = BytesStr::from(webrtc_offer(video_sections));
+
+        c.bench_function(
+            &format!("parse offer with {video_sections} video sections"),
+            |b| b.iter(|| SessionDescription::parse(black_box(&offer)).unwrap()),
+        );
+
+        let parsed = SessionDescription::parse(&offer).unwrap();
+
+        c.bench_function(
+            &format!("print offer with {video_sections} video sections"),
+            |b| b.iter(|| black_box(&parsed).to_string()),
+        );
+    }
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -4,18 +4,30 @@ use bytesstr::BytesStr;
 use internal::IResult;
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while},
+    bytes::complete::{tag, take_while, take_while1},
     character::complete::digit1,
-    combinator::{map, map_res, opt},
+    combinator::{map, map_res, opt, verify},
     sequence::{preceded, tuple},
 };
 use std::{fmt, str::FromStr};
 
+/// URI which marks an extension as encrypted, followed by the URI of the actual extension
+///
+/// [RFC6904](https://www.rfc-editor.org/rfc/rfc6904.html#section-4)
+const ENCRYPT_URI: &str = "urn:ietf:params:rtp-hdrext:encrypt";
+
 #[derive(Debug, Clone)]
 pub struct ExtMap {
     pub id: u8,
     pub uri: BytesStr,
     pub direction: Direction,
+
+    /// The extension is encrypted using SRTP header extension encryption
+    /// ([RFC6904](https://www.rfc-editor.org/rfc/rfc6904.html))
+    pub encrypted: bool,
+
+    /// Extension specific attributes following the URI
+    pub attributes: Option<BytesStr>,
 }
 
 impl ExtMap {
@@ -31,16 +43,33 @@ impl ExtMap {
                     map(tag("/sendonly"), |_| Direction::SendOnly),
                     map(tag("/inactive"), |_| Direction::Inactive),
                 ))),
+                // encrypt uri
+                opt(preceded(
+                    take_while(char::is_whitespace),
+                    verify(take_while1(|c: char| !c.is_whitespace()), |uri: &str| {
+                        uri == ENCRYPT_URI
+                    }),
+                )),
                 // uri
                 preceded(
                     take_while(char::is_whitespace),
                     take_while(|c: char| !c.is_whitespace()),
                 ),
+                // extension attributes
+                opt(preceded(
+                    take_while1(char::is_whitespace),
+                    take_while1(|_| true),
+                )),
             )),
-            |(id, direction, uri)| Self {
+            |(id, direction, encrypt, uri, attributes)| Self {
                 id,
                 uri: BytesStr::from_parse(src, uri.trim()),
                 direction: direction.unwrap_or(Direction::SendRecv),
+                encrypted: encrypt.is_some(),
+                attributes: attributes
+                    .map(str::trim)
+                    .filter(|attributes| !attributes.is_empty())
+                    .map(|attributes| BytesStr::from_parse(src, attributes)),
             },
         )(i)
     }
@@ -50,11 +79,21 @@ impl fmt::Display for ExtMap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.id)?;
 
-        if self.direction == Direction::SendRecv {
-            write!(f, " {}", self.uri)
-        } else {
-            write!(f, "/{} {}", self.direction, self.uri)
+        if self.direction != Direction::SendRecv {
+            write!(f, "/{}", self.direction)?;
+        }
+
+        if self.encrypted {
+            write!(f, " {ENCRYPT_URI}")?;
+        }
+
+        write!(f, " {}", self.uri)?;
+
+        if let Some(attributes) = &self.attributes {
+            write!(f, " {attributes}")?;
         }
+
+        Ok(())
     }
 }
 
@@ -73,6 +112,38 @@ mod test {
         assert_eq!(extmap.id, 1);
         assert_eq!(extmap.uri, "myuri");
         assert_eq!(extmap.direction, Direction::SendRecv);
+        assert!(!extmap.encrypted);
+        assert!(extmap.attributes.is_none());
+    }
+
+    #[test]
+    fn extmap_encrypted() {
+        let input = BytesStr::from_static(
+            "2/sendonly urn:ietf:params:rtp-hdrext:encrypt urn:ietf:params:rtp-hdrext:smpte-tc 25@600/24",
+        );
+
+        let (rem, extmap) = ExtMap::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(extmap.id, 2);
+        assert_eq!(extmap.uri, "urn:ietf:params:rtp-hdrext:smpte-tc");
+        assert_eq!(extmap.direction, Direction::SendOnly);
+        assert!(extmap.encrypted);
+        assert_eq!(extmap.attributes.unwrap(), "25@600/24");
+    }
+
+    #[test]
+    fn extmap_encrypt_prefix() {
+        let input = BytesStr::from_static("1 urn:ietf:params:rtp-hdrext:encrypted-foo");
+
+        let (rem, extmap) = ExtMap::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(extmap.uri, "urn:ietf:params:rtp-hdrext:encrypted-foo");
+        assert!(!extmap.encrypted);
+        assert!(extmap.attributes.is_none());
     }
 
     #[test]
@@ -81,6 +152,8 @@ mod test {
             id: 3,
             uri: "myuri".into(),
             direction: Direction::SendRecv,
+            encrypted: false,
+            attributes: None,
         };
 
         assert_eq!(extmap.to_string(), "3 myuri");
@@ -92,8 +165,26 @@ mod test {
             id: 3,
             uri: "myuri".into(),
             direction: Direction::SendOnly,
+            encrypted: false,
+            attributes: None,
         };
 
         assert_eq!(extmap.to_string(), "3/sendonly myuri");
     }
+
+    #[test]
+    fn extmap_print_encrypted() {
+        let extmap = ExtMap {
+            id: 4,
+            uri: "myuri".into(),
+            direction: Direction::RecvOnly,
+            encrypted: true,
+            attributes: Some("myattr".into()),
+        };
+
+        assert_eq!(
+            extmap.to_string(),
+            "4/recvonly urn:ietf:params:rtp-hdrext:encrypt myuri myattr"
+        );
+    }
 }
@@ -7,7 +7,7 @@ use internal::IResult;
 use nom::bytes::complete::{take_while1, take_while_m_n};
 use nom::combinator::map;
 use nom::error::context;
-use nom::multi::many1;
+use nom::multi::separated_list1;
 use std::fmt;
 
 /// Ice options attribute (`a=ice-options`)
@@ -22,27 +22,52 @@ pub struct IceOptions {
 }
 
 impl IceOptions {
+    /// Option signaling support for trickle ICE
+    ///
+    /// [RFC8840](https://www.rfc-editor.org/rfc/rfc8840.html#section-4.1.1)
+    pub const TRICKLE: &'static str = "trickle";
+
     pub fn parse<'i>(src: &Bytes, i: &'i str) -> IResult<&'i str, Self> {
         context(
             "parsing ice-options",
             map(
-                many1(map(take_while1(ice_char), |option| {
-                    BytesStr::from_parse(src, option)
-                })),
+                separated_list1(
+                    take_while1(char::is_whitespace),
+                    map(take_while1(ice_char), |option| {
+                        BytesStr::from_parse(src, option)
+                    }),
+                ),
                 |options| Self { options },
             ),
         )(i)
     }
+
+    /// Returns if the given option is present
+    pub fn contains(&self, option: &str) -> bool {
+        self.options.iter().any(|o| o == option)
+    }
+
+    /// Add the option if not already present
+    pub fn insert(&mut self, option: &'static str) {
+        if !self.contains(option) {
+            self.options.push(BytesStr::from_static(option));
+        }
+    }
+
+    /// Returns if trickle ICE is supported
+    pub fn trickle(&self) -> bool {
+        self.contains(Self::TRICKLE)
+    }
 }
 
 impl fmt::Display for IceOptions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.options.is_empty() {
-            return Ok(());
-        }
+        for (i, option) in self.options.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
 
-        for option in &self.options {
-            write!(f, " {}", option)?;
+            write!(f, "{}", option)?;
         }
 
         Ok(())
@@ -98,3 +123,30 @@ impl IcePassword {
         )(i)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ice_options() {
+        let input = BytesStr::from_static("trickle renomination");
+
+        let (rem, options) = IceOptions::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(options.options, ["trickle", "renomination"]);
+        assert!(options.trickle());
+    }
+
+    #[test]
+    fn ice_options_print() {
+        let mut options = IceOptions::default();
+        options.insert(IceOptions::TRICKLE);
+        options.insert("ice2");
+        options.insert(IceOptions::TRICKLE);
+
+        assert_eq!(options.to_string(), "trickle ice2");
+    }
+}
@@ -10,10 +10,15 @@ mod fingerprint;
 mod fmtp;
 mod group;
 mod ice;
+mod msid;
+mod ptime;
+mod rid;
 mod rtcp;
 mod rtpmap;
 mod setup;
+mod simulcast;
 mod ssrc;
+mod ssrc_group;
 
 pub use candidate::{IceCandidate, InvalidCandidateParamError, UntaggedAddress};
 pub use crypto::{SrtpCrypto, SrtpFecOrder, SrtpKeyingMaterial, SrtpSessionParam, SrtpSuite};
@@ -23,10 +28,15 @@ pub use fingerprint::{Fingerprint, FingerprintAlgorithm};
 pub use fmtp::Fmtp;
 pub use group::Group;
 pub use ice::{IceOptions, IcePassword, IceUsernameFragment};
+pub use msid::Msid;
+pub(crate) use ptime::{parse_ptime, DisplayPtime};
+pub use rid::{Rid, RidDirection, RidRestriction};
 pub use rtcp::Rtcp;
 pub use rtpmap::RtpMap;
 pub use setup::Setup;
+pub use simulcast::{Simulcast, SimulcastRid};
 pub use ssrc::{SourceAttribute, Ssrc};
+pub use ssrc_group::{SsrcGroup, SsrcGroupSemantics};
 
 /// `name:[value]` pair which contains an unparsed/unknown attribute
 #[derive(Debug, Clone)]
@@ -0,0 +1,99 @@
+//! Media stream identification attribute (`a=msid:...`)
+
+use crate::not_whitespace;
+use bytes::Bytes;
+use bytesstr::BytesStr;
+use internal::IResult;
+use nom::bytes::complete::take_while1;
+use nom::combinator::{map, opt};
+use nom::error::context;
+use nom::sequence::{preceded, tuple};
+use std::fmt;
+
+/// Msid attribute (`a=msid`)
+///
+/// Associates the media description with a media stream, used by WebRTC to group tracks into streams
+///
+/// Media Level attribute
+///
+/// [RFC8830](https://www.rfc-editor.org/rfc/rfc8830.html)
+#[derive(Debug, Clone)]
+pub struct Msid {
+    /// Identifier of the media stream
+    pub stream_id: BytesStr,
+
+    /// Optional application data, used by WebRTC as track identifier
+    pub track_id: Option<BytesStr>,
+}
+
+impl Msid {
+    pub fn parse<'i>(src: &Bytes, i: &'i str) -> IResult<&'i str, Self> {
+        context(
+            "parsing msid",
+            map(
+                tuple((
+                    take_while1(not_whitespace),
+                    opt(preceded(
+                        take_while1(char::is_whitespace),
+                        take_while1(not_whitespace),
+                    )),
+                )),
+                |(stream_id, track_id)| Msid {
+                    stream_id: BytesStr::from_parse(src, stream_id),
+                    track_id: track_id.map(|track_id| BytesStr::from_parse(src, track_id)),
+                },
+            ),
+        )(i)
+    }
+}
+
+impl fmt::Display for Msid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.stream_id)?;
+
+        if let Some(track_id) = &self.track_id {
+            write!(f, " {track_id}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn msid() {
+        let input = BytesStr::from_static("stream track");
+
+        let (rem, msid) = Msid::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(msid.stream_id, "stream");
+        assert_eq!(msid.track_id.unwrap(), "track");
+    }
+
+    #[test]
+    fn msid_without_track() {
+        let input = BytesStr::from_static("-");
+
+        let (rem, msid) = Msid::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(msid.stream_id, "-");
+        assert!(msid.track_id.is_none());
+    }
+
+    #[test]
+    fn msid_print() {
+        let msid = Msid {
+            stream_id: "stream".into(),
+            track_id: Some("track".into()),
+        };
+
+        assert_eq!(msid.to_string(), "stream track");
+    }
+}
@@ -0,0 +1,49 @@
+//! Packet time attributes (`a=ptime:...` & `a=maxptime:...`)
+//!
+//! Both are expressed in milliseconds, fractional values are allowed
+
+use std::fmt;
+use std::time::Duration;
+
+/// Parse the value of a `ptime` or `maxptime` attribute, returns `None` if the value is invalid
+pub(crate) fn parse_ptime(i: &str) -> Option<Duration> {
+    let millis: f64 = i.trim().parse().ok()?;
+
+    if millis.is_finite() && millis > 0.0 {
+        Some(Duration::from_secs_f64(millis / 1000.0))
+    } else {
+        None
+    }
+}
+
+/// Displays a packet time in milliseconds
+pub(crate) struct DisplayPtime(pub(crate) Duration);
+
+impl fmt::Display for DisplayPtime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.subsec_nanos().is_multiple_of(1_000_000) {
+            write!(f, "{}", self.0.as_millis())
+        } else {
+            write!(f, "{}", self.0.as_secs_f64() * 1000.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ptime() {
+        assert_eq!(parse_ptime("20"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_ptime("2.5"), Some(Duration::from_micros(2500)));
+        assert_eq!(parse_ptime("0"), None);
+        assert_eq!(parse_ptime("abc"), None);
+    }
+
+    #[test]
+    fn ptime_print() {
+        assert_eq!(DisplayPtime(Duration::from_millis(20)).to_string(), "20");
+        assert_eq!(DisplayPtime(Duration::from_micros(2500)).to_string(), "2.5");
+    }
+}
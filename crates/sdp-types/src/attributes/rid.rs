@@ -0,0 +1,222 @@
+//! RTP stream identifier attribute (`a=rid:...`)
+
+use bytes::Bytes;
+use bytesstr::BytesStr;
+use internal::IResult;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1};
+use nom::character::complete::char;
+use nom::combinator::{map, opt};
+use nom::error::context;
+use nom::multi::separated_list1;
+use nom::sequence::{preceded, tuple};
+use std::fmt;
+
+/// Direction of the RTP stream identified by a [`Rid`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RidDirection {
+    Send,
+    Recv,
+}
+
+impl fmt::Display for RidDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RidDirection::Send => f.write_str("send"),
+            RidDirection::Recv => f.write_str("recv"),
+        }
+    }
+}
+
+/// Restriction parameter of a [`Rid`] (e.g. `max-width=1280`)
+#[derive(Debug, Clone)]
+pub struct RidRestriction {
+    pub name: BytesStr,
+    pub value: Option<BytesStr>,
+}
+
+/// Rid attribute (`a=rid`)
+///
+/// Identifies an RTP stream and restricts its encoding, used to negotiate simulcast
+///
+/// Media Level attribute
+///
+/// [RFC8851](https://www.rfc-editor.org/rfc/rfc8851.html)
+#[derive(Debug, Clone)]
+pub struct Rid {
+    /// The RTP stream id
+    pub id: BytesStr,
+
+    pub direction: RidDirection,
+
+    /// Payload types the stream may use (`pt=` parameter), empty if all payload types of the media may be used
+    pub payloads: Vec<u8>,
+
+    /// All other restrictions
+    pub restrictions: Vec<RidRestriction>,
+}
+
+pub(crate) fn rid_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_')
+}
+
+impl Rid {
+    pub fn parse<'i>(src: &Bytes, i: &'i str) -> IResult<&'i str, Self> {
+        context(
+            "parsing rid",
+            map(
+                tuple((
+                    // id
+                    take_while1(rid_id_char),
+                    // direction
+                    preceded(
+                        take_while1(char::is_whitespace),
+                        alt((
+                            map(tag("send"), |_| RidDirection::Send),
+                            map(tag("recv"), |_| RidDirection::Recv),
+                        )),
+                    ),
+                    // params
+                    opt(preceded(
+                        take_while1(char::is_whitespace),
+                        separated_list1(
+                            char(';'),
+                            tuple((
+                                take_while1(|c: char| {
+                                    !matches!(c, '=' | ';') && !c.is_whitespace()
+                                }),
+                                opt(preceded(
+                                    char('='),
+                                    take_while1(|c: char| c != ';' && !c.is_whitespace()),
+                                )),
+                            )),
+                        ),
+                    )),
+                )),
+                |(id, direction, params)| {
+                    let mut payloads = vec![];
+                    let mut restrictions = vec![];
+
+                    for (name, value) in params.unwrap_or_default() {
+                        match (name, value) {
+                            ("pt", Some(value)) => payloads
+                                .extend(value.split(',').filter_map(|pt| pt.parse::<u8>().ok())),
+                            (name, value) => restrictions.push(RidRestriction {
+                                name: BytesStr::from_parse(src, name),
+                                value: value.map(|value| BytesStr::from_parse(src, value)),
+                            }),
+                        }
+                    }
+
+                    Self {
+                        id: BytesStr::from_parse(src, id),
+                        direction,
+                        payloads,
+                        restrictions,
+                    }
+                },
+            ),
+        )(i)
+    }
+}
+
+impl fmt::Display for Rid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.id, self.direction)?;
+
+        let mut separator = " ";
+
+        if !self.payloads.is_empty() {
+            write!(f, " pt=")?;
+
+            for (i, pt) in self.payloads.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+
+                write!(f, "{pt}")?;
+            }
+
+            separator = ";";
+        }
+
+        for restriction in &self.restrictions {
+            write!(f, "{separator}{}", restriction.name)?;
+
+            if let Some(value) = &restriction.value {
+                write!(f, "={value}")?;
+            }
+
+            separator = ";";
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rid() {
+        let input = BytesStr::from_static("hi send");
+
+        let (rem, rid) = Rid::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(rid.id, "hi");
+        assert_eq!(rid.direction, RidDirection::Send);
+        assert!(rid.payloads.is_empty());
+        assert!(rid.restrictions.is_empty());
+    }
+
+    #[test]
+    fn rid_params() {
+        let input = BytesStr::from_static("1 recv pt=96,97;max-width=1280;max-height=720");
+
+        let (rem, rid) = Rid::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(rid.id, "1");
+        assert_eq!(rid.direction, RidDirection::Recv);
+        assert_eq!(rid.payloads, [96, 97]);
+        assert_eq!(rid.restrictions.len(), 2);
+        assert_eq!(rid.restrictions[0].name, "max-width");
+        assert_eq!(rid.restrictions[0].value.as_deref(), Some("1280"));
+        assert_eq!(rid.restrictions[1].name, "max-height");
+        assert_eq!(rid.restrictions[1].value.as_deref(), Some("720"));
+    }
+
+    #[test]
+    fn rid_print() {
+        let rid = Rid {
+            id: "lo".into(),
+            direction: RidDirection::Send,
+            payloads: vec![96, 97],
+            restrictions: vec![RidRestriction {
+                name: "max-fps".into(),
+                value: Some("15".into()),
+            }],
+        };
+
+        assert_eq!(rid.to_string(), "lo send pt=96,97;max-fps=15");
+    }
+
+    #[test]
+    fn rid_print_restrictions_only() {
+        let rid = Rid {
+            id: "lo".into(),
+            direction: RidDirection::Recv,
+            payloads: vec![],
+            restrictions: vec![RidRestriction {
+                name: "max-br".into(),
+                value: Some("64000".into()),
+            }],
+        };
+
+        assert_eq!(rid.to_string(), "lo recv max-br=64000");
+    }
+}
@@ -0,0 +1,187 @@
+//! Simulcast attribute (`a=simulcast:...`)
+
+use super::rid::rid_id_char;
+use bytes::Bytes;
+use bytesstr::BytesStr;
+use internal::IResult;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1};
+use nom::character::complete::char;
+use nom::combinator::{map, opt};
+use nom::error::context;
+use nom::multi::separated_list1;
+use nom::sequence::{separated_pair, tuple};
+use std::fmt;
+
+/// Reference to a [`Rid`](super::Rid) inside a [`Simulcast`] attribute
+#[derive(Debug, Clone)]
+pub struct SimulcastRid {
+    pub rid: BytesStr,
+
+    /// The stream is initially paused (`~` prefix)
+    pub paused: bool,
+}
+
+/// Simulcast attribute (`a=simulcast`)
+///
+/// Each stream is described by a list of alternative RIDs, of which one is used
+///
+/// Media Level attribute
+///
+/// [RFC8853](https://www.rfc-editor.org/rfc/rfc8853.html)
+#[derive(Debug, Clone, Default)]
+pub struct Simulcast {
+    /// Streams in send direction
+    pub send: Vec<Vec<SimulcastRid>>,
+
+    /// Streams in receive direction
+    pub recv: Vec<Vec<SimulcastRid>>,
+}
+
+#[derive(Clone, Copy)]
+enum SimulcastDirection {
+    Send,
+    Recv,
+}
+
+impl Simulcast {
+    pub fn parse<'i>(src: &Bytes, i: &'i str) -> IResult<&'i str, Self> {
+        let rid = map(
+            tuple((opt(char('~')), take_while1(rid_id_char))),
+            |(paused, rid)| SimulcastRid {
+                rid: BytesStr::from_parse(src, rid),
+                paused: paused.is_some(),
+            },
+        );
+
+        let streams = separated_list1(char(';'), separated_list1(char(','), rid));
+
+        context(
+            "parsing simulcast",
+            map(
+                separated_list1(
+                    take_while1(char::is_whitespace),
+                    separated_pair(
+                        alt((
+                            map(tag("send"), |_| SimulcastDirection::Send),
+                            map(tag("recv"), |_| SimulcastDirection::Recv),
+                        )),
+                        take_while1(char::is_whitespace),
+                        streams,
+                    ),
+                ),
+                |directions| {
+                    let mut simulcast = Simulcast::default();
+
+                    for (direction, streams) in directions {
+                        match direction {
+                            SimulcastDirection::Send => simulcast.send.extend(streams),
+                            SimulcastDirection::Recv => simulcast.recv.extend(streams),
+                        }
+                    }
+
+                    simulcast
+                },
+            ),
+        )(i)
+    }
+}
+
+fn print_streams(f: &mut fmt::Formatter, streams: &[Vec<SimulcastRid>]) -> fmt::Result {
+    for (i, alternatives) in streams.iter().enumerate() {
+        if i > 0 {
+            write!(f, ";")?;
+        }
+
+        for (j, rid) in alternatives.iter().enumerate() {
+            if j > 0 {
+                write!(f, ",")?;
+            }
+
+            if rid.paused {
+                write!(f, "~")?;
+            }
+
+            write!(f, "{}", rid.rid)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Simulcast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.send.is_empty() {
+            write!(f, "send ")?;
+            print_streams(f, &self.send)?;
+
+            if !self.recv.is_empty() {
+                write!(f, " ")?;
+            }
+        }
+
+        if !self.recv.is_empty() {
+            write!(f, "recv ")?;
+            print_streams(f, &self.recv)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn simulcast() {
+        let input = BytesStr::from_static("send 1;2,~3 recv 4");
+
+        let (rem, simulcast) = Simulcast::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(simulcast.send.len(), 2);
+        assert_eq!(simulcast.send[0].len(), 1);
+        assert_eq!(simulcast.send[0][0].rid, "1");
+        assert_eq!(simulcast.send[1].len(), 2);
+        assert_eq!(simulcast.send[1][0].rid, "2");
+        assert!(!simulcast.send[1][0].paused);
+        assert_eq!(simulcast.send[1][1].rid, "3");
+        assert!(simulcast.send[1][1].paused);
+
+        assert_eq!(simulcast.recv.len(), 1);
+        assert_eq!(simulcast.recv[0][0].rid, "4");
+    }
+
+    #[test]
+    fn simulcast_recv_only() {
+        let input = BytesStr::from_static("recv hi;mid;lo");
+
+        let (rem, simulcast) = Simulcast::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert!(simulcast.send.is_empty());
+        assert_eq!(simulcast.recv.len(), 3);
+        assert_eq!(simulcast.recv[2][0].rid, "lo");
+    }
+
+    #[test]
+    fn simulcast_print() {
+        let rid = |rid: &'static str, paused| SimulcastRid {
+            rid: rid.into(),
+            paused,
+        };
+
+        let simulcast = Simulcast {
+            send: vec![
+                vec![rid("hi", false)],
+                vec![rid("lo", false), rid("x", true)],
+            ],
+            recv: vec![vec![rid("1", false)]],
+        };
+
+        assert_eq!(simulcast.to_string(), "send hi;lo,~x recv 1");
+    }
+}
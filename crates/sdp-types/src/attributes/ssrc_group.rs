@@ -0,0 +1,147 @@
+//! SSRC group attribute (`a=ssrc-group:...`)
+
+use crate::not_whitespace;
+use bytes::Bytes;
+use bytesstr::BytesStr;
+use internal::IResult;
+use nom::bytes::complete::take_while1;
+use nom::character::complete::u32;
+use nom::combinator::map;
+use nom::error::context;
+use nom::multi::separated_list1;
+use nom::sequence::separated_pair;
+use std::fmt;
+
+/// Semantics of a [`SsrcGroup`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SsrcGroupSemantics {
+    /// Flow identification, e.g. a media stream and its retransmission (RTX) stream
+    ///
+    /// [RFC5576](https://www.rfc-editor.org/rfc/rfc5576.html#section-7.1)
+    FID,
+
+    /// Forward error correction
+    ///
+    /// [RFC5576](https://www.rfc-editor.org/rfc/rfc5576.html#section-7.2)
+    FEC,
+
+    /// Forward error correction framework
+    ///
+    /// [RFC5956](https://www.rfc-editor.org/rfc/rfc5956.html#section-4.3)
+    FECFR,
+
+    /// Simulcast streams, not standardized but used by WebRTC implementations
+    SIM,
+
+    Other(BytesStr),
+}
+
+impl fmt::Display for SsrcGroupSemantics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SsrcGroupSemantics::FID => "FID",
+            SsrcGroupSemantics::FEC => "FEC",
+            SsrcGroupSemantics::FECFR => "FEC-FR",
+            SsrcGroupSemantics::SIM => "SIM",
+            SsrcGroupSemantics::Other(other) => other.as_str(),
+        })
+    }
+}
+
+/// SSRC group attribute (`a=ssrc-group`)
+///
+/// Expresses a relationship between the listed SSRCs
+///
+/// Media Level attribute
+///
+/// [RFC5576](https://www.rfc-editor.org/rfc/rfc5576.html#section-4.2)
+#[derive(Debug, Clone)]
+pub struct SsrcGroup {
+    pub semantics: SsrcGroupSemantics,
+    pub ssrcs: Vec<u32>,
+}
+
+impl SsrcGroup {
+    pub fn parse<'i>(src: &Bytes, i: &'i str) -> IResult<&'i str, Self> {
+        context(
+            "parsing ssrc-group-attribute",
+            map(
+                separated_pair(
+                    map(take_while1(not_whitespace), |semantics| match semantics {
+                        "FID" => SsrcGroupSemantics::FID,
+                        "FEC" => SsrcGroupSemantics::FEC,
+                        "FEC-FR" => SsrcGroupSemantics::FECFR,
+                        "SIM" => SsrcGroupSemantics::SIM,
+                        other => SsrcGroupSemantics::Other(BytesStr::from_parse(src, other)),
+                    }),
+                    take_while1(char::is_whitespace),
+                    separated_list1(take_while1(char::is_whitespace), u32),
+                ),
+                |(semantics, ssrcs)| Self { semantics, ssrcs },
+            ),
+        )(i)
+    }
+}
+
+impl fmt::Display for SsrcGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.semantics)?;
+
+        for ssrc in &self.ssrcs {
+            write!(f, " {ssrc}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ssrc_group_fid() {
+        let input = BytesStr::from_static("FID 1234 5678");
+
+        let (rem, group) = SsrcGroup::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(group.semantics, SsrcGroupSemantics::FID);
+        assert_eq!(group.ssrcs, [1234, 5678]);
+    }
+
+    #[test]
+    fn ssrc_group_fec_fr() {
+        let input = BytesStr::from_static("FEC-FR 1 2");
+
+        let (rem, group) = SsrcGroup::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(group.semantics, SsrcGroupSemantics::FECFR);
+        assert_eq!(group.ssrcs, [1, 2]);
+    }
+
+    #[test]
+    fn ssrc_group_other() {
+        let input = BytesStr::from_static("DUP 1 2 3");
+
+        let (rem, group) = SsrcGroup::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+
+        assert_eq!(group.semantics, SsrcGroupSemantics::Other("DUP".into()));
+        assert_eq!(group.ssrcs, [1, 2, 3]);
+    }
+
+    #[test]
+    fn ssrc_group_print() {
+        let group = SsrcGroup {
+            semantics: SsrcGroupSemantics::SIM,
+            ssrcs: vec![1, 2, 3],
+        };
+
+        assert_eq!(group.to_string(), "SIM 1 2 3");
+    }
+}
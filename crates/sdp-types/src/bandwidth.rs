@@ -26,6 +26,37 @@ pub struct Bandwidth {
 }
 
 impl Bandwidth {
+    /// Create a `TIAS` bandwidth (transport independent application specific) in bits per second
+    ///
+    /// [RFC3890](https://www.rfc-editor.org/rfc/rfc3890.html)
+    pub fn tias(bps: u32) -> Self {
+        Self {
+            type_: BytesStr::from_static("TIAS"),
+            bandwidth: bps,
+        }
+    }
+
+    /// Create an `AS` bandwidth (application specific) in kilobits per second
+    pub fn application_specific(kbps: u32) -> Self {
+        Self {
+            type_: BytesStr::from_static("AS"),
+            bandwidth: kbps,
+        }
+    }
+
+    /// Returns the bandwidth in bits per second if the type is `AS`, `CT` or `TIAS`
+    pub fn bitrate(&self) -> Option<u64> {
+        let bandwidth = u64::from(self.bandwidth);
+
+        if self.type_.eq_ignore_ascii_case("TIAS") {
+            Some(bandwidth)
+        } else if self.type_.eq_ignore_ascii_case("AS") || self.type_.eq_ignore_ascii_case("CT") {
+            Some(bandwidth * 1000)
+        } else {
+            None
+        }
+    }
+
     pub fn parse<'i>(src: &Bytes, i: &'i str) -> IResult<&'i str, Self> {
         context(
             "parsing bandwidth",
@@ -50,6 +81,31 @@ impl fmt::Display for Bandwidth {
     }
 }
 
+/// Returns the maximum bitrate in bits per second signaled by the bandwidth fields
+///
+/// `TIAS` is preferred since it excludes the transport overhead, otherwise the lowest `AS` or `CT` value is used.
+pub(crate) fn max_bitrate(bandwidths: &[Bandwidth]) -> Option<u64> {
+    let tias = bandwidths
+        .iter()
+        .filter(|bandwidth| bandwidth.type_.eq_ignore_ascii_case("TIAS"))
+        .map(|bandwidth| u64::from(bandwidth.bandwidth))
+        .min();
+
+    tias.or_else(|| bandwidths.iter().filter_map(Bandwidth::bitrate).min())
+}
+
+/// Replace all `TIAS` and `AS` fields with the given bitrate
+///
+/// Both are set, since peers not supporting `TIAS` only respect `AS`.
+pub(crate) fn set_max_bitrate(bandwidths: &mut Vec<Bandwidth>, bps: u32) {
+    bandwidths.retain(|bandwidth| {
+        !bandwidth.type_.eq_ignore_ascii_case("TIAS") && !bandwidth.type_.eq_ignore_ascii_case("AS")
+    });
+
+    bandwidths.push(Bandwidth::tias(bps));
+    bandwidths.push(Bandwidth::application_specific(bps.div_ceil(1000)));
+}
+
 fn token(c: char) -> bool {
     matches!(c, '\x21' | '\x23'..='\x27' | '\x2A'..='\x2B' | '\x2D'..='\x2E' | '\x30'..='\x39' | '\x41'..='\x5A' | '\x5E'..='\x7E')
 }
@@ -79,4 +135,51 @@ mod test {
 
         assert_eq!(origin.to_string(), "AS:96000");
     }
+
+    #[test]
+    fn bandwidth_bitrate() {
+        assert_eq!(Bandwidth::tias(64000).bitrate(), Some(64000));
+        assert_eq!(Bandwidth::application_specific(64).bitrate(), Some(64000));
+
+        let other = Bandwidth {
+            type_: "RR".into(),
+            bandwidth: 800,
+        };
+
+        assert_eq!(other.bitrate(), None);
+    }
+
+    #[test]
+    fn bandwidth_max_bitrate() {
+        assert_eq!(max_bitrate(&[]), None);
+
+        assert_eq!(
+            max_bitrate(&[
+                Bandwidth::application_specific(128),
+                Bandwidth::application_specific(64)
+            ]),
+            Some(64000)
+        );
+
+        assert_eq!(
+            max_bitrate(&[Bandwidth::application_specific(64), Bandwidth::tias(96000)]),
+            Some(96000)
+        );
+    }
+
+    #[test]
+    fn bandwidth_set_max_bitrate() {
+        let mut bandwidths = vec![
+            Bandwidth::application_specific(256),
+            Bandwidth {
+                type_: "RS".into(),
+                bandwidth: 0,
+            },
+        ];
+
+        set_max_bitrate(&mut bandwidths, 64500);
+
+        let printed: Vec<String> = bandwidths.iter().map(ToString::to_string).collect();
+        assert_eq!(printed, ["RS:0", "TIAS:64500", "AS:65"]);
+    }
 }
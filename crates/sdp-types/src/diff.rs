@@ -0,0 +1,127 @@
+use crate::{MediaDescription, SessionDescription};
+
+/// Changes of the media descriptions between two [`SessionDescription`]s,
+/// created using [`SessionDescription::diff`]
+///
+/// Media descriptions are matched by their index, since they keep their position for the lifetime of a session.
+/// A media description with port 0 counts as removed.
+///
+/// Media descriptions are compared in their printed form, so a different order of attributes is not a change.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionDescriptionDiff {
+    /// Indices of media descriptions which have been added
+    pub added: Vec<usize>,
+
+    /// Indices of media descriptions which have been removed or rejected
+    pub removed: Vec<usize>,
+
+    /// Indices of media descriptions which are present in both but differ
+    pub changed: Vec<usize>,
+}
+
+impl SessionDescriptionDiff {
+    /// Returns true if no media description changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub(crate) fn new(old: &SessionDescription, new: &SessionDescription) -> Self {
+        let mut diff = Self::default();
+
+        let len = old
+            .media_descriptions
+            .len()
+            .max(new.media_descriptions.len());
+
+        for i in 0..len {
+            let old = old.media_descriptions.get(i).filter(|m| is_active(m));
+            let new = new.media_descriptions.get(i).filter(|m| is_active(m));
+
+            match (old, new) {
+                (None, None) => {}
+                (None, Some(_)) => diff.added.push(i),
+                (Some(_), None) => diff.removed.push(i),
+                (Some(old), Some(new)) => {
+                    if old.media.media_type != new.media.media_type {
+                        // The media description has been replaced
+                        diff.removed.push(i);
+                        diff.added.push(i);
+                    } else if old.to_string() != new.to_string() {
+                        diff.changed.push(i);
+                    }
+                }
+            }
+        }
+
+        diff
+    }
+}
+
+fn is_active(media_description: &MediaDescription) -> bool {
+    media_description.media.port != 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytesstr::BytesStr;
+
+    fn sdp(media: &str) -> SessionDescription {
+        let src = format!("v=0\r\no=- 1 1 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n{media}");
+
+        SessionDescription::parse(&BytesStr::from(src)).unwrap()
+    }
+
+    #[test]
+    fn diff_unchanged() {
+        let old = sdp("m=audio 5000 RTP/AVP 0\r\na=sendrecv\r\n");
+        let new = sdp("m=audio 5000 RTP/AVP 0\r\na=sendrecv\r\n");
+
+        assert!(old.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn diff_reordered_attributes() {
+        let old = sdp("m=audio 5000 RTP/AVP 0\r\na=rtpmap:0 PCMU/8000\r\na=sendrecv\r\n");
+        let new = sdp("m=audio 5000 RTP/AVP 0\r\na=sendrecv\r\na=rtpmap:0 PCMU/8000\r\n");
+
+        assert!(old.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn diff_changed() {
+        let old = sdp("m=audio 5000 RTP/AVP 0\r\na=sendrecv\r\n");
+        let new = sdp("m=audio 5000 RTP/AVP 0\r\na=sendonly\r\n");
+
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.changed, [0]);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+    }
+
+    #[test]
+    fn diff_added_removed() {
+        let old = sdp("m=audio 5000 RTP/AVP 0\r\nm=video 5002 RTP/AVP 96\r\n");
+        let new =
+            sdp("m=audio 5000 RTP/AVP 0\r\nm=video 0 RTP/AVP 96\r\nm=video 5004 RTP/AVP 96\r\n");
+
+        let diff = old.diff(&new);
+
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.removed, [1]);
+        assert_eq!(diff.added, [2]);
+    }
+
+    #[test]
+    fn diff_replaced() {
+        let old = sdp("m=audio 5000 RTP/AVP 0\r\n");
+        let new = sdp("m=video 5000 RTP/AVP 96\r\n");
+
+        let diff = old.diff(&new);
+
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.removed, [0]);
+        assert_eq!(diff.added, [0]);
+    }
+}
@@ -9,6 +9,7 @@ use std::str::FromStr;
 mod attributes;
 mod bandwidth;
 mod connection;
+mod diff;
 mod media;
 mod media_description;
 mod origin;
@@ -19,12 +20,14 @@ mod time;
 
 pub use attributes::{
     Direction, ExtMap, Fingerprint, FingerprintAlgorithm, Fmtp, Group, IceCandidate, IceOptions,
-    IcePassword, IceUsernameFragment, InvalidCandidateParamError, Rtcp, RtpMap, Setup,
-    SourceAttribute, SrtpCrypto, SrtpFecOrder, SrtpKeyingMaterial, SrtpSessionParam, SrtpSuite,
-    Ssrc, UnknownAttribute, UntaggedAddress,
+    IcePassword, IceUsernameFragment, InvalidCandidateParamError, Msid, Rid, RidDirection,
+    RidRestriction, Rtcp, RtpMap, Setup, Simulcast, SimulcastRid, SourceAttribute, SrtpCrypto,
+    SrtpFecOrder, SrtpKeyingMaterial, SrtpSessionParam, SrtpSuite, Ssrc, SsrcGroup,
+    SsrcGroupSemantics, UnknownAttribute, UntaggedAddress,
 };
 pub use bandwidth::Bandwidth;
 pub use connection::Connection;
+pub use diff::SessionDescriptionDiff;
 pub use media::{Media, MediaType, TransportProtocol};
 pub use media_description::MediaDescription;
 pub use origin::Origin;
@@ -63,6 +63,12 @@ pub enum TransportProtocol {
     /// DTLS-SRTP with [RFC5124](https://www.rfc-editor.org/rfc/rfc5124.html)
     UdpTlsRtpSavpf,
 
+    /// SCTP over DTLS over UDP, used by WebRTC data channels ([RFC8841](https://www.rfc-editor.org/rfc/rfc8841.html))
+    UdpDtlsSctp,
+
+    /// SCTP over DTLS over TCP ([RFC8841](https://www.rfc-editor.org/rfc/rfc8841.html))
+    TcpDtlsSctp,
+
     /// Other unknown
     Other(BytesStr),
 }
@@ -70,21 +76,17 @@ pub enum TransportProtocol {
 impl TransportProtocol {
     pub fn parse(src: &Bytes) -> impl Fn(&str) -> IResult<&str, Self> + '_ {
         move |i| {
-            alt((
-                map(tag("udp"), |_| TransportProtocol::Unspecified),
-                map(tag("RTP/AVP"), |_| TransportProtocol::RtpAvp),
-                map(tag("RTP/SAVP"), |_| TransportProtocol::RtpSavp),
-                map(tag("RTP/SAVPF"), |_| TransportProtocol::RtpSavpf),
-                map(tag("UDP/TLS/RTP/SAVP"), |_| {
-                    TransportProtocol::UdpTlsRtpSavp
-                }),
-                map(tag("UDP/TLS/RTP/SAVPF"), |_| {
-                    TransportProtocol::UdpTlsRtpSavpf
-                }),
-                map(take_while1(not_whitespace), |tp| {
-                    TransportProtocol::Other(BytesStr::from_parse(src, tp))
-                }),
-            ))(i)
+            map(take_while1(not_whitespace), |tp| match tp {
+                "udp" => TransportProtocol::Unspecified,
+                "RTP/AVP" => TransportProtocol::RtpAvp,
+                "RTP/SAVP" => TransportProtocol::RtpSavp,
+                "RTP/SAVPF" => TransportProtocol::RtpSavpf,
+                "UDP/TLS/RTP/SAVP" => TransportProtocol::UdpTlsRtpSavp,
+                "UDP/TLS/RTP/SAVPF" => TransportProtocol::UdpTlsRtpSavpf,
+                "UDP/DTLS/SCTP" => TransportProtocol::UdpDtlsSctp,
+                "TCP/DTLS/SCTP" => TransportProtocol::TcpDtlsSctp,
+                _ => TransportProtocol::Other(BytesStr::from_parse(src, tp)),
+            })(i)
         }
     }
 }
@@ -98,6 +100,8 @@ impl fmt::Display for TransportProtocol {
             TransportProtocol::RtpSavpf => f.write_str("RTP/SAVPF"),
             TransportProtocol::UdpTlsRtpSavp => f.write_str("UDP/TLS/RTP/SAVP"),
             TransportProtocol::UdpTlsRtpSavpf => f.write_str("UDP/TLS/RTP/SAVPF"),
+            TransportProtocol::UdpDtlsSctp => f.write_str("UDP/DTLS/SCTP"),
+            TransportProtocol::TcpDtlsSctp => f.write_str("TCP/DTLS/SCTP"),
             TransportProtocol::Other(str) => f.write_str(str),
         }
     }
@@ -112,7 +116,14 @@ pub struct Media {
     pub port: u16,
     pub ports_num: Option<u32>,
     pub proto: TransportProtocol,
+
+    /// RTP payload types
     pub fmts: Vec<u8>,
+
+    /// Media formats which are not RTP payload types, e.g. `webrtc-datachannel`
+    ///
+    /// Printed after [`Media::fmts`], the order of an `m=` line mixing both kinds is not kept.
+    pub other_fmts: Vec<BytesStr>,
 }
 
 impl Media {
@@ -125,14 +136,27 @@ impl Media {
                     map_res(digit1, FromStr::from_str),
                     opt(slash_num),
                     TransportProtocol::parse(src),
-                    many0(map(ws((map_res(digit1, FromStr::from_str),)), |t| t.0)),
+                    many0(map(ws((take_while1(not_whitespace),)), |t| t.0)),
                 )),
-                |(media, port, ports_num, proto, fmts)| Media {
-                    media_type: media,
-                    port,
-                    ports_num,
-                    proto,
-                    fmts,
+                |(media, port, ports_num, proto, all_fmts)| {
+                    let mut fmts = vec![];
+                    let mut other_fmts = vec![];
+
+                    for fmt in all_fmts {
+                        match fmt.parse() {
+                            Ok(pt) => fmts.push(pt),
+                            Err(_) => other_fmts.push(BytesStr::from_parse(src, fmt)),
+                        }
+                    }
+
+                    Media {
+                        media_type: media,
+                        port,
+                        ports_num,
+                        proto,
+                        fmts,
+                        other_fmts,
+                    }
                 },
             ),
         )(i)
@@ -155,6 +179,10 @@ impl fmt::Display for Media {
             write!(f, " {}", fmt)?;
         }
 
+        for fmt in &self.other_fmts {
+            write!(f, " {}", fmt)?;
+        }
+
         Ok(())
     }
 }
@@ -178,4 +206,65 @@ mod test {
 
         assert!(rem.is_empty());
     }
+
+    #[test]
+    fn media_savpf() {
+        let input = BytesStr::from_static("video 9 UDP/TLS/RTP/SAVPF 96 97");
+
+        let (rem, media) = Media::parse(input.as_ref(), &input).unwrap();
+
+        assert_eq!(media.media_type, MediaType::Video);
+        assert_eq!(media.proto, TransportProtocol::UdpTlsRtpSavpf);
+        assert_eq!(media.fmts, [96, 97]);
+        assert!(media.other_fmts.is_empty());
+
+        assert!(rem.is_empty());
+    }
+
+    #[test]
+    fn media_data_channel() {
+        let input = BytesStr::from_static("application 9 UDP/DTLS/SCTP webrtc-datachannel");
+
+        let (rem, media) = Media::parse(input.as_ref(), &input).unwrap();
+
+        assert_eq!(media.media_type, MediaType::App);
+        assert_eq!(media.port, 9);
+        assert_eq!(media.proto, TransportProtocol::UdpDtlsSctp);
+        assert!(media.fmts.is_empty());
+        assert_eq!(media.other_fmts, ["webrtc-datachannel"]);
+
+        assert!(rem.is_empty());
+    }
+
+    #[test]
+    fn media_data_channel_print() {
+        let media = Media {
+            media_type: MediaType::App,
+            port: 9,
+            ports_num: None,
+            proto: TransportProtocol::UdpDtlsSctp,
+            fmts: vec![],
+            other_fmts: vec!["webrtc-datachannel".into()],
+        };
+
+        assert_eq!(
+            media.to_string(),
+            "application 9 UDP/DTLS/SCTP webrtc-datachannel"
+        );
+    }
+
+    #[test]
+    fn media_mixed_fmts() {
+        let input = BytesStr::from_static("application 9 TCP/MSRP * 5000 text/plain 0");
+
+        let (rem, media) = Media::parse(input.as_ref(), &input).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(media.fmts, [0]);
+        assert_eq!(media.other_fmts, ["*", "5000", "text/plain"]);
+        assert_eq!(
+            media.to_string(),
+            "application 9 TCP/MSRP 0 * 5000 text/plain"
+        );
+    }
 }
@@ -1,12 +1,16 @@
+use crate::attributes::DisplayPtime;
+use crate::bandwidth::{self, Bandwidth};
 use crate::connection::Connection;
 use crate::media::Media;
-use crate::{bandwidth::Bandwidth, Rtcp};
+use crate::Rtcp;
 use crate::{
     Direction, ExtMap, Fingerprint, Fmtp, IceCandidate, IcePassword, IceUsernameFragment,
-    MediaType, RtpMap, Setup, SrtpCrypto, Ssrc, TransportProtocol, UnknownAttribute,
+    MediaType, Msid, Rid, RtpMap, Setup, Simulcast, SrtpCrypto, Ssrc, SsrcGroup, TransportProtocol,
+    UnknownAttribute,
 };
 use bytesstr::BytesStr;
 use std::fmt::{self, Debug};
+use std::time::Duration;
 
 /// Part of the [`SessionDescription`] describes a single media session
 ///
@@ -31,6 +35,9 @@ pub struct MediaDescription {
     /// rtcp-mux attribute
     pub rtcp_mux: bool,
 
+    /// rtcp-rsize attribute, signals support for reduced-size RTCP ([RFC5506](https://www.rfc-editor.org/rfc/rfc5506.html))
+    pub rtcp_rsize: bool,
+
     /// Media ID (a=mid)
     pub mid: Option<BytesStr>,
 
@@ -64,13 +71,37 @@ pub struct MediaDescription {
     /// SSRC attribute (a=ssrc)
     pub ssrc: Vec<Ssrc>,
 
+    /// SSRC group attribute (a=ssrc-group)
+    pub ssrc_group: Vec<SsrcGroup>,
+
     /// Setup attribute (a=setup)
     pub setup: Option<Setup>,
 
     /// Fingerprint attribute (a=fingerprint)
     pub fingerprint: Vec<Fingerprint>,
 
-    /// Additional attributes
+    /// RTP stream identifiers (a=rid)
+    pub rid: Vec<Rid>,
+
+    /// Simulcast attribute (a=simulcast)
+    pub simulcast: Option<Simulcast>,
+
+    /// Media stream identification (a=msid)
+    pub msid: Vec<Msid>,
+
+    /// Packet time (a=ptime), the length of media in a single packet
+    pub ptime: Option<Duration>,
+
+    /// Maximum packet time (a=maxptime)
+    pub maxptime: Option<Duration>,
+
+    /// SCTP port of a data channel (a=sctp-port)
+    pub sctp_port: Option<u16>,
+
+    /// Maximum SCTP message size of a data channel (a=max-message-size), 0 means unlimited
+    pub max_message_size: Option<u64>,
+
+    /// Additional attributes, printed after all typed attributes in their original order
     pub attributes: Vec<UnknownAttribute>,
 }
 
@@ -96,6 +127,10 @@ impl fmt::Display for MediaDescription {
             write!(f, "a=rtcp-mux\r\n")?;
         }
 
+        if self.rtcp_rsize {
+            write!(f, "a=rtcp-rsize\r\n")?;
+        }
+
         if let Some(mid) = &self.mid {
             write!(f, "a=mid:{}\r\n", mid)?;
         }
@@ -140,6 +175,10 @@ impl fmt::Display for MediaDescription {
             write!(f, "a=ssrc:{ssrc}\r\n")?;
         }
 
+        for ssrc_group in &self.ssrc_group {
+            write!(f, "a=ssrc-group:{ssrc_group}\r\n")?;
+        }
+
         if let Some(setup) = self.setup {
             write!(f, "a=setup:{setup}\r\n")?;
         }
@@ -148,6 +187,34 @@ impl fmt::Display for MediaDescription {
             write!(f, "a=fingerprint:{fingerprint}\r\n")?;
         }
 
+        for rid in &self.rid {
+            write!(f, "a=rid:{rid}\r\n")?;
+        }
+
+        if let Some(simulcast) = &self.simulcast {
+            write!(f, "a=simulcast:{simulcast}\r\n")?;
+        }
+
+        for msid in &self.msid {
+            write!(f, "a=msid:{msid}\r\n")?;
+        }
+
+        if let Some(ptime) = self.ptime {
+            write!(f, "a=ptime:{}\r\n", DisplayPtime(ptime))?;
+        }
+
+        if let Some(maxptime) = self.maxptime {
+            write!(f, "a=maxptime:{}\r\n", DisplayPtime(maxptime))?;
+        }
+
+        if let Some(sctp_port) = self.sctp_port {
+            write!(f, "a=sctp-port:{sctp_port}\r\n")?;
+        }
+
+        if let Some(max_message_size) = self.max_message_size {
+            write!(f, "a=max-message-size:{max_message_size}\r\n")?;
+        }
+
         for attr in &self.attributes {
             write!(f, "{}\r\n", attr)?;
         }
@@ -157,6 +224,19 @@ impl fmt::Display for MediaDescription {
 }
 
 impl MediaDescription {
+    /// Returns the maximum bitrate in bits per second signaled by the media level bandwidth fields
+    ///
+    /// Use [`SessionDescription::media_max_bitrate`](crate::SessionDescription::media_max_bitrate)
+    /// to respect the session level bandwidth as well.
+    pub fn max_bitrate(&self) -> Option<u64> {
+        bandwidth::max_bitrate(&self.bandwidth)
+    }
+
+    /// Limit the bitrate of the media by replacing the `TIAS` and `AS` bandwidth fields
+    pub fn set_max_bitrate(&mut self, bps: u32) {
+        bandwidth::set_max_bitrate(&mut self.bandwidth, bps);
+    }
+
     /// Create media description which signals rejected media
     pub fn rejected(media_type: MediaType) -> Self {
         MediaDescription {
@@ -166,12 +246,14 @@ impl MediaDescription {
                 ports_num: None,
                 proto: TransportProtocol::RtpAvp,
                 fmts: vec![],
+                other_fmts: vec![],
             },
             connection: None,
             bandwidth: vec![],
             direction: Direction::Inactive,
             rtcp: None,
             rtcp_mux: false,
+            rtcp_rsize: false,
             mid: None,
             rtpmap: vec![],
             fmtp: vec![],
@@ -185,6 +267,14 @@ impl MediaDescription {
             ssrc: vec![],
             setup: None,
             fingerprint: vec![],
+            rid: vec![],
+            simulcast: None,
+            msid: vec![],
+            ssrc_group: vec![],
+            ptime: None,
+            maxptime: None,
+            sctp_port: None,
+            max_message_size: None,
             attributes: vec![],
         }
     }
@@ -1,16 +1,17 @@
+use crate::attributes::parse_ptime;
 use crate::{
     Bandwidth, Connection, Direction, ExtMap, Fingerprint, Fmtp, Group, IceCandidate, IceOptions,
-    IcePassword, IceUsernameFragment, Media, MediaDescription, Origin, Rtcp, RtpMap,
-    SessionDescription, Setup, SrtpCrypto, Ssrc, Time, UnknownAttribute,
+    IcePassword, IceUsernameFragment, Media, MediaDescription, Msid, Origin, Rid, Rtcp, RtpMap,
+    SessionDescription, Setup, Simulcast, SrtpCrypto, Ssrc, SsrcGroup, Time, UnknownAttribute,
 };
 use bytesstr::BytesStr;
-use internal::verbose_error_to_owned;
+use internal::ParseError;
 use nom::Finish;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ParseSessionDescriptionError {
     #[error("{0}")]
-    ParseError(nom::error::VerboseError<String>),
+    ParseError(ParseError),
     #[error("message ended unexpectedly")]
     Incomplete,
     #[error("message is missing the origin field (o=)")]
@@ -21,10 +22,12 @@ pub enum ParseSessionDescriptionError {
     MissingTime,
 }
 
-impl From<nom::error::VerboseError<&str>> for ParseSessionDescriptionError {
-    fn from(value: nom::error::VerboseError<&str>) -> Self {
-        Self::ParseError(verbose_error_to_owned(value))
-    }
+/// Returns a function that maps a parser error to a [`ParseSessionDescriptionError`]
+/// with the error position relative to the complete message `src`
+fn parse_error(
+    src: &str,
+) -> impl FnOnce(nom::error::VerboseError<&str>) -> ParseSessionDescriptionError + '_ {
+    move |err| ParseSessionDescriptionError::ParseError(ParseError::new(src, err))
 }
 
 #[derive(Default)]
@@ -42,6 +45,7 @@ pub(crate) struct Parser {
     ice_lite: bool,
     ice_ufrag: Option<IceUsernameFragment>,
     ice_pwd: Option<IcePassword>,
+    ice_end_of_candidates: bool,
     setup: Option<Setup>,
     fingerprint: Vec<Fingerprint>,
     attributes: Vec<UnknownAttribute>,
@@ -66,15 +70,19 @@ impl Parser {
                 self.name = Some(BytesStr::from_parse(src.as_ref(), line));
             }
             [b'o', b'=', ..] => {
-                let (_, o) = Origin::parse(src.as_ref(), line).finish()?;
+                let (_, o) = Origin::parse(src.as_ref(), line)
+                    .finish()
+                    .map_err(parse_error(src))?;
                 self.origin = Some(o);
             }
             [b't', b'=', ..] => {
-                let (_, t) = Time::parse(line).finish()?;
+                let (_, t) = Time::parse(line).finish().map_err(parse_error(src))?;
                 self.time = Some(t);
             }
             [b'c', b'=', ..] => {
-                let (_, c) = Connection::parse(src.as_ref(), line).finish()?;
+                let (_, c) = Connection::parse(src.as_ref(), line)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.connection = Some(c);
@@ -83,7 +91,9 @@ impl Parser {
                 }
             }
             [b'b', b'=', ..] => {
-                let (_, b) = Bandwidth::parse(src.as_ref(), line).finish()?;
+                let (_, b) = Bandwidth::parse(src.as_ref(), line)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.bandwidth.push(b);
@@ -92,7 +102,9 @@ impl Parser {
                 }
             }
             [b'm', b'=', ..] => {
-                let (_, media) = Media::parse(src.as_ref(), line).finish()?;
+                let (_, media) = Media::parse(src.as_ref(), line)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 self.media_descriptions.push(MediaDescription {
                     media,
@@ -102,6 +114,7 @@ impl Parser {
                     direction: self.direction,
                     rtcp: None,
                     rtcp_mux: false,
+                    rtcp_rsize: false,
                     mid: None,
                     rtpmap: vec![],
                     fmtp: vec![],
@@ -116,6 +129,14 @@ impl Parser {
                     ssrc: vec![],
                     setup: self.setup,
                     fingerprint: vec![],
+                    rid: vec![],
+                    simulcast: None,
+                    msid: vec![],
+                    ssrc_group: vec![],
+                    ptime: None,
+                    maxptime: None,
+                    sctp_port: None,
+                    max_message_size: None,
                     attributes: vec![],
                 });
             }
@@ -148,52 +169,64 @@ impl Parser {
     ) -> Result<(), ParseSessionDescriptionError> {
         match name {
             "group" => {
-                let (_, group) = Group::parse(src.as_ref(), value).finish()?;
+                let (_, group) = Group::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
                 self.group.push(group);
             }
             "rtcp" => {
-                let (_, rtcp) = Rtcp::parse(src.as_ref(), value).finish()?;
+                let (_, rtcp) = Rtcp::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.rtcp = Some(rtcp);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
                 }
-
-                // TODO error here?
             }
             "mid" => {
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.mid = Some(BytesStr::from_parse(src.as_ref(), value.trim()));
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
                 }
-
-                // TODO error here ?
             }
             "rtpmap" => {
-                let (_, rtpmap) = RtpMap::parse(src.as_ref(), value).finish()?;
+                let (_, rtpmap) = RtpMap::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.rtpmap.push(rtpmap);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
                 }
-
-                // TODO error here ?
             }
             "fmtp" => {
-                let (_, fmtp) = Fmtp::parse(src.as_ref(), value).finish()?;
+                let (_, fmtp) = Fmtp::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.fmtp.push(fmtp);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
                 }
-
-                // TODO error here ?
             }
             "ice-lite" => {
                 self.ice_lite = true;
             }
             "ice-options" => {
-                let (_, options) = IceOptions::parse(src.as_ref(), value).finish()?;
+                let (_, options) = IceOptions::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
                 self.ice_options = options;
             }
             "ice-ufrag" => {
-                let (_, ufrag) = IceUsernameFragment::parse(src.as_ref(), value).finish()?;
+                let (_, ufrag) = IceUsernameFragment::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.ice_ufrag = Some(ufrag);
@@ -202,7 +235,9 @@ impl Parser {
                 }
             }
             "ice-pwd" => {
-                let (_, pwd) = IcePassword::parse(src.as_ref(), value).finish()?;
+                let (_, pwd) = IcePassword::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.ice_pwd = Some(pwd);
@@ -211,25 +246,31 @@ impl Parser {
                 }
             }
             "candidate" => {
-                let (_, candidate) = IceCandidate::parse(src.as_ref(), value).finish()?;
+                let (_, candidate) = IceCandidate::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.ice_candidates.push(candidate);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
                 }
-
-                // TODO error here?
             }
             "crypto" => {
-                let (_, crypto) = SrtpCrypto::parse(src.as_ref(), value).finish()?;
+                let (_, crypto) = SrtpCrypto::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.crypto.push(crypto);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
                 }
-
-                // TODO error here?
             }
             "extmap" => {
-                let (_, extmap) = ExtMap::parse(src.as_ref(), value).finish()?;
+                let (_, extmap) = ExtMap::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.extmap.push(extmap);
@@ -238,13 +279,15 @@ impl Parser {
                 }
             }
             "ssrc" => {
-                let (_, ssrc) = Ssrc::parse(src.as_ref(), value).finish()?;
+                let (_, ssrc) = Ssrc::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.ssrc.push(ssrc);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
                 }
-
-                // TODO error here?
             }
             "setup" => {
                 let setup = match value {
@@ -252,7 +295,10 @@ impl Parser {
                     "passive" => Setup::Passive,
                     "actpass" => Setup::ActPass,
                     "holdconn" => Setup::HoldConn,
-                    _ => return Ok(()),
+                    _ => {
+                        self.unknown_attribute(src, name, Some(value));
+                        return Ok(());
+                    }
                 };
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
@@ -260,10 +306,11 @@ impl Parser {
                 } else {
                     self.setup = Some(setup);
                 }
-                // TODO error here?
             }
             "fingerprint" => {
-                let (_, fingerprint) = Fingerprint::parse(src.as_ref(), value).finish()?;
+                let (_, fingerprint) = Fingerprint::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.fingerprint.push(fingerprint);
@@ -271,18 +318,99 @@ impl Parser {
                     self.fingerprint.push(fingerprint)
                 }
             }
-            _ => {
-                let attr = UnknownAttribute {
-                    name: src.slice_ref(name),
-                    value: Some(src.slice_ref(value)),
+            "rid" => {
+                let (_, rid) = Rid::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
+
+                if let Some(media_description) = self.media_descriptions.last_mut() {
+                    media_description.rid.push(rid);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
+                }
+            }
+            "simulcast" => {
+                let (_, simulcast) = Simulcast::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
+
+                if let Some(media_description) = self.media_descriptions.last_mut() {
+                    media_description.simulcast = Some(simulcast);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
+                }
+            }
+            "msid" => {
+                let (_, msid) = Msid::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
+
+                if let Some(media_description) = self.media_descriptions.last_mut() {
+                    media_description.msid.push(msid);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
+                }
+            }
+            "ssrc-group" => {
+                let (_, ssrc_group) = SsrcGroup::parse(src.as_ref(), value)
+                    .finish()
+                    .map_err(parse_error(src))?;
+
+                if let Some(media_description) = self.media_descriptions.last_mut() {
+                    media_description.ssrc_group.push(ssrc_group);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
+                }
+            }
+            "ptime" => {
+                let Some(ptime) = parse_ptime(value) else {
+                    self.unknown_attribute(src, name, Some(value));
+                    return Ok(());
+                };
+
+                if let Some(media_description) = self.media_descriptions.last_mut() {
+                    media_description.ptime = Some(ptime);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
+                }
+            }
+            "maxptime" => {
+                let Some(maxptime) = parse_ptime(value) else {
+                    self.unknown_attribute(src, name, Some(value));
+                    return Ok(());
+                };
+
+                if let Some(media_description) = self.media_descriptions.last_mut() {
+                    media_description.maxptime = Some(maxptime);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
+                }
+            }
+            "sctp-port" => {
+                let Ok(sctp_port) = value.trim().parse() else {
+                    self.unknown_attribute(src, name, Some(value));
+                    return Ok(());
+                };
+
+                if let Some(media_description) = self.media_descriptions.last_mut() {
+                    media_description.sctp_port = Some(sctp_port);
+                } else {
+                    self.unknown_attribute(src, name, Some(value));
+                }
+            }
+            "max-message-size" => {
+                let Ok(max_message_size) = value.trim().parse() else {
+                    self.unknown_attribute(src, name, Some(value));
+                    return Ok(());
                 };
 
                 if let Some(media_description) = self.media_descriptions.last_mut() {
-                    media_description.attributes.push(attr);
+                    media_description.max_message_size = Some(max_message_size);
                 } else {
-                    self.attributes.push(attr);
+                    self.unknown_attribute(src, name, Some(value));
                 }
             }
+            _ => self.unknown_attribute(src, name, Some(value)),
         }
 
         Ok(())
@@ -310,27 +438,42 @@ impl Parser {
             "rtcp-mux" => {
                 if let Some(media_description) = self.media_descriptions.last_mut() {
                     media_description.rtcp_mux = true;
+                } else {
+                    self.unknown_attribute(src, line, None);
                 }
             }
-            "end-of-candidates" => {
+            "rtcp-rsize" => {
                 if let Some(media_description) = self.media_descriptions.last_mut() {
-                    media_description.ice_end_of_candidates = true;
+                    media_description.rtcp_rsize = true;
+                } else {
+                    self.unknown_attribute(src, line, None);
                 }
-
-                // TODO error here?
             }
-            _ => {
-                let attr = UnknownAttribute {
-                    name: src.slice_ref(line),
-                    value: None,
-                };
-
+            "end-of-candidates" => {
                 if let Some(media_description) = self.media_descriptions.last_mut() {
-                    media_description.attributes.push(attr);
+                    media_description.ice_end_of_candidates = true;
                 } else {
-                    self.attributes.push(attr);
+                    self.ice_end_of_candidates = true;
                 }
             }
+            _ => self.unknown_attribute(src, line, None),
+        }
+    }
+
+    /// Store an attribute which is unknown or cannot be represented at the current level,
+    /// so it is not lost when printing the session description again
+    ///
+    /// Unknown attributes keep their relative order, but are printed after all typed attributes
+    fn unknown_attribute(&mut self, src: &BytesStr, name: &str, value: Option<&str>) {
+        let attr = UnknownAttribute {
+            name: src.slice_ref(name),
+            value: value.map(|value| src.slice_ref(value)),
+        };
+
+        if let Some(media_description) = self.media_descriptions.last_mut() {
+            media_description.attributes.push(attr);
+        } else {
+            self.attributes.push(attr);
         }
     }
 
@@ -351,6 +494,7 @@ impl Parser {
             ice_options: self.ice_options,
             ice_ufrag: self.ice_ufrag,
             ice_pwd: self.ice_pwd,
+            ice_end_of_candidates: self.ice_end_of_candidates,
             setup: self.setup,
             fingerprint: self.fingerprint,
             attributes: self.attributes,
@@ -1,6 +1,7 @@
 use crate::attributes::Group;
-use crate::bandwidth::Bandwidth;
+use crate::bandwidth::{self, Bandwidth};
 use crate::connection::Connection;
+use crate::diff::SessionDescriptionDiff;
 use crate::origin::Origin;
 use crate::parser::{ParseSessionDescriptionError, Parser};
 use crate::time::Time;
@@ -13,6 +14,9 @@ use std::fmt::{self, Debug};
 
 /// The Session Description message. Can be serialized to valid SDP using the [`fmt::Display`] implementation and
 /// parse SDP using [`SessionDescription::parse`].
+///
+/// Printing does not reproduce the original attribute order. Typed attributes are printed in a fixed order,
+/// followed by all unknown attributes in the order they were parsed.
 #[derive(Debug, Clone)]
 pub struct SessionDescription {
     /// Origin (o field)
@@ -56,13 +60,16 @@ pub struct SessionDescription {
     /// ICE password
     pub ice_pwd: Option<IcePassword>,
 
+    /// ICE a=end-of-candidates attribute, applies to all media descriptions if set
+    pub ice_end_of_candidates: bool,
+
     /// Setup attribute (a=setup)
     pub setup: Option<Setup>,
 
     /// Fingerprint attribute (a=fingerprint)
     pub fingerprint: Vec<Fingerprint>,
 
-    /// All attributes not parsed directly
+    /// All attributes not parsed directly, printed after all typed attributes in their original order
     pub attributes: Vec<UnknownAttribute>,
 
     /// Media descriptions
@@ -81,6 +88,29 @@ impl SessionDescription {
 
         parser.finish()
     }
+
+    /// Compare the media descriptions with the ones of `new`, e.g. the previous and current remote description
+    pub fn diff(&self, new: &SessionDescription) -> SessionDescriptionDiff {
+        SessionDescriptionDiff::new(self, new)
+    }
+
+    /// Returns the maximum bitrate in bits per second signaled by the session level bandwidth fields
+    pub fn max_bitrate(&self) -> Option<u64> {
+        bandwidth::max_bitrate(&self.bandwidth)
+    }
+
+    /// Limit the bitrate of the session by replacing the session level `TIAS` and `AS` bandwidth fields
+    pub fn set_max_bitrate(&mut self, bps: u32) {
+        bandwidth::set_max_bitrate(&mut self.bandwidth, bps);
+    }
+
+    /// Returns the maximum bitrate in bits per second of the media, limited by the session level bandwidth
+    pub fn media_max_bitrate(&self, media_description: &MediaDescription) -> Option<u64> {
+        match (media_description.max_bitrate(), self.max_bitrate()) {
+            (Some(media), Some(session)) => Some(media.min(session)),
+            (media, session) => media.or(session),
+        }
+    }
 }
 
 impl fmt::Display for SessionDescription {
@@ -129,6 +159,10 @@ impl fmt::Display for SessionDescription {
             write!(f, "a=ice-pwd:{}\r\n", pwd.pwd)?;
         }
 
+        if self.ice_end_of_candidates {
+            write!(f, "a=end-of-candidates\r\n")?;
+        }
+
         if let Some(setup) = self.setup {
             write!(f, "a=setup:{setup}\r\n")?;
         }
@@ -148,3 +182,115 @@ impl fmt::Display for SessionDescription {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TransportProtocol;
+
+    #[test]
+    fn unknown_attributes_roundtrip() {
+        let input = BytesStr::from_static(
+            "v=0\r\n\
+             o=- 1 1 IN IP4 127.0.0.1\r\n\
+             s=-\r\n\
+             t=0 0\r\n\
+             a=rtcp-mux\r\n\
+             a=mid:0\r\n\
+             a=x-first:1\r\n\
+             a=x-second\r\n\
+             m=audio 5000 RTP/AVP 0\r\n\
+             a=sendrecv\r\n\
+             a=x-third:3\r\n\
+             a=setup:invalid\r\n\
+             a=ptime:abc\r\n\
+             a=x-fourth\r\n",
+        );
+
+        let sdp = SessionDescription::parse(&input).unwrap();
+
+        let session: Vec<String> = sdp.attributes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            session,
+            ["a=rtcp-mux", "a=mid:0", "a=x-first:1", "a=x-second"]
+        );
+
+        let media: Vec<String> = sdp.media_descriptions[0]
+            .attributes
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            media,
+            [
+                "a=x-third:3",
+                "a=setup:invalid",
+                "a=ptime:abc",
+                "a=x-fourth"
+            ]
+        );
+
+        let printed = SessionDescription::parse(&BytesStr::from(sdp.to_string())).unwrap();
+        assert_eq!(printed.to_string(), sdp.to_string());
+    }
+
+    #[test]
+    fn typed_attributes_reordered() {
+        let input = BytesStr::from_static(
+            "v=0\r\n\
+             o=- 1 1 IN IP4 127.0.0.1\r\n\
+             s=-\r\n\
+             t=0 0\r\n\
+             m=audio 5000 RTP/AVP 0\r\n\
+             a=x-first\r\n\
+             a=rtpmap:0 PCMU/8000\r\n\
+             a=x-second\r\n\
+             a=sendonly\r\n",
+        );
+
+        let sdp = SessionDescription::parse(&input).unwrap();
+
+        let printed = sdp.to_string();
+        let media = printed.split_once("m=").unwrap().1;
+
+        assert_eq!(
+            media,
+            "audio 5000 RTP/AVP 0\r\n\
+             a=sendonly\r\n\
+             a=rtpmap:0 PCMU/8000\r\n\
+             a=x-first\r\n\
+             a=x-second\r\n"
+        );
+    }
+
+    #[test]
+    fn data_channel() {
+        let input = BytesStr::from_static(
+            "v=0\r\n\
+             o=- 1 1 IN IP4 127.0.0.1\r\n\
+             s=-\r\n\
+             t=0 0\r\n\
+             m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+             a=mid:2\r\n\
+             a=sctp-port:5000\r\n\
+             a=max-message-size:262144\r\n",
+        );
+
+        let sdp = SessionDescription::parse(&input).unwrap();
+
+        let media_description = &sdp.media_descriptions[0];
+        assert_eq!(
+            media_description.media.proto,
+            TransportProtocol::UdpDtlsSctp
+        );
+        assert_eq!(media_description.media.other_fmts, ["webrtc-datachannel"]);
+        assert_eq!(media_description.sctp_port, Some(5000));
+        assert_eq!(media_description.max_message_size, Some(262144));
+        assert!(media_description.attributes.is_empty());
+
+        let printed = sdp.to_string();
+        assert!(printed.contains("m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n"));
+        assert!(printed.contains("a=sctp-port:5000\r\n"));
+        assert!(printed.contains("a=max-message-size:262144\r\n"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Bearer token authorization ([RFC8898](https://datatracker.ietf.org/doc/html/rfc8898))
+
+use crate::{BoxFuture, Error, RequestParts, ResponseEntry, UacAuthenticator};
+use bytesstr::BytesStr;
+use sip_types::header::typed::{Auth, AuthChallenge, AuthResponse};
+use std::future::Future;
+
+const BEARER: &str = "Bearer";
+
+/// Access token used to authorize requests
+#[derive(Clone)]
+pub struct BearerCredentials {
+    token: String,
+}
+
+impl BearerCredentials {
+    pub fn new<T>(token: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            token: token.into(),
+        }
+    }
+}
+
+type TokenRefresh = Box<dyn Fn(&Auth) -> BoxFuture<'static, Option<String>> + Send + Sync>;
+
+/// Used to authorize 401 & 407 Bearer responses
+///
+/// The token from the credentials is used to respond to the first challenge of a realm.
+/// If the token gets rejected, a new one is requested from the token refresh callback
+/// (e.g. using the `authz_server` and `scope` parameters of the challenge).
+///
+/// Refreshing tokens is asynchronous, it requires the challenges to be handled using
+/// [`UacAuthSession::handle_authenticate_async`](crate::UacAuthSession::handle_authenticate_async)
+/// or [`UacAuthSession::handle_authenticate_with_provider`](crate::UacAuthSession::handle_authenticate_with_provider).
+#[derive(Default)]
+pub struct BearerAuthenticator {
+    refresh: Option<TokenRefresh>,
+
+    /// Tokens received from the refresh callback, mapped to their realm
+    tokens: Vec<(BytesStr, BytesStr)>,
+
+    /// Realms whose token was refreshed since it was last rejected
+    refreshed: Vec<BytesStr>,
+}
+
+impl BearerAuthenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the callback used to acquire a new token when the current one was rejected
+    pub fn set_token_refresh<F, Fut>(&mut self, refresh: F)
+    where
+        F: Fn(&Auth) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<String>> + Send + 'static,
+    {
+        self.refresh = Some(Box::new(move |challenge| Box::pin(refresh(challenge))));
+    }
+
+    async fn refresh_token(&mut self, realm: &BytesStr, challenge: &Auth) {
+        let Some(refresh) = &self.refresh else {
+            return;
+        };
+
+        let Some(token) = refresh(challenge).await else {
+            return;
+        };
+
+        let token = BytesStr::from(token);
+
+        if let Some((_, old_token)) = self.tokens.iter_mut().find(|(r, _)| r == realm) {
+            *old_token = token;
+        } else {
+            self.tokens.push((realm.clone(), token));
+        }
+
+        self.refreshed.push(realm.clone());
+    }
+}
+
+/// Returns the value of the challenge parameter with the given name
+pub fn bearer_param<'a>(challenge: &'a Auth, name: &str) -> Option<&'a BytesStr> {
+    challenge
+        .params
+        .iter()
+        .find(|param| param.name.eq_ignore_ascii_case(name))
+        .map(|param| &param.value)
+}
+
+impl UacAuthenticator for BearerAuthenticator {
+    type Credentials = BearerCredentials;
+
+    fn get_realm<'s>(&mut self, auth: &'s AuthChallenge) -> Result<&'s BytesStr, Error> {
+        match auth {
+            AuthChallenge::Other(other) if other.scheme.eq_ignore_ascii_case(BEARER) => {
+                bearer_param(other, "realm").ok_or(Error::MissingRealm)
+            }
+            AuthChallenge::Digest(_) => Err(Error::UnknownScheme(BytesStr::from_static("Digest"))),
+            AuthChallenge::Other(other) => Err(Error::UnknownScheme(other.scheme.clone())),
+        }
+    }
+
+    fn prepare_challenge<'a>(
+        &'a mut self,
+        responses: &'a [ResponseEntry],
+        challenge: &'a AuthChallenge,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let AuthChallenge::Other(auth) = challenge else {
+                return Ok(());
+            };
+
+            let realm = self.get_realm(challenge)?.clone();
+
+            let rejected = responses.iter().any(|response| response.realm == realm);
+
+            if rejected && !self.refreshed.contains(&realm) {
+                self.refresh_token(&realm, auth).await;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn handle_challenge(
+        &mut self,
+        responses: &[ResponseEntry],
+        _request_parts: RequestParts<'_>,
+        challenge: AuthChallenge,
+        credentials: &BearerCredentials,
+    ) -> Result<AuthResponse, Error> {
+        let realm = self.get_realm(&challenge)?.clone();
+
+        // A previous response for the realm means the token got rejected
+        let rejected = responses.iter().any(|response| response.realm == realm);
+
+        let token = if rejected {
+            // Use the token acquired in `prepare_challenge`
+            let refreshed = self
+                .refreshed
+                .iter()
+                .position(|r| *r == realm)
+                .ok_or_else(|| Error::FailedToAuthenticate(realm.clone()))?;
+
+            self.refreshed.remove(refreshed);
+
+            self.tokens
+                .iter()
+                .find(|(r, _)| *r == realm)
+                .map(|(_, token)| token.clone())
+                .ok_or_else(|| Error::FailedToAuthenticate(realm.clone()))?
+        } else if let Some((_, token)) = self.tokens.iter().find(|(r, _)| *r == realm) {
+            token.clone()
+        } else {
+            BytesStr::from(credentials.token.as_str())
+        };
+
+        Ok(AuthResponse::Other(Auth {
+            scheme: BytesStr::from_static(BEARER),
+            token: Some(token),
+            params: vec![],
+        }))
+    }
+
+    fn on_authorize_request(&mut self, _: &mut ResponseEntry) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::block_on;
+    use crate::{CredentialStore, UacAuthSession};
+    use sip_types::msg::RequestLine;
+    use sip_types::uri::sip::SipUri;
+    use sip_types::{Headers, Method, Name};
+
+    fn authorize(session: &mut UacAuthSession<BearerAuthenticator>) -> Result<Headers, Error> {
+        let mut credentials = CredentialStore::new();
+        credentials.add_for_realm("atlanta.com", BearerCredentials::new("token1"));
+
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::WWW_AUTHENTICATE,
+            r#"Bearer realm="atlanta.com", scope="sip:register", authz_server="https://as.example.com/token""#,
+        );
+
+        let uri: SipUri = "sip:atlanta.com".parse().unwrap();
+        let line = RequestLine {
+            method: Method::REGISTER,
+            uri: Box::new(uri),
+        };
+
+        block_on(session.handle_authenticate_async(
+            &headers,
+            &credentials,
+            RequestParts {
+                line: &line,
+                headers: &Headers::new(),
+                body: &[],
+            },
+        ))?;
+
+        let mut request_headers = Headers::new();
+        session.authorize_request(&mut request_headers);
+        Ok(request_headers)
+    }
+
+    #[test]
+    fn bearer_token_refresh() {
+        let mut authenticator = BearerAuthenticator::new();
+        authenticator.set_token_refresh(|challenge| {
+            let authz_server = bearer_param(challenge, "authz_server").cloned();
+
+            async move {
+                assert_eq!(authz_server.unwrap(), "https://as.example.com/token");
+
+                Some("token2".into())
+            }
+        });
+
+        let mut session = UacAuthSession::new(authenticator);
+
+        let headers = authorize(&mut session).unwrap();
+        assert_eq!(headers.to_string(), "Authorization: Bearer token1\r\n");
+
+        // Challenged again, the token was rejected
+        let headers = authorize(&mut session).unwrap();
+        assert_eq!(headers.to_string(), "Authorization: Bearer token2\r\n");
+    }
+
+    #[test]
+    fn bearer_token_rejected_after_failed_refresh() {
+        let mut authenticator = BearerAuthenticator::new();
+        authenticator.set_token_refresh(|_| async { None });
+
+        let mut session = UacAuthSession::new(authenticator);
+
+        authorize(&mut session).unwrap();
+        assert!(matches!(
+            authorize(&mut session),
+            Err(Error::FailedToAuthenticate(_))
+        ));
+    }
+
+    #[test]
+    fn bearer_token_rejected_without_refresh() {
+        let mut session = UacAuthSession::new(BearerAuthenticator::new());
+
+        authorize(&mut session).unwrap();
+        assert!(matches!(
+            authorize(&mut session),
+            Err(Error::FailedToAuthenticate(_))
+        ));
+    }
+}
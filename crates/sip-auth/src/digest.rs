@@ -2,14 +2,18 @@ use crate::{Error, RequestParts, ResponseEntry, UacAuthenticator};
 use bytesstr::BytesStr;
 use sha2::Digest;
 use sip_types::header::typed::{
-    Algorithm, AlgorithmValue, AuthChallenge, AuthResponse, DigestChallenge, DigestResponse,
-    QopOption, QopResponse, Username,
+    Algorithm, AlgorithmValue, AuthChallenge, AuthResponse, AuthenticationInfo, DigestChallenge,
+    DigestResponse, QopOption, QopResponse, Username,
 };
+use sip_types::msg::RequestLine;
 use sip_types::print::{AppendCtx, PrintCtx, UriContext};
+use sip_types::{Headers, Name};
+use std::sync::{Arc, Mutex};
 
+#[derive(Clone)]
 pub struct DigestCredentials {
-    user: String,
-    password: Vec<u8>,
+    pub(crate) user: String,
+    pub(crate) password: Vec<u8>,
 }
 
 impl DigestCredentials {
@@ -41,8 +45,56 @@ fn hash_sha512_trunc256(i: &[u8]) -> String {
     format!("{:x}", hasher.finalize())
 }
 
-type HashFn = fn(&[u8]) -> String;
+pub(crate) type HashFn = fn(&[u8]) -> String;
+
+/// Returns the hash function of the algorithm and if it is a session variant (`-sess`)
+pub(crate) fn algorithm_hash(algorithm: &AlgorithmValue) -> Option<(HashFn, bool)> {
+    match algorithm {
+        AlgorithmValue::MD5 => Some((hash_md5, false)),
+        AlgorithmValue::MD5Sess => Some((hash_md5, true)),
+        AlgorithmValue::SHA256 => Some((hash_sha256, false)),
+        AlgorithmValue::SHA256Sess => Some((hash_sha256, true)),
+        AlgorithmValue::SHA512256 => Some((hash_sha512_trunc256, false)),
+        AlgorithmValue::SHA512256Sess => Some((hash_sha512_trunc256, true)),
+        AlgorithmValue::Other(_) => None,
+    }
+}
+
+/// Nonce-count state of a digest authorization using qop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceState {
+    pub realm: BytesStr,
+    pub nonce: BytesStr,
+    pub cnonce: BytesStr,
+
+    /// The last nonce-count used with the nonce
+    pub nc: u32,
+}
+
+/// Persists the nonce-counts used by a [`DigestAuthenticator`]
+///
+/// Allows a restarted client to continue using a nonce with providers that require
+/// strictly increasing nonce-counts.
+pub trait NonceStore: Send + Sync {
+    /// Called every time a nonce-count is used to authorize a request
+    fn store(&self, state: &NonceState);
 
+    /// Returns the last state stored for the `realm` and `nonce`
+    fn restore(&self, realm: &str, nonce: &str) -> Option<NonceState>;
+}
+
+impl<S: NonceStore> NonceStore for Arc<S> {
+    fn store(&self, state: &NonceState) {
+        S::store(self, state)
+    }
+
+    fn restore(&self, realm: &str, nonce: &str) -> Option<NonceState> {
+        S::restore(self, realm, nonce)
+    }
+}
+
+/// Hashes of a previous response, used to re-calculate it with a new nonce-count or nonce
+#[derive(Clone)]
 struct QopEntry {
     ha1: String,
     ha2: String,
@@ -50,9 +102,14 @@ struct QopEntry {
 }
 
 /// Used to authorize 401 & 407 Digest responses
+///
+/// Supports the MD5, SHA-256 and SHA-512-256 algorithms (RFC8760). If a realm is challenged
+/// with multiple algorithms the strongest one is used.
 #[derive(Default)]
 pub struct DigestAuthenticator {
     qop_responses: Vec<(BytesStr, QopEntry)>,
+    shared: Option<SharedDigestState>,
+    nonce_store: Option<Box<dyn NonceStore>>,
     /// Respond with qop `Auth` when a challenge does not contain qop field (RFC8760 Section 2.6). Is false by default
     pub enforce_qop: bool,
     /// Reject challenges with MD5 algorithm. Is false by default
@@ -69,6 +126,24 @@ impl UacAuthenticator for DigestAuthenticator {
         }
     }
 
+    fn challenge_preference(&self, challenge: &AuthChallenge) -> u32 {
+        let AuthChallenge::Digest(digest) = challenge else {
+            return 0;
+        };
+
+        let algorithm_value = match &digest.algorithm {
+            Algorithm::AkaNamespace((_, av)) => av,
+            Algorithm::AlgorithmValue(av) => av,
+        };
+
+        match algorithm_value {
+            AlgorithmValue::SHA512256 | AlgorithmValue::SHA512256Sess => 3,
+            AlgorithmValue::SHA256 | AlgorithmValue::SHA256Sess => 2,
+            AlgorithmValue::MD5 | AlgorithmValue::MD5Sess => 1,
+            AlgorithmValue::Other(_) => 0,
+        }
+    }
+
     fn handle_challenge(
         &mut self,
         responses: &[ResponseEntry],
@@ -110,52 +185,158 @@ impl UacAuthenticator for DigestAuthenticator {
     }
 
     fn on_authorize_request(&mut self, response: &mut ResponseEntry) {
+        let use_count = response.use_count;
+
         let digest = match &mut response.response {
             AuthResponse::Digest(response) => response,
             AuthResponse::Other(_) => return,
         };
 
-        // nc is already correct
-        if response.use_count == 0 {
-            return;
-        }
-
         let digest_realm = &digest.realm;
 
         // qop response needs its nonce-count incremented and response re-calculated
-        let qop_response = if let Some(qop_response) = &mut digest.qop_response {
-            qop_response.nc += 1;
-            qop_response
-        } else {
-            return;
+        if let Some(qop_response) = &mut digest.qop_response {
+            // nc is already correct on first use, unless it was used by another session
+            let mut nc = if use_count == 0 {
+                qop_response.nc
+            } else {
+                qop_response.nc + 1
+            };
+
+            if let Some(shared) = &self.shared {
+                nc = shared.next_nc(digest_realm, &digest.nonce, nc);
+            }
+
+            if let Some(store) = &self.nonce_store {
+                store.store(&NonceState {
+                    realm: digest_realm.clone(),
+                    nonce: digest.nonce.clone(),
+                    cnonce: qop_response.cnonce.clone(),
+                    nc,
+                });
+            }
+
+            if nc != qop_response.nc {
+                qop_response.nc = nc;
+
+                let (_, qop_entry) = self
+                    .qop_responses
+                    .iter()
+                    .find(|(realm, _)| realm == digest_realm)
+                    .expect("qop_entry must be some");
+
+                digest.response = qop_entry
+                    .qop_response(&digest.nonce, qop_response, &qop_entry.ha2)
+                    .into();
+            }
+        }
+
+        self.share(response);
+    }
+
+    fn handle_authentication_info(
+        &mut self,
+        response: &mut ResponseEntry,
+        info: &AuthenticationInfo,
+        response_body: &[u8],
+    ) -> Result<(), Error> {
+        let digest = match &mut response.response {
+            AuthResponse::Digest(response) => response,
+            AuthResponse::Other(_) => return Ok(()),
         };
 
-        let (_, qop_entry) = self
+        let Some((_, qop_entry)) = self
             .qop_responses
-            .iter_mut()
-            .find(|(realm, _)| realm == digest_realm)
-            .expect("qop_entry must be some");
+            .iter()
+            .find(|(realm, _)| *realm == digest.realm)
+        else {
+            return Ok(());
+        };
 
-        let response = match qop_response.qop {
-            QopOption::Auth | QopOption::AuthInt => (qop_entry.hash)(
+        // Verify the server's response digest for mutual authentication (RFC2617 Section 3.2.3)
+        if let (Some(rspauth), Some(qop_response)) = (&info.rspauth, &digest.qop_response) {
+            let (qop, ha2) = match qop_response.qop {
+                QopOption::Auth => (
+                    "auth",
+                    (qop_entry.hash)(format!(":{}", digest.uri).as_bytes()),
+                ),
+                QopOption::AuthInt => (
+                    "auth-int",
+                    (qop_entry.hash)(
+                        format!(":{}:{}", digest.uri, (qop_entry.hash)(response_body)).as_bytes(),
+                    ),
+                ),
+                QopOption::Other(_) => unreachable!(),
+            };
+
+            let expected = (qop_entry.hash)(
                 format!(
-                    "{}:{}:{:08X}:{}:auth:{}",
-                    qop_entry.ha1,
-                    digest.nonce,
-                    qop_response.nc,
-                    qop_response.cnonce,
-                    qop_entry.ha2
+                    "{}:{}:{:08X}:{}:{}:{}",
+                    qop_entry.ha1, digest.nonce, qop_response.nc, qop_response.cnonce, qop, ha2
                 )
                 .as_bytes(),
-            ),
-            QopOption::Other(_) => unreachable!(),
-        };
+            );
+
+            if !expected.eq_ignore_ascii_case(rspauth) {
+                return Err(Error::InvalidRspAuth(digest.realm.clone()));
+            }
+        }
+
+        // Adopt the next nonce, so the next request doesn't get challenged again
+        if let Some(nextnonce) = &info.nextnonce {
+            digest.nonce = nextnonce.clone();
+
+            if let Some(qop_response) = &mut digest.qop_response {
+                // Incremented to 1 and re-calculated in `on_authorize_request`
+                qop_response.nc = 0;
+            } else {
+                digest.response = (qop_entry.hash)(
+                    format!("{}:{}:{}", qop_entry.ha1, digest.nonce, qop_entry.ha2).as_bytes(),
+                )
+                .into();
+            }
+        }
+
+        self.share(response);
 
-        digest.response = response.into();
+        Ok(())
     }
 }
 
 impl DigestAuthenticator {
+    /// Create an authenticator which shares its authorizations using `shared`
+    pub fn with_shared_state(shared: SharedDigestState) -> Self {
+        Self {
+            shared: Some(shared),
+            ..Self::default()
+        }
+    }
+
+    /// Persist the nonce-counts used with `store` and restore them when answering a challenge
+    /// with a known nonce, e.g. to continue a nonce session after a restart
+    pub fn set_nonce_store<S>(&mut self, store: S)
+    where
+        S: NonceStore + 'static,
+    {
+        self.nonce_store = Some(Box::new(store));
+    }
+
+    /// Publish the current state of the response to the shared state, if set
+    fn share(&self, response: &ResponseEntry) {
+        let (Some(shared), AuthResponse::Digest(digest)) = (&self.shared, &response.response)
+        else {
+            return;
+        };
+
+        if let Some((_, qop_entry)) = self
+            .qop_responses
+            .iter()
+            .find(|(realm, _)| *realm == digest.realm)
+        {
+            shared.store(digest, response.is_proxy, qop_entry);
+        }
+    }
+
     fn handle_digest_challenge(
         &mut self,
         credentials: &DigestCredentials,
@@ -167,26 +348,19 @@ impl DigestAuthenticator {
             Algorithm::AlgorithmValue(av) => av,
         };
 
-        let (hash, is_session): (HashFn, bool) = match algorithm_value {
-            AlgorithmValue::MD5 => {
-                if self.reject_md5 {
-                    return Err(Error::UnsupportedAlgorithm(BytesStr::from_static("MD5")));
-                } else {
-                    (hash_md5, false)
-                }
-            }
-            AlgorithmValue::MD5Sess => {
-                if self.reject_md5 {
-                    return Err(Error::UnsupportedAlgorithm(BytesStr::from_static("MD5")));
-                } else {
-                    (hash_md5, true)
-                }
-            }
-            AlgorithmValue::SHA256 => (hash_sha256, false),
-            AlgorithmValue::SHA256Sess => (hash_sha256, true),
-            AlgorithmValue::SHA512256 => (hash_sha512_trunc256, false),
-            AlgorithmValue::SHA512256Sess => (hash_sha512_trunc256, true),
-            AlgorithmValue::Other(other) => return Err(Error::UnsupportedAlgorithm(other)),
+        if self.reject_md5
+            && matches!(
+                algorithm_value,
+                AlgorithmValue::MD5 | AlgorithmValue::MD5Sess
+            )
+        {
+            return Err(Error::UnsupportedAlgorithm(BytesStr::from_static("MD5")));
+        }
+
+        let Some((hash, is_session)) = algorithm_hash(&algorithm_value) else {
+            return Err(Error::UnsupportedAlgorithm(
+                algorithm_value.to_string().into(),
+            ));
         };
 
         let response = self.digest_respond(digest, request_parts, credentials, is_session, hash)?;
@@ -202,7 +376,15 @@ impl DigestAuthenticator {
         is_session: bool,
         hash: HashFn,
     ) -> Result<DigestResponse, Error> {
-        let cnonce = BytesStr::from(uuid::Uuid::new_v4().simple().to_string());
+        // Continue a nonce session persisted before, servers may reject a reused nonce-count
+        let (cnonce, nc) = match self
+            .nonce_store
+            .as_ref()
+            .and_then(|store| store.restore(&challenge.realm, &challenge.nonce))
+        {
+            Some(state) => (state.cnonce, state.nc + 1),
+            None => (BytesStr::from(uuid::Uuid::new_v4().simple().to_string()), 1),
+        };
 
         let mut ha1 = hash(
             [
@@ -214,7 +396,7 @@ impl DigestAuthenticator {
         );
 
         if is_session {
-            ha1 = format!("{}:{}:{}", ha1, challenge.nonce, cnonce);
+            ha1 = hash(format!("{}:{}:{}", ha1, challenge.nonce, cnonce).as_bytes());
         }
 
         let ctx = PrintCtx {
@@ -241,8 +423,6 @@ impl DigestAuthenticator {
                     .as_bytes(),
                 );
 
-                let nc = 1;
-
                 let response = hash(
                     format!(
                         "{}:{}:{:08X}:{}:auth-int:{}",
@@ -264,8 +444,6 @@ impl DigestAuthenticator {
                 let a2 = format!("{}:{}", &request_parts.line.method, uri);
                 let ha2 = hash(a2.as_bytes());
 
-                let nc = 1;
-
                 let response = hash(
                     format!(
                         "{}:{}:{:08X}:{}:auth:{}",
@@ -288,11 +466,13 @@ impl DigestAuthenticator {
             }
         } else {
             let a2 = format!("{}:{}", &request_parts.line.method, uri);
+            let ha2 = hash(a2.as_bytes());
 
-            (
-                hash(format!("{}:{}:{}", ha1, challenge.nonce, hash(a2.as_bytes())).as_bytes()),
-                None,
-            )
+            let response = hash(format!("{}:{}:{}", ha1, challenge.nonce, ha2).as_bytes());
+
+            self.save_qop_response(&challenge.realm, ha1, ha2, hash);
+
+            (response, None)
         };
 
         let username = if challenge.userhash {
@@ -343,11 +523,156 @@ impl DigestAuthenticator {
     }
 }
 
+impl QopEntry {
+    /// Calculate the response of a request using qop
+    fn qop_response(&self, nonce: &str, qop_response: &QopResponse, ha2: &str) -> String {
+        let qop = match qop_response.qop {
+            QopOption::Auth => "auth",
+            QopOption::AuthInt => "auth-int",
+            QopOption::Other(_) => unreachable!(),
+        };
+
+        (self.hash)(
+            format!(
+                "{}:{}:{:08X}:{}:{}:{}",
+                self.ha1, nonce, qop_response.nc, qop_response.cnonce, qop, ha2
+            )
+            .as_bytes(),
+        )
+    }
+}
+
+struct SharedEntry {
+    response: DigestResponse,
+    is_proxy: bool,
+    qop_entry: QopEntry,
+}
+
+/// Digest authorizations shared between multiple [`UacAuthSession`](crate::UacAuthSession)s
+///
+/// Every [`DigestAuthenticator`] created with [`DigestAuthenticator::with_shared_state`] publishes
+/// the nonces it authorized to this state. They can be used to authorize other requests
+/// (e.g. a BYE or re-INVITE of the same account) before they get challenged. Nonce-counts
+/// are shared as well, to keep them increasing across all requests using the same nonce.
+#[derive(Default, Clone)]
+pub struct SharedDigestState {
+    entries: Arc<Mutex<Vec<SharedEntry>>>,
+}
+
+impl SharedDigestState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add authorization headers for all known realms to the `headers` of a request
+    ///
+    /// Returns `false` if no realm has been authorized yet.
+    pub fn authorize_request(
+        &self,
+        line: &RequestLine,
+        body: &[u8],
+        headers: &mut Headers,
+    ) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+
+        let ctx = PrintCtx {
+            method: Some(&line.method),
+            uri: Some(UriContext::ReqUri),
+        };
+
+        let uri = line.uri.print_ctx(ctx).to_string();
+
+        for entry in entries.iter_mut() {
+            let hash = entry.qop_entry.hash;
+
+            let mut response = entry.response.clone();
+            response.uri = uri.as_str().into();
+
+            response.response = if let Some(qop_response) = &mut entry.response.qop_response {
+                let ha2 = match qop_response.qop {
+                    QopOption::AuthInt => {
+                        hash(format!("{}:{}:{}", line.method, uri, hash(body)).as_bytes())
+                    }
+                    _ => hash(format!("{}:{}", line.method, uri).as_bytes()),
+                };
+
+                qop_response.nc += 1;
+                response.qop_response = Some(qop_response.clone());
+
+                entry
+                    .qop_entry
+                    .qop_response(&response.nonce, qop_response, &ha2)
+                    .into()
+            } else {
+                let ha2 = hash(format!("{}:{}", line.method, uri).as_bytes());
+
+                hash(format!("{}:{}:{}", entry.qop_entry.ha1, response.nonce, ha2).as_bytes())
+                    .into()
+            };
+
+            let name = if entry.is_proxy {
+                Name::PROXY_AUTHORIZATION
+            } else {
+                Name::AUTHORIZATION
+            };
+
+            headers.insert_type(name, &AuthResponse::Digest(response));
+        }
+
+        !entries.is_empty()
+    }
+
+    /// Forget the authorization for `realm`, e.g. after the credentials were changed
+    pub fn remove_realm(&self, realm: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|entry| entry.response.realm != realm);
+    }
+
+    /// Returns the nonce-count to use, which must be greater than any
+    /// nonce-count used with the same nonce by another session
+    fn next_nc(&self, realm: &str, nonce: &str, nc: u32) -> u32 {
+        let entries = self.entries.lock().unwrap();
+
+        let shared_nc = entries
+            .iter()
+            .find(|entry| entry.response.realm == realm && entry.response.nonce == nonce)
+            .and_then(|entry| entry.response.qop_response.as_ref())
+            .map(|qop_response| qop_response.nc);
+
+        match shared_nc {
+            Some(shared_nc) => nc.max(shared_nc + 1),
+            None => nc,
+        }
+    }
+
+    fn store(&self, response: &DigestResponse, is_proxy: bool, qop_entry: &QopEntry) {
+        let mut entries = self.entries.lock().unwrap();
+
+        let entry = SharedEntry {
+            response: response.clone(),
+            is_proxy,
+            qop_entry: qop_entry.clone(),
+        };
+
+        if let Some(old_entry) = entries
+            .iter_mut()
+            .find(|old_entry| old_entry.response.realm == response.realm)
+        {
+            *old_entry = entry;
+        } else {
+            entries.push(entry);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::CredentialStore;
     use crate::UacAuthSession;
+    use crate::{UasAuthenticator, Verification};
     use sip_types::msg::RequestLine;
     use sip_types::uri::sip::SipUri;
     use sip_types::Headers;
@@ -442,6 +767,181 @@ mod test {
         }
     }
 
+    #[test]
+    fn digest_challenge_prefers_strongest_algorithm() {
+        let credentials = test_credentials();
+
+        let mut headers = Headers::new();
+
+        for algorithm in [AlgorithmValue::MD5, AlgorithmValue::SHA256] {
+            headers.insert_type(
+                Name::WWW_AUTHENTICATE,
+                &AuthChallenge::Digest(DigestChallenge {
+                    realm: "example.org".into(),
+                    domain: None,
+                    nonce: "YWmh5GFpoLjiTDCA1hTSSygkgdj99aHE".into(),
+                    opaque: None,
+                    stale: false,
+                    algorithm: Algorithm::AlgorithmValue(algorithm),
+                    qop: vec![],
+                    userhash: false,
+                    other: vec![],
+                }),
+            );
+        }
+
+        let uri: SipUri = "sip:example.org".parse().unwrap();
+
+        let line = RequestLine {
+            method: Method::REGISTER,
+            uri: Box::new(uri),
+        };
+
+        let mut session = UacAuthSession::<DigestAuthenticator>::default();
+
+        session
+            .handle_authenticate(
+                &headers,
+                &credentials,
+                RequestParts {
+                    line: &line,
+                    headers: &Headers::new(),
+                    body: &[],
+                },
+            )
+            .unwrap();
+
+        let mut response_headers = Headers::new();
+        session.authorize_request(&mut response_headers);
+
+        let authorization: Vec<AuthResponse> = response_headers.get(Name::AUTHORIZATION).unwrap();
+        assert_eq!(authorization.len(), 1);
+
+        match &authorization[0] {
+            AuthResponse::Digest(digest) => {
+                assert_eq!(
+                    digest.algorithm,
+                    Algorithm::AlgorithmValue(AlgorithmValue::SHA256)
+                );
+                assert_eq!(
+                    digest.response,
+                    "7e0884590ebfc039aec04beb098400da5774c810547fd46c05a73e397f3c82d1"
+                );
+            }
+            _ => panic!("Expected digest"),
+        }
+    }
+
+    #[test]
+    fn authentication_info() {
+        let credentials = test_credentials();
+
+        let mut headers = Headers::new();
+
+        headers.insert_type(
+            Name::WWW_AUTHENTICATE,
+            &AuthChallenge::Digest(DigestChallenge {
+                realm: "example.org".into(),
+                domain: None,
+                nonce: "YWmh5GFpoLjiTDCA1hTSSygkgdj99aHE".into(),
+                opaque: None,
+                stale: false,
+                algorithm: Algorithm::AlgorithmValue(AlgorithmValue::MD5),
+                qop: vec![QopOption::Auth],
+                userhash: false,
+                other: vec![],
+            }),
+        );
+
+        let uri: SipUri = "sip:example.org".parse().unwrap();
+
+        let line = RequestLine {
+            method: Method::REGISTER,
+            uri: Box::new(uri),
+        };
+
+        let mut session = UacAuthSession::<DigestAuthenticator>::default();
+
+        session
+            .handle_authenticate(
+                &headers,
+                &credentials,
+                RequestParts {
+                    line: &line,
+                    headers: &Headers::new(),
+                    body: &[],
+                },
+            )
+            .unwrap();
+
+        let mut request_headers = Headers::new();
+        session.authorize_request(&mut request_headers);
+
+        let AuthResponse::Digest(digest) = request_headers
+            .get::<AuthResponse>(Name::AUTHORIZATION)
+            .unwrap()
+        else {
+            panic!("Expected digest")
+        };
+
+        let qop_response = digest.qop_response.unwrap();
+
+        let ha1 = hash_md5(b"user123:example.org:password123");
+        let ha2 = hash_md5(b":sip:example.org");
+        let rspauth = hash_md5(
+            format!(
+                "{}:{}:{:08X}:{}:auth:{}",
+                ha1, digest.nonce, qop_response.nc, qop_response.cnonce, ha2
+            )
+            .as_bytes(),
+        );
+
+        let mut response_headers = Headers::new();
+        response_headers.insert_type(
+            Name::AUTHENTICATION_INFO,
+            &AuthenticationInfo {
+                nextnonce: Some("47364c23432d2e131a5fb210812c".into()),
+                qop: Some(QopOption::Auth),
+                rspauth: Some(rspauth.into()),
+                cnonce: Some(qop_response.cnonce.clone()),
+                nc: Some(qop_response.nc),
+                other: vec![],
+            },
+        );
+
+        session
+            .handle_authentication_info(&response_headers, &[])
+            .unwrap();
+
+        let mut request_headers = Headers::new();
+        session.authorize_request(&mut request_headers);
+
+        let AuthResponse::Digest(digest) = request_headers
+            .get::<AuthResponse>(Name::AUTHORIZATION)
+            .unwrap()
+        else {
+            panic!("Expected digest")
+        };
+
+        assert_eq!(digest.nonce, "47364c23432d2e131a5fb210812c");
+        assert_eq!(digest.qop_response.unwrap().nc, 1);
+
+        // Forged server response
+        let mut response_headers = Headers::new();
+        response_headers.insert_type(
+            Name::AUTHENTICATION_INFO,
+            &AuthenticationInfo {
+                rspauth: Some("6629fae49393a05397450978507c4ef1".into()),
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(
+            session.handle_authentication_info(&response_headers, &[]),
+            Err(Error::InvalidRspAuth(_))
+        ));
+    }
+
     #[test]
     fn digest_challenge_and_response() {
         let credentials = test_credentials();
@@ -557,4 +1057,172 @@ mod test {
             _ => panic!("Expected digest"),
         }
     }
+
+    #[test]
+    fn shared_state_pre_authorizes() {
+        let authenticator = UasAuthenticator::new("example.org", |_: &str, _: &str| {
+            Some(DigestCredentials::new("user123", "password123"))
+        });
+
+        let mut challenge_headers = Headers::new();
+        authenticator.challenge(&mut challenge_headers, true, false);
+
+        let uri: SipUri = "sip:example.org".parse().unwrap();
+        let invite = RequestLine {
+            method: Method::INVITE,
+            uri: Box::new(uri),
+        };
+
+        let shared = SharedDigestState::new();
+        let mut session =
+            UacAuthSession::new(DigestAuthenticator::with_shared_state(shared.clone()));
+
+        session
+            .handle_authenticate(
+                &challenge_headers,
+                &test_credentials(),
+                RequestParts {
+                    line: &invite,
+                    headers: &Headers::new(),
+                    body: &[],
+                },
+            )
+            .unwrap();
+
+        let mut invite_headers = Headers::new();
+        session.authorize_request(&mut invite_headers);
+
+        // Authorize a BYE without a challenge
+        let uri: SipUri = "sip:bob@example.org".parse().unwrap();
+        let bye = RequestLine {
+            method: Method::BYE,
+            uri: Box::new(uri),
+        };
+
+        let mut bye_headers = Headers::new();
+        assert!(shared.authorize_request(&bye, &[], &mut bye_headers));
+
+        let verification = authenticator.verify(
+            RequestParts {
+                line: &bye,
+                headers: &bye_headers,
+                body: &[],
+            },
+            true,
+        );
+
+        assert_eq!(verification, Verification::Authenticated("user123".into()));
+
+        // The session continues with the shared nonce-count
+        let mut invite_headers = Headers::new();
+        session.authorize_request(&mut invite_headers);
+
+        let AuthResponse::Digest(digest) = invite_headers
+            .get::<AuthResponse>(Name::PROXY_AUTHORIZATION)
+            .unwrap()
+        else {
+            panic!("Expected digest")
+        };
+
+        assert_eq!(digest.qop_response.unwrap().nc, 3);
+    }
+
+    #[derive(Default)]
+    struct MemoryNonceStore(Mutex<Vec<NonceState>>);
+
+    impl NonceStore for MemoryNonceStore {
+        fn store(&self, state: &NonceState) {
+            let mut states = self.0.lock().unwrap();
+            states.retain(|s| s.realm != state.realm);
+            states.push(state.clone());
+        }
+
+        fn restore(&self, realm: &str, nonce: &str) -> Option<NonceState> {
+            let states = self.0.lock().unwrap();
+            states
+                .iter()
+                .find(|s| s.realm == realm && s.nonce == nonce)
+                .cloned()
+        }
+    }
+
+    #[test]
+    fn nonce_store_restores_nc() {
+        let mut headers = Headers::new();
+
+        headers.insert_type(
+            Name::WWW_AUTHENTICATE,
+            &AuthChallenge::Digest(DigestChallenge {
+                realm: "example.org".into(),
+                domain: None,
+                nonce: "YWmh5GFpoLjiTDCA1hTSSygkgdj99aHE".into(),
+                opaque: None,
+                stale: false,
+                algorithm: Algorithm::AlgorithmValue(AlgorithmValue::MD5),
+                qop: vec![QopOption::Auth],
+                userhash: false,
+                other: vec![],
+            }),
+        );
+
+        let uri: SipUri = "sip:example.org".parse().unwrap();
+
+        let line = RequestLine {
+            method: Method::REGISTER,
+            uri: Box::new(uri),
+        };
+
+        let store = Arc::new(MemoryNonceStore::default());
+
+        let authorize = |use_count| {
+            let mut authenticator = DigestAuthenticator::default();
+            authenticator.set_nonce_store(store.clone());
+
+            let mut session = UacAuthSession::new(authenticator);
+
+            session
+                .handle_authenticate(
+                    &headers,
+                    &test_credentials(),
+                    RequestParts {
+                        line: &line,
+                        headers: &Headers::new(),
+                        body: &[],
+                    },
+                )
+                .unwrap();
+
+            let mut request_headers = Headers::new();
+            for _ in 0..use_count {
+                request_headers = Headers::new();
+                session.authorize_request(&mut request_headers);
+            }
+
+            let AuthResponse::Digest(digest) = request_headers
+                .get::<AuthResponse>(Name::AUTHORIZATION)
+                .unwrap()
+            else {
+                panic!("Expected digest")
+            };
+
+            digest.qop_response.unwrap()
+        };
+
+        let first = authorize(2);
+        assert_eq!(first.nc, 2);
+
+        // A new session (e.g. after a restart) continues with the persisted state
+        let restored = authorize(1);
+        assert_eq!(restored.nc, 3);
+        assert_eq!(restored.cnonce, first.cnonce);
+        assert_eq!(
+            store.restore("example.org", "YWmh5GFpoLjiTDCA1hTSSygkgdj99aHE"),
+            Some(NonceState {
+                realm: "example.org".into(),
+                nonce: "YWmh5GFpoLjiTDCA1hTSSygkgdj99aHE".into(),
+                cnonce: first.cnonce,
+                nc: 3,
+            })
+        );
+    }
 }
@@ -7,6 +7,8 @@ pub enum Error {
     Header(HeaderError),
     #[error("response contains no challenges")]
     NoAuthHeaders,
+    #[error("challenge is missing the realm parameter")]
+    MissingRealm,
     #[error("unknown challenge scheme: {0}")]
     UnknownScheme(BytesStr),
     #[error("failed to authenticate realms: {0}")]
@@ -15,4 +17,6 @@ pub enum Error {
     UnsupportedQop,
     #[error("unsupported algorithm: {0}")]
     UnsupportedAlgorithm(BytesStr),
+    #[error("invalid rspauth in authentication info for realm: {0}")]
+    InvalidRspAuth(BytesStr),
 }
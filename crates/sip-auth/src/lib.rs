@@ -1,14 +1,28 @@
 use bytesstr::BytesStr;
 use digest::{DigestAuthenticator, DigestCredentials};
-use sip_types::header::typed::{AuthChallenge, AuthResponse};
+use sip_types::header::typed::{AuthChallenge, AuthResponse, AuthenticationInfo};
 use sip_types::msg::RequestLine;
 use sip_types::{Headers, Name};
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 
+pub mod bearer;
 pub mod digest;
 mod error;
+mod provider;
+mod uas;
+
+#[cfg(test)]
+mod test_util;
 
 pub use error::Error;
+pub use provider::{CachedCredentials, CredentialProvider};
+pub use uas::{CredentialLookup, UasAuthenticator, Verification};
+
+/// Boxed future returned by asynchronous callbacks and trait methods
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
 /// Information about the request that has to be authenticated
 #[derive(Debug, Clone, Copy)]
@@ -80,6 +94,28 @@ pub trait UacAuthenticator: Default + Send + Sync {
     /// Each scheme is required to provide a realm of some sort.
     fn get_realm<'s>(&mut self, auth: &'s AuthChallenge) -> Result<&'s BytesStr, Error>;
 
+    /// Rank a challenge when multiple challenges are offered for the same realm.
+    ///
+    /// Challenges with a higher preference are tried first, challenges with the same
+    /// preference keep the order they were received in.
+    fn challenge_preference(&self, challenge: &AuthChallenge) -> u32 {
+        let _ = challenge;
+        0
+    }
+
+    /// Asynchronously prepare to handle the `challenge` (e.g. acquire a new token).
+    ///
+    /// Called before [`UacAuthenticator::handle_challenge`] by [`UacAuthSession::handle_authenticate_async`]
+    /// and [`UacAuthSession::handle_authenticate_with_provider`].
+    fn prepare_challenge<'a>(
+        &'a mut self,
+        responses: &'a [ResponseEntry],
+        challenge: &'a AuthChallenge,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let _ = (responses, challenge);
+        Box::pin(async { Ok(()) })
+    }
+
     /// Handle the [`AuthChallenge`] and provide the [`AuthResponse`]
     fn handle_challenge(
         &mut self,
@@ -91,12 +127,26 @@ pub trait UacAuthenticator: Default + Send + Sync {
 
     /// Gets called when a header gets used/reused for a request.
     fn on_authorize_request(&mut self, response: &mut ResponseEntry);
+
+    /// Handle the Authentication-Info received in a response to a request authorized with `response`
+    /// (e.g. verify the server's credentials or update the nonce to use)
+    fn handle_authentication_info(
+        &mut self,
+        response: &mut ResponseEntry,
+        info: &AuthenticationInfo,
+        response_body: &[u8],
+    ) -> Result<(), Error> {
+        let _ = (response, info, response_body);
+        Ok(())
+    }
 }
 
 /// Contains a list of authentication challenges that want to authenticate the same realm.
 ///
 /// As each realm may only be authenticated once per request, only the topmost supported challenge will
 /// be used for authentication. (See RFC8760 Section 2.4)
+///
+/// Challenges are ordered by [`UacAuthenticator::challenge_preference`] before being used.
 struct ChallengedRealm {
     realm: BytesStr,
     challenges: Vec<(bool, AuthChallenge)>,
@@ -148,6 +198,12 @@ impl<A: UacAuthenticator> UacAuthSession<A> {
         self.read_challenges(false, headers, &mut challenged_realms)?;
         self.read_challenges(true, headers, &mut challenged_realms)?;
 
+        for challenged_realm in &mut challenged_realms {
+            challenged_realm.challenges.sort_by_key(|(_, challenge)| {
+                Reverse(self.authenticator.challenge_preference(challenge))
+            });
+        }
+
         let mut failed_realms = vec![];
 
         'outer: for challenged_realm in challenged_realms {
@@ -218,6 +274,53 @@ impl<A: UacAuthenticator> UacAuthSession<A> {
         Ok(())
     }
 
+    /// Same as [`Self::handle_authenticate`], but calls [`UacAuthenticator::prepare_challenge`]
+    /// for every challenge first.
+    pub async fn handle_authenticate_async(
+        &mut self,
+        headers: &Headers,
+        credential_store: &CredentialStore<A::Credentials>,
+        request_parts: RequestParts<'_>,
+    ) -> Result<(), Error> {
+        let mut challenged_realms = vec![];
+
+        self.read_challenges(false, headers, &mut challenged_realms)?;
+        self.read_challenges(true, headers, &mut challenged_realms)?;
+
+        self.prepare_challenges(&challenged_realms).await?;
+
+        self.handle_authenticate(headers, credential_store, request_parts)
+    }
+
+    /// Same as [`Self::handle_authenticate_async`], but fetches the credentials of the challenged
+    /// realms from the `provider`.
+    pub async fn handle_authenticate_with_provider<P>(
+        &mut self,
+        headers: &Headers,
+        provider: &P,
+        request_parts: RequestParts<'_>,
+    ) -> Result<(), Error>
+    where
+        P: CredentialProvider<Credentials = A::Credentials>,
+    {
+        let mut challenged_realms = vec![];
+
+        self.read_challenges(false, headers, &mut challenged_realms)?;
+        self.read_challenges(true, headers, &mut challenged_realms)?;
+
+        self.prepare_challenges(&challenged_realms).await?;
+
+        let mut credential_store = CredentialStore::new();
+
+        for challenged_realm in challenged_realms {
+            if let Some(credentials) = provider.fetch(&challenged_realm.realm, None).await {
+                credential_store.add_for_realm(challenged_realm.realm.as_str(), credentials);
+            }
+        }
+
+        self.handle_authenticate(headers, &credential_store, request_parts)
+    }
+
     /// Apply the generated authentication headers to the provided `headers`
     pub fn authorize_request(&mut self, headers: &mut Headers) {
         for entry in &mut self.responses {
@@ -235,6 +338,66 @@ impl<A: UacAuthenticator> UacAuthSession<A> {
         }
     }
 
+    /// Handle the Authentication-Info and Proxy-Authentication-Info headers of a successful response.
+    ///
+    /// Verifies the server's `rspauth` (mutual authentication) and adopts the `nextnonce`,
+    /// avoiding a new challenge on the next request. As the headers don't contain a realm, they
+    /// are ignored if more than one realm has been authorized using the same header kind.
+    pub fn handle_authentication_info(
+        &mut self,
+        headers: &Headers,
+        response_body: &[u8],
+    ) -> Result<(), Error> {
+        for is_proxy in [false, true] {
+            let name = if is_proxy {
+                Name::PROXY_AUTHENTICATION_INFO
+            } else {
+                Name::AUTHENTICATION_INFO
+            };
+
+            let Some(info) = headers
+                .try_get::<AuthenticationInfo>(name)
+                .transpose()
+                .map_err(Error::Header)?
+            else {
+                continue;
+            };
+
+            let mut entries = self
+                .responses
+                .iter_mut()
+                .filter(|entry| entry.is_proxy == is_proxy);
+
+            match (entries.next(), entries.next()) {
+                (Some(entry), None) => {
+                    self.authenticator
+                        .handle_authentication_info(entry, &info, response_body)?;
+                }
+                (Some(_), Some(_)) => {
+                    log::debug!("ignoring authentication info, multiple realms are authorized");
+                }
+                (None, _) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn prepare_challenges(
+        &mut self,
+        challenged_realms: &[ChallengedRealm],
+    ) -> Result<(), Error> {
+        for challenged_realm in challenged_realms {
+            for (_, challenge) in &challenged_realm.challenges {
+                self.authenticator
+                    .prepare_challenge(&self.responses, challenge)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Read all authentication headers and group them by realm
     fn read_challenges(
         &mut self,
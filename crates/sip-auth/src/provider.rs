@@ -0,0 +1,174 @@
+use crate::CredentialStore;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Asynchronous source of credentials (e.g. a database or secret vault)
+///
+/// Can be used by [`UacAuthSession::handle_authenticate_with_provider`](crate::UacAuthSession::handle_authenticate_with_provider)
+/// and [`UasAuthenticator::verify_with_provider`](crate::UasAuthenticator::verify_with_provider).
+/// Wrap it in a [`CachedCredentials`] to avoid fetching the same credentials for every request.
+#[async_trait::async_trait]
+pub trait CredentialProvider: Send + Sync {
+    type Credentials: Clone + Send + Sync;
+
+    /// Fetch the credentials for `realm`.
+    ///
+    /// `username` is only set when verifying a request as UAS, to look up the
+    /// credentials of the user trying to authenticate.
+    async fn fetch(&self, realm: &str, username: Option<&str>) -> Option<Self::Credentials>;
+}
+
+#[async_trait::async_trait]
+impl<C> CredentialProvider for CredentialStore<C>
+where
+    C: Clone + Send + Sync,
+{
+    type Credentials = C;
+
+    async fn fetch(&self, realm: &str, _username: Option<&str>) -> Option<C> {
+        self.get_for_realm(realm).cloned()
+    }
+}
+
+type CacheMap<C> = HashMap<(String, Option<String>), (Instant, C)>;
+
+/// Caches credentials fetched from a [`CredentialProvider`] until they expire
+///
+/// Only found credentials are cached, unknown realms or users are fetched again on every request.
+/// Expired entries are removed whenever new credentials are inserted into the cache.
+pub struct CachedCredentials<P: CredentialProvider> {
+    provider: P,
+    ttl: Duration,
+    cache: Mutex<CacheMap<P::Credentials>>,
+}
+
+impl<P: CredentialProvider> CachedCredentials<P> {
+    /// Cache credentials of `provider` for the duration of `ttl`
+    pub fn new(provider: P, ttl: Duration) -> Self {
+        Self {
+            provider,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remove all cached credentials for the `realm`, e.g. after they were rejected
+    pub fn invalidate(&self, realm: &str) {
+        self.cache
+            .lock()
+            .unwrap()
+            .retain(|(cached_realm, _), _| cached_realm != realm);
+    }
+
+    /// Remove all cached credentials
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn get_cached(&self, key: &(String, Option<String>)) -> Option<P::Credentials> {
+        let mut cache = self.cache.lock().unwrap();
+
+        let (fetched_at, credentials) = cache.get(key)?;
+
+        if fetched_at.elapsed() < self.ttl {
+            Some(credentials.clone())
+        } else {
+            cache.remove(key);
+            None
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<P: CredentialProvider> CredentialProvider for CachedCredentials<P> {
+    type Credentials = P::Credentials;
+
+    async fn fetch(&self, realm: &str, username: Option<&str>) -> Option<P::Credentials> {
+        let key = (realm.to_owned(), username.map(ToOwned::to_owned));
+
+        if let Some(credentials) = self.get_cached(&key) {
+            return Some(credentials);
+        }
+
+        let credentials = self.provider.fetch(realm, username).await?;
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < self.ttl);
+        cache.insert(key, (Instant::now(), credentials.clone()));
+
+        Some(credentials)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::block_on;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingProvider {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl CredentialProvider for CountingProvider {
+        type Credentials = String;
+
+        async fn fetch(&self, realm: &str, username: Option<&str>) -> Option<String> {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+
+            (realm == "example.org").then(|| format!("{}@{realm}", username.unwrap_or("default")))
+        }
+    }
+
+    #[test]
+    fn cached_credentials() {
+        let cached = CachedCredentials::new(CountingProvider::default(), Duration::from_secs(60));
+
+        assert_eq!(
+            block_on(cached.fetch("example.org", Some("alice"))).unwrap(),
+            "alice@example.org"
+        );
+        assert_eq!(
+            block_on(cached.fetch("example.org", Some("alice"))).unwrap(),
+            "alice@example.org"
+        );
+        assert_eq!(
+            block_on(cached.fetch("example.org", None)).unwrap(),
+            "default@example.org"
+        );
+        assert_eq!(cached.provider.fetches.load(Ordering::Relaxed), 2);
+
+        // Misses are not cached
+        assert!(block_on(cached.fetch("example.com", None)).is_none());
+        assert!(block_on(cached.fetch("example.com", None)).is_none());
+        assert_eq!(cached.provider.fetches.load(Ordering::Relaxed), 4);
+
+        cached.invalidate("example.org");
+        block_on(cached.fetch("example.org", Some("alice"))).unwrap();
+        assert_eq!(cached.provider.fetches.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn cached_credentials_expire() {
+        let cached = CachedCredentials::new(CountingProvider::default(), Duration::ZERO);
+
+        block_on(cached.fetch("example.org", None)).unwrap();
+        block_on(cached.fetch("example.org", None)).unwrap();
+        assert_eq!(cached.provider.fetches.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn expired_credentials_are_pruned() {
+        let cached = CachedCredentials::new(CountingProvider::default(), Duration::ZERO);
+
+        for user in ["alice", "bob", "carol"] {
+            block_on(cached.fetch("example.org", Some(user))).unwrap();
+        }
+
+        // Only the last inserted entry remains, the others expired before it was inserted
+        assert_eq!(cached.cache.lock().unwrap().len(), 1);
+    }
+}
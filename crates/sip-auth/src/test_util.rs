@@ -0,0 +1,19 @@
+//! Utilities shared by the tests of this crate
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+/// Poll `future` to completion on the current thread
+///
+/// Only suited for futures which don't depend on a runtime, e.g. credential providers returning immediately.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
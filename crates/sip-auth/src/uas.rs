@@ -0,0 +1,626 @@
+use crate::digest::{algorithm_hash, DigestCredentials, HashFn};
+use crate::{CredentialProvider, RequestParts};
+use bytesstr::BytesStr;
+use sha2::Digest;
+use sip_types::header::typed::{
+    Algorithm, AlgorithmValue, AuthChallenge, AuthResponse, DigestChallenge, DigestResponse,
+    QopOption, Username,
+};
+use sip_types::print::{AppendCtx, PrintCtx, UriContext};
+use sip_types::uri::sip::SipUri;
+use sip_types::{Headers, Name};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Used by [`UasAuthenticator`] to look up the credentials of the user trying to authenticate
+///
+/// Implemented for closures taking the realm and username.
+pub trait CredentialLookup: Send + Sync {
+    fn lookup(&self, realm: &str, username: &str) -> Option<DigestCredentials>;
+}
+
+impl<F> CredentialLookup for F
+where
+    F: Fn(&str, &str) -> Option<DigestCredentials> + Send + Sync,
+{
+    fn lookup(&self, realm: &str, username: &str) -> Option<DigestCredentials> {
+        self(realm, username)
+    }
+}
+
+/// Result of [`UasAuthenticator::verify`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// The request contains a valid authorization for the user
+    Authenticated(BytesStr),
+
+    /// The request contains no authorization for the realm, it must be challenged
+    Missing,
+
+    /// The credentials were correct, but the nonce expired. The request must be
+    /// challenged with `stale=true`, so the client can retry without asking for credentials.
+    Stale,
+
+    /// The authorization is invalid (e.g. wrong password, unknown user, a forged nonce
+    /// or a replayed nonce-count)
+    Invalid,
+}
+
+/// The UAS (User Agent Server) side of digest authentication
+///
+/// Creates challenges for WWW-Authenticate or Proxy-Authenticate headers and verifies the
+/// Authorization or Proxy-Authorization headers of requests using a [`CredentialLookup`].
+///
+/// Nonces contain their creation time and are signed using a random secret. The highest nonce-count
+/// seen is remembered for each nonce until it becomes stale, so authorizations cannot be replayed.
+/// Responses without qop carry no nonce-count, their nonce can only be used once.
+pub struct UasAuthenticator<L> {
+    realm: BytesStr,
+    lookup: L,
+    secret: String,
+    opaque: BytesStr,
+
+    /// Highest nonce-count used with each nonce, together with the nonce's creation time
+    nonce_counts: Mutex<HashMap<BytesStr, (SystemTime, u32)>>,
+
+    /// Algorithms to challenge with, one challenge is created for each algorithm.
+    /// Defaults to SHA-256 and MD5.
+    pub algorithms: Vec<AlgorithmValue>,
+
+    /// Qop options offered in the challenges. Defaults to `auth`
+    pub qop: Vec<QopOption>,
+
+    /// Duration after which a nonce is considered stale. Defaults to 5 minutes
+    pub nonce_lifetime: Duration,
+}
+
+impl<L: CredentialLookup> UasAuthenticator<L> {
+    pub fn new<R>(realm: R, lookup: L) -> Self
+    where
+        R: Into<BytesStr>,
+    {
+        Self {
+            realm: realm.into(),
+            lookup,
+            secret: format!(
+                "{}{}",
+                uuid::Uuid::new_v4().simple(),
+                uuid::Uuid::new_v4().simple()
+            ),
+            opaque: uuid::Uuid::new_v4().simple().to_string().into(),
+            nonce_counts: Mutex::new(HashMap::new()),
+            algorithms: vec![AlgorithmValue::SHA256, AlgorithmValue::MD5],
+            qop: vec![QopOption::Auth],
+            nonce_lifetime: Duration::from_secs(300),
+        }
+    }
+
+    pub fn realm(&self) -> &BytesStr {
+        &self.realm
+    }
+
+    /// Create challenges with a new nonce, one for each configured algorithm
+    pub fn create_challenges(&self, stale: bool) -> Vec<AuthChallenge> {
+        let nonce = self.create_nonce(SystemTime::now());
+
+        self.algorithms
+            .iter()
+            .map(|algorithm| {
+                AuthChallenge::Digest(DigestChallenge {
+                    realm: self.realm.clone(),
+                    domain: None,
+                    nonce: nonce.clone(),
+                    opaque: Some(self.opaque.clone()),
+                    stale,
+                    algorithm: Algorithm::AlgorithmValue(algorithm.clone()),
+                    qop: self.qop.clone(),
+                    userhash: false,
+                    other: vec![],
+                })
+            })
+            .collect()
+    }
+
+    /// Add challenges to the headers of a 401 (`is_proxy = false`) or 407 (`is_proxy = true`) response
+    pub fn challenge(&self, headers: &mut Headers, is_proxy: bool, stale: bool) {
+        let name = if is_proxy {
+            Name::PROXY_AUTHENTICATE
+        } else {
+            Name::WWW_AUTHENTICATE
+        };
+
+        for challenge in self.create_challenges(stale) {
+            headers.insert_type(name.clone(), &challenge);
+        }
+    }
+
+    /// Verify the Authorization (`is_proxy = false`) or Proxy-Authorization (`is_proxy = true`)
+    /// headers of a request
+    pub fn verify(&self, request_parts: RequestParts<'_>, is_proxy: bool) -> Verification {
+        let (response, username) = match self.find_response(request_parts, is_proxy) {
+            Ok(found) => found,
+            Err(verification) => return verification,
+        };
+
+        let credentials = self.lookup.lookup(&self.realm, &username);
+
+        self.verify_digest(request_parts, response, username, credentials)
+    }
+
+    /// Same as [`Self::verify`], but fetches the user's credentials from the `provider`
+    /// instead of using the authenticator's [`CredentialLookup`].
+    pub async fn verify_with_provider<P>(
+        &self,
+        request_parts: RequestParts<'_>,
+        is_proxy: bool,
+        provider: &P,
+    ) -> Verification
+    where
+        P: CredentialProvider<Credentials = DigestCredentials>,
+    {
+        let (response, username) = match self.find_response(request_parts, is_proxy) {
+            Ok(found) => found,
+            Err(verification) => return verification,
+        };
+
+        let credentials = provider.fetch(&self.realm, Some(&username)).await;
+
+        self.verify_digest(request_parts, response, username, credentials)
+    }
+
+    /// Find the digest response for the realm and return it with the username it was created for
+    fn find_response(
+        &self,
+        request_parts: RequestParts<'_>,
+        is_proxy: bool,
+    ) -> Result<(DigestResponse, BytesStr), Verification> {
+        let name = if is_proxy {
+            Name::PROXY_AUTHORIZATION
+        } else {
+            Name::AUTHORIZATION
+        };
+
+        let Some(Ok(responses)) = request_parts.headers.try_get::<Vec<AuthResponse>>(name) else {
+            return Err(Verification::Missing);
+        };
+
+        let response = responses
+            .into_iter()
+            .find_map(|response| match response {
+                AuthResponse::Digest(digest) if digest.realm == self.realm => Some(digest),
+                _ => None,
+            })
+            .ok_or(Verification::Missing)?;
+
+        if response.opaque.as_ref() != Some(&self.opaque) || response.userhash {
+            return Err(Verification::Invalid);
+        }
+
+        let Username::Username(username) = &response.username else {
+            return Err(Verification::Invalid);
+        };
+
+        let username = username.clone();
+
+        Ok((response, username))
+    }
+
+    fn verify_digest(
+        &self,
+        request_parts: RequestParts<'_>,
+        response: DigestResponse,
+        username: BytesStr,
+        credentials: Option<DigestCredentials>,
+    ) -> Verification {
+        let algorithm = match &response.algorithm {
+            Algorithm::AlgorithmValue(algorithm) => algorithm,
+            Algorithm::AkaNamespace(_) => return Verification::Invalid,
+        };
+
+        if !self.algorithms.contains(algorithm) {
+            return Verification::Invalid;
+        }
+
+        let Some((hash, is_session)) = algorithm_hash(algorithm) else {
+            return Verification::Invalid;
+        };
+
+        let Some(credentials) = credentials else {
+            return Verification::Invalid;
+        };
+
+        if !digest_uri_matches(&response.uri, request_parts) {
+            return Verification::Invalid;
+        }
+
+        let Some(expected) =
+            expected_response(hash, is_session, &credentials, &response, request_parts)
+        else {
+            return Verification::Invalid;
+        };
+
+        if !constant_time_eq(
+            expected.as_bytes(),
+            response.response.to_ascii_lowercase().as_bytes(),
+        ) {
+            return Verification::Invalid;
+        }
+
+        let Some(created) = self.nonce_created(&response.nonce) else {
+            return Verification::Invalid;
+        };
+
+        if SystemTime::now()
+            .duration_since(created)
+            .unwrap_or_default()
+            > self.nonce_lifetime
+        {
+            return Verification::Stale;
+        }
+
+        // Without qop there is no nonce-count, treat it as the first and only use of the nonce
+        let nc = response.qop_response.as_ref().map_or(1, |qop| qop.nc);
+
+        if !self.use_nonce_count(&response.nonce, created, nc) {
+            return Verification::Invalid;
+        }
+
+        Verification::Authenticated(username)
+    }
+
+    /// Record the nonce-count used with a nonce, returns false if it was not greater than the
+    /// previously used one. Also removes all stale nonces.
+    fn use_nonce_count(&self, nonce: &BytesStr, created: SystemTime, nc: u32) -> bool {
+        let mut nonce_counts = self.nonce_counts.lock().unwrap();
+
+        let now = SystemTime::now();
+        nonce_counts.retain(|_, (created, _)| {
+            now.duration_since(*created).unwrap_or_default() <= self.nonce_lifetime
+        });
+
+        let (_, last_nc) = nonce_counts.entry(nonce.clone()).or_insert((created, 0));
+
+        if nc <= *last_nc {
+            return false;
+        }
+
+        *last_nc = nc;
+        true
+    }
+
+    fn create_nonce(&self, time: SystemTime) -> BytesStr {
+        let timestamp = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        format!("{timestamp:016x}{}", self.sign_timestamp(timestamp)).into()
+    }
+
+    /// Returns the creation time of the nonce, or `None` if it wasn't created by this authenticator
+    fn nonce_created(&self, nonce: &str) -> Option<SystemTime> {
+        let timestamp = nonce.get(..16)?;
+        let signature = nonce.get(16..)?;
+
+        let timestamp = u64::from_str_radix(timestamp, 16).ok()?;
+
+        if !constant_time_eq(
+            self.sign_timestamp(timestamp).as_bytes(),
+            signature.as_bytes(),
+        ) {
+            return None;
+        }
+
+        Some(UNIX_EPOCH + Duration::from_secs(timestamp))
+    }
+
+    fn sign_timestamp(&self, timestamp: u64) -> String {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(format!("{timestamp}:{}", self.secret));
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Check that the digest-uri of the response refers to the Request-URI
+fn digest_uri_matches(digest_uri: &str, request_parts: RequestParts<'_>) -> bool {
+    let ctx = PrintCtx {
+        method: Some(&request_parts.line.method),
+        uri: Some(UriContext::ReqUri),
+    };
+
+    if request_parts.line.uri.print_ctx(ctx).to_string() == digest_uri {
+        return true;
+    }
+
+    // Printing may differ (e.g. parameter order or escaping), compare the parsed URIs instead
+    digest_uri
+        .parse::<SipUri>()
+        .is_ok_and(|digest_uri| request_parts.line.uri.compare(&digest_uri))
+}
+
+/// Compare two byte slices in constant time (for slices of equal length)
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn expected_response(
+    hash: HashFn,
+    is_session: bool,
+    credentials: &DigestCredentials,
+    response: &DigestResponse,
+    request_parts: RequestParts<'_>,
+) -> Option<String> {
+    let mut ha1 = hash(
+        [
+            format!("{}:{}:", credentials.user, response.realm).as_bytes(),
+            &credentials.password,
+        ]
+        .concat()
+        .as_slice(),
+    );
+
+    let Some(qop_response) = &response.qop_response else {
+        if is_session {
+            return None;
+        }
+
+        let ha2 = hash(format!("{}:{}", request_parts.line.method, response.uri).as_bytes());
+
+        return Some(hash(
+            format!("{}:{}:{}", ha1, response.nonce, ha2).as_bytes(),
+        ));
+    };
+
+    if is_session {
+        ha1 = hash(format!("{}:{}:{}", ha1, response.nonce, qop_response.cnonce).as_bytes());
+    }
+
+    let (qop, ha2) = match qop_response.qop {
+        QopOption::Auth => (
+            "auth",
+            hash(format!("{}:{}", request_parts.line.method, response.uri).as_bytes()),
+        ),
+        QopOption::AuthInt => (
+            "auth-int",
+            hash(
+                format!(
+                    "{}:{}:{}",
+                    request_parts.line.method,
+                    response.uri,
+                    hash(request_parts.body)
+                )
+                .as_bytes(),
+            ),
+        ),
+        QopOption::Other(_) => return None,
+    };
+
+    Some(hash(
+        format!(
+            "{}:{}:{:08X}:{}:{}:{}",
+            ha1, response.nonce, qop_response.nc, qop_response.cnonce, qop, ha2
+        )
+        .as_bytes(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CredentialStore, DigestAuthenticator, UacAuthSession};
+    use sip_types::msg::RequestLine;
+    use sip_types::uri::sip::SipUri;
+    use sip_types::Method;
+
+    fn lookup(realm: &str, username: &str) -> Option<DigestCredentials> {
+        (realm == "example.org" && username == "user123")
+            .then(|| DigestCredentials::new("user123", "password123"))
+    }
+
+    fn authorize(
+        challenge_headers: &Headers,
+        line: &RequestLine,
+        credentials: &CredentialStore,
+    ) -> Headers {
+        let mut session = UacAuthSession::<DigestAuthenticator>::default();
+
+        session
+            .handle_authenticate(
+                challenge_headers,
+                credentials,
+                RequestParts {
+                    line,
+                    headers: &Headers::new(),
+                    body: &[],
+                },
+            )
+            .unwrap();
+
+        let mut headers = Headers::new();
+        session.authorize_request(&mut headers);
+        headers
+    }
+
+    #[test]
+    fn challenge_and_verify() {
+        let authenticator = UasAuthenticator::new("example.org", lookup);
+
+        let uri: SipUri = "sip:example.org".parse().unwrap();
+        let line = RequestLine {
+            method: Method::REGISTER,
+            uri: Box::new(uri),
+        };
+
+        let mut challenge_headers = Headers::new();
+        authenticator.challenge(&mut challenge_headers, false, false);
+
+        let mut credentials = CredentialStore::new();
+        credentials.add_for_realm(
+            "example.org",
+            DigestCredentials::new("user123", "password123"),
+        );
+
+        let headers = authorize(&challenge_headers, &line, &credentials);
+
+        let verification = authenticator.verify(
+            RequestParts {
+                line: &line,
+                headers: &headers,
+                body: &[],
+            },
+            false,
+        );
+
+        assert_eq!(verification, Verification::Authenticated("user123".into()));
+
+        // Wrong password
+        credentials.add_for_realm("example.org", DigestCredentials::new("user123", "wrong"));
+
+        let headers = authorize(&challenge_headers, &line, &credentials);
+
+        let verification = authenticator.verify(
+            RequestParts {
+                line: &line,
+                headers: &headers,
+                body: &[],
+            },
+            false,
+        );
+
+        assert_eq!(verification, Verification::Invalid);
+    }
+
+    #[test]
+    fn missing_authorization() {
+        let authenticator = UasAuthenticator::new("example.org", lookup);
+
+        let uri: SipUri = "sip:example.org".parse().unwrap();
+        let line = RequestLine {
+            method: Method::INVITE,
+            uri: Box::new(uri),
+        };
+
+        let verification = authenticator.verify(
+            RequestParts {
+                line: &line,
+                headers: &Headers::new(),
+                body: &[],
+            },
+            true,
+        );
+
+        assert_eq!(verification, Verification::Missing);
+    }
+
+    #[test]
+    fn nonce_age() {
+        let authenticator = UasAuthenticator::new("example.org", lookup);
+
+        let old = SystemTime::now() - Duration::from_secs(600);
+        let nonce = authenticator.create_nonce(old);
+
+        let created = authenticator.nonce_created(&nonce).unwrap();
+        assert!(SystemTime::now().duration_since(created).unwrap() >= Duration::from_secs(600));
+
+        let forged = format!("{:016x}{}", 0, &nonce[16..]);
+        assert!(authenticator.nonce_created(&forged).is_none());
+    }
+
+    fn register_line(uri: &str) -> RequestLine {
+        RequestLine {
+            method: Method::REGISTER,
+            uri: Box::new(uri.parse::<SipUri>().unwrap()),
+        }
+    }
+
+    fn verify(
+        authenticator: &UasAuthenticator<impl CredentialLookup>,
+        line: &RequestLine,
+        headers: &Headers,
+    ) -> Verification {
+        authenticator.verify(
+            RequestParts {
+                line,
+                headers,
+                body: &[],
+            },
+            false,
+        )
+    }
+
+    fn authorized_headers(
+        authenticator: &UasAuthenticator<impl CredentialLookup>,
+        line: &RequestLine,
+    ) -> Headers {
+        let mut challenge_headers = Headers::new();
+        authenticator.challenge(&mut challenge_headers, false, false);
+
+        let mut credentials = CredentialStore::new();
+        credentials.add_for_realm(
+            "example.org",
+            DigestCredentials::new("user123", "password123"),
+        );
+
+        authorize(&challenge_headers, line, &credentials)
+    }
+
+    #[test]
+    fn replayed_nonce_count() {
+        let authenticator = UasAuthenticator::new("example.org", lookup);
+        let line = register_line("sip:example.org");
+
+        let headers = authorized_headers(&authenticator, &line);
+
+        assert_eq!(
+            verify(&authenticator, &line, &headers),
+            Verification::Authenticated("user123".into())
+        );
+        assert_eq!(
+            verify(&authenticator, &line, &headers),
+            Verification::Invalid
+        );
+    }
+
+    #[test]
+    fn digest_uri_mismatch() {
+        let authenticator = UasAuthenticator::new("example.org", lookup);
+
+        let headers = authorized_headers(&authenticator, &register_line("sip:example.org"));
+
+        assert_eq!(
+            verify(&authenticator, &register_line("sip:other.org"), &headers),
+            Verification::Invalid
+        );
+    }
+
+    #[test]
+    fn uppercase_response_hex() {
+        let authenticator = UasAuthenticator::new("example.org", lookup);
+        let line = register_line("sip:example.org");
+
+        let headers = authorized_headers(&authenticator, &line);
+
+        let mut response = headers
+            .get::<Vec<AuthResponse>>(Name::AUTHORIZATION)
+            .unwrap()
+            .remove(0);
+
+        let AuthResponse::Digest(digest) = &mut response else {
+            panic!("expected digest response")
+        };
+        digest.response = digest.response.to_ascii_uppercase().into();
+
+        let mut headers = Headers::new();
+        headers.insert_type(Name::AUTHORIZATION, &response);
+
+        assert_eq!(
+            verify(&authenticator, &line, &headers),
+            Verification::Authenticated("user123".into())
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::id::random_alphanumeric;
+use crate::Request;
+use bytes::Bytes;
+use sip_types::header::typed::{CSeq, CallID, Contact, ContentType, FromTo, MaxForwards};
+use sip_types::header::{DynNamed, ExtendValues};
+use sip_types::msg::RequestLine;
+use sip_types::uri::Uri;
+use sip_types::{Headers, Method, Name};
+
+/// Marker for a mandatory header which has not been set on a [`RequestBuilder`]
+#[derive(Debug)]
+pub struct Missing;
+
+/// Builder for [`Request`]s, created using [`Request::builder`].
+///
+/// The From and To headers must be set before the request can be built. Call-ID, CSeq and Max-Forwards
+/// are set to a random Call-ID, `1` and `70` unless specified otherwise. Via headers are added when
+/// the request is sent using a transaction.
+#[derive(Debug)]
+#[must_use]
+pub struct RequestBuilder<From = Missing, To = Missing> {
+    line: RequestLine,
+    from: From,
+    to: To,
+    call_id: Option<CallID>,
+    cseq: u32,
+    max_forwards: u8,
+    headers: Headers,
+    body: Bytes,
+}
+
+impl Request {
+    /// Create a [`RequestBuilder`] for a request with the given method and request URI
+    pub fn builder<U>(method: Method, uri: U) -> RequestBuilder
+    where
+        U: Into<Box<dyn Uri>>,
+    {
+        RequestBuilder {
+            line: RequestLine {
+                method,
+                uri: uri.into(),
+            },
+            from: Missing,
+            to: Missing,
+            call_id: None,
+            cseq: 1,
+            max_forwards: 70,
+            headers: Headers::new(),
+            body: Bytes::new(),
+        }
+    }
+}
+
+impl<F, T> RequestBuilder<F, T> {
+    /// Set the From header
+    pub fn from(self, from: FromTo) -> RequestBuilder<FromTo, T> {
+        RequestBuilder {
+            line: self.line,
+            from,
+            to: self.to,
+            call_id: self.call_id,
+            cseq: self.cseq,
+            max_forwards: self.max_forwards,
+            headers: self.headers,
+            body: self.body,
+        }
+    }
+
+    /// Set the To header
+    pub fn to(self, to: FromTo) -> RequestBuilder<F, FromTo> {
+        RequestBuilder {
+            line: self.line,
+            from: self.from,
+            to,
+            call_id: self.call_id,
+            cseq: self.cseq,
+            max_forwards: self.max_forwards,
+            headers: self.headers,
+            body: self.body,
+        }
+    }
+
+    pub fn call_id(mut self, call_id: CallID) -> Self {
+        self.call_id = Some(call_id);
+        self
+    }
+
+    /// Set the CSeq number, the method is always the request's method
+    pub fn cseq(mut self, cseq: u32) -> Self {
+        self.cseq = cseq;
+        self
+    }
+
+    pub fn max_forwards(mut self, max_forwards: u8) -> Self {
+        self.max_forwards = max_forwards;
+        self
+    }
+
+    pub fn contact(self, contact: Contact) -> Self {
+        self.header(&contact)
+    }
+
+    /// Add any other header
+    pub fn header<H>(mut self, header: &H) -> Self
+    where
+        H: DynNamed + ExtendValues + ?Sized,
+    {
+        self.headers.insert_named(header);
+        self
+    }
+
+    /// Set the body and its Content-Type
+    pub fn body<B>(mut self, content_type: ContentType, body: B) -> Self
+    where
+        B: Into<Bytes>,
+    {
+        self.headers.insert_named(&content_type);
+        self.body = body.into();
+        self
+    }
+}
+
+impl RequestBuilder<FromTo, FromTo> {
+    pub fn build(self) -> Request {
+        let mut headers = Headers::new();
+
+        let call_id = self
+            .call_id
+            .unwrap_or_else(|| CallID::new(random_alphanumeric(30)));
+
+        headers.insert_type(Name::FROM, &self.from);
+        headers.insert_type(Name::TO, &self.to);
+        headers.insert_named(&call_id);
+        headers.insert_named(&CSeq::new(self.cseq, self.line.method.clone()));
+        headers.insert_named(&MaxForwards(self.max_forwards));
+
+        let mut other = self.headers;
+        other.drain_into(&mut headers);
+
+        Request {
+            line: self.line,
+            headers,
+            body: self.body,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sip_types::uri::sip::SipUri;
+    use sip_types::uri::NameAddr;
+
+    #[test]
+    fn build_request() {
+        let alice: SipUri = "sip:alice@example.com".parse().unwrap();
+        let bob: SipUri = "sip:bob@example.com".parse().unwrap();
+
+        let request = Request::builder(Method::INVITE, bob.clone())
+            .from(FromTo::new(
+                NameAddr::uri(alice.clone()),
+                Some("1234".into()),
+            ))
+            .to(FromTo::new(NameAddr::uri(bob), None))
+            .call_id(CallID::new("call-id"))
+            .contact(Contact::new(NameAddr::uri(alice)))
+            .body(ContentType("application/sdp".into()), "v=0\r\n")
+            .build();
+
+        assert_eq!(request.line.method, Method::INVITE);
+        assert_eq!(request.body, "v=0\r\n");
+        assert_eq!(
+            request.headers.to_string(),
+            "From: <sip:alice@example.com>;tag=1234\r\n\
+            To: <sip:bob@example.com>\r\n\
+            Call-ID: call-id\r\n\
+            CSeq: 1 INVITE\r\n\
+            Max-Forwards: 70\r\n\
+            Contact: <sip:alice@example.com>\r\n\
+            Content-Type: application/sdp\r\n"
+        );
+    }
+}
@@ -1,19 +1,26 @@
+use crate::id::{to_branch, IdGenerator, RandomIdGenerator};
+use crate::trace::{MessageTracer, TraceDirection, TracedMessage};
 use crate::transaction::{ClientInvTsx, ClientTsx, ServerInvTsx, ServerTsx, TsxKey};
-use crate::transaction::{Transactions, TsxMessage};
+use crate::transaction::{Transactions, TsxCounts, TsxEvent, TsxInfo, TsxMessage};
+use crate::transport::filter::AddressFilter;
 use crate::transport::{
-    Direction, Factory, OutgoingParts, OutgoingRequest, OutgoingResponse, ReceivedMessage,
-    TargetTransportInfo, TpHandle, Transports, TransportsBuilder,
+    Direction, Factory, MessageLimits, OutgoingParts, OutgoingRequest, OutgoingResponse,
+    ReceivedMessage, TargetTransportInfo, TpHandle, TransportEvent, Transports, TransportsBuilder,
 };
 use crate::{BaseHeaders, IncomingRequest, Layer, MayTake, Request, Response, Result, StunError};
 use bytes::{Bytes, BytesMut};
 use bytesstr::BytesStr;
-use internal::{verbose_error_to_owned, Finish};
-use sip_types::header::typed::{Accept, Allow, Supported, Via};
+use internal::{Finish, ParseError};
+use sip_types::header::typed::{
+    Accept, Allow, CallID, Require, Routing, Supported, Unsupported, Via,
+};
 use sip_types::host::{Host, HostPort};
 use sip_types::msg::{MessageLine, StatusLine};
 use sip_types::parse::{ParseCtx, Parser};
 use sip_types::print::{AppendCtx, BytesPrint, PrintCtx};
-use sip_types::uri::Uri;
+use sip_types::uri::params::Param;
+use sip_types::uri::sip::SipUri;
+use sip_types::uri::{NameAddr, Uri};
 use sip_types::{Code, Headers, Method, Name};
 use std::fmt::Write;
 use std::marker::PhantomData;
@@ -21,6 +28,7 @@ use std::mem::take;
 use std::net::{IpAddr, SocketAddr};
 use std::ops::Index;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use std::{fmt, io};
 use stun_types::Message;
 use tokio::sync::broadcast;
@@ -48,12 +56,26 @@ impl fmt::Debug for Endpoint {
 
 struct Inner {
     // capabilities
+    accept: Vec<Accept>,
     allow: Vec<Allow>,
     supported: Vec<Supported>,
 
+    // Add rport param to outgoing requests
+    rport: bool,
+
+    // Route pre-loaded into requests created outside of a dialog
+    outbound_proxy: Option<Routing>,
+
+    // Contains a proxy layer, checks only applying to user agent servers are left to the layers
+    proxy: bool,
+
     // Parser used for all parsing operations.
     parser: Parser,
 
+    tracers: Box<[Box<dyn MessageTracer>]>,
+
+    id_generator: Box<dyn IdGenerator>,
+
     transports: Transports,
     transactions: Transactions,
 
@@ -72,16 +94,13 @@ impl Endpoint {
     }
 
     /// Utility function to parse an uri
-    pub fn parse_uri(
-        &self,
-        i: impl AsRef<str>,
-    ) -> Result<Box<dyn Uri>, nom::error::VerboseError<String>> {
+    pub fn parse_uri(&self, i: impl AsRef<str>) -> Result<Box<dyn Uri>, ParseError> {
         let bytes = BytesStr::from(i.as_ref());
         let ctx = ParseCtx::new(bytes.as_ref(), self.parser());
 
         let (_, uri) = ctx.parse_uri()(&bytes)
             .finish()
-            .map_err(verbose_error_to_owned)?;
+            .map_err(|err| ParseError::new(&bytes, err))?;
 
         Ok(uri)
     }
@@ -116,6 +135,11 @@ impl Endpoint {
         ServerInvTsx::new(request)
     }
 
+    /// Returns all ACCEPT headers this endpoint supports
+    pub fn accepted(&self) -> &Vec<Accept> {
+        &self.inner.accept
+    }
+
     /// Returns all ALLOW headers this endpoint supports
     pub fn allowed(&self) -> &Vec<Allow> {
         &self.inner.allow
@@ -126,28 +150,155 @@ impl Endpoint {
         &self.inner.supported
     }
 
+    /// Returns all extensions required by the request which are not contained in the endpoint's
+    /// SUPPORTED headers. ACK and CANCEL requests never require any extensions.
+    ///
+    /// Requests requiring unsupported extensions are rejected by the endpoint, unless it contains a
+    /// [`ProxyLayer`](crate::proxy::ProxyLayer). Proxies only check [`Endpoint::unsupported_proxy_extensions`].
+    pub fn unsupported_extensions(&self, request: &IncomingRequest) -> Vec<Unsupported> {
+        self.unsupported(request, Name::REQUIRE)
+    }
+
+    /// Like [`Endpoint::unsupported_extensions`] but for the extensions required from proxies
+    /// using the Proxy-Require header ([RFC3261 Section 16.3](https://datatracker.ietf.org/doc/html/rfc3261#section-16.3))
+    pub fn unsupported_proxy_extensions(&self, request: &IncomingRequest) -> Vec<Unsupported> {
+        self.unsupported(request, Name::PROXY_REQUIRE)
+    }
+
+    fn unsupported(&self, request: &IncomingRequest, name: Name) -> Vec<Unsupported> {
+        if matches!(request.line.method, Method::ACK | Method::CANCEL) {
+            return vec![];
+        }
+
+        let Some(Ok(required)) = request.headers.try_get::<Vec<Require>>(name) else {
+            return vec![];
+        };
+
+        required
+            .into_iter()
+            .filter(|Require(extension)| {
+                !self
+                    .inner
+                    .supported
+                    .iter()
+                    .any(|Supported(supported)| supported.eq_ignore_ascii_case(extension))
+            })
+            .map(|Require(extension)| Unsupported(extension))
+            .collect()
+    }
+
+    /// Returns if the request is already handled by another server transaction, because it
+    /// reached the endpoint on multiple paths ([RFC3261 Section 8.2.2.2](https://datatracker.ietf.org/doc/html/rfc3261#section-8.2.2.2))
+    ///
+    /// Only user agent servers reject merged requests, a proxy forwards requests spiraling back to it.
+    /// The endpoint rejects merged requests itself, unless it contains a [`ProxyLayer`](crate::proxy::ProxyLayer).
+    /// Layers handling requests locally in such an endpoint must check it themselves.
+    pub fn is_merged_request(&self, request: &IncomingRequest) -> bool {
+        self.transactions()
+            .is_merged(&request.tsx_key, &request.base_headers)
+    }
+
+    /// Create a `200 OK` response to an OPTIONS request containing the endpoint's capabilities
+    /// (ALLOW, ACCEPT & SUPPORTED headers)
+    pub fn create_options_response(&self, request: &IncomingRequest) -> OutgoingResponse {
+        let mut response = self.create_response(request, Code::OK, None);
+
+        if !self.inner.allow.is_empty() {
+            response.msg.headers.insert_named(&self.inner.allow);
+        }
+
+        if !self.inner.accept.is_empty() {
+            response.msg.headers.insert_named(&self.inner.accept);
+        }
+
+        if !self.inner.supported.is_empty() {
+            response.msg.headers.insert_named(&self.inner.supported);
+        }
+
+        response
+    }
+
+    /// Generate a new Via branch parameter using the endpoint's [`IdGenerator`]
+    pub fn generate_branch(&self) -> BytesStr {
+        to_branch(self.inner.id_generator.branch())
+    }
+
+    /// Generate a new From/To tag using the endpoint's [`IdGenerator`]
+    pub fn generate_tag(&self) -> BytesStr {
+        self.inner.id_generator.tag().into()
+    }
+
+    /// Generate a new Call-ID using the endpoint's [`IdGenerator`]
+    pub fn generate_call_id(&self) -> CallID {
+        CallID::new(self.inner.id_generator.call_id())
+    }
+
+    /// Create a key for a new client transaction with a branch created by [`Endpoint::generate_branch`]
+    pub fn create_client_tsx_key(&self, method: &Method) -> TsxKey {
+        TsxKey::client_with_branch(&self.generate_branch(), method)
+    }
+
+    /// Returns the outbound proxy route if one was configured using [`EndpointBuilder::set_outbound_proxy`]
+    pub fn outbound_proxy(&self) -> Option<&Routing> {
+        self.inner.outbound_proxy.as_ref()
+    }
+
+    /// Add the outbound proxy route (if configured) to a request created outside of a dialog
+    /// ([RFC3261 Section 8.1.2](https://datatracker.ietf.org/doc/html/rfc3261#section-8.1.2))
+    ///
+    /// Requests which already have a Route header are not modified. Must not be used for requests
+    /// inside a dialog, they are routed using the dialog's route set.
+    pub fn add_outbound_proxy_route(&self, request: &mut Request) {
+        if let Some(outbound_proxy) = &self.inner.outbound_proxy {
+            if !request.headers.contains(&Name::ROUTE) {
+                request.headers.insert_type(Name::ROUTE, outbound_proxy);
+            }
+        }
+    }
+
     /// Create a VIA header with the given transport and transaction key
+    ///
+    /// Contains an empty `rport` parameter if enabled using [`EndpointBuilder::set_rport`].
     pub fn create_via(
         &self,
         transport: &TpHandle,
         tsx_key: &TsxKey,
         via_host_port: Option<HostPort>,
     ) -> Via {
-        Via::new(
+        let mut via = Via::new(
             transport.name(),
             via_host_port.unwrap_or_else(|| transport.sent_by().into()),
             tsx_key.branch().clone(),
-        )
+        );
+
+        if self.inner.rport {
+            via.params.push(Param::name("rport"));
+        }
+
+        via
     }
 
     /// Try to find or create a suitable transport for a given uri and return a non-empty list
     /// of resolved socket addresses
     pub async fn select_transport(&self, uri: &dyn Uri) -> Result<(TpHandle, SocketAddr)> {
-        self.transports().select(self, uri).await
+        self.transports().select(self, uri, &[]).await
+    }
+
+    /// Like [`Endpoint::select_transport`] but skips all resolved addresses contained in `exclude`.
+    ///
+    /// Used to fail over to the next target when a previously selected one stopped responding.
+    pub async fn select_transport_excluding(
+        &self,
+        uri: &dyn Uri,
+        exclude: &[SocketAddr],
+    ) -> Result<(TpHandle, SocketAddr)> {
+        self.transports().select(self, uri, exclude).await
     }
 
     /// Takes a request and converts it into an `Outgoing`.
     /// To do so it calculates the destination and retrieves a suitable transport
+    ///
+    /// The destination is the URI of the first Route header if it's a loose router, the request URI otherwise.
     pub async fn create_outgoing(
         &self,
         request: Request,
@@ -156,7 +307,18 @@ impl Endpoint {
         let (transport, destination) = if let Some((transport, destination)) = &target.transport {
             (transport.clone(), *destination)
         } else {
-            let (transport, destination) = self.select_transport(&*request.line.uri).await?;
+            let route = request
+                .headers
+                .try_get::<Routing>(Name::ROUTE)
+                .transpose()?;
+
+            // Requests to strict routers carry the router's URI as request URI
+            let next_hop = match &route {
+                Some(route) if route.is_loose() => &*route.uri.uri,
+                _ => &*request.line.uri,
+            };
+
+            let (transport, destination) = self.select_transport(next_hop).await?;
             target.transport = Some((transport.clone(), destination));
             (transport, destination)
         };
@@ -194,7 +356,7 @@ impl Endpoint {
             )
             .map_err(|e| {
                 // wrap
-                io::Error::new(io::ErrorKind::Other, e)
+                io::Error::other(e)
             })?;
 
             buffer.extend_from_slice(&message.msg.body);
@@ -208,6 +370,14 @@ impl Endpoint {
             BytesPrint(&message.parts.buffer)
         );
 
+        self.trace(
+            TraceDirection::Outgoing,
+            SystemTime::now(),
+            &message.parts.transport,
+            message.parts.destination,
+            &message.parts.buffer,
+        );
+
         message
             .parts
             .transport
@@ -238,7 +408,7 @@ impl Endpoint {
             )
             .map_err(|e| {
                 // wrap
-                io::Error::new(io::ErrorKind::Other, e)
+                io::Error::other(e)
             })?;
 
             buffer.extend_from_slice(&message.msg.body);
@@ -252,6 +422,14 @@ impl Endpoint {
             BytesPrint(&message.parts.buffer)
         );
 
+        self.trace(
+            TraceDirection::Outgoing,
+            SystemTime::now(),
+            &message.parts.transport,
+            message.parts.destination,
+            &message.parts.buffer,
+        );
+
         message
             .parts
             .transport
@@ -329,9 +507,42 @@ impl Endpoint {
     /// Spawns a task internally which will let every registered layer have a look at the message
     /// and let it decide if it is going to handle it.
     pub fn receive(&self, message: ReceivedMessage) {
+        self.trace(
+            TraceDirection::Incoming,
+            message.tp_info.timestamp,
+            &message.tp_info.transport,
+            message.tp_info.source,
+            &message.tp_info.buffer,
+        );
+
         tokio::spawn(self.clone().do_receive(message));
     }
 
+    fn trace(
+        &self,
+        direction: TraceDirection,
+        timestamp: SystemTime,
+        transport: &TpHandle,
+        remote: SocketAddr,
+        buffer: &[u8],
+    ) {
+        if self.inner.tracers.is_empty() {
+            return;
+        }
+
+        let message = TracedMessage {
+            direction,
+            timestamp,
+            transport,
+            remote,
+            buffer,
+        };
+
+        for tracer in self.inner.tracers.iter() {
+            tracer.trace(&message);
+        }
+    }
+
     #[tracing::instrument(level = "debug", skip(self, message), fields(%message))]
     async fn do_receive(self, mut message: ReceivedMessage) {
         log::trace!(
@@ -340,6 +551,8 @@ impl Endpoint {
             BytesPrint(&message.tp_info.buffer)
         );
 
+        let exceeded_limit = message.exceeded_limit;
+
         let mut base_headers = match BaseHeaders::extract_from(&message.headers) {
             Ok(base_headers) => base_headers,
             Err(e) => {
@@ -363,7 +576,12 @@ impl Endpoint {
         let mut tsx = None;
 
         // Try to find a transaction that might be able to handle the message
-        match self.transactions().get_handler(&self, &tsx_key) {
+        match self.transactions().get_handler(
+            &self,
+            &tsx_key,
+            &base_headers.cseq.method,
+            message.tp_info.source,
+        ) {
             Ok(handler) => {
                 let tsx_message = TsxMessage {
                     tp_info: message.tp_info,
@@ -385,6 +603,7 @@ impl Endpoint {
                         line: rejected_tsx_message.line,
                         headers: rejected_tsx_message.headers,
                         body: rejected_tsx_message.body,
+                        exceeded_limit,
                     };
                 } else {
                     // Handled
@@ -417,6 +636,50 @@ impl Endpoint {
             tsx_key,
         };
 
+        if let Some(code) = exceeded_limit {
+            log::debug!("Rejecting request exceeding message limits with {:?}", code);
+
+            if let Err(e) = self.reject_request(incoming, code).await {
+                log::error!("Failed to reject incoming request, {:?}", e);
+            }
+
+            return;
+        }
+
+        if incoming.tsx.is_some()
+            && (self
+                .transactions()
+                .is_looped(&incoming.line, &incoming.base_headers)
+                || (!self.inner.proxy && self.is_merged_request(&incoming)))
+        {
+            log::debug!("Rejecting looped or merged request {}", incoming.tsx_key);
+
+            if let Err(e) = self.reject_request(incoming, Code::LOOP_DETECTED).await {
+                log::error!("Failed to reject incoming request, {:?}", e);
+            }
+
+            return;
+        }
+
+        let unsupported = if self.inner.proxy {
+            vec![]
+        } else {
+            self.unsupported_extensions(&incoming)
+        };
+
+        if !unsupported.is_empty() {
+            log::debug!("Rejecting request requiring unsupported extensions");
+
+            let mut response = self.create_response(&incoming, Code::BAD_EXTENSION, None);
+            response.msg.headers.insert_named(&unsupported);
+
+            if let Err(e) = self.respond_unhandled(incoming, response).await {
+                log::error!("Failed to reject incoming request, {:?}", e);
+            }
+
+            return;
+        }
+
         let mut request = Some(incoming);
 
         for layer in self.inner.layer.iter() {
@@ -437,20 +700,42 @@ impl Endpoint {
         // Safe unwrap. Loop checks every iteration if request is none
         let request = request.unwrap();
 
-        if let Err(e) = self.handle_unwanted_request(request).await {
+        if request.line.method == Method::OPTIONS {
+            let response = self.create_options_response(&request);
+
+            if let Err(e) = self.respond_unhandled(request, response).await {
+                log::error!("Failed to respond to incoming OPTIONS request, {:?}", e);
+            }
+
+            return;
+        }
+
+        if let Err(e) = self
+            .reject_request(request, Code::CALL_OR_TRANSACTION_DOES_NOT_EXIST)
+            .await
+        {
             log::error!("Failed to respond to unhandled incoming request, {:?}", e);
         }
     }
 
-    async fn handle_unwanted_request(&self, mut request: IncomingRequest) -> Result<()> {
+    /// Respond to a request which will not be handled with the given error code
+    async fn reject_request(&self, request: IncomingRequest, code: Code) -> Result<()> {
         if request.line.method == Method::ACK {
             // Cannot respond to unhandled ACK requests
             return Ok(());
         }
 
-        let response =
-            self.create_response(&request, Code::CALL_OR_TRANSACTION_DOES_NOT_EXIST, None);
+        let response = self.create_response(&request, code, None);
 
+        self.respond_unhandled(request, response).await
+    }
+
+    /// Respond to a request which is not handled by any layer using a new server transaction
+    async fn respond_unhandled(
+        &self,
+        mut request: IncomingRequest,
+        response: OutgoingResponse,
+    ) -> Result<()> {
         if request.line.method == Method::INVITE {
             let tsx = self.create_server_inv_tsx(&mut request);
 
@@ -483,6 +768,28 @@ impl Endpoint {
             .await
     }
 
+    /// Returns a snapshot of all transactions currently registered inside the endpoint.
+    ///
+    /// Useful to monitor long running services for leaking or stuck transactions.
+    pub fn transactions_info(&self) -> Vec<TsxInfo> {
+        self.transactions().info()
+    }
+
+    /// Returns the number of client and server transactions currently registered inside the endpoint
+    pub fn transaction_counts(&self) -> TsxCounts {
+        self.transactions().counts()
+    }
+
+    /// Subscribe to events about connection oriented transports being opened or closed
+    pub fn subscribe_transport_events(&self) -> broadcast::Receiver<TransportEvent> {
+        self.transports().subscribe_events()
+    }
+
+    /// Subscribe to events about transactions being created or terminated
+    pub fn subscribe_transaction_events(&self) -> broadcast::Receiver<TsxEvent> {
+        self.transactions().subscribe_events()
+    }
+
     pub(crate) fn transactions(&self) -> &Transactions {
         &self.inner.transactions
     }
@@ -513,6 +820,13 @@ pub struct EndpointBuilder {
     allow: Vec<Allow>,
     supported: Vec<Supported>,
 
+    rport: bool,
+    outbound_proxy: Option<Routing>,
+    proxy: bool,
+
+    tracers: Vec<Box<dyn MessageTracer>>,
+    id_generator: Option<Box<dyn IdGenerator>>,
+
     transports: TransportsBuilder,
     layer: Vec<Box<dyn Layer>>,
 }
@@ -532,6 +846,11 @@ impl EndpointBuilder {
             accept: vec![],
             allow: vec![],
             supported: vec![],
+            rport: false,
+            outbound_proxy: None,
+            proxy: false,
+            tracers: vec![],
+            id_generator: None,
             transports: Default::default(),
             layer: Default::default(),
         }
@@ -558,6 +877,50 @@ impl EndpointBuilder {
         self.supported.push(Supported(supported.into()))
     }
 
+    /// Request symmetric response routing ([RFC3581](https://datatracker.ietf.org/doc/html/rfc3581))
+    /// by adding an empty `rport` parameter to the Via header of all outgoing requests.
+    ///
+    /// The public address reported back by the server can then be read from the Via header of the response
+    /// using [`Via::public_address`]. Disabled by default.
+    ///
+    /// Incoming requests are always answered using the `received` & `rport` parameters.
+    pub fn set_rport(&mut self, rport: bool) {
+        self.rport = rport;
+    }
+
+    /// Send all requests created outside of a dialog to the given outbound proxy by pre-loading it
+    /// as Route header ([RFC3261 Section 8.1.2](https://datatracker.ietf.org/doc/html/rfc3261#section-8.1.2)).
+    ///
+    /// The route is added by the request builders of the user agent using
+    /// [`Endpoint::add_outbound_proxy_route`]. Requests inside a dialog and requests forwarded by a
+    /// proxy are not affected. The `lr` parameter is added to the URI if missing.
+    pub fn set_outbound_proxy(&mut self, mut proxy: SipUri) {
+        if proxy.uri_params.get("lr").is_none() {
+            proxy.uri_params.push(Param::name("lr"));
+        }
+
+        self.outbound_proxy = Some(Routing {
+            uri: NameAddr::uri(proxy),
+            params: Default::default(),
+        });
+    }
+
+    /// Mark the endpoint as proxy, called by the [`ProxyLayer`](crate::proxy::ProxyLayer)
+    pub(crate) fn set_proxy(&mut self) {
+        self.proxy = true;
+    }
+
+    /// Set the generator used to create Via branch parameters and From/To tags.
+    /// Defaults to [`RandomIdGenerator`].
+    pub fn set_id_generator<G: IdGenerator>(&mut self, generator: G) {
+        self.id_generator = Some(Box::new(generator));
+    }
+
+    /// Add a tracer which will be called with every raw message received or sent by the endpoint
+    pub fn add_message_tracer<T: MessageTracer>(&mut self, tracer: T) {
+        self.tracers.push(Box::new(tracer));
+    }
+
     /// Add an unmanaged transport to the endpoint which will never vanish or break (e.g. UDP)
     pub fn add_unmanaged_transport(&mut self, transport: TpHandle) -> &mut Self {
         self.transports.insert_unmanaged(transport);
@@ -577,6 +940,47 @@ impl EndpointBuilder {
         self.transports.set_dns_resolver(dns_resolver)
     }
 
+    /// Set the duration after which connections (e.g. TCP, TLS) are closed when not used anymore.
+    ///
+    /// Defaults to 32 seconds.
+    pub fn set_transport_idle_timeout(&mut self, idle_timeout: Duration) {
+        self.transports.set_idle_timeout(idle_timeout)
+    }
+
+    /// Set the interval in which CRLF keep-alives are sent over connections (e.g. TCP, TLS),
+    /// or `None` to disable sending them. Keep-alives received from peers are always answered.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn set_keep_alive_interval(&mut self, keep_alive_interval: Option<Duration>) {
+        self.transports.set_keep_alive_interval(keep_alive_interval)
+    }
+
+    /// Limit the number of simultaneously open connections.
+    ///
+    /// When reached, no new connections are established and incoming ones are refused.
+    /// Unlimited by default.
+    pub fn set_max_connections(&mut self, max_connections: usize) {
+        self.transports.set_max_connections(max_connections)
+    }
+
+    /// Set the limits applied to received messages
+    pub fn set_message_limits(&mut self, limits: MessageLimits) {
+        self.transports.set_limits(limits)
+    }
+
+    /// Add a filter which is checked for traffic received on any transport before it is parsed.
+    ///
+    /// Datagrams from rejected sources are dropped, connections are closed after being accepted.
+    pub fn add_address_filter<F: AddressFilter>(&mut self, filter: F) {
+        self.transports.add_filter(None, Box::new(filter))
+    }
+
+    /// Like [`EndpointBuilder::add_address_filter`] but only applies to the transport with the given name (e.g. `UDP`)
+    pub fn add_transport_address_filter<F: AddressFilter>(&mut self, transport: &str, filter: F) {
+        self.transports
+            .add_filter(Some(transport.into()), Box::new(filter))
+    }
+
     /// Add a implementation of [`Layer`] to the endpoint.
     ///
     /// Note that the insertion order is relevant in how the SIP Stack may react to requests,
@@ -612,10 +1016,24 @@ impl EndpointBuilder {
             layer.init(self);
         }
 
+        // OPTIONS requests are always answered by the endpoint
+        if !self.allow.contains(&Allow(Method::OPTIONS)) {
+            self.allow.push(Allow(Method::OPTIONS));
+        }
+
         let inner = Inner {
+            accept: take(&mut self.accept),
             allow: take(&mut self.allow),
             supported: take(&mut self.supported),
+            rport: self.rport,
+            outbound_proxy: self.outbound_proxy.take(),
+            proxy: self.proxy,
             parser: Default::default(),
+            tracers: take(&mut self.tracers).into_boxed_slice(),
+            id_generator: self
+                .id_generator
+                .take()
+                .unwrap_or_else(|| Box::new(RandomIdGenerator)),
             transports: self.transports.build(),
             transactions: Default::default(),
             layer,
@@ -0,0 +1,55 @@
+//! Generation of Via branch parameters, From/To tags and Call-IDs
+
+use crate::transaction::consts::RFC3261_BRANCH_PREFIX;
+use bytesstr::BytesStr;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+
+/// Generates identifiers used by the endpoint when creating requests and dialogs.
+///
+/// Can be installed using [`EndpointBuilder::set_id_generator`](crate::EndpointBuilder::set_id_generator),
+/// e.g. to embed shard identifiers or to create deterministic values in tests.
+/// All generated values must be unique.
+pub trait IdGenerator: Send + Sync + 'static {
+    /// Generate the unique part of a Via branch parameter.
+    ///
+    /// The RFC3261 magic cookie (`z9hG4bK`) is prepended by the endpoint.
+    fn branch(&self) -> String;
+
+    /// Generate a From or To tag
+    fn tag(&self) -> String;
+
+    /// Generate a Call-ID
+    fn call_id(&self) -> String;
+}
+
+/// Default [`IdGenerator`] creating random alphanumeric strings
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn branch(&self) -> String {
+        random_alphanumeric(23)
+    }
+
+    fn tag(&self) -> String {
+        random_alphanumeric(30)
+    }
+
+    fn call_id(&self) -> String {
+        random_alphanumeric(30)
+    }
+}
+
+pub(crate) fn random_alphanumeric(len: usize) -> String {
+    thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Prepend the RFC3261 magic cookie to a generated branch
+pub(crate) fn to_branch(generated: String) -> BytesStr {
+    format!("{RFC3261_BRANCH_PREFIX}{generated}").into()
+}
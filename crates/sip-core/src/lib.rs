@@ -19,11 +19,17 @@ use transport::MessageTpInfo;
 
 #[macro_use]
 mod error;
+mod builder;
 mod endpoint;
+pub mod id;
 mod may_take;
+pub mod proxy;
+pub mod rate_limit;
+pub mod trace;
 pub mod transaction;
 pub mod transport;
 
+pub use builder::{Missing, RequestBuilder};
 pub use endpoint::Endpoint;
 pub use endpoint::EndpointBuilder;
 pub use endpoint::LayerKey;
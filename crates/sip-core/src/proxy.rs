@@ -0,0 +1,1114 @@
+//! Stateful proxy ([RFC 3261 Section 16](https://datatracker.ietf.org/doc/html/rfc3261#section-16))
+
+use crate::transaction::consts::T1;
+use crate::transaction::{Accepted, ClientInvTsx, ServerInvTsx, ServerTsx, TsxResponse};
+use crate::transport::{OutgoingResponse, TargetTransportInfo};
+use crate::{Endpoint, EndpointBuilder, Error, IncomingRequest, Layer, MayTake, Request, Result};
+use bytesstr::BytesStr;
+use parking_lot::Mutex;
+use sip_types::header::typed::{MaxForwards, Routing};
+use sip_types::header::HeaderError;
+use sip_types::msg::RequestLine;
+use sip_types::uri::params::Param;
+use sip_types::uri::sip::SipUri;
+use sip_types::uri::{NameAddr, Uri};
+use sip_types::{Code, CodeKind, Method, Name};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::select;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{sleep, sleep_until};
+
+/// Timer C, the time a forwarded INVITE may stay without final response after its last provisional response
+/// ([RFC 3261 Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6) step 11, must be greater than 3 minutes)
+const TIMER_C: Duration = Duration::from_secs(181);
+
+/// Determines the targets of requests which are not routed using Route headers
+/// ([RFC 3261 Section 16.5](https://datatracker.ietf.org/doc/html/rfc3261#section-16.5))
+#[async_trait::async_trait]
+pub trait Locator: Send + Sync + 'static {
+    /// Returns the URIs the request is forwarded to, the request is rejected with `404 Not Found` if empty
+    async fn locate(&self, request: &IncomingRequest) -> Vec<Box<dyn Uri>>;
+}
+
+/// [`Locator`] which forwards every request to its Request-URI
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestUriLocator;
+
+#[async_trait::async_trait]
+impl Locator for RequestUriLocator {
+    async fn locate(&self, request: &IncomingRequest) -> Vec<Box<dyn Uri>> {
+        vec![request.line.uri.clone()]
+    }
+}
+
+/// How requests are forwarded when a [`Locator`] returns multiple targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkMode {
+    /// Forward the request to all targets at once
+    Parallel,
+
+    /// Forward the request to one target after another, until one of them responds with a 2XX or 6XX response
+    Sequential,
+}
+
+/// Layer which statefully forwards all requests it receives
+///
+/// Responses of all branches are aggregated and the best response is sent back
+/// ([RFC 3261 Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7)).
+/// CANCEL requests for forwarded INVITE requests are propagated to all pending branches.
+///
+/// The layer takes every request it receives, so it must be added after all layers which handle
+/// requests locally (e.g. a registrar). The endpoint no longer rejects merged requests, as they
+/// may be spirals which must be forwarded, layers handling requests locally can check
+/// [`Endpoint::is_merged_request`] instead. The same applies to the Require header, which is only
+/// checked by user agent servers. Requests are forwarded only if the endpoint supports all extensions
+/// listed in their Proxy-Require header.
+pub struct ProxyLayer {
+    locator: Box<dyn Locator>,
+    fork_mode: ForkMode,
+
+    /// URI inserted as Record-Route, also used to recognize Route headers referring to this proxy
+    record_route: Option<SipUri>,
+
+    /// Forwarded INVITE requests which can still be cancelled
+    cancellables: Mutex<HashMap<CancellableKey, Arc<watch::Sender<bool>>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CancellableKey {
+    branch: BytesStr,
+    cseq: u32,
+}
+
+impl ProxyLayer {
+    pub fn new<L: Locator>(locator: L) -> Self {
+        Self {
+            locator: Box::new(locator),
+            fork_mode: ForkMode::Parallel,
+            record_route: None,
+            cancellables: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set how requests are forked to multiple targets (default [`ForkMode::Parallel`])
+    pub fn with_fork_mode(mut self, fork_mode: ForkMode) -> Self {
+        self.fork_mode = fork_mode;
+        self
+    }
+
+    /// Insert a Record-Route header with the given URI into forwarded requests, to stay on the path of
+    /// all requests inside dialogs created by them. The `lr` parameter is added to the URI if missing.
+    ///
+    /// Route headers with the same host and port are removed from incoming requests before forwarding them.
+    pub fn with_record_route(mut self, mut uri: SipUri) -> Self {
+        if uri.uri_params.get("lr").is_none() {
+            uri.uri_params.push(Param::name("lr"));
+        }
+
+        self.record_route = Some(uri);
+        self
+    }
+
+    /// Returns if the route refers to this proxy
+    fn is_local_route(&self, route: &Routing) -> bool {
+        let Some(record_route) = &self.record_route else {
+            return false;
+        };
+
+        route
+            .uri
+            .uri
+            .downcast_ref::<SipUri>()
+            .is_some_and(|uri| uri.host_port == record_route.host_port)
+    }
+
+    /// Returns the Route headers of the request, without the top one if it refers to this proxy
+    /// ([RFC 3261 Section 16.4](https://datatracker.ietf.org/doc/html/rfc3261#section-16.4))
+    fn remaining_routes(&self, request: &IncomingRequest) -> Result<Vec<Routing>, HeaderError> {
+        let mut routes = match request.headers.try_get::<Vec<Routing>>(Name::ROUTE) {
+            Some(routes) => routes?,
+            None => vec![],
+        };
+
+        if routes
+            .first()
+            .is_some_and(|route| self.is_local_route(route))
+        {
+            routes.remove(0);
+        }
+
+        Ok(routes)
+    }
+
+    /// Create the request forwarded to `target` ([RFC 3261 Section 16.6](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6))
+    ///
+    /// The Via of this proxy is added by the client transaction.
+    fn create_forward_request(
+        &self,
+        request: &IncomingRequest,
+        routes: &[Routing],
+        max_forwards: u8,
+        target: Box<dyn Uri>,
+    ) -> Request {
+        let mut headers = request.headers.clone();
+
+        headers.remove(&Name::VIA);
+        headers.remove(&Name::ROUTE);
+        headers.remove(&Name::MAX_FORWARDS);
+        headers.remove(&Name::CONTENT_LENGTH);
+
+        // Contains the received & rport parameters added to the top Via
+        headers.insert_named_front(&request.base_headers.via);
+        headers.insert_named(&MaxForwards(max_forwards - 1));
+
+        if !routes.is_empty() {
+            headers.insert_type(Name::ROUTE, &routes.to_vec());
+        }
+
+        if let Some(record_route) = &self.record_route {
+            if request.line.method != Method::ACK {
+                headers.insert_type_front(
+                    Name::RECORD_ROUTE,
+                    &Routing {
+                        uri: NameAddr::uri(record_route.clone()),
+                        params: Default::default(),
+                    },
+                );
+            }
+        }
+
+        Request {
+            line: RequestLine {
+                method: request.line.method.clone(),
+                uri: target,
+            },
+            headers,
+            body: request.body.clone(),
+        }
+    }
+
+    async fn handle_cancel(
+        &self,
+        endpoint: &Endpoint,
+        cancel: MayTake<'_, IncomingRequest>,
+    ) -> Result<()> {
+        let key = CancellableKey {
+            branch: cancel.tsx_key.branch().clone(),
+            cseq: cancel.base_headers.cseq.cseq,
+        };
+
+        let Some(cancelled) = self.cancellables.lock().get(&key).cloned() else {
+            // Not forwarded by this proxy, the endpoint responds with 481
+            return Ok(());
+        };
+
+        let mut cancel = cancel.take();
+        let transaction = endpoint.create_server_tsx(&mut cancel);
+
+        cancelled.send_replace(true);
+
+        let response = endpoint.create_response(&cancel, Code::OK, None);
+        transaction.respond(response).await
+    }
+
+    /// Forward an ACK request, which can only be an ACK to a 2XX response, as the
+    /// ACK to error responses is absorbed by the server transaction
+    async fn forward_ack(&self, endpoint: &Endpoint, ack: IncomingRequest) -> Result<()> {
+        let max_forwards = max_forwards(&ack);
+
+        if max_forwards == 0 {
+            return Ok(());
+        }
+
+        let routes = self.remaining_routes(&ack)?;
+        let request =
+            self.create_forward_request(&ack, &routes, max_forwards, ack.line.uri.clone());
+
+        let mut outgoing = endpoint
+            .create_outgoing(request, &mut TargetTransportInfo::default())
+            .await?;
+
+        let tsx_key = endpoint.create_client_tsx_key(&Method::ACK);
+        let via = endpoint.create_via(&outgoing.parts.transport, &tsx_key, None);
+        outgoing.msg.headers.insert_named_front(&via);
+
+        endpoint.send_outgoing_request(&mut outgoing).await?;
+
+        Ok(())
+    }
+
+    async fn handle_request(
+        &self,
+        endpoint: &Endpoint,
+        mut request: IncomingRequest,
+    ) -> Result<()> {
+        let mut transaction = if request.line.method == Method::INVITE {
+            ServerTransaction::Invite(endpoint.create_server_inv_tsx(&mut request))
+        } else {
+            ServerTransaction::NonInvite(endpoint.create_server_tsx(&mut request))
+        };
+
+        let max_forwards = max_forwards(&request);
+
+        if max_forwards == 0 {
+            let response = endpoint.create_response(&request, Code::TOO_MANY_HOPS, None);
+            return transaction.respond(response).await.map(drop);
+        }
+
+        let unsupported = endpoint.unsupported_proxy_extensions(&request);
+
+        if !unsupported.is_empty() {
+            let mut response = endpoint.create_response(&request, Code::BAD_EXTENSION, None);
+            response.msg.headers.insert_named(&unsupported);
+            return transaction.respond(response).await.map(drop);
+        }
+
+        let routes = match self.remaining_routes(&request) {
+            Ok(routes) => routes,
+            Err(e) => {
+                log::debug!("Failed to parse Route headers of request, {e}");
+
+                let response = endpoint.create_response(&request, Code::BAD_REQUEST, None);
+                return transaction.respond(response).await.map(drop);
+            }
+        };
+
+        // Requests with a Route header are forwarded to the Request-URI, the next hop is the first route
+        let targets = if routes.is_empty() {
+            self.locator.locate(&request).await
+        } else {
+            vec![request.line.uri.clone()]
+        };
+
+        if targets.is_empty() {
+            let response = endpoint.create_response(&request, Code::NOT_FOUND, None);
+            return transaction.respond(response).await.map(drop);
+        }
+
+        let requests = targets
+            .into_iter()
+            .map(|target| self.create_forward_request(&request, &routes, max_forwards, target))
+            .collect();
+
+        if request.line.method != Method::INVITE {
+            return self
+                .fork(endpoint, &request, transaction, requests, None)
+                .await;
+        }
+
+        let mut trying = endpoint.create_response(&request, Code::TRYING, None);
+        transaction.respond_provisional(&mut trying).await?;
+
+        let key = CancellableKey {
+            branch: request.tsx_key.branch().clone(),
+            cseq: request.base_headers.cseq.cseq,
+        };
+
+        let cancelled = Arc::new(watch::Sender::new(false));
+
+        self.cancellables
+            .lock()
+            .insert(key.clone(), cancelled.clone());
+
+        let result = self
+            .fork(endpoint, &request, transaction, requests, Some(cancelled))
+            .await;
+
+        self.cancellables.lock().remove(&key);
+
+        result
+    }
+
+    /// Forward the requests according to the fork mode and send the aggregated response
+    async fn fork(
+        &self,
+        endpoint: &Endpoint,
+        request: &IncomingRequest,
+        transaction: ServerTransaction,
+        requests: Vec<Request>,
+        cancelled: Option<Arc<watch::Sender<bool>>>,
+    ) -> Result<()> {
+        let cancelled = cancelled.unwrap_or_else(|| Arc::new(watch::Sender::new(false)));
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut sender = Some(sender);
+
+        let mut requests = requests.into_iter();
+        let mut branches = 0;
+
+        let initial = match self.fork_mode {
+            ForkMode::Parallel => requests.len(),
+            ForkMode::Sequential => 1,
+        };
+
+        for request in requests.by_ref().take(initial) {
+            let sender = sender.as_ref().expect("sender is set");
+            start_branch(endpoint, branches, request, &cancelled, sender);
+            branches += 1;
+        }
+
+        let mut transaction = Some(transaction);
+
+        // Kept for 64*T1 to absorb retransmissions of the INVITE after a 2XX response was forwarded
+        let mut accepted = None;
+
+        let mut finished_branches = vec![];
+        let mut best_response: Option<TsxResponse> = None;
+
+        // Challenges of all 401 and 407 responses, merged into the forwarded response (RFC 3261 Section 16.7 step 7)
+        let mut challenges = vec![];
+
+        while let Some((branch, result)) = receiver.recv().await {
+            let response = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    // Treated like a 408 response, which is never forwarded
+                    log::debug!("Forwarded request failed, {e}");
+
+                    if !finished_branches.contains(&branch) {
+                        finished_branches.push(branch);
+                    }
+
+                    None
+                }
+            };
+
+            if let Some(response) = response {
+                let code = response.line.code;
+
+                match code.kind() {
+                    CodeKind::Provisional => {
+                        if let Some(transaction) = &mut transaction {
+                            if code != Code::TRYING {
+                                let mut upstream =
+                                    create_upstream_response(endpoint, request, &response);
+                                transaction.respond_provisional(&mut upstream).await?;
+                            }
+                        }
+
+                        continue;
+                    }
+                    CodeKind::Success => {
+                        let mut upstream = create_upstream_response(endpoint, request, &response);
+
+                        if let Some(transaction) = transaction.take() {
+                            cancelled.send_replace(true);
+                            sender = None;
+
+                            accepted = transaction
+                                .respond(upstream)
+                                .await?
+                                .map(|accepted| (Instant::now() + T1 * 64, accepted));
+                        } else if request.line.method == Method::INVITE {
+                            // Retransmissions of the 2XX response or 2XX responses of other branches
+                            endpoint.send_outgoing_response(&mut upstream).await?;
+                        }
+                    }
+                    _ => {
+                        if code.kind() == CodeKind::GlobalFailure {
+                            cancelled.send_replace(true);
+                        }
+
+                        if matches!(
+                            code,
+                            Code::UNAUTHORIZED | Code::PROXY_AUTHENTICATION_REQUIRED
+                        ) {
+                            challenges.extend(
+                                response
+                                    .headers
+                                    .iter()
+                                    .filter(|(name, _)| is_challenge(name))
+                                    .map(|(name, value)| (name.clone(), value.clone())),
+                            );
+                        }
+
+                        let is_better = best_response
+                            .as_ref()
+                            .is_none_or(|best| is_better_response(code, best.line.code));
+
+                        if is_better {
+                            best_response = Some(response);
+                        }
+                    }
+                }
+
+                if finished_branches.contains(&branch) {
+                    continue;
+                }
+
+                finished_branches.push(branch);
+            }
+
+            if finished_branches.len() < branches {
+                continue;
+            }
+
+            // All started branches are finished, try the next target
+            let stop_forking = *cancelled.borrow()
+                || best_response
+                    .as_ref()
+                    .is_some_and(|best| best.line.code.kind() == CodeKind::GlobalFailure);
+
+            if let (Some(sender), false) = (&sender, stop_forking) {
+                if let Some(request) = requests.next() {
+                    start_branch(endpoint, branches, request, &cancelled, sender);
+                    branches += 1;
+                    continue;
+                }
+            }
+
+            sender = None;
+
+            if let Some(transaction) = transaction.take() {
+                let response = match &best_response {
+                    // A 503 response must not be forwarded, as it would cause upstream elements to stop
+                    // sending requests to this proxy (RFC 3261 Section 16.7 step 6)
+                    Some(best) if best.line.code == Code::SERVICE_UNAVAILABLE => {
+                        endpoint.create_response(request, Code::SERVER_INTERNAL_ERROR, None)
+                    }
+                    Some(best) => {
+                        let mut response = create_upstream_response(endpoint, request, best);
+
+                        if matches!(
+                            best.line.code,
+                            Code::UNAUTHORIZED | Code::PROXY_AUTHENTICATION_REQUIRED
+                        ) {
+                            merge_challenges(&mut response, &challenges);
+                        }
+
+                        response
+                    }
+                    None => endpoint.create_response(request, Code::REQUEST_TIMEOUT, None),
+                };
+
+                transaction.respond(response).await?;
+            }
+        }
+
+        if let Some((deadline, accepted)) = accepted {
+            tokio::spawn(async move {
+                sleep_until(deadline.into()).await;
+                drop(accepted);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Layer for ProxyLayer {
+    fn name(&self) -> &'static str {
+        "proxy"
+    }
+
+    fn init(&mut self, endpoint: &mut EndpointBuilder) {
+        endpoint.set_proxy();
+    }
+
+    async fn receive(&self, endpoint: &Endpoint, mut request: MayTake<'_, IncomingRequest>) {
+        let result = match request.line.method {
+            Method::CANCEL => {
+                self.handle_cancel(endpoint, MayTake::new(request.inner()))
+                    .await
+            }
+            Method::ACK => self.forward_ack(endpoint, request.take()).await,
+            _ => self.handle_request(endpoint, request.take()).await,
+        };
+
+        if let Err(e) = result {
+            log::warn!("Failed to proxy request, {e}");
+        }
+    }
+}
+
+/// Server transaction of a proxied request
+enum ServerTransaction {
+    Invite(ServerInvTsx),
+    NonInvite(ServerTsx),
+}
+
+impl ServerTransaction {
+    async fn respond_provisional(&mut self, response: &mut OutgoingResponse) -> Result<()> {
+        match self {
+            ServerTransaction::Invite(transaction) => {
+                transaction.respond_provisional(response).await
+            }
+            ServerTransaction::NonInvite(transaction) => {
+                transaction.respond_provisional(response).await
+            }
+        }
+    }
+
+    /// Respond with a final response, returns the [`Accepted`] state of an INVITE transaction responded with a 2XX response
+    async fn respond(self, response: OutgoingResponse) -> Result<Option<Accepted>> {
+        match self {
+            ServerTransaction::Invite(transaction) => {
+                if response.msg.line.code.kind() == CodeKind::Success {
+                    transaction.respond_success(response).await.map(Some)
+                } else {
+                    transaction.respond_failure(response).await.map(|_| None)
+                }
+            }
+            ServerTransaction::NonInvite(transaction) => {
+                transaction.respond(response).await.map(|_| None)
+            }
+        }
+    }
+}
+
+type BranchResponse = (usize, Result<Option<TsxResponse>>);
+
+fn start_branch(
+    endpoint: &Endpoint,
+    branch: usize,
+    request: Request,
+    cancelled: &watch::Sender<bool>,
+    responses: &mpsc::UnboundedSender<BranchResponse>,
+) {
+    tokio::spawn(run_branch(
+        endpoint.clone(),
+        branch,
+        request,
+        cancelled.subscribe(),
+        responses.clone(),
+    ));
+}
+
+/// Send a request to a single target and pass all responses to the [`ProxyLayer::fork`]
+///
+/// Requests without a final response are cancelled once `cancelled` is set.
+async fn run_branch(
+    endpoint: Endpoint,
+    branch: usize,
+    request: Request,
+    mut cancelled: watch::Receiver<bool>,
+    responses: mpsc::UnboundedSender<BranchResponse>,
+) {
+    let mut target = TargetTransportInfo::default();
+
+    if request.line.method != Method::INVITE {
+        let result = match endpoint.send_request(request, &mut target).await {
+            Ok(mut transaction) => loop {
+                match transaction.receive().await {
+                    Ok(response) if response.line.code.kind() == CodeKind::Provisional => {
+                        let _ = responses.send((branch, Ok(Some(response))));
+                    }
+                    result => break result.map(Some),
+                }
+            },
+            Err(e) => Err(e),
+        };
+
+        let _ = responses.send((branch, result));
+        return;
+    }
+
+    let mut transaction = match endpoint.send_invite(request, &mut target).await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            let _ = responses.send((branch, Err(e)));
+            return;
+        }
+    };
+
+    let mut proceeding = false;
+    let mut cancel_sent = false;
+    let mut timed_out = false;
+
+    let timer_c = sleep(TIMER_C);
+    tokio::pin!(timer_c);
+
+    loop {
+        // A CANCEL must only be sent after a provisional response was received (RFC 3261 Section 9.1)
+        let cancel = (*cancelled.borrow() || timed_out) && proceeding && !cancel_sent;
+
+        if cancel {
+            cancel_sent = true;
+            send_cancel(&transaction).await;
+        }
+
+        let result = select! {
+            result = transaction.receive() => result,
+            _ = cancelled.wait_for(|cancelled| *cancelled), if !cancel_sent && proceeding => continue,
+            _ = &mut timer_c, if !timed_out => {
+                // Treated like a 408 response, the branch is cancelled if possible (RFC 3261 Section 16.8)
+                log::debug!("Timer C of proxy branch fired");
+
+                timed_out = true;
+
+                if responses.send((branch, Err(Error::RequestTimedOut))).is_err() || !proceeding {
+                    return;
+                }
+
+                continue;
+            }
+        };
+
+        match result {
+            Ok(Some(response)) => {
+                proceeding = true;
+
+                if response.line.code.kind() == CodeKind::Provisional {
+                    timer_c.as_mut().reset((Instant::now() + TIMER_C).into());
+                }
+
+                if responses.send((branch, Ok(Some(response)))).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => return,
+            Err(e) => {
+                let _ = responses.send((branch, Err(e)));
+                return;
+            }
+        }
+    }
+}
+
+async fn send_cancel(transaction: &ClientInvTsx) {
+    match transaction.cancel().await {
+        Ok(Some(mut cancel)) => {
+            tokio::spawn(async move {
+                if let Err(e) = cancel.receive_final().await {
+                    log::debug!("Failed to receive response to CANCEL, {e}");
+                }
+            });
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to send CANCEL, {e}"),
+    }
+}
+
+/// Create the response forwarded to the sender of `request` by removing the topmost Via of `response`
+/// ([RFC 3261 Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7) step 9)
+fn create_upstream_response(
+    endpoint: &Endpoint,
+    request: &IncomingRequest,
+    response: &TsxResponse,
+) -> OutgoingResponse {
+    let mut upstream =
+        endpoint.create_response(request, response.line.code, response.line.reason.clone());
+
+    let mut headers = response.headers.clone();
+    headers.remove(&Name::VIA);
+    headers.remove(&Name::CONTENT_LENGTH);
+    headers.insert_named_front(&request.base_headers.via);
+
+    upstream.msg.headers = headers;
+    upstream.msg.body = response.body.clone();
+    upstream
+}
+
+fn is_challenge(name: &Name) -> bool {
+    *name == Name::WWW_AUTHENTICATE || *name == Name::PROXY_AUTHENTICATE
+}
+
+/// Replace the challenges of the forwarded 401 or 407 response with the challenges of all branches
+fn merge_challenges(response: &mut OutgoingResponse, challenges: &[(Name, BytesStr)]) {
+    response.msg.headers.remove(&Name::WWW_AUTHENTICATE);
+    response.msg.headers.remove(&Name::PROXY_AUTHENTICATE);
+
+    for (name, value) in challenges {
+        response.msg.headers.insert(name.clone(), value.clone());
+    }
+}
+
+fn max_forwards(request: &IncomingRequest) -> u8 {
+    request
+        .headers
+        .get_named::<MaxForwards>()
+        .map(|max_forwards| max_forwards.0)
+        .unwrap_or(70)
+}
+
+/// Returns if the final error response `candidate` is preferred over `current`
+/// ([RFC 3261 Section 16.7](https://datatracker.ietf.org/doc/html/rfc3261#section-16.7) step 6)
+///
+/// 6XX responses are preferred over all other responses, otherwise the response of the lowest class wins.
+fn is_better_response(candidate: Code, current: Code) -> bool {
+    fn rank(code: Code) -> u16 {
+        match code.kind() {
+            CodeKind::GlobalFailure => 0,
+            _ => code.into_u16() / 100,
+        }
+    }
+
+    rank(candidate) < rank(current)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::udp::Udp;
+    use crate::LayerKey;
+    use sip_types::header::typed::{FromTo, Unsupported};
+    use sip_types::print::{AppendCtx, PrintCtx};
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Locator forwarding every request to a fixed set of targets
+    struct Targets(Vec<SipUri>);
+
+    #[async_trait::async_trait]
+    impl Locator for Targets {
+        async fn locate(&self, _: &IncomingRequest) -> Vec<Box<dyn Uri>> {
+            self.0
+                .iter()
+                .map(|uri| Box::new(uri.clone()) as Box<dyn Uri>)
+                .collect()
+        }
+    }
+
+    /// Locator mapping the user of the Request-URI to a new user and address
+    struct Users(Vec<(&'static str, &'static str, SocketAddr)>);
+
+    #[async_trait::async_trait]
+    impl Locator for Users {
+        async fn locate(&self, request: &IncomingRequest) -> Vec<Box<dyn Uri>> {
+            let uri = request.line.uri.print_ctx(PrintCtx::default()).to_string();
+
+            self.0
+                .iter()
+                .filter(|(user, ..)| uri.starts_with(&format!("sip:{user}@")))
+                .map(|(_, target, addr)| -> Box<dyn Uri> {
+                    Box::new(format!("sip:{target}@{addr}").parse::<SipUri>().unwrap())
+                })
+                .collect()
+        }
+    }
+
+    /// UAS responding to every request with `code`
+    ///
+    /// INVITE requests responded with a provisional response are kept until they are cancelled.
+    struct Uas {
+        code: Code,
+        received: Arc<AtomicUsize>,
+        cancelled: Arc<AtomicUsize>,
+        pending: Mutex<Option<(ServerInvTsx, IncomingRequest)>>,
+    }
+
+    impl Uas {
+        fn new(code: Code) -> Self {
+            Self {
+                code,
+                received: Default::default(),
+                cancelled: Default::default(),
+                pending: Mutex::new(None),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Layer for Uas {
+        fn name(&self) -> &'static str {
+            "test-uas"
+        }
+
+        async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+            let mut request = request.take();
+
+            match request.line.method {
+                Method::ACK => {}
+                Method::CANCEL => {
+                    self.cancelled.fetch_add(1, Ordering::SeqCst);
+
+                    let response = endpoint.create_response(&request, Code::OK, None);
+                    let tsx = endpoint.create_server_tsx(&mut request);
+                    tsx.respond(response).await.unwrap();
+
+                    let pending = self.pending.lock().take();
+
+                    if let Some((tsx, invite)) = pending {
+                        let response =
+                            endpoint.create_response(&invite, Code::REQUEST_TERMINATED, None);
+                        tsx.respond_failure(response).await.unwrap();
+                    }
+                }
+                Method::INVITE => {
+                    self.received.fetch_add(1, Ordering::SeqCst);
+
+                    let mut tsx = endpoint.create_server_inv_tsx(&mut request);
+                    let mut response = endpoint.create_response(&request, self.code, None);
+
+                    if self.code.kind() == CodeKind::Provisional {
+                        tsx.respond_provisional(&mut response).await.unwrap();
+                        *self.pending.lock() = Some((tsx, request));
+                    } else {
+                        tsx.respond_failure(response).await.unwrap();
+                    }
+                }
+                _ => {
+                    self.received.fetch_add(1, Ordering::SeqCst);
+
+                    let mut response = endpoint.create_response(&request, self.code, None);
+
+                    if self.code == Code::UNAUTHORIZED {
+                        response.msg.headers.insert(
+                            Name::WWW_AUTHENTICATE,
+                            BytesStr::from_static("Digest realm=\"a\", nonce=\"1\""),
+                        );
+                    } else if self.code == Code::PROXY_AUTHENTICATION_REQUIRED {
+                        response.msg.headers.insert(
+                            Name::PROXY_AUTHENTICATE,
+                            BytesStr::from_static("Digest realm=\"b\", nonce=\"2\""),
+                        );
+                    }
+
+                    let tsx = endpoint.create_server_tsx(&mut request);
+                    tsx.respond(response).await.unwrap();
+                }
+            }
+        }
+    }
+
+    async fn spawn_endpoint<L: Layer>(layer: Option<L>) -> (Endpoint, SocketAddr) {
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+
+        if let Some(layer) = layer {
+            builder.add_layer(layer);
+        }
+
+        (builder.build(), transport.bound())
+    }
+
+    /// Spawn UAS endpoints responding with `codes` and a proxy forwarding to them
+    async fn spawn_proxy(
+        codes: &[Code],
+        fork_mode: ForkMode,
+    ) -> (Endpoint, SocketAddr, Vec<(Endpoint, LayerKey<Uas>)>) {
+        let mut targets = vec![];
+        let mut uas = vec![];
+
+        for code in codes {
+            let mut builder = Endpoint::builder();
+            let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+            let key = builder.add_layer(Uas::new(*code));
+
+            targets.push(format!("sip:{}", transport.bound()).parse().unwrap());
+            uas.push((builder.build(), key));
+        }
+
+        let proxy = ProxyLayer::new(Targets(targets)).with_fork_mode(fork_mode);
+        let (proxy, addr) = spawn_endpoint(Some(proxy)).await;
+
+        (proxy, addr, uas)
+    }
+
+    fn request(method: Method, proxy: SocketAddr) -> Request {
+        let uri: SipUri = format!("sip:bob@{proxy}").parse().unwrap();
+
+        Request::builder(method, uri.clone())
+            .from(FromTo::new(
+                NameAddr::uri(uri.clone()),
+                Some("from-tag".into()),
+            ))
+            .to(FromTo::new(NameAddr::uri(uri), None))
+            .build()
+    }
+
+    async fn send_request(method: Method, proxy: SocketAddr) -> TsxResponse {
+        let (uac, _) = spawn_endpoint::<Uas>(None).await;
+
+        let mut transaction = uac
+            .send_request(request(method, proxy), &mut TargetTransportInfo::default())
+            .await
+            .unwrap();
+
+        transaction.receive_final().await.unwrap()
+    }
+
+    fn received(uas: &(Endpoint, LayerKey<Uas>)) -> usize {
+        uas.0[uas.1].received.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn parallel_forking() {
+        let (_proxy, proxy, uas) =
+            spawn_proxy(&[Code::NOT_FOUND, Code::OK], ForkMode::Parallel).await;
+
+        let response = send_request(Method::OPTIONS, proxy).await;
+
+        assert_eq!(response.line.code, Code::OK);
+        assert_eq!(received(&uas[0]), 1);
+        assert_eq!(received(&uas[1]), 1);
+    }
+
+    #[tokio::test]
+    async fn sequential_forking() {
+        let (_proxy, proxy, uas) = spawn_proxy(
+            &[Code::NOT_FOUND, Code::DECLINE, Code::OK],
+            ForkMode::Sequential,
+        )
+        .await;
+
+        let response = send_request(Method::OPTIONS, proxy).await;
+
+        // forking stops after the 6XX response
+        assert_eq!(response.line.code, Code::DECLINE);
+        assert_eq!(received(&uas[0]), 1);
+        assert_eq!(received(&uas[1]), 1);
+        assert_eq!(received(&uas[2]), 0);
+    }
+
+    #[tokio::test]
+    async fn service_unavailable_is_mapped() {
+        let (_proxy, proxy, _uas) =
+            spawn_proxy(&[Code::SERVICE_UNAVAILABLE], ForkMode::Parallel).await;
+
+        let response = send_request(Method::OPTIONS, proxy).await;
+
+        assert_eq!(response.line.code, Code::SERVER_INTERNAL_ERROR);
+    }
+
+    #[tokio::test]
+    async fn challenges_are_merged() {
+        let (_proxy, proxy, _uas) = spawn_proxy(
+            &[Code::UNAUTHORIZED, Code::PROXY_AUTHENTICATION_REQUIRED],
+            ForkMode::Parallel,
+        )
+        .await;
+
+        let response = send_request(Method::OPTIONS, proxy).await;
+
+        assert!(matches!(
+            response.line.code,
+            Code::UNAUTHORIZED | Code::PROXY_AUTHENTICATION_REQUIRED
+        ));
+        assert!(response.headers.contains(&Name::WWW_AUTHENTICATE));
+        assert!(response.headers.contains(&Name::PROXY_AUTHENTICATE));
+    }
+
+    #[tokio::test]
+    async fn cancel_is_propagated() {
+        let (_proxy, proxy, uas) = spawn_proxy(&[Code::RINGING], ForkMode::Parallel).await;
+        let (uac, _) = spawn_endpoint::<Uas>(None).await;
+
+        let mut transaction = uac
+            .send_invite(
+                request(Method::INVITE, proxy),
+                &mut TargetTransportInfo::default(),
+            )
+            .await
+            .unwrap();
+
+        loop {
+            let response = transaction.receive().await.unwrap().unwrap();
+
+            if response.line.code == Code::RINGING {
+                break;
+            }
+        }
+
+        let mut cancel = transaction.cancel().await.unwrap().unwrap();
+        assert_eq!(cancel.receive_final().await.unwrap().line.code, Code::OK);
+
+        let response = transaction.receive().await.unwrap().unwrap();
+        assert_eq!(response.line.code, Code::REQUEST_TERMINATED);
+
+        assert_eq!(uas[0].0[uas[0].1].cancelled.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn spiral_is_forwarded() {
+        let mut builder = Endpoint::builder();
+        let uas_transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let key = builder.add_layer(Uas::new(Code::OK));
+        let uas = (builder.build(), key);
+
+        let mut proxy_builder = Endpoint::builder();
+        let proxy = Udp::spawn(&mut proxy_builder, "127.0.0.1:0")
+            .await
+            .unwrap()
+            .bound();
+
+        // Second proxy rewriting the Request-URI and sending it back to the first one
+        let mut builder = Endpoint::builder();
+        let rewriter = Udp::spawn(&mut builder, "127.0.0.1:0")
+            .await
+            .unwrap()
+            .bound();
+        builder.add_layer(ProxyLayer::new(Users(vec![("carol", "dave", proxy)])));
+        let _rewriter = builder.build();
+
+        proxy_builder.add_layer(ProxyLayer::new(Users(vec![
+            ("bob", "carol", rewriter),
+            ("dave", "dave", uas_transport.bound()),
+        ])));
+        let _proxy = proxy_builder.build();
+
+        let response = send_request(Method::OPTIONS, proxy).await;
+
+        assert_eq!(response.line.code, Code::OK);
+        assert_eq!(received(&uas), 1);
+    }
+
+    #[tokio::test]
+    async fn require_is_not_checked() {
+        let mut builder = Endpoint::builder();
+        let uas_transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        builder.add_supported("100rel");
+        let key = builder.add_layer(Uas::new(Code::OK));
+        let uas = (builder.build(), key);
+
+        let target = format!("sip:{}", uas_transport.bound()).parse().unwrap();
+        let (_proxy, proxy) = spawn_endpoint(Some(ProxyLayer::new(Targets(vec![target])))).await;
+        let (uac, _) = spawn_endpoint::<Uas>(None).await;
+
+        let mut request = request(Method::OPTIONS, proxy);
+        request
+            .headers
+            .insert(Name::REQUIRE, BytesStr::from_static("100rel"));
+
+        let mut transaction = uac
+            .send_request(request, &mut TargetTransportInfo::default())
+            .await
+            .unwrap();
+        let response = transaction.receive_final().await.unwrap();
+
+        assert_eq!(response.line.code, Code::OK);
+        assert_eq!(received(&uas), 1);
+    }
+
+    #[tokio::test]
+    async fn unsupported_proxy_require() {
+        let (_proxy, proxy, uas) = spawn_proxy(&[Code::OK], ForkMode::Parallel).await;
+        let (uac, _) = spawn_endpoint::<Uas>(None).await;
+
+        let mut request = request(Method::OPTIONS, proxy);
+        request
+            .headers
+            .insert(Name::PROXY_REQUIRE, BytesStr::from_static("foo"));
+
+        let mut transaction = uac
+            .send_request(request, &mut TargetTransportInfo::default())
+            .await
+            .unwrap();
+        let response = transaction.receive_final().await.unwrap();
+
+        assert_eq!(response.line.code, Code::BAD_EXTENSION);
+        assert_eq!(
+            response.headers.get_named::<Vec<Unsupported>>().unwrap(),
+            [Unsupported(BytesStr::from_static("foo"))]
+        );
+        assert_eq!(received(&uas[0]), 0);
+    }
+
+    #[test]
+    fn best_response() {
+        assert!(is_better_response(Code::DECLINE, Code::NOT_FOUND));
+        assert!(is_better_response(
+            Code::NOT_FOUND,
+            Code::SERVICE_UNAVAILABLE
+        ));
+        assert!(is_better_response(Code::MOVED_TEMPORARILY, Code::BUSY_HERE));
+        assert!(!is_better_response(Code::BUSY_HERE, Code::NOT_FOUND));
+        assert!(!is_better_response(
+            Code::SERVER_INTERNAL_ERROR,
+            Code::DECLINE
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+//! Rate limiting of incoming requests per source IP address
+
+use crate::{Endpoint, IncomingRequest, Layer, MayTake, Result};
+use parking_lot::Mutex;
+use sip_types::header::typed::RetryAfter;
+use sip_types::{Code, Method};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Interval in which idle entries are removed from the rate limiter
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Layer which limits the rate of incoming requests per source IP address using a token bucket.
+///
+/// Requests exceeding the limit are answered with `503 Service Unavailable` and a `Retry-After` header.
+/// ACK requests exceeding the limit are silently dropped.
+///
+/// The layer must be added before any other layer to the endpoint, since layers receive
+/// requests in insertion order.
+pub struct RateLimitLayer {
+    /// Requests per second each source IP address may send
+    rate: f64,
+
+    /// Maximum number of requests that can be sent at once
+    burst: f64,
+
+    /// Value of the Retry-After header in responses to rejected requests
+    retry_after: u32,
+
+    state: Mutex<State>,
+}
+
+struct State {
+    buckets: HashMap<IpAddr, Bucket>,
+    last_cleanup: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_update: Instant,
+}
+
+impl RateLimitLayer {
+    /// Create a new rate limiting layer, allowing each IP address to send `rate` requests per second
+    /// with bursts of up to `burst` requests.
+    pub fn new(rate: u32, burst: u32) -> Self {
+        assert!(rate > 0, "rate must not be zero");
+
+        Self {
+            rate: f64::from(rate),
+            burst: f64::from(burst.max(1)),
+            retry_after: 5,
+            state: Mutex::new(State {
+                buckets: HashMap::new(),
+                last_cleanup: Instant::now(),
+            }),
+        }
+    }
+
+    /// Set the value of the Retry-After header sent when rejecting requests, in seconds (default 5)
+    pub fn with_retry_after(mut self, retry_after: u32) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Take a token from the source's bucket, returns false if the bucket is empty
+    fn try_acquire(&self, source: IpAddr, now: Instant) -> bool {
+        let mut state = self.state.lock();
+
+        if now.duration_since(state.last_cleanup) > CLEANUP_INTERVAL {
+            state.last_cleanup = now;
+
+            // Buckets which would be refilled completely can be removed
+            let refill_duration = Duration::from_secs_f64(self.burst / self.rate);
+
+            state
+                .buckets
+                .retain(|_, bucket| now.duration_since(bucket.last_update) < refill_duration);
+        }
+
+        let bucket = state.buckets.entry(source).or_insert(Bucket {
+            tokens: self.burst,
+            last_update: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_update).as_secs_f64();
+
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.last_update = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    async fn reject(&self, endpoint: &Endpoint, mut request: IncomingRequest) -> Result<()> {
+        if request.line.method == Method::ACK {
+            // Cannot respond to ACK requests
+            return Ok(());
+        }
+
+        let mut response = endpoint.create_response(&request, Code::SERVICE_UNAVAILABLE, None);
+
+        response
+            .msg
+            .headers
+            .insert_named(&RetryAfter::new(self.retry_after));
+
+        if request.line.method == Method::INVITE {
+            let tsx = endpoint.create_server_inv_tsx(&mut request);
+
+            tsx.respond_failure(response).await
+        } else {
+            let tsx = endpoint.create_server_tsx(&mut request);
+
+            tsx.respond(response).await
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Layer for RateLimitLayer {
+    fn name(&self) -> &'static str {
+        "rate-limit"
+    }
+
+    async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+        let source = request.tp_info.source.ip();
+
+        if self.try_acquire(source, Instant::now()) {
+            return;
+        }
+
+        log::debug!("Rate limit exceeded for {source}, rejecting request");
+
+        if let Err(e) = self.reject(endpoint, request.take()).await {
+            log::warn!("Failed to reject rate limited request, {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn token_bucket() {
+        let layer = RateLimitLayer::new(2, 3);
+
+        let a = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+        let now = Instant::now();
+
+        // burst is allowed
+        assert!(layer.try_acquire(a, now));
+        assert!(layer.try_acquire(a, now));
+        assert!(layer.try_acquire(a, now));
+        assert!(!layer.try_acquire(a, now));
+
+        // other sources are not affected
+        assert!(layer.try_acquire(b, now));
+
+        // refilled with 2 tokens per second
+        let now = now + Duration::from_millis(500);
+        assert!(layer.try_acquire(a, now));
+        assert!(!layer.try_acquire(a, now));
+    }
+}
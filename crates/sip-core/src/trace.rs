@@ -0,0 +1,61 @@
+//! Hooks to observe all SIP messages received and sent by an [`Endpoint`](crate::Endpoint)
+//!
+//! Tracers see the raw message buffers as they are passed from or to the transports,
+//! which makes them suitable for siptrace-style logging or exporting messages to external
+//! capture systems (e.g. HEP/EEP).
+
+use crate::transport::TpHandle;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+/// Direction of a traced message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceDirection {
+    /// The message was received from a transport
+    Incoming,
+
+    /// The message is about to be sent using a transport
+    Outgoing,
+}
+
+/// Message passed to a [`MessageTracer`]
+#[derive(Debug)]
+pub struct TracedMessage<'a> {
+    pub direction: TraceDirection,
+
+    /// Timestamp the message was received at or is sent at
+    pub timestamp: SystemTime,
+
+    /// Transport the message was received on or is sent with
+    pub transport: &'a TpHandle,
+
+    /// Address of the peer, source of incoming and destination of outgoing messages
+    pub remote: SocketAddr,
+
+    /// The raw message
+    pub buffer: &'a [u8],
+}
+
+impl TracedMessage<'_> {
+    /// Local address of the transport
+    pub fn local(&self) -> SocketAddr {
+        self.transport.bound()
+    }
+}
+
+/// Receives every SIP message passing through the endpoint.
+///
+/// Tracers are called inline while handling messages and must not block.
+/// Implemented for closures taking a [`TracedMessage`].
+pub trait MessageTracer: Send + Sync + 'static {
+    fn trace(&self, message: &TracedMessage<'_>);
+}
+
+impl<F> MessageTracer for F
+where
+    F: Fn(&TracedMessage<'_>) + Send + Sync + 'static,
+{
+    fn trace(&self, message: &TracedMessage<'_>) {
+        self(message)
+    }
+}
@@ -1,6 +1,5 @@
 use super::consts::{T1, T2};
-use super::key::TsxKey;
-use super::{TsxRegistration, TsxResponse};
+use super::{TsxKey, TsxRegistration, TsxResponse, TsxState};
 use crate::error::Error;
 use crate::transaction::consts::T4;
 use crate::transport::{OutgoingRequest, TargetTransportInfo};
@@ -49,7 +48,10 @@ impl ClientTsx {
 
         let mut request = endpoint.create_outgoing(request, target).await?;
 
-        let registration = TsxRegistration::create(endpoint, TsxKey::client(&method));
+        let tsx_key = endpoint.create_client_tsx_key(&method);
+
+        let registration =
+            TsxRegistration::create(endpoint, tsx_key, method, request.parts.destination);
 
         let via = registration.endpoint.create_via(
             &request.parts.transport,
@@ -58,6 +60,33 @@ impl ClientTsx {
         );
 
         request.msg.headers.insert_named_front(&via);
+        registration.set_loop_key(&request.msg);
+        registration
+            .endpoint
+            .send_outgoing_request(&mut request)
+            .await?;
+
+        let timeout = Instant::now() + T1 * 64;
+
+        Ok(Self {
+            registration: Some(registration),
+            request,
+            timeout,
+            state: State::Init,
+        })
+    }
+
+    /// Internal: Used by [`ClientInvTsx::cancel`](super::ClientInvTsx::cancel)
+    ///
+    /// The CANCEL request is already complete, as it must contain the Via of the INVITE request it cancels.
+    pub(crate) async fn send_cancel(
+        endpoint: Endpoint,
+        mut request: OutgoingRequest,
+        tsx_key: TsxKey,
+    ) -> Result<Self> {
+        let registration =
+            TsxRegistration::create(endpoint, tsx_key, Method::CANCEL, request.parts.destination);
+
         registration
             .endpoint
             .send_outgoing_request(&mut request)
@@ -106,6 +135,8 @@ impl ClientTsx {
                                 .endpoint
                                 .send_outgoing_request(&mut self.request)
                                 .await?;
+
+                            registration.retransmitted();
                         }
                         Err(_) => return Err(Error::RequestTimedOut),
                     }
@@ -142,6 +173,10 @@ impl ClientTsx {
         match response.line.code.kind() {
             CodeKind::Provisional => {
                 self.state = State::Proceeding;
+
+                if let Some(registration) = &self.registration {
+                    registration.set_state(TsxState::Proceeding);
+                }
             }
             _ => {
                 let mut registration = self.registration.take().expect("already checked");
@@ -150,6 +185,7 @@ impl ClientTsx {
                     self.state = State::Terminated;
                 } else {
                     self.state = State::Completed;
+                    registration.set_state(TsxState::Completed);
 
                     // TODO can this be handled via tsx-registration instead of spawning a new task
                     tokio::spawn(async move {
@@ -1,12 +1,11 @@
 use super::consts::T1;
-use super::key::TsxKey;
-use super::{TsxRegistration, TsxResponse};
+use super::{ClientTsx, TsxKey, TsxRegistration, TsxResponse, TsxState};
 use crate::error::Error;
 use crate::transport::{OutgoingParts, OutgoingRequest, TargetTransportInfo};
 use crate::Result;
 use crate::{Endpoint, Request};
 use bytes::Bytes;
-use sip_types::header::typed::{CSeq, MaxForwards};
+use sip_types::header::typed::{CSeq, MaxForwards, Via};
 use sip_types::header::HeaderError;
 use sip_types::msg::RequestLine;
 use sip_types::{CodeKind, Headers, Method, Name};
@@ -59,7 +58,10 @@ impl ClientInvTsx {
 
         let mut request = endpoint.create_outgoing(request, target).await?;
 
-        let registration = TsxRegistration::create(endpoint, TsxKey::client(&Method::INVITE));
+        let tsx_key = endpoint.create_client_tsx_key(&Method::INVITE);
+
+        let registration =
+            TsxRegistration::create(endpoint, tsx_key, Method::INVITE, request.parts.destination);
 
         let via = registration.endpoint.create_via(
             &request.parts.transport,
@@ -68,6 +70,7 @@ impl ClientInvTsx {
         );
 
         request.msg.headers.insert_named_front(&via);
+        registration.set_loop_key(&request.msg);
         registration
             .endpoint
             .send_outgoing_request(&mut request)
@@ -88,6 +91,27 @@ impl ClientInvTsx {
         &self.request
     }
 
+    /// Cancel the INVITE request by sending a CANCEL request ([RFC 3261 Section 9.1](https://datatracker.ietf.org/doc/html/rfc3261#section-9.1))
+    ///
+    /// Should only be called after a provisional response was received. Returns the transaction of the
+    /// CANCEL request, or `None` if a final response was already received and there is nothing to cancel.
+    ///
+    /// The INVITE transaction must still be driven, to receive the final response (usually
+    /// `487 Request Terminated`) or a 2XX response which crossed the CANCEL.
+    pub async fn cancel(&self) -> Result<Option<ClientTsx>> {
+        let registration = match (&self.registration, &self.state) {
+            (Some(registration), State::Init | State::Proceeding) => registration,
+            _ => return Ok(None),
+        };
+
+        let cancel = create_cancel(&self.request)?;
+        let tsx_key = TsxKey::client_with_branch(registration.tsx_key.branch(), &Method::CANCEL);
+
+        ClientTsx::send_cancel(registration.endpoint.clone(), cancel, tsx_key)
+            .await
+            .map(Some)
+    }
+
     /// Receive one or more responses.
     ///
     /// The return type differs from [`ClientTsx::receive`](super::ClientTsx::receive)
@@ -119,6 +143,8 @@ impl ClientInvTsx {
                                 .send_outgoing_request(&mut self.request)
                                 .await?;
 
+                            registration.retransmitted();
+
                             n *= 2;
                         }
                         Err(_) => return Err(Error::RequestTimedOut),
@@ -149,10 +175,18 @@ impl ClientInvTsx {
             CodeKind::Provisional => {
                 self.timeout = Instant::now() + T1 * 240; // 2 minutes
                 self.state = State::Proceeding;
+
+                if let Some(registration) = &self.registration {
+                    registration.set_state(TsxState::Proceeding);
+                }
             }
             CodeKind::Success => {
                 self.timeout = Instant::now() + T1 * 64;
                 self.state = State::Accepted;
+
+                if let Some(registration) = &self.registration {
+                    registration.set_state(TsxState::Accepted);
+                }
             }
             _ => {
                 let mut registration = self.registration.take().expect("already checked");
@@ -168,6 +202,7 @@ impl ClientInvTsx {
                     self.state = State::Terminated;
                 } else {
                     self.state = State::Completed;
+                    registration.set_state(TsxState::Completed);
 
                     tokio::spawn(async move {
                         let timeout = Instant::now() + Duration::from_secs(32);
@@ -197,7 +232,8 @@ fn create_ack(
 ) -> Result<OutgoingRequest, HeaderError> {
     let mut headers = Headers::with_capacity(5);
 
-    request.msg.headers.clone_into(&mut headers, Name::VIA)?;
+    // Must only contain the top Via of the request, which is relevant when the INVITE was forwarded by a proxy
+    headers.insert_named(&request.msg.headers.get_named::<Via>()?);
     request.msg.headers.clone_into(&mut headers, Name::FROM)?;
     response.headers.clone_into(&mut headers, Name::TO)?;
     request
@@ -229,3 +265,45 @@ fn create_ack(
         },
     })
 }
+
+/// Create a CANCEL request for the INVITE `request` ([RFC 3261 Section 9.1](https://datatracker.ietf.org/doc/html/rfc3261#section-9.1))
+fn create_cancel(request: &OutgoingRequest) -> Result<OutgoingRequest, HeaderError> {
+    let mut headers = Headers::with_capacity(6);
+
+    // The top Via contains the branch of the INVITE, which is used to match the CANCEL
+    headers.insert_named(&request.msg.headers.get_named::<Via>()?);
+    request.msg.headers.clone_into(&mut headers, Name::FROM)?;
+    request.msg.headers.clone_into(&mut headers, Name::TO)?;
+    request
+        .msg
+        .headers
+        .clone_into(&mut headers, Name::CALL_ID)?;
+    headers.insert_named(&MaxForwards(70));
+
+    let cseq = request.msg.headers.get_named::<CSeq>()?;
+
+    headers.insert_named(&CSeq {
+        cseq: cseq.cseq,
+        method: Method::CANCEL,
+    });
+
+    if request.msg.headers.contains(&Name::ROUTE) {
+        request.msg.headers.clone_into(&mut headers, Name::ROUTE)?;
+    }
+
+    Ok(OutgoingRequest {
+        msg: Request {
+            line: RequestLine {
+                method: Method::CANCEL,
+                uri: request.msg.line.uri.clone(),
+            },
+            headers,
+            body: Bytes::new(),
+        },
+        parts: OutgoingParts {
+            transport: request.parts.transport.clone(),
+            destination: request.parts.destination,
+            buffer: Default::default(),
+        },
+    })
+}
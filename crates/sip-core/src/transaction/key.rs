@@ -1,5 +1,5 @@
 use super::consts::RFC3261_BRANCH_PREFIX;
-use super::generate_branch;
+use crate::id::{to_branch, IdGenerator, RandomIdGenerator};
 use crate::BaseHeaders;
 use bytesstr::BytesStr;
 use sip_types::header::typed::{CSeq, Via};
@@ -88,11 +88,18 @@ impl TsxKey {
         }
     }
 
+    /// Create a client key with a random branch, use [`Endpoint::create_client_tsx_key`](crate::Endpoint::create_client_tsx_key)
+    /// to respect the endpoint's [`IdGenerator`].
     #[inline]
     pub fn client(method: &Method) -> Self {
+        Self::client_with_branch(&to_branch(RandomIdGenerator.branch()), method)
+    }
+
+    /// Key of the client transaction which would have created a Via header with the given branch
+    pub fn client_with_branch(branch: &BytesStr, method: &Method) -> Self {
         TsxKey(Repr::RFC3261(Rfc3261 {
             role: Role::Client,
-            branch: generate_branch(),
+            branch: branch.clone(),
             method: filter_method(method),
         }))
     }
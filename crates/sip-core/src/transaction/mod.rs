@@ -4,11 +4,15 @@ use bytes::Bytes;
 use bytesstr::BytesStr;
 use parking_lot::lock_api::MutexGuard;
 use parking_lot::{MappedMutexGuard, Mutex};
-use sip_types::msg::{MessageLine, StatusLine};
-use sip_types::Headers;
+use sip_types::header::typed::Via;
+use sip_types::msg::{MessageLine, RequestLine, StatusLine};
+use sip_types::print::AppendCtx;
+use sip_types::{Headers, Method};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
 
 mod client;
 mod client_inv;
@@ -37,9 +41,141 @@ pub(crate) use registration::TsxRegistration;
 
 pub(crate) type TsxHandler = Box<dyn Fn(TsxMessage) -> Option<TsxMessage> + Send + Sync>;
 
-#[derive(Default)]
+/// State of a transaction, as reported by [`Endpoint::transactions_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsxState {
+    /// Client INVITE transaction sent the request and awaits a response
+    Calling,
+    /// Non-INVITE transaction was created and hasn't seen/sent a provisional response yet
+    Trying,
+    /// A provisional response was received or sent
+    Proceeding,
+    /// A final response was received or sent, the transaction is absorbing retransmissions
+    Completed,
+    /// INVITE transaction received or sent a success response
+    Accepted,
+}
+
+/// Snapshot of a transaction which is currently registered inside the endpoint
+#[derive(Debug, Clone)]
+pub struct TsxInfo {
+    pub key: TsxKey,
+    pub method: Method,
+    pub state: TsxState,
+
+    /// Time the transaction was created at
+    pub created: Instant,
+
+    /// Address of the peer the transaction is communicating with
+    pub remote: SocketAddr,
+
+    /// Number of times the request (client) or response (server) has been retransmitted
+    pub retransmissions: u32,
+}
+
+impl TsxInfo {
+    /// Time elapsed since the transaction was created
+    pub fn age(&self) -> Duration {
+        self.created.elapsed()
+    }
+}
+
+/// Number of transactions currently registered inside the endpoint
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TsxCounts {
+    pub client: usize,
+    pub server: usize,
+}
+
+impl TsxCounts {
+    pub fn total(&self) -> usize {
+        self.client + self.server
+    }
+}
+
+/// Event emitted when a transaction is created or terminated
+#[derive(Debug, Clone)]
+pub enum TsxEvent {
+    Created(TsxInfo),
+    Terminated(TsxInfo),
+}
+
+struct TsxEntry {
+    handler: TsxHandler,
+    info: TsxInfo,
+    merge_key: Option<MergeKey>,
+    loop_key: Option<LoopKey>,
+}
+
+/// Identifies a request which arrived over multiple paths, e.g. because a proxy forked it
+/// ([RFC3261 Section 8.2.2.2](https://datatracker.ietf.org/doc/html/rfc3261#section-8.2.2.2))
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+struct MergeKey {
+    from_tag: BytesStr,
+    call_id: BytesStr,
+    cseq: u32,
+    method: Method,
+}
+
+impl MergeKey {
+    /// Only requests outside of a dialog (without To tag) can be merged
+    fn from_headers(base_headers: &BaseHeaders) -> Option<Self> {
+        if base_headers.to.tag.is_some() {
+            return None;
+        }
+
+        Some(Self {
+            from_tag: base_headers.from.tag.clone()?,
+            call_id: base_headers.call_id.0.clone(),
+            cseq: base_headers.cseq.cseq,
+            method: base_headers.cseq.method.clone(),
+        })
+    }
+}
+
+/// Values of a request sent by a client transaction which a proxy would hash into the branch parameter
+/// ([RFC3261 Section 16.6 step 8](https://datatracker.ietf.org/doc/html/rfc3261#section-16.6)).
+///
+/// A request carrying the transaction's branch is only looped if these are unchanged,
+/// otherwise it spiraled (e.g. the Request-URI was rewritten) and must be handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LoopKey {
+    request_uri: String,
+    from_tag: Option<BytesStr>,
+    to_tag: Option<BytesStr>,
+    call_id: BytesStr,
+    cseq: u32,
+    /// Branch of the Via below the transaction's own Via
+    previous_branch: Option<BytesStr>,
+}
+
+impl LoopKey {
+    fn new(line: &RequestLine, base_headers: &BaseHeaders, previous_via: Option<&Via>) -> Self {
+        Self {
+            request_uri: line.uri.default_print_ctx().to_string(),
+            from_tag: base_headers.from.tag.clone(),
+            to_tag: base_headers.to.tag.clone(),
+            call_id: base_headers.call_id.0.clone(),
+            cseq: base_headers.cseq.cseq,
+            previous_branch: previous_via.and_then(|via| via.params.get_val("branch").cloned()),
+        }
+    }
+}
+
 pub(crate) struct Transactions {
-    map: Mutex<HashMap<TsxKey, TsxHandler>>,
+    map: Mutex<HashMap<TsxKey, TsxEntry>>,
+    merge_index: Mutex<HashMap<MergeKey, TsxKey>>,
+    events: broadcast::Sender<TsxEvent>,
+}
+
+impl Default for Transactions {
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+            merge_index: Default::default(),
+            events: broadcast::channel(64).0,
+        }
+    }
 }
 
 impl Transactions {
@@ -47,19 +183,31 @@ impl Transactions {
         &'a self,
         endoint: &Endpoint,
         tsx_key: &TsxKey,
+        method: &Method,
+        remote: SocketAddr,
     ) -> Result<MappedMutexGuard<'a, TsxHandler>, TsxRegistration> {
         let map = self.map.lock();
 
-        let mut map = match MutexGuard::try_map(map, |map| map.get_mut(tsx_key)) {
+        let mut map = match MutexGuard::try_map(map, |map| {
+            map.get_mut(tsx_key).map(|entry| &mut entry.handler)
+        }) {
             Ok(handler) => return Ok(handler),
             Err(map) => map,
         };
 
         let (sender, receiver) = mpsc::unbounded_channel();
 
+        let info = TsxInfo::new(tsx_key.clone(), method.clone(), remote);
+        let _ = self.events.send(TsxEvent::Created(info.clone()));
+
         map.insert(
             tsx_key.clone(),
-            Box::new(move |msg| sender.send(msg).map_err(|e| e.0).err()),
+            TsxEntry {
+                handler: Box::new(move |msg| sender.send(msg).map_err(|e| e.0).err()),
+                info,
+                merge_key: None,
+                loop_key: None,
+            },
         );
 
         Err(TsxRegistration {
@@ -69,19 +217,156 @@ impl Transactions {
         })
     }
 
-    pub(crate) fn register_transaction(&self, key: TsxKey, handler: TsxHandler) {
+    pub(crate) fn register_transaction(&self, info: TsxInfo, handler: TsxHandler) {
         let mut map = self.map.lock();
 
-        match map.entry(key) {
+        match map.entry(info.key.clone()) {
             Entry::Occupied(e) => panic!("Tried to create a second transaction for {:?}", e.key()),
             Entry::Vacant(e) => {
-                e.insert(handler);
+                let _ = self.events.send(TsxEvent::Created(info.clone()));
+
+                e.insert(TsxEntry {
+                    handler,
+                    info,
+                    merge_key: None,
+                    loop_key: None,
+                });
             }
         }
     }
 
     pub(crate) fn remove_transaction(&self, key: &TsxKey) {
-        self.map.lock().remove(key);
+        let entry = self.map.lock().remove(key);
+
+        if let Some(entry) = entry {
+            if let Some(merge_key) = entry.merge_key {
+                let mut merge_index = self.merge_index.lock();
+
+                if merge_index.get(&merge_key) == Some(key) {
+                    merge_index.remove(&merge_key);
+                }
+            }
+
+            let _ = self.events.send(TsxEvent::Terminated(entry.info));
+        }
+    }
+
+    /// Returns if the new server transaction `key` was created by a request which is already
+    /// handled by another server transaction ([RFC3261 Section 8.2.2.2](https://datatracker.ietf.org/doc/html/rfc3261#section-8.2.2.2))
+    pub(crate) fn is_merged(&self, key: &TsxKey, base_headers: &BaseHeaders) -> bool {
+        let Some(merge_key) = MergeKey::from_headers(base_headers) else {
+            return false;
+        };
+
+        let mut map = self.map.lock();
+        let mut merge_index = self.merge_index.lock();
+
+        match merge_index.entry(merge_key.clone()) {
+            Entry::Occupied(e) if e.get() != key && map.contains_key(e.get()) => return true,
+            Entry::Occupied(mut e) => {
+                e.insert(key.clone());
+            }
+            Entry::Vacant(e) => {
+                e.insert(key.clone());
+            }
+        }
+
+        if let Some(entry) = map.get_mut(key) {
+            entry.merge_key = Some(merge_key);
+        }
+
+        false
+    }
+
+    /// Remember the request sent by the client transaction `key`, to detect it looping back
+    ///
+    /// The request must already contain the transaction's Via as topmost Via header.
+    pub(crate) fn set_loop_key(&self, key: &TsxKey, line: &RequestLine, headers: &Headers) {
+        let Ok(base_headers) = BaseHeaders::extract_from(headers) else {
+            return;
+        };
+
+        if let Some(entry) = self.map.lock().get_mut(key) {
+            entry.loop_key = Some(LoopKey::new(line, &base_headers, base_headers.via.get(1)));
+        }
+    }
+
+    /// Returns if the request has been sent by one of this endpoint's client transactions
+    /// and has looped back to it unchanged. Requests which spiraled back are not looped.
+    pub(crate) fn is_looped(&self, line: &RequestLine, base_headers: &BaseHeaders) -> bool {
+        let map = self.map.lock();
+
+        base_headers.via.iter().enumerate().any(|(i, via)| {
+            let Some(branch) = via
+                .params
+                .get_val("branch")
+                .filter(|branch| branch.starts_with(consts::RFC3261_BRANCH_PREFIX))
+            else {
+                return false;
+            };
+
+            let key = TsxKey::client_with_branch(branch, &base_headers.cseq.method);
+
+            map.get(&key)
+                .and_then(|entry| entry.loop_key.as_ref())
+                .is_some_and(|loop_key| {
+                    *loop_key == LoopKey::new(line, base_headers, base_headers.via.get(i + 1))
+                })
+        })
+    }
+
+    pub(crate) fn retransmitted(&self, key: &TsxKey) {
+        if let Some(entry) = self.map.lock().get_mut(key) {
+            entry.info.retransmissions += 1;
+        }
+    }
+
+    pub(crate) fn subscribe_events(&self) -> broadcast::Receiver<TsxEvent> {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn set_state(&self, key: &TsxKey, state: TsxState) {
+        if let Some(entry) = self.map.lock().get_mut(key) {
+            entry.info.state = state;
+        }
+    }
+
+    pub(crate) fn info(&self) -> Vec<TsxInfo> {
+        self.map
+            .lock()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    pub(crate) fn counts(&self) -> TsxCounts {
+        let map = self.map.lock();
+
+        let server = map.keys().filter(|key| key.is_server()).count();
+
+        TsxCounts {
+            client: map.len() - server,
+            server,
+        }
+    }
+}
+
+impl TsxInfo {
+    fn new(key: TsxKey, method: Method, remote: SocketAddr) -> Self {
+        let state = match (key.is_server(), &method) {
+            (false, &Method::INVITE) => TsxState::Calling,
+            (true, &Method::INVITE) => TsxState::Proceeding,
+            _ => TsxState::Trying,
+        };
+
+        Self {
+            key,
+            method,
+            state,
+            created: Instant::now(),
+            remote,
+            retransmissions: 0,
+        }
     }
 }
 
@@ -107,14 +392,92 @@ pub struct TsxMessage {
     pub body: Bytes,
 }
 
-fn generate_branch() -> BytesStr {
-    use rand::distributions::Alphanumeric;
-    use rand::{thread_rng, Rng};
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sip_types::header::typed::{CSeq, CallID, FromTo};
+    use sip_types::uri::sip::SipUri;
+    use sip_types::uri::NameAddr;
+    use sip_types::Name;
+
+    const BRANCH: &str = "z9hG4bK-own";
+
+    fn request_line(uri: &str) -> RequestLine {
+        RequestLine {
+            method: Method::INVITE,
+            uri: Box::new(uri.parse::<SipUri>().unwrap()),
+        }
+    }
+
+    fn base_headers(vias: &[&str]) -> BaseHeaders {
+        let addr: SocketAddr = "127.0.0.1:5060".parse().unwrap();
+        let uri: SipUri = "sip:alice@example.org".parse().unwrap();
+
+        BaseHeaders {
+            via: vias
+                .iter()
+                .map(|branch| Via::new("UDP", addr, *branch))
+                .collect(),
+            from: FromTo::new(NameAddr::uri(uri.clone()), Some("from-tag".into())),
+            to: FromTo::new(NameAddr::uri(uri), None),
+            call_id: CallID::new("call-id"),
+            cseq: CSeq::new(1, Method::INVITE),
+        }
+    }
+
+    fn transactions_with_sent_request() -> Transactions {
+        let transactions = Transactions::default();
+        let key = TsxKey::client_with_branch(&BRANCH.into(), &Method::INVITE);
+
+        transactions.register_transaction(
+            TsxInfo::new(
+                key.clone(),
+                Method::INVITE,
+                "127.0.0.1:5060".parse().unwrap(),
+            ),
+            Box::new(|_| None),
+        );
+
+        // Request forwarded by this endpoint, with its own Via on top
+        let sent = base_headers(&[BRANCH, "z9hG4bK-upstream"]);
 
-    consts::RFC3261_BRANCH_PREFIX
-        .bytes()
-        .chain(thread_rng().sample_iter(Alphanumeric).take(23))
-        .map(char::from)
-        .collect::<String>()
-        .into()
+        let mut headers = Headers::new();
+        headers.insert_named(&sent.via[0]);
+        headers.insert_named(&sent.via[1]);
+        headers.insert_type(Name::FROM, &sent.from);
+        headers.insert_type(Name::TO, &sent.to);
+        headers.insert_named(&sent.call_id);
+        headers.insert_named(&sent.cseq);
+
+        transactions.set_loop_key(&key, &request_line("sip:bob@example.org"), &headers);
+        transactions
+    }
+
+    #[test]
+    fn looped_request() {
+        let transactions = transactions_with_sent_request();
+
+        let looped = base_headers(&["z9hG4bK-other", BRANCH, "z9hG4bK-upstream"]);
+
+        assert!(transactions.is_looped(&request_line("sip:bob@example.org"), &looped));
+    }
+
+    #[test]
+    fn spiraled_request() {
+        let transactions = transactions_with_sent_request();
+
+        let spiraled = base_headers(&["z9hG4bK-other", BRANCH, "z9hG4bK-upstream"]);
+
+        // The Request-URI was rewritten by the next hop
+        assert!(!transactions.is_looped(&request_line("sip:bob@192.0.2.1"), &spiraled));
+    }
+
+    #[test]
+    fn unknown_branch() {
+        let transactions = transactions_with_sent_request();
+
+        let other = base_headers(&["z9hG4bK-other", "z9hG4bK-upstream"]);
+
+        assert!(!transactions.is_looped(&request_line("sip:bob@example.org"), &other));
+    }
 }
@@ -1,10 +1,12 @@
 use core::mem::replace;
 
-use super::TsxResponse;
+use super::{TsxInfo, TsxResponse, TsxState};
 use crate::transaction::key::TsxKey;
 use crate::transaction::TsxMessage;
-use crate::Endpoint;
+use crate::{Endpoint, Request};
 use sip_types::msg::MessageLine;
+use sip_types::Method;
+use std::net::SocketAddr;
 use tokio::sync::mpsc;
 
 /// Internal: Used by every transaction impl to
@@ -19,11 +21,16 @@ pub(crate) struct TsxRegistration {
 }
 
 impl TsxRegistration {
-    pub(crate) fn create(endpoint: Endpoint, tsx_key: TsxKey) -> Self {
+    pub(crate) fn create(
+        endpoint: Endpoint,
+        tsx_key: TsxKey,
+        method: Method,
+        remote: SocketAddr,
+    ) -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
 
         endpoint.transactions().register_transaction(
-            tsx_key.clone(),
+            TsxInfo::new(tsx_key.clone(), method, remote),
             Box::new(move |msg| sender.send(msg).map_err(|e| e.0).err()),
         );
 
@@ -44,6 +51,7 @@ impl TsxRegistration {
         let mut tsx_map = transactions.map.lock();
         let handler = tsx_map
             .get_mut(&self.tsx_key)
+            .map(|entry| &mut entry.handler)
             .expect("registration is responsible of handler lifetime inside endpoint");
 
         let old_handler = replace(handler, Box::new(|_| unreachable!()));
@@ -57,6 +65,23 @@ impl TsxRegistration {
         });
     }
 
+    /// Remember the request sent by this client transaction, so it's detected when looping back
+    pub(crate) fn set_loop_key(&self, request: &Request) {
+        self.endpoint
+            .transactions()
+            .set_loop_key(&self.tsx_key, &request.line, &request.headers);
+    }
+
+    /// Update the transaction state reported by [`Endpoint::transactions_info`]
+    pub(crate) fn set_state(&self, state: TsxState) {
+        self.endpoint.transactions().set_state(&self.tsx_key, state);
+    }
+
+    /// Count a retransmission of the transaction's request or response
+    pub(crate) fn retransmitted(&self) {
+        self.endpoint.transactions().retransmitted(&self.tsx_key);
+    }
+
     pub(crate) async fn receive(&mut self) -> TsxMessage {
         self.receiver
             .recv()
@@ -1,5 +1,5 @@
 use super::consts::T1;
-use super::TsxRegistration;
+use super::{TsxRegistration, TsxState};
 use crate::transport::OutgoingResponse;
 use crate::{IncomingRequest, Result};
 use sip_types::{CodeKind, Method};
@@ -43,6 +43,8 @@ impl ServerTsx {
             .send_outgoing_response(response)
             .await?;
 
+        self.registration.set_state(TsxState::Proceeding);
+
         Ok(())
     }
 
@@ -67,6 +69,8 @@ impl ServerTsx {
             return Ok(());
         }
 
+        self.registration.set_state(TsxState::Completed);
+
         let abandon = Instant::now() + T1 * 64;
 
         tokio::spawn(async move {
@@ -80,6 +84,8 @@ impl ServerTsx {
                     {
                         log::warn!("Failed to retransmit message, {}", e);
                     }
+
+                    self.registration.retransmitted();
                 }
             }
         });
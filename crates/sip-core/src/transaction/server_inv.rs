@@ -1,6 +1,6 @@
 use crate::error::Error;
 use crate::transaction::consts::{T1, T2};
-use crate::transaction::TsxRegistration;
+use crate::transaction::{TsxRegistration, TsxState};
 use crate::transport::OutgoingResponse;
 use crate::{IncomingRequest, Result};
 use sip_types::msg::MessageLine;
@@ -73,6 +73,8 @@ impl ServerInvTsx {
             .send_outgoing_response(&mut response)
             .await?;
 
+        self.registration.set_state(TsxState::Accepted);
+
         Ok(Accepted {
             registration: self.registration,
             response,
@@ -94,6 +96,8 @@ impl ServerInvTsx {
             .send_outgoing_response(&mut response)
             .await?;
 
+        self.registration.set_state(TsxState::Completed);
+
         // after this instant is over the tsx will time out
         let abandon_retransmit = Instant::now() + T1 * 64;
 
@@ -118,6 +122,8 @@ impl ServerInvTsx {
                                 .endpoint
                                 .send_outgoing_response(&mut response)
                                 .await?;
+
+                            self.registration.retransmitted();
                         }
                         MessageLine::Request(line) if line.method == Method::ACK => {
                             // in case of an ACK the transaction is completed
@@ -141,6 +147,8 @@ impl ServerInvTsx {
                         .send_outgoing_response(&mut response)
                         .await?;
 
+                    self.registration.retransmitted();
+
                     // increase the wait time until next retransmit
                     retransmit_delta = (retransmit_delta * 2).min(T2);
 
@@ -166,6 +174,10 @@ impl Accepted {
         self.registration
             .endpoint
             .send_outgoing_response(&mut self.response)
-            .await
+            .await?;
+
+        self.registration.retransmitted();
+
+        Ok(())
     }
 }
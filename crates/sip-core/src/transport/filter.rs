@@ -0,0 +1,201 @@
+//! Filtering of incoming traffic by source address
+//!
+//! Filters are checked before any received data is parsed. Datagrams from denied sources are dropped
+//! and connections from denied sources are closed right after being accepted.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+/// Decides whether traffic from a source address is accepted.
+///
+/// Implemented for closures taking the transport name (e.g. `UDP`, `TCP`) and the source address.
+pub trait AddressFilter: Send + Sync + 'static {
+    fn allow(&self, transport: &'static str, source: SocketAddr) -> bool;
+}
+
+impl<F> AddressFilter for F
+where
+    F: Fn(&'static str, SocketAddr) -> bool + Send + Sync + 'static,
+{
+    fn allow(&self, transport: &'static str, source: SocketAddr) -> bool {
+        self(transport, source)
+    }
+}
+
+/// IP network in CIDR notation (e.g. `192.168.0.0/16` or `2001:db8::/32`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNet {
+    /// Create a new network, returns `None` if the prefix length is larger than the address
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let max_prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        if prefix_len > max_prefix_len {
+            return None;
+        }
+
+        Some(Self { addr, prefix_len })
+    }
+
+    /// Returns if `ip` is part of this network
+    ///
+    /// IPv4-mapped IPv6 addresses (e.g. `::ffff:192.0.2.1` received on a dual-stack socket) are treated as IPv4 addresses.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpNet {
+    fn from(addr: IpAddr) -> Self {
+        let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        Self { addr, prefix_len }
+    }
+}
+
+/// Error returned when parsing an invalid [`IpNet`]
+#[derive(Debug, thiserror::Error)]
+#[error("invalid IP network")]
+pub struct InvalidIpNet;
+
+impl FromStr for IpNet {
+    type Err = InvalidIpNet;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((addr, prefix_len)) = s.split_once('/') else {
+            return s
+                .parse::<IpAddr>()
+                .map(Self::from)
+                .map_err(|_| InvalidIpNet);
+        };
+
+        let addr = addr.parse().map_err(|_| InvalidIpNet)?;
+        let prefix_len = prefix_len.parse().map_err(|_| InvalidIpNet)?;
+
+        Self::new(addr, prefix_len).ok_or(InvalidIpNet)
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// [`AddressFilter`] using allow and deny lists of IP networks.
+///
+/// Sources matching any denied network are always rejected. If the allow list is not empty,
+/// only sources matching an allowed network are accepted.
+#[derive(Debug, Default, Clone)]
+pub struct CidrFilter {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+}
+
+impl CidrFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allow traffic from the given network (and all others added using this function)
+    pub fn allow(mut self, net: IpNet) -> Self {
+        self.allow.push(net);
+        self
+    }
+
+    /// Deny all traffic from the given network
+    pub fn deny(mut self, net: IpNet) -> Self {
+        self.deny.push(net);
+        self
+    }
+
+    fn is_allowed(&self, ip: IpAddr) -> bool {
+        if self.deny.iter().any(|net| net.contains(ip)) {
+            return false;
+        }
+
+        self.allow.is_empty() || self.allow.iter().any(|net| net.contains(ip))
+    }
+}
+
+impl AddressFilter for CidrFilter {
+    fn allow(&self, _: &'static str, source: SocketAddr) -> bool {
+        self.is_allowed(source.ip())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn ip_net() {
+        let net: IpNet = "192.168.0.0/16".parse().unwrap();
+        assert!(net.contains(ip("192.168.12.1")));
+        assert!(!net.contains(ip("192.169.0.1")));
+        assert!(!net.contains(ip("::1")));
+
+        let net: IpNet = "2001:db8::/32".parse().unwrap();
+        assert!(net.contains(ip("2001:db8:1::1")));
+        assert!(!net.contains(ip("2001:db9::1")));
+
+        let net: IpNet = "0.0.0.0/0".parse().unwrap();
+        assert!(net.contains(ip("1.2.3.4")));
+
+        let net: IpNet = "10.0.0.1".parse().unwrap();
+        assert_eq!(net.to_string(), "10.0.0.1/32");
+
+        assert!("10.0.0.0/33".parse::<IpNet>().is_err());
+        assert!("10.0.0/8".parse::<IpNet>().is_err());
+    }
+
+    #[test]
+    fn cidr_filter() {
+        let filter = CidrFilter::new()
+            .allow("10.0.0.0/8".parse().unwrap())
+            .deny("10.0.1.0/24".parse().unwrap());
+
+        assert!(filter.is_allowed(ip("10.0.0.1")));
+        assert!(!filter.is_allowed(ip("10.0.1.1")));
+        assert!(!filter.is_allowed(ip("192.168.0.1")));
+
+        let filter = CidrFilter::new().deny("10.0.1.0/24".parse().unwrap());
+        assert!(filter.is_allowed(ip("192.168.0.1")));
+    }
+
+    #[test]
+    fn ipv4_mapped() {
+        let net: IpNet = "10.0.1.0/24".parse().unwrap();
+        assert!(net.contains(ip("::ffff:10.0.1.7")));
+        assert!(!net.contains(ip("::ffff:10.0.2.7")));
+
+        let filter = CidrFilter::new().deny(net);
+        assert!(!filter.is_allowed(ip("::ffff:10.0.1.7")));
+        assert!(filter.is_allowed(ip("::ffff:192.168.0.1")));
+    }
+}
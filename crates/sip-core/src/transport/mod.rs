@@ -1,26 +1,32 @@
+use self::filter::AddressFilter;
 use self::managed::{DropNotifier, ManagedTransportState, MangedTransport, RefOwner, WeakRefOwner};
 use self::resolver::ServerEntry;
 use self::stun_user::StunUser;
 use crate::{Endpoint, Request, Response, Result};
 use bytes::Bytes;
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt;
 use parking_lot::Mutex;
 use sip_types::host::{Host, HostPort};
 use sip_types::msg::MessageLine;
 use sip_types::print::AppendCtx;
 use sip_types::uri::{Uri, UriInfo};
-use sip_types::Headers;
+use sip_types::{Code, Headers};
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
+use std::future::Future;
 use std::mem::take;
 use std::net::SocketAddr;
 use std::ops::Deref;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use std::{fmt, io};
 use stun::StunEndpoint;
 use stun_types::Message;
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::timeout;
 
+pub mod filter;
 mod managed;
 mod parse;
 mod resolver;
@@ -33,6 +39,8 @@ pub mod native_tls;
 pub mod rustls;
 pub mod tcp;
 pub mod udp;
+#[cfg(feature = "websocket")]
+pub mod ws;
 
 /// Abstraction over a transport factory.
 ///
@@ -218,6 +226,11 @@ pub struct ReceivedMessage {
 
     /// Body part of the messages as raw bytes
     pub body: Bytes,
+
+    /// Set by the transport if the request exceeds the [`MessageLimits`], the code it is rejected with.
+    ///
+    /// Such requests are not passed to any layer.
+    pub exceeded_limit: Option<Code>,
 }
 
 impl fmt::Display for ReceivedMessage {
@@ -245,6 +258,7 @@ impl ReceivedMessage {
             line,
             headers,
             body,
+            exceeded_limit: None,
         }
     }
 }
@@ -294,42 +308,120 @@ impl TpKey {
     }
 }
 
+/// Limits applied to received messages, to protect against malicious peers
+///
+/// All limits are checked by the transports on the raw message, before it is parsed.
+#[derive(Debug, Clone, Copy)]
+pub struct MessageLimits {
+    /// Maximum size of a message in bytes. Larger datagrams are discarded,
+    /// connections sending larger messages are closed.
+    pub max_message_size: usize,
+
+    /// Maximum size of a request body, larger requests are rejected with `413 Request Entity Too Large`
+    pub max_body_size: usize,
+
+    /// Maximum number of header lines, requests with more are rejected with `513 Message Too Large`
+    pub max_headers: usize,
+
+    /// Maximum length of the Request-URI, longer ones are rejected with `414 Request-URI Too Long`
+    pub max_uri_length: usize,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: u16::MAX as usize,
+            max_body_size: u16::MAX as usize,
+            max_headers: 256,
+            max_uri_length: 8192,
+        }
+    }
+}
+
+/// Event emitted when a connection oriented transport is opened or closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportEvent {
+    /// Transport was connected or accepted
+    Opened(TpKey),
+
+    /// Transport was closed and removed from the endpoint
+    Closed(TpKey),
+}
+
+/// Default duration a connection is kept open without being used
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(32);
+
+/// Delay between starting concurrent connection attempts to the resolved servers
+/// ([RFC8305](https://datatracker.ietf.org/doc/html/rfc8305#section-5))
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Default interval in which keep-alives are sent over connections
+const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Address filter, optionally restricted to the transport with the given name
+type FilterEntry = (Option<String>, Box<dyn AddressFilter>);
+
 pub(crate) struct Transports {
     unmanaged: Box<[TpHandle]>,
     factories: Box<[Arc<dyn Factory>]>,
 
     transports: Mutex<HashMap<TpKey, MangedTransport>>,
 
+    idle_timeout: Duration,
+    keep_alive_interval: Option<Duration>,
+    max_connections: Option<usize>,
+    limits: MessageLimits,
+    events: broadcast::Sender<TransportEvent>,
+
+    filters: Box<[FilterEntry]>,
+
     stun: StunEndpoint<StunUser>,
 
     dns_resolver: hickory_resolver::TokioResolver,
 }
 
 impl Transports {
-    async fn resolve_host_port(&self, host: &Host, port: u16) -> io::Result<Vec<ServerEntry>> {
-        match host {
-            Host::IP6(ip) => Ok(vec![ServerEntry::from((*ip, port))]),
-            Host::IP4(ip) => Ok(vec![ServerEntry::from((*ip, port))]),
-            Host::Name(name) => resolver::resolve_host(&self.dns_resolver, name, port).await,
+    async fn resolve_uri(&self, info: &UriInfo<'_>) -> io::Result<Vec<ServerEntry>> {
+        if !info.has_host() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "URI has no host, it can only be reached using an outbound proxy",
+            ));
         }
-    }
 
-    async fn resolve_uri(&self, info: &UriInfo<'_>) -> io::Result<Vec<ServerEntry>> {
         let port = match info.host_port.port {
             Some(port) => port,
             None if info.secure => 5061,
             None => 5060,
         };
 
-        self.resolve_host_port(&info.host_port.host, port).await
+        // There are no NAPTR/SRV records defined for WebSocket transports
+        let explicit_port = match &info.transport {
+            Some(transport) if is_websocket(transport) => Some(port),
+            _ => info.host_port.port,
+        };
+
+        match &info.host_port.host {
+            Host::IP6(ip) => Ok(vec![ServerEntry::from((*ip, port))]),
+            Host::IP4(ip) => Ok(vec![ServerEntry::from((*ip, port))]),
+            Host::Name(name) => {
+                resolver::resolve_host(&self.dns_resolver, name, explicit_port, info.secure).await
+            }
+        }
     }
 
     /// Will try to find or create a suitable transport the given Uri
+    ///
+    /// Resolved servers with an address contained in `exclude` are skipped.
+    ///
+    /// If a connection must be established, the next server is tried in parallel when the
+    /// connection attempt doesn't complete within 250ms, the first successful connection is used.
     #[tracing::instrument(name = "select_transport", level = "trace", skip(self, endpoint))]
     pub(crate) async fn select(
         &self,
         endpoint: &Endpoint,
         uri: &dyn Uri,
+        exclude: &[SocketAddr],
     ) -> Result<(TpHandle, SocketAddr)> {
         log::trace!("select transport for {:?}", uri);
 
@@ -338,7 +430,15 @@ impl Transports {
         // Resolve host_port to possible remote addresses
         let servers = self.resolve_uri(&info).await?;
 
+        // Connection attempts which are currently in progress
+        let mut connecting = FuturesUnordered::new();
+
         for server in servers {
+            if exclude.contains(&server.address) {
+                log::trace!("skipping excluded server {}", server.address);
+                continue;
+            }
+
             // Search unmanaged ones (connectionless, e.g. udp)
             if let Some(transport) = self.find_matching_unmanaged_transport(&info, &server) {
                 log::trace!("selected connectionless: {}", transport);
@@ -352,17 +452,28 @@ impl Transports {
             }
 
             // No existing transport found, try and connect a new one
-
-            if let Some(found) = self.connect(endpoint, &info, &server).await {
-                return Ok((found, server.address));
+            let info = &info;
+
+            connecting.push(async move {
+                self.connect(endpoint, info, &server)
+                    .await
+                    .map(|found| (found, server.address))
+            });
+
+            // Give the attempts some time before trying the next server
+            if let Ok(Some(found)) =
+                timeout(CONNECTION_ATTEMPT_DELAY, next_connected(&mut connecting)).await
+            {
+                return Ok(found);
             }
         }
 
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to select transport for {uri:?}"),
-        )
-        .into())
+        // Wait for the remaining connection attempts
+        if let Some(found) = next_connected(&mut connecting).await {
+            return Ok(found);
+        }
+
+        Err(io::Error::other(format!("Failed to select transport for {uri:?}")).into())
     }
 
     fn find_matching_unmanaged_transport(
@@ -449,6 +560,15 @@ impl Transports {
     ) -> Option<TpHandle> {
         // Try to build new transport with a factory
         for factory in self.factories.iter() {
+            if self.connection_limit_reached() {
+                log::warn!(
+                    "Not connecting to {}, connection limit reached",
+                    server.address
+                );
+
+                return None;
+            }
+
             if let Some(transport) = server.transport {
                 if transport.as_str() != factory.name() {
                     continue;
@@ -485,6 +605,48 @@ impl Transports {
         None
     }
 
+    /// Duration a connection is kept open after it was last used
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Returns if traffic from `source` received on the transport `transport` passes all address filters
+    pub fn is_source_allowed(&self, transport: &'static str, source: SocketAddr) -> bool {
+        let allowed = self
+            .filters
+            .iter()
+            .filter(|(name, _)| {
+                name.as_ref()
+                    .is_none_or(|name| name.eq_ignore_ascii_case(transport))
+            })
+            .all(|(_, filter)| filter.allow(transport, source));
+
+        if !allowed {
+            log::trace!("dropping {transport} traffic from filtered source {source}");
+        }
+
+        allowed
+    }
+
+    pub fn limits(&self) -> &MessageLimits {
+        &self.limits
+    }
+
+    /// Interval in which keep-alives are sent over connections, `None` if disabled
+    pub fn keep_alive_interval(&self) -> Option<Duration> {
+        self.keep_alive_interval
+    }
+
+    /// Returns if no more connections may be opened or accepted
+    pub fn connection_limit_reached(&self) -> bool {
+        self.max_connections
+            .is_some_and(|max| self.transports.lock().len() >= max)
+    }
+
+    pub fn subscribe_events(&self) -> broadcast::Receiver<TransportEvent> {
+        self.events.subscribe()
+    }
+
     /// Adds the given connected transport and return a strong tp-handle and notifier
     pub fn add_managed_used<T>(&self, transport: T) -> (TpHandle, DropNotifier)
     where
@@ -492,16 +654,18 @@ impl Transports {
     {
         let (transport, weak, rx) = TpHandle::new_managed(transport);
 
-        let mut transports = self.transports.lock();
+        let key = transport.key();
 
-        transports.insert(
-            transport.key(),
+        self.transports.lock().insert(
+            key,
             MangedTransport {
                 transport: transport.transport.clone(),
                 state: ManagedTransportState::Used(weak),
             },
         );
 
+        let _ = self.events.send(TransportEvent::Opened(key));
+
         (transport, rx)
     }
 
@@ -515,20 +679,18 @@ impl Transports {
     {
         let (tx, rx) = oneshot::channel();
 
-        let mut transports = self.transports.lock();
+        let key = TpKey::from_dyn(&transport);
 
-        transports.insert(
-            TpKey {
-                name: transport.name(),
-                bound: transport.bound(),
-                direction: transport.direction(),
-            },
+        self.transports.lock().insert(
+            key,
             MangedTransport {
                 transport: Arc::new(transport),
                 state: ManagedTransportState::Unused(tx),
             },
         );
 
+        let _ = self.events.send(TransportEvent::Opened(key));
+
         rx
     }
 
@@ -565,7 +727,9 @@ impl Transports {
     pub fn drop_transport(&self, tp_key: &TpKey) {
         log::trace!("drop transport {:?}", tp_key);
 
-        self.transports.lock().remove(tp_key);
+        if self.transports.lock().remove(tp_key).is_some() {
+            let _ = self.events.send(TransportEvent::Closed(*tp_key));
+        }
     }
 
     pub async fn receive_stun(&self, message: Message, source: SocketAddr, transport: TpHandle) {
@@ -573,11 +737,34 @@ impl Transports {
     }
 }
 
+/// Returns the first successfully connected transport, or `None` if all attempts failed
+async fn next_connected<F>(connecting: &mut FuturesUnordered<F>) -> Option<(TpHandle, SocketAddr)>
+where
+    F: Future<Output = Option<(TpHandle, SocketAddr)>>,
+{
+    while let Some(result) = connecting.next().await {
+        if result.is_some() {
+            return result;
+        }
+    }
+
+    None
+}
+
+fn is_websocket(transport: &str) -> bool {
+    transport.eq_ignore_ascii_case("ws") || transport.eq_ignore_ascii_case("wss")
+}
+
 #[derive(Default)]
 pub(crate) struct TransportsBuilder {
     unmanaged: Vec<TpHandle>,
     factories: Vec<Arc<dyn Factory>>,
     dns_resolver: Option<hickory_resolver::TokioResolver>,
+    idle_timeout: Option<Duration>,
+    keep_alive_interval: Option<Option<Duration>>,
+    max_connections: Option<usize>,
+    limits: MessageLimits,
+    filters: Vec<FilterEntry>,
 }
 
 impl TransportsBuilder {
@@ -595,6 +782,26 @@ impl TransportsBuilder {
         self.dns_resolver = Some(dns_resolver);
     }
 
+    pub(crate) fn set_idle_timeout(&mut self, idle_timeout: Duration) {
+        self.idle_timeout = Some(idle_timeout);
+    }
+
+    pub(crate) fn set_keep_alive_interval(&mut self, keep_alive_interval: Option<Duration>) {
+        self.keep_alive_interval = Some(keep_alive_interval);
+    }
+
+    pub(crate) fn set_max_connections(&mut self, max_connections: usize) {
+        self.max_connections = Some(max_connections);
+    }
+
+    pub(crate) fn set_limits(&mut self, limits: MessageLimits) {
+        self.limits = limits;
+    }
+
+    pub(crate) fn add_filter(&mut self, transport: Option<String>, filter: Box<dyn AddressFilter>) {
+        self.filters.push((transport, filter));
+    }
+
     pub(crate) fn build(&mut self) -> Transports {
         let dns_resolver = self.dns_resolver.take().unwrap_or_else(|| {
             hickory_resolver::TokioResolver::tokio_from_system_conf()
@@ -606,6 +813,14 @@ impl TransportsBuilder {
             factories: take(&mut self.factories).into_boxed_slice(),
             stun: StunEndpoint::new(StunUser),
             transports: Default::default(),
+            idle_timeout: self.idle_timeout.unwrap_or(DEFAULT_IDLE_TIMEOUT),
+            keep_alive_interval: self
+                .keep_alive_interval
+                .unwrap_or(Some(DEFAULT_KEEP_ALIVE_INTERVAL)),
+            max_connections: self.max_connections,
+            limits: self.limits,
+            events: broadcast::channel(64).0,
+            filters: take(&mut self.filters).into_boxed_slice(),
             dns_resolver,
         }
     }
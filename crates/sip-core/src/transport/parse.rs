@@ -1,9 +1,13 @@
+use super::MessageLimits;
 use bytes::Bytes;
 use internal::Finish;
 use sip_types::header::typed::ContentLength;
-use sip_types::msg::{Line, MessageLine, PullParser};
+use sip_types::host::{Host, HostPort};
+use sip_types::msg::{Line, MessageLine, PullParser, RequestLine};
 use sip_types::parse::{ParseCtx, Parser};
-use sip_types::Headers;
+use sip_types::uri::sip::SipUri;
+use sip_types::{Code, Headers, Method, Name};
+use std::net::Ipv4Addr;
 use std::str::from_utf8;
 use stun_types::{is_stun_message, Message};
 
@@ -22,10 +26,107 @@ pub enum CompleteItem {
         headers: Headers,
         body: Bytes,
         buffer: Bytes,
+        exceeded_limit: Option<Code>,
     },
 }
 
-pub fn parse_complete(parser: Parser, bytes: &[u8]) -> Result<CompleteItem, Error> {
+/// Check the raw head of a received message against the [`MessageLimits`]
+///
+/// Only requests are checked, returns the code the request must be rejected with.
+pub(crate) fn check_head_limits(
+    head: &[u8],
+    body_len: usize,
+    limits: &MessageLimits,
+) -> Option<Code> {
+    let mut parser = PullParser::new(head, 0);
+
+    let first_line = parser.next()?.ok()?;
+    let header_count = parser.take_while(Result::is_ok).count();
+
+    check_limits(first_line, header_count, body_len, limits)
+}
+
+fn check_limits(
+    first_line: &[u8],
+    header_count: usize,
+    body_len: usize,
+    limits: &MessageLimits,
+) -> Option<Code> {
+    if first_line.starts_with(b"SIP/") {
+        return None;
+    }
+
+    if body_len > limits.max_body_size {
+        Some(Code::REQUEST_ENTITY_TOO_LARGE)
+    } else if header_count > limits.max_headers {
+        Some(Code::MESSAGE_TOO_LARGE)
+    } else if request_uri_len(first_line) > limits.max_uri_length {
+        Some(Code::REQUEST_URI_TOO_LONG)
+    } else {
+        None
+    }
+}
+
+/// Length of the Request-URI in a raw `Method SP Request-URI SP SIP-Version` line
+fn request_uri_len(line: &[u8]) -> usize {
+    let start = line.iter().position(|&b| b == b' ');
+    let end = line.iter().rposition(|&b| b == b' ');
+
+    match (start, end) {
+        (Some(start), Some(end)) => end.saturating_sub(start + 1),
+        _ => 0,
+    }
+}
+
+/// Parse the first line of a message
+///
+/// A Request-URI exceeding the limits is not parsed but replaced with a placeholder,
+/// the request is rejected with `414 Request-URI Too Long` anyway.
+pub(crate) fn parse_message_line<'p>(
+    ctx: ParseCtx<'p>,
+    line: &'p str,
+    limits: &MessageLimits,
+) -> Option<MessageLine> {
+    if !line.starts_with("SIP/") && request_uri_len(line.as_bytes()) > limits.max_uri_length {
+        let (method, _) = line.split_once(' ')?;
+
+        return Some(MessageLine::Request(RequestLine {
+            method: Method::from_parse(ctx.src, method),
+            uri: Box::new(SipUri::new(HostPort {
+                host: Host::IP4(Ipv4Addr::UNSPECIFIED),
+                port: None,
+            })),
+        }));
+    }
+
+    match MessageLine::parse(ctx)(line) {
+        Ok((_, line)) => Some(line),
+        Err(_) => None,
+    }
+}
+
+/// Insert a header line of a received message
+///
+/// After `max_headers` header lines only the headers required to respond to a request are kept.
+/// `index` is the position of the header line inside the message head.
+pub(crate) fn insert_header(
+    headers: &mut Headers,
+    index: usize,
+    line: Line,
+    limits: &MessageLimits,
+) {
+    let required = [Name::VIA, Name::FROM, Name::TO, Name::CALL_ID, Name::CSEQ];
+
+    if index < limits.max_headers || required.contains(&line.name) {
+        headers.insert(line.name, line.value);
+    }
+}
+
+pub fn parse_complete(
+    parser: Parser,
+    bytes: &[u8],
+    limits: &MessageLimits,
+) -> Result<CompleteItem, Error> {
     if bytes == b"\r\n\r\n" {
         return Ok(CompleteItem::KeepAliveRequest);
     } else if bytes == b"\r\n" {
@@ -36,7 +137,7 @@ pub fn parse_complete(parser: Parser, bytes: &[u8]) -> Result<CompleteItem, Erro
         stun_types::IsStunMessageInfo::TooShort
         | stun_types::IsStunMessageInfo::YesIncomplete { needed: _ } => Err(Error::FailedToParse),
         stun_types::IsStunMessageInfo::Yes { len } => parse_complete_stun(&bytes[..len]),
-        stun_types::IsStunMessageInfo::No => parse_complete_sip(parser, bytes),
+        stun_types::IsStunMessageInfo::No => parse_complete_sip(parser, bytes, limits),
     }
 }
 
@@ -52,7 +153,11 @@ fn parse_complete_stun(bytes: &[u8]) -> Result<CompleteItem, Error> {
     Ok(CompleteItem::Stun(msg))
 }
 
-fn parse_complete_sip(parser_: Parser, bytes: &[u8]) -> Result<CompleteItem, Error> {
+fn parse_complete_sip(
+    parser_: Parser,
+    bytes: &[u8],
+    limits: &MessageLimits,
+) -> Result<CompleteItem, Error> {
     let buffer = Bytes::copy_from_slice(bytes);
 
     let mut parser = PullParser::new(&buffer, 0);
@@ -60,7 +165,7 @@ fn parse_complete_sip(parser_: Parser, bytes: &[u8]) -> Result<CompleteItem, Err
     let mut message_line = None;
     let mut headers = Headers::new();
 
-    for item in &mut parser {
+    for (index, item) in (&mut parser).enumerate() {
         let line = match item {
             Ok(line) => line,
             Err(_) => {
@@ -77,11 +182,11 @@ fn parse_complete_sip(parser_: Parser, bytes: &[u8]) -> Result<CompleteItem, Err
         if message_line.is_none() {
             let ctx = ParseCtx::new(&buffer, parser_);
 
-            match MessageLine::parse(ctx)(line) {
-                Ok((_, line)) => {
+            match parse_message_line(ctx, line, limits) {
+                Some(line) => {
                     message_line = Some(line);
                 }
-                Err(_) => {
+                None => {
                     log::warn!(
                         "Incoming SIP message contained invalid Request/Status Line: {:?}",
                         line
@@ -91,7 +196,7 @@ fn parse_complete_sip(parser_: Parser, bytes: &[u8]) -> Result<CompleteItem, Err
             }
         } else {
             match Line::parse(&buffer, line).finish() {
-                Ok((_, line)) => headers.insert(line.name, line.value),
+                Ok((_, line)) => insert_header(&mut headers, index - 1, line, limits),
                 Err(e) => {
                     log::error!("Incoming SIP message has malformed header line, {}", e);
                     return Err(Error::FailedToParse);
@@ -102,6 +207,8 @@ fn parse_complete_sip(parser_: Parser, bytes: &[u8]) -> Result<CompleteItem, Err
 
     let head_end = parser.head_end();
 
+    let exceeded_limit = check_head_limits(&buffer, buffer.len() - head_end, limits);
+
     // look for optional content-length header
     let body = match headers.get_named::<ContentLength>() {
         Ok(len) => {
@@ -130,5 +237,110 @@ fn parse_complete_sip(parser_: Parser, bytes: &[u8]) -> Result<CompleteItem, Err
         headers,
         body,
         buffer,
+        exceeded_limit,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn limits() -> MessageLimits {
+        MessageLimits {
+            max_message_size: 1024,
+            max_body_size: 16,
+            max_headers: 5,
+            max_uri_length: 32,
+        }
+    }
+
+    fn parse_sip(bytes: &[u8]) -> (MessageLine, Headers, Option<Code>) {
+        match parse_complete(Parser::default(), bytes, &limits()).unwrap() {
+            CompleteItem::Sip {
+                line,
+                headers,
+                exceeded_limit,
+                ..
+            } => (line, headers, exceeded_limit),
+            _ => panic!("expected sip message"),
+        }
+    }
+
+    #[test]
+    fn uri_len() {
+        assert_eq!(request_uri_len(b"INVITE sip:bob@example.com SIP/2.0"), 19);
+        assert_eq!(request_uri_len(b"INVITE"), 0);
+    }
+
+    #[test]
+    fn within_limits() {
+        let (_, headers, exceeded_limit) = parse_sip(
+            b"OPTIONS sip:bob@example.com SIP/2.0\r\n\
+            Call-ID: abc\r\n\
+            Content-Length: 5\r\n\
+            \r\n\
+            hello",
+        );
+
+        assert_eq!(exceeded_limit, None);
+        assert_eq!(headers.iter().count(), 2);
+    }
+
+    #[test]
+    fn body_too_large() {
+        let (_, _, exceeded_limit) = parse_sip(
+            b"MESSAGE sip:bob@example.com SIP/2.0\r\n\
+            Content-Length: 17\r\n\
+            \r\n\
+            01234567890123456",
+        );
+
+        assert_eq!(exceeded_limit, Some(Code::REQUEST_ENTITY_TOO_LARGE));
+    }
+
+    #[test]
+    fn too_many_headers() {
+        let (_, headers, exceeded_limit) = parse_sip(
+            b"OPTIONS sip:bob@example.com SIP/2.0\r\n\
+            X-A: a\r\n\
+            X-B: b\r\n\
+            X-C: c\r\n\
+            X-D: d\r\n\
+            X-E: e\r\n\
+            X-F: f\r\n\
+            Call-ID: abc\r\n\
+            \r\n",
+        );
+
+        assert_eq!(exceeded_limit, Some(Code::MESSAGE_TOO_LARGE));
+
+        // headers past the limit are dropped, except those required to respond
+        assert!(headers.contains(&Name::from("X-E")));
+        assert!(!headers.contains(&Name::from("X-F")));
+        assert!(headers.contains(&Name::CALL_ID));
+    }
+
+    #[test]
+    fn uri_too_long() {
+        let (line, _, exceeded_limit) = parse_sip(
+            b"OPTIONS sip:bob@a-very-long-domain-name.example.com SIP/2.0\r\n\
+            Call-ID: abc\r\n\
+            \r\n",
+        );
+
+        assert_eq!(exceeded_limit, Some(Code::REQUEST_URI_TOO_LONG));
+        assert!(matches!(line, MessageLine::Request(line) if line.method == Method::OPTIONS));
+    }
+
+    #[test]
+    fn responses_are_not_limited() {
+        let (_, _, exceeded_limit) = parse_sip(
+            b"SIP/2.0 200 OK\r\n\
+            Content-Length: 17\r\n\
+            \r\n\
+            01234567890123456",
+        );
+
+        assert_eq!(exceeded_limit, None);
+    }
+}
@@ -56,6 +56,10 @@ impl Transport {
         }
     }
 
+    fn is_secure(&self) -> bool {
+        matches!(self, Transport::TlsOverTcp)
+    }
+
     fn default_port(&self) -> u16 {
         match self {
             Transport::Udp => 5060,
@@ -66,25 +70,38 @@ impl Transport {
     }
 }
 
+/// Resolve the host of an URI into a list of servers ordered by preference.
+///
+/// Implements [RFC3263](https://datatracker.ietf.org/doc/html/rfc3263#section-4):
+/// NAPTR and SRV lookups are skipped if the URI contains an explicit port,
+/// and for secure (`sips:`) URIs only TLS targets are returned.
 #[tracing::instrument(err, skip(dns_resolver, uri_port))]
 pub(super) async fn resolve_host(
     dns_resolver: &TokioResolver,
     name: &str,
-    uri_port: u16,
+    uri_port: Option<u16>,
+    secure: bool,
 ) -> io::Result<Vec<ServerEntry>> {
+    use Transport::*;
+
     log::debug!("Resolving hostname {:?}", name);
 
     let name = Name::from_utf8(name)?;
 
     let mut entries: Vec<ServerEntry> = vec![];
 
+    // The URI contains a port, only resolve A/AAAA records
+    if let Some(port) = uri_port {
+        resolve_a_records(dns_resolver, name.clone(), None, port, &mut entries).await?;
+
+        return ensure_not_empty(entries, &name);
+    }
+
     // First find NAPTR DNS records
-    resolve_naptr_records(dns_resolver, name.clone(), &mut entries).await?;
+    resolve_naptr_records(dns_resolver, name.clone(), secure, &mut entries).await?;
 
     // If there are none, look for SRV entries directly
     if entries.is_empty() {
-        use Transport::*;
-
         // Try all transports this library should support
         let records = [
             (Name::from_utf8(format!("_sips._tcp.{name}"))?, TlsOverTcp),
@@ -93,20 +110,29 @@ pub(super) async fn resolve_host(
         ];
 
         for (name, transport) in records {
+            if secure && !transport.is_secure() {
+                continue;
+            }
+
             resolve_srv_records(dns_resolver, name, Some(transport), &mut entries).await?;
         }
     }
 
     // Neither NAPTR nor SRV entries exist - just resolve A/AAAA records
     if entries.is_empty() {
-        resolve_a_records(dns_resolver, name.clone(), None, uri_port, &mut entries).await?;
+        let port = if secure { 5061 } else { 5060 };
+
+        resolve_a_records(dns_resolver, name.clone(), None, port, &mut entries).await?;
     }
 
+    ensure_not_empty(entries, &name)
+}
+
+fn ensure_not_empty(entries: Vec<ServerEntry>, name: &Name) -> io::Result<Vec<ServerEntry>> {
     if entries.is_empty() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("No DNS records for host '{name}' found"),
-        ));
+        return Err(io::Error::other(format!(
+            "No DNS records for host '{name}' found"
+        )));
     }
 
     Ok(entries)
@@ -115,6 +141,7 @@ pub(super) async fn resolve_host(
 async fn resolve_naptr_records(
     dns_resolver: &TokioResolver,
     name: Name,
+    secure: bool,
     entries: &mut Vec<ServerEntry>,
 ) -> Result<(), ResolveError> {
     log::debug!("Resolving NAPTR records for \"{name}\"");
@@ -153,6 +180,11 @@ async fn resolve_naptr_records(
             continue;
         };
 
+        if secure && !transport.is_secure() {
+            // Secure URIs must only be contacted using TLS
+            continue;
+        }
+
         match record.flags() {
             b"s" => {
                 resolve_srv_records(
@@ -263,14 +295,48 @@ async fn resolve_a_records(
         lookup.as_lookup().records().len()
     );
 
-    entries.extend(lookup.iter().map(|ip| ServerEntry {
-        address: SocketAddr::new(ip, port),
-        transport,
-    }));
+    entries.extend(
+        interleave_address_families(lookup.iter())
+            .into_iter()
+            .map(|ip| ServerEntry {
+                address: SocketAddr::new(ip, port),
+                transport,
+            }),
+    );
 
     Ok(())
 }
 
+/// Alternate between IPv6 and IPv4 addresses, starting with the family of the first address
+/// ([RFC8305](https://datatracker.ietf.org/doc/html/rfc8305#section-4)).
+///
+/// This way a connection attempt to the other family is made early if one is broken.
+fn interleave_address_families(ips: impl Iterator<Item = IpAddr>) -> Vec<IpAddr> {
+    let (mut preferred, mut other): (Vec<_>, Vec<_>) = (vec![], vec![]);
+
+    for ip in ips {
+        if preferred
+            .first()
+            .is_none_or(|first: &IpAddr| first.is_ipv4() == ip.is_ipv4())
+        {
+            preferred.push(ip);
+        } else {
+            other.push(ip);
+        }
+    }
+
+    let mut interleaved = Vec::with_capacity(preferred.len() + other.len());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => return interleaved,
+            (a, b) => interleaved.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
 /// Filter out errors where no records for a given name weren't found and instead return an Ok(None)
 fn filter_no_records<T>(e: Result<T, ResolveError>) -> Result<Option<T>, ResolveError> {
     match e {
@@ -279,3 +345,23 @@ fn filter_no_records<T>(e: Result<T, ResolveError>) -> Result<Option<T>, Resolve
         Err(e) => Err(e),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interleave() {
+        let ips: Vec<IpAddr> = ["1.1.1.1", "2.2.2.2", "3.3.3.3", "::1", "::2"]
+            .into_iter()
+            .map(|ip| ip.parse().unwrap())
+            .collect();
+
+        let interleaved: Vec<String> = interleave_address_families(ips.into_iter())
+            .into_iter()
+            .map(|ip| ip.to_string())
+            .collect();
+
+        assert_eq!(interleaved, ["1.1.1.1", "::1", "2.2.2.2", "::2", "3.3.3.3"]);
+    }
+}
@@ -1,9 +1,11 @@
+use crate::transport::parse::{check_head_limits, insert_header, parse_message_line};
+use crate::transport::MessageLimits;
 use crate::Result;
 use bytes::{Buf, Bytes, BytesMut};
 use internal::Finish;
 use sip_types::msg::{Line, MessageLine, PullParser};
 use sip_types::parse::{ParseCtx, Parser};
-use sip_types::Headers;
+use sip_types::{Code, Headers};
 use std::io;
 use std::str::{from_utf8, Utf8Error};
 use tokio_util::codec::Decoder;
@@ -42,18 +44,25 @@ pub struct DecodedMessage {
     pub body: Bytes,
 
     pub buffer: Bytes,
+
+    /// Set if the request exceeds the [`MessageLimits`]
+    pub exceeded_limit: Option<Code>,
 }
 
 pub struct StreamingDecoder {
     head_progress: usize,
     parser: Parser,
+
+    /// Limits of a single message, `max_message_size` applies to head + body
+    limits: MessageLimits,
 }
 
 impl StreamingDecoder {
-    pub fn new(parser: Parser) -> Self {
+    pub fn new(parser: Parser, limits: MessageLimits) -> Self {
         Self {
             head_progress: 0,
             parser,
+            limits,
         }
     }
 }
@@ -63,26 +72,21 @@ impl Decoder for StreamingDecoder {
     type Error = Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        // strip leading newlines
-        let whitespace_count = src.iter().take_while(|b| b.is_ascii_whitespace()).count();
-        if whitespace_count > 0 {
-            let is_keep_alive_request = src.starts_with(b"\r\n\r\n");
-            let is_keep_alive_response = src.starts_with(b"\r\n");
-
-            src.advance(whitespace_count);
-
-            if is_keep_alive_request {
-                return Ok(Some(Item::KeepAliveRequest));
-            } else if is_keep_alive_response {
-                return Ok(Some(Item::KeepAliveResponse));
-            }
+        // handle keep-alives one at a time, multiple may be received at once
+        if src.starts_with(b"\r\n\r\n") {
+            src.advance(4);
+            return Ok(Some(Item::KeepAliveRequest));
+        } else if src.starts_with(b"\r\n") {
+            src.advance(2);
+            return Ok(Some(Item::KeepAliveResponse));
         }
 
-        // limit message size
-        if src.len() > 65535 {
-            src.clear();
+        // strip any other leading whitespace
+        let whitespace_count = src.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        src.advance(whitespace_count);
 
-            return Err(Error::MessageTooLarge);
+        if src.is_empty() {
+            return Ok(None);
         }
 
         let mut parser = PullParser::new(src, self.head_progress);
@@ -93,6 +97,12 @@ impl Decoder for StreamingDecoder {
             let Ok(line) = line else {
                 // cannot parse complete message head yet
                 self.head_progress = parser.progress();
+
+                // the buffer only contains the incomplete head, limit its size
+                if src.len() > self.limits.max_message_size {
+                    return Err(Error::MessageTooLarge);
+                }
+
                 return Ok(None);
             };
 
@@ -120,7 +130,7 @@ impl Decoder for StreamingDecoder {
                     .parse::<usize>()
                     .map_err(|_| Error::Malformed)?;
 
-                if content_len > (u16::MAX as usize) {
+                if content_len > self.limits.max_message_size {
                     return Err(Error::MessageTooLarge);
                 }
             }
@@ -129,9 +139,15 @@ impl Decoder for StreamingDecoder {
         // parser completed without errors
         // message head should be complete
 
+        let exceeded_limit = check_head_limits(src, content_len, &self.limits);
+
         // Calculate the complete message size
         let expected_complete_message_size = parser.head_end() + content_len;
 
+        if expected_complete_message_size > self.limits.max_message_size {
+            return Err(Error::MessageTooLarge);
+        }
+
         // if the message is not completely inside the buffer, allocate the rest
         // and return
         if src.len() < expected_complete_message_size {
@@ -153,7 +169,7 @@ impl Decoder for StreamingDecoder {
         let mut message_line = None;
         let mut headers = Headers::new();
 
-        for item in &mut parser {
+        for (index, item) in (&mut parser).enumerate() {
             let item = item.expect("got error when input was already checked");
 
             let line = from_utf8(item)?;
@@ -161,13 +177,13 @@ impl Decoder for StreamingDecoder {
             if message_line.is_none() {
                 let ctx = ParseCtx::new(&src_bytes, self.parser);
 
-                match MessageLine::parse(ctx)(line) {
-                    Ok((_, line)) => message_line = Some(line),
-                    Err(_) => return Err(Error::Malformed),
+                match parse_message_line(ctx, line, &self.limits) {
+                    Some(line) => message_line = Some(line),
+                    None => return Err(Error::Malformed),
                 }
             } else {
                 match Line::parse(&src_bytes, line).finish() {
-                    Ok((_, line)) => headers.insert(line.name, line.value),
+                    Ok((_, line)) => insert_header(&mut headers, index - 1, line, &self.limits),
                     Err(e) => {
                         log::error!("Incoming SIP message has malformed header line, {}", e);
                     }
@@ -186,6 +202,146 @@ impl Decoder for StreamingDecoder {
             headers,
             body,
             buffer: src_bytes,
+            exceeded_limit,
         })))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAX_MESSAGE_SIZE: usize = u16::MAX as usize;
+
+    const MESSAGE: &[u8] = b"OPTIONS sip:bob@example.com SIP/2.0\r\n\
+        Call-ID: abc\r\n\
+        Content-Length: 5\r\n\
+        \r\n\
+        hello";
+
+    fn decode_message(
+        decoder: &mut StreamingDecoder,
+        src: &mut BytesMut,
+    ) -> Option<DecodedMessage> {
+        match decoder.decode(src).unwrap()? {
+            Item::DecodedMessage(message) => Some(message),
+            _ => panic!("expected message"),
+        }
+    }
+
+    #[test]
+    fn message_split_across_reads() {
+        let mut decoder = StreamingDecoder::new(
+            Parser::default(),
+            MessageLimits {
+                max_message_size: MAX_MESSAGE_SIZE,
+                ..MessageLimits::default()
+            },
+        );
+        let mut src = BytesMut::new();
+
+        for chunk in MESSAGE[..MESSAGE.len() - 1].chunks(7) {
+            src.extend_from_slice(chunk);
+            assert!(decode_message(&mut decoder, &mut src).is_none());
+        }
+
+        src.extend_from_slice(&MESSAGE[MESSAGE.len() - 1..]);
+
+        let message = decode_message(&mut decoder, &mut src).unwrap();
+        assert_eq!(message.body, "hello");
+        assert_eq!(message.buffer, MESSAGE);
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn pipelined_messages() {
+        let mut decoder = StreamingDecoder::new(
+            Parser::default(),
+            MessageLimits {
+                max_message_size: MAX_MESSAGE_SIZE,
+                ..MessageLimits::default()
+            },
+        );
+        let mut src = BytesMut::new();
+
+        src.extend_from_slice(MESSAGE);
+        src.extend_from_slice(b"\r\n\r\n");
+        src.extend_from_slice(MESSAGE);
+        src.extend_from_slice(&MESSAGE[..10]);
+
+        assert!(decode_message(&mut decoder, &mut src).is_some());
+        assert!(matches!(
+            decoder.decode(&mut src).unwrap(),
+            Some(Item::KeepAliveRequest)
+        ));
+        assert!(decode_message(&mut decoder, &mut src).is_some());
+        assert!(decode_message(&mut decoder, &mut src).is_none());
+        assert_eq!(&src[..], &MESSAGE[..10]);
+    }
+
+    #[test]
+    fn pipelined_messages_exceeding_limit() {
+        let mut decoder = StreamingDecoder::new(
+            Parser::default(),
+            MessageLimits {
+                max_message_size: MAX_MESSAGE_SIZE,
+                ..MessageLimits::default()
+            },
+        );
+        let mut src = BytesMut::new();
+
+        let count = MAX_MESSAGE_SIZE / MESSAGE.len() + 1;
+
+        for _ in 0..count {
+            src.extend_from_slice(MESSAGE);
+        }
+
+        for _ in 0..count {
+            assert!(decode_message(&mut decoder, &mut src).is_some());
+        }
+
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn message_too_large() {
+        let mut decoder = StreamingDecoder::new(
+            Parser::default(),
+            MessageLimits {
+                max_message_size: MAX_MESSAGE_SIZE,
+                ..MessageLimits::default()
+            },
+        );
+
+        let mut src = BytesMut::from(
+            &b"OPTIONS sip:bob@example.com SIP/2.0\r\nContent-Length: 70000\r\n\r\n"[..],
+        );
+        assert!(matches!(
+            decoder.decode(&mut src),
+            Err(Error::MessageTooLarge)
+        ));
+
+        let mut src = BytesMut::from(&b"OPTIONS sip:bob@example.com SIP/2.0\r\nX-Long: "[..]);
+        src.resize(MAX_MESSAGE_SIZE + 1, b'a');
+        assert!(matches!(
+            decoder.decode(&mut src),
+            Err(Error::MessageTooLarge)
+        ));
+    }
+
+    #[test]
+    fn exceeded_limit_is_reported() {
+        let mut decoder = StreamingDecoder::new(
+            Parser::default(),
+            MessageLimits {
+                max_body_size: 4,
+                ..MessageLimits::default()
+            },
+        );
+        let mut src = BytesMut::from(MESSAGE);
+
+        let message = decode_message(&mut decoder, &mut src).unwrap();
+        assert_eq!(message.exceeded_limit, Some(Code::REQUEST_ENTITY_TOO_LARGE));
+        assert_eq!(message.body, "hello");
+    }
+}
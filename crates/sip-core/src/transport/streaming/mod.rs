@@ -3,6 +3,7 @@ use crate::transport::{Direction, Factory, ReceivedMessage, TpHandle, TpKey, Tra
 use crate::{Endpoint, EndpointBuilder};
 use decode::{Item, StreamingDecoder};
 use sip_types::uri::UriInfo;
+use std::future::pending;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -11,7 +12,7 @@ use std::{fmt, io};
 use tokio::io::{split, AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
 use tokio::net::ToSocketAddrs;
 use tokio::sync::{broadcast, oneshot, Mutex};
-use tokio::time::{interval, sleep, Sleep};
+use tokio::time::{interval_at, sleep, Instant, Interval, MissedTickBehavior, Sleep};
 use tokio_stream::StreamExt;
 use tokio_util::codec::FramedRead;
 
@@ -139,10 +140,7 @@ where
     }
 
     async fn send(&self, bytes: &[u8], _target: SocketAddr) -> io::Result<()> {
-        let mut socket = self.write_half.lock().await;
-        socket.write_all(bytes).await?;
-        socket.flush().await?;
-        Ok(())
+        write_all(&self.write_half, bytes).await
     }
 }
 
@@ -186,7 +184,10 @@ where
             incoming: false,
         };
 
-        let framed = FramedRead::new(read, StreamingDecoder::new(endpoint.parser()));
+        let framed = FramedRead::new(
+            read,
+            StreamingDecoder::new(endpoint.parser(), *endpoint.transports().limits()),
+        );
 
         let (transport, notifier) = endpoint.transports().add_managed_used(transport);
 
@@ -216,6 +217,21 @@ where
     loop {
         match incoming.accept().await {
             Ok((stream, remote)) => {
+                if !endpoint
+                    .transports()
+                    .is_source_allowed(I::Transport::NAME, remote)
+                {
+                    continue;
+                }
+
+                if endpoint.transports().connection_limit_reached() {
+                    log::warn!(
+                        "Refusing connection from {}, connection limit reached",
+                        remote
+                    );
+                    continue;
+                }
+
                 let local = match stream.local_addr() {
                     Ok(local) => local,
                     Err(e) => {
@@ -239,13 +255,19 @@ where
 
                 let rx = endpoint.transports().add_managed_unused(transport);
 
-                let framed = FramedRead::new(read, StreamingDecoder::new(endpoint.parser()));
+                let framed = FramedRead::new(
+                    read,
+                    StreamingDecoder::new(endpoint.parser(), *endpoint.transports().limits()),
+                );
 
                 tokio::spawn(receive_task(
                     endpoint.clone(),
                     framed,
                     write_half,
-                    ReceiveTaskState::Unused(Box::pin(sleep(Duration::from_secs(32))), rx),
+                    ReceiveTaskState::Unused(
+                        Box::pin(sleep(endpoint.transports().idle_timeout())),
+                        rx,
+                    ),
                     local,
                     remote,
                     true,
@@ -256,7 +278,7 @@ where
     }
 }
 
-enum ReceiveTaskState {
+pub(super) enum ReceiveTaskState {
     InUse(DropNotifier),
     Unused(Pin<Box<Sleep>>, oneshot::Receiver<DropNotifier>),
 }
@@ -287,7 +309,7 @@ async fn receive_task<T>(
         tp_key,
     };
 
-    let mut keep_alive_request_interval = interval(Duration::from_secs(10));
+    let mut keep_alive = KeepAliveTimer::new(endpoint.transports().keep_alive_interval());
 
     loop {
         let item = match &mut state {
@@ -297,11 +319,11 @@ async fn receive_task<T>(
                     _ = notifier => {
                         log::debug!("all refs to transport dropped, destroying soon if not used");
                         let rx = endpoint.transports().set_unused(&tp_key);
-                        state = ReceiveTaskState::Unused(Box::pin(sleep(Duration::from_secs(32))), rx);
+                        state = ReceiveTaskState::Unused(Box::pin(sleep(endpoint.transports().idle_timeout())), rx);
                         continue;
                     }
-                    _ = keep_alive_request_interval.tick() => {
-                        if let Err(e) = write_half.lock().await.write(b"\r\n\r\n").await {
+                    _ = keep_alive.tick() => {
+                        if let Err(e) = write_all(&write_half, b"\r\n\r\n").await {
                             log::debug!("Failed to send keep alive request, {e}");
                         }
                         continue;
@@ -321,8 +343,8 @@ async fn receive_task<T>(
                             return;
                         }
                     }
-                    _ = keep_alive_request_interval.tick() => {
-                        if let Err(e) = write_half.lock().await.write(b"\r\n\r\n").await {
+                    _ = keep_alive.tick() => {
+                        if let Err(e) = write_all(&write_half, b"\r\n\r\n").await {
                             log::debug!("Failed to send keep alive request, {e}");
                         }
                         continue;
@@ -340,7 +362,7 @@ async fn receive_task<T>(
         let message = match item {
             Some(Ok(Item::DecodedMessage(item))) => item,
             Some(Ok(Item::KeepAliveRequest)) => {
-                if let Err(e) = write_half.lock().await.write(b"\r\n").await {
+                if let Err(e) = write_all(&write_half, b"\r\n").await {
                     log::debug!("Failed to respond to keep alive request, {e}");
                 }
 
@@ -360,7 +382,7 @@ async fn receive_task<T>(
             }
         };
 
-        let message = ReceivedMessage::new(
+        let mut received = ReceivedMessage::new(
             remote,
             message.buffer,
             transport,
@@ -368,14 +390,48 @@ async fn receive_task<T>(
             message.headers,
             message.body,
         );
+        received.exceeded_limit = message.exceeded_limit;
 
-        endpoint.receive(message);
+        endpoint.receive(received);
+    }
+}
+
+async fn write_all<T: AsyncWrite>(
+    write_half: &Mutex<WriteHalf<T>>,
+    bytes: &[u8],
+) -> io::Result<()> {
+    let mut socket = write_half.lock().await;
+    socket.write_all(bytes).await?;
+    socket.flush().await
+}
+
+/// Timer to send keep-alive requests ([RFC5626](https://datatracker.ietf.org/doc/html/rfc5626#section-4.4.1))
+/// in the configured interval. Never fires if keep-alives are disabled.
+pub(super) struct KeepAliveTimer(Option<Interval>);
+
+impl KeepAliveTimer {
+    pub(super) fn new(period: Option<Duration>) -> Self {
+        Self(period.map(|period| {
+            let mut interval = interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        }))
+    }
+
+    pub(super) async fn tick(&mut self) {
+        match &mut self.0 {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => pending().await,
+        }
     }
 }
 
-struct UnclaimedGuard<'e> {
-    endpoint: &'e Endpoint,
-    tp_key: TpKey,
+/// Removes the transport from the endpoint when the receive task exits
+pub(super) struct UnclaimedGuard<'e> {
+    pub(super) endpoint: &'e Endpoint,
+    pub(super) tp_key: TpKey,
 }
 
 impl Drop for UnclaimedGuard<'_> {
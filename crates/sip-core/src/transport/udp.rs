@@ -112,9 +112,18 @@ async fn handle_msg(
 ) -> Result<()> {
     let (len, remote) = result?;
 
+    if !endpoint.transports().is_source_allowed(UDP, remote) {
+        return Ok(());
+    }
+
+    if len > endpoint.transports().limits().max_message_size {
+        log::debug!("Discarding datagram from {remote}, message too large");
+        return Ok(());
+    }
+
     let bytes = &bytes[..len];
 
-    match parse_complete(endpoint.parser(), bytes) {
+    match parse_complete(endpoint.parser(), bytes, endpoint.transports().limits()) {
         Ok(CompleteItem::KeepAliveRequest) => {
             inner.socket.send_to(b"\r\n", remote).await?;
         }
@@ -129,15 +138,13 @@ async fn handle_msg(
             headers,
             body,
             buffer,
+            exceeded_limit,
         }) => {
-            endpoint.receive(ReceivedMessage::new(
-                remote,
-                buffer,
-                handle.clone(),
-                line,
-                headers,
-                body,
-            ));
+            let mut message =
+                ReceivedMessage::new(remote, buffer, handle.clone(), line, headers, body);
+            message.exceeded_limit = exceeded_limit;
+
+            endpoint.receive(message);
         }
         Err(_e) => {
             // ignore for now
@@ -0,0 +1,549 @@
+//! SIP over WebSocket transports ([RFC7118](https://datatracker.ietf.org/doc/html/rfc7118))
+//!
+//! The WebSocket transports are layered over the [streaming](super::streaming) transports.
+//! Wrapping a [`TcpConnector`](super::tcp::TcpConnector) yields the `WS` transport,
+//! wrapping a TLS connector yields the `WSS` transport.
+
+use super::parse::{parse_complete, CompleteItem};
+use super::streaming::{
+    KeepAliveTimer, ReceiveTaskState, StreamingFactory, StreamingListener,
+    StreamingListenerBuilder, StreamingTransport, UnclaimedGuard,
+};
+use crate::transport::{Direction, Factory, ReceivedMessage, TpHandle, TpKey, Transport};
+use crate::{Endpoint, EndpointBuilder};
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use sip_types::uri::UriInfo;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::{fmt, io};
+use tokio::net::ToSocketAddrs;
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::sleep;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{accept_hdr_async_with_config, client_async_with_config, WebSocketStream};
+
+/// WebSocket subprotocol used to transport SIP
+const SUBPROTOCOL: &str = "sip";
+const SEC_WEBSOCKET_PROTOCOL: &str = "Sec-WebSocket-Protocol";
+
+type WsSink<T> = Arc<Mutex<SplitSink<WebSocketStream<T>, Message>>>;
+
+fn ws_name(secure: bool) -> &'static str {
+    if secure {
+        "WSS"
+    } else {
+        "WS"
+    }
+}
+
+fn ws_error(e: tungstenite::Error) -> io::Error {
+    match e {
+        tungstenite::Error::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+// ==== Connector
+
+/// Factory for outgoing WebSocket connections, using `F` to establish the underlying stream
+pub struct WsConnector<F> {
+    inner: F,
+}
+
+impl<F: StreamingFactory> WsConnector<F> {
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait::async_trait]
+impl<F> Factory for WsConnector<F>
+where
+    F: StreamingFactory,
+    F::Transport: Unpin,
+{
+    fn name(&self) -> &'static str {
+        ws_name(F::Transport::SECURE)
+    }
+
+    fn matches_transport_param(&self, name: &str) -> bool {
+        // Secure websockets are also indicated with `transport=ws`
+        name.eq_ignore_ascii_case("ws") || name.eq_ignore_ascii_case(self.name())
+    }
+
+    fn secure(&self) -> bool {
+        F::Transport::SECURE
+    }
+
+    async fn create(
+        &self,
+        endpoint: Endpoint,
+        uri_info: &UriInfo,
+        addr: SocketAddr,
+    ) -> io::Result<TpHandle> {
+        log::trace!("{} trying to connect to {}", self.name(), addr);
+
+        let stream = self.inner.connect::<SocketAddr>(uri_info, addr).await?;
+        let local = stream.local_addr()?;
+        let remote = stream.peer_addr()?;
+
+        let scheme = if F::Transport::SECURE { "wss" } else { "ws" };
+        let port = uri_info.host_port.port.unwrap_or(addr.port());
+        let url = format!("{scheme}://{}:{port}/", uri_info.host_port.host);
+
+        let mut request = url.into_client_request().map_err(ws_error)?;
+        request.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(SUBPROTOCOL),
+        );
+
+        let (stream, _) = client_async_with_config(request, stream, Some(ws_config(&endpoint)))
+            .await
+            .map_err(ws_error)?;
+        let (sink, stream) = stream.split();
+
+        let sink = Arc::new(Mutex::new(sink));
+
+        let transport = WsTransport {
+            bound: local,
+            remote,
+            incoming: false,
+            sink: sink.clone(),
+        };
+
+        let (transport, notifier) = endpoint.transports().add_managed_used(transport);
+
+        tokio::spawn(receive_task(
+            endpoint.clone(),
+            stream,
+            sink,
+            ReceiveTaskState::InUse(notifier),
+            local,
+            remote,
+            false,
+        ));
+
+        Ok(transport)
+    }
+}
+
+// ==== Listener
+
+/// Accepts incoming WebSocket connections over the streams accepted by `B`
+pub struct WsListener<B> {
+    inner: B,
+}
+
+impl<B> WsListener<B>
+where
+    B: StreamingListenerBuilder,
+    B::Transport: Unpin,
+{
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    pub async fn spawn<A: ToSocketAddrs + Send>(
+        self,
+        endpoint: &mut EndpointBuilder,
+        addr: A,
+    ) -> io::Result<()> {
+        let (listener, bound) = self.inner.bind(addr).await?;
+
+        log::info!(
+            "Accepting {} connections on {}",
+            ws_name(B::Transport::SECURE),
+            bound
+        );
+
+        tokio::spawn(task_accept(endpoint.subscribe(), listener));
+
+        Ok(())
+    }
+}
+
+async fn task_accept<I>(mut endpoint: broadcast::Receiver<Endpoint>, mut incoming: I)
+where
+    I: StreamingListener,
+    I::Transport: Unpin,
+{
+    let endpoint = match endpoint.recv().await.ok() {
+        Some(endpoint) => endpoint,
+        None => return,
+    };
+
+    loop {
+        match incoming.accept().await {
+            Ok((stream, remote)) => {
+                if !endpoint
+                    .transports()
+                    .is_source_allowed(ws_name(I::Transport::SECURE), remote)
+                {
+                    continue;
+                }
+
+                if endpoint.transports().connection_limit_reached() {
+                    log::warn!(
+                        "Refusing connection from {}, connection limit reached",
+                        remote
+                    );
+                    continue;
+                }
+
+                // Perform the handshake in its own task to not block accepting other connections
+                tokio::spawn(accept_connection(endpoint.clone(), stream, remote));
+            }
+            Err(e) => log::error!("Error accepting connection, {}", e),
+        }
+    }
+}
+
+async fn accept_connection<T>(endpoint: Endpoint, stream: T, remote: SocketAddr)
+where
+    T: StreamingTransport + Unpin,
+{
+    let local = match stream.local_addr() {
+        Ok(local) => local,
+        Err(e) => {
+            log::error!("Could not retrieve local addr for incoming stream {}", e);
+            return;
+        }
+    };
+
+    let config = Some(ws_config(&endpoint));
+
+    let stream = match accept_hdr_async_with_config(stream, negotiate_subprotocol, config).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            log::debug!("WebSocket handshake with {} failed, {}", remote, e);
+            return;
+        }
+    };
+
+    log::trace!("WebSocket connection accepted from {} on {}", remote, local);
+
+    let (sink, stream) = stream.split();
+
+    let sink = Arc::new(Mutex::new(sink));
+
+    let transport = WsTransport {
+        bound: local,
+        remote,
+        incoming: true,
+        sink: sink.clone(),
+    };
+
+    let rx = endpoint.transports().add_managed_unused(transport);
+
+    receive_task(
+        endpoint.clone(),
+        stream,
+        sink,
+        ReceiveTaskState::Unused(Box::pin(sleep(endpoint.transports().idle_timeout())), rx),
+        local,
+        remote,
+        true,
+    )
+    .await;
+}
+
+/// WebSocket config which limits message and frame sizes to the endpoint's `max_message_size`
+fn ws_config(endpoint: &Endpoint) -> WebSocketConfig {
+    let max_message_size = endpoint.transports().limits().max_message_size;
+
+    WebSocketConfig::default()
+        .max_message_size(Some(max_message_size))
+        .max_frame_size(Some(max_message_size))
+}
+
+/// Only accept WebSocket clients which negotiate the `sip` subprotocol
+#[allow(clippy::result_large_err)]
+fn negotiate_subprotocol(
+    request: &Request,
+    mut response: Response,
+) -> Result<Response, ErrorResponse> {
+    let offers_sip = request
+        .headers()
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|protocol| protocol.trim().eq_ignore_ascii_case(SUBPROTOCOL));
+
+    if !offers_sip {
+        let mut response = ErrorResponse::new(Some("missing sip subprotocol".into()));
+        *response.status_mut() = StatusCode::BAD_REQUEST;
+        return Err(response);
+    }
+
+    response.headers_mut().insert(
+        SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static(SUBPROTOCOL),
+    );
+
+    Ok(response)
+}
+
+// ==== Transport
+
+/// Connected WebSocket transport
+pub struct WsTransport<T> {
+    bound: SocketAddr,
+    remote: SocketAddr,
+    incoming: bool,
+
+    sink: WsSink<T>,
+}
+
+impl<T: StreamingTransport + Unpin> fmt::Debug for WsTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsTransport")
+            .field("bound", &self.bound)
+            .field("remote", &self.remote)
+            .field("incoming", &self.incoming)
+            .finish()
+    }
+}
+
+impl<T: StreamingTransport + Unpin> fmt::Display for WsTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:bound={}:remote={}",
+            ws_name(T::SECURE),
+            self.bound,
+            self.remote
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> Transport for WsTransport<T>
+where
+    T: StreamingTransport + Unpin,
+{
+    fn name(&self) -> &'static str {
+        ws_name(T::SECURE)
+    }
+
+    fn matches_transport_param(&self, name: &str) -> bool {
+        name.eq_ignore_ascii_case("ws") || name.eq_ignore_ascii_case(self.name())
+    }
+
+    fn secure(&self) -> bool {
+        T::SECURE
+    }
+
+    fn reliable(&self) -> bool {
+        true
+    }
+
+    fn bound(&self) -> SocketAddr {
+        self.bound
+    }
+
+    fn sent_by(&self) -> SocketAddr {
+        self.bound
+    }
+
+    fn direction(&self) -> Direction {
+        if self.incoming {
+            Direction::Incoming(self.remote)
+        } else {
+            Direction::Outgoing(self.remote)
+        }
+    }
+
+    async fn send(&self, bytes: &[u8], _target: SocketAddr) -> io::Result<()> {
+        // SIP messages are sent as text frames unless they contain binary bodies
+        let message = match std::str::from_utf8(bytes) {
+            Ok(text) => Message::text(text),
+            Err(_) => Message::binary(bytes.to_vec()),
+        };
+
+        self.sink.lock().await.send(message).await.map_err(ws_error)
+    }
+}
+
+async fn receive_task<T>(
+    endpoint: Endpoint,
+    mut stream: SplitStream<WebSocketStream<T>>,
+    sink: WsSink<T>,
+    mut state: ReceiveTaskState,
+    local: SocketAddr,
+    remote: SocketAddr,
+    incoming: bool,
+) where
+    T: StreamingTransport + Unpin,
+{
+    let tp_key = TpKey {
+        name: ws_name(T::SECURE),
+        bound: local,
+        direction: if incoming {
+            Direction::Incoming(remote)
+        } else {
+            Direction::Outgoing(remote)
+        },
+    };
+
+    let _drop_guard = UnclaimedGuard {
+        endpoint: &endpoint,
+        tp_key,
+    };
+
+    let mut keep_alive = KeepAliveTimer::new(endpoint.transports().keep_alive_interval());
+
+    loop {
+        let item = match &mut state {
+            ReceiveTaskState::InUse(notifier) => {
+                tokio::select! {
+                    item = stream.next() => item,
+                    _ = notifier => {
+                        log::debug!("all refs to transport dropped, destroying soon if not used");
+                        let rx = endpoint.transports().set_unused(&tp_key);
+                        state = ReceiveTaskState::Unused(Box::pin(sleep(endpoint.transports().idle_timeout())), rx);
+                        continue;
+                    }
+                    _ = keep_alive.tick() => {
+                        send_ping(&sink).await;
+                        continue;
+                    }
+                }
+            }
+            ReceiveTaskState::Unused(timeout, rx) => {
+                tokio::select! {
+                    item = stream.next() => item,
+                    notifier = rx => {
+                        if let Ok(notifier) = notifier {
+                            state = ReceiveTaskState::InUse(notifier);
+
+                            continue;
+                        } else {
+                            log::error!("failed to receive notifier");
+                            return;
+                        }
+                    }
+                    _ = keep_alive.tick() => {
+                        send_ping(&sink).await;
+                        continue;
+                    }
+                    _ = timeout => {
+                        log::debug!("dropping transport, not used anymore");
+                        return;
+                    }
+                }
+            }
+        };
+
+        let bytes = match item {
+            Some(Ok(Message::Text(text))) => text.as_bytes().to_vec(),
+            Some(Ok(Message::Binary(bytes))) => bytes.to_vec(),
+            Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {
+                // pings are answered by tungstenite
+                continue;
+            }
+            Some(Ok(Message::Close(_))) | None => {
+                log::debug!("Connection closed");
+                return;
+            }
+            Some(Err(e)) => {
+                log::warn!(
+                    "An error occurred when reading {} stream {}",
+                    ws_name(T::SECURE),
+                    e
+                );
+                return;
+            }
+        };
+
+        if bytes.len() > endpoint.transports().limits().max_message_size {
+            log::warn!(
+                "Closing {} connection, message too large",
+                ws_name(T::SECURE)
+            );
+            return;
+        }
+
+        let transport = endpoint.transports().set_used(&tp_key);
+
+        // Every WebSocket message contains exactly one SIP message
+        match parse_complete(endpoint.parser(), &bytes, endpoint.transports().limits()) {
+            Ok(CompleteItem::Sip {
+                line,
+                headers,
+                body,
+                buffer,
+                exceeded_limit,
+            }) => {
+                let mut message =
+                    ReceivedMessage::new(remote, buffer, transport, line, headers, body);
+                message.exceeded_limit = exceeded_limit;
+
+                endpoint.receive(message);
+            }
+            Ok(CompleteItem::Stun(message)) => {
+                endpoint.receive_stun(message, remote, transport);
+            }
+            Ok(CompleteItem::KeepAliveRequest | CompleteItem::KeepAliveResponse) => {
+                // keep alive is handled using websocket pings
+            }
+            Err(e) => {
+                log::warn!("Failed to parse message received from {}, {}", remote, e);
+            }
+        }
+    }
+}
+
+async fn send_ping<T: StreamingTransport + Unpin>(sink: &WsSink<T>) {
+    if let Err(e) = sink
+        .lock()
+        .await
+        .send(Message::Ping(Vec::new().into()))
+        .await
+    {
+        log::debug!("Failed to send keep alive ping, {e}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn request(protocols: Option<&'static str>) -> Request {
+        let mut request = Request::new(());
+
+        if let Some(protocols) = protocols {
+            request
+                .headers_mut()
+                .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(protocols));
+        }
+
+        request
+    }
+
+    #[test]
+    fn subprotocol_negotiated() {
+        let response =
+            negotiate_subprotocol(&request(Some("foo, SIP")), Response::new(())).unwrap();
+
+        assert_eq!(
+            response.headers().get(SEC_WEBSOCKET_PROTOCOL).unwrap(),
+            SUBPROTOCOL
+        );
+    }
+
+    #[test]
+    fn subprotocol_missing() {
+        for protocols in [None, Some("foo, sipx")] {
+            let response = negotiate_subprotocol(&request(protocols), Response::new(()))
+                .expect_err("must reject clients without sip subprotocol");
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+}
@@ -205,6 +205,10 @@ codes! {
     /// 410 Gone
     [410 => GONE, "Gone"];
 
+    /// [[RFC3903, Section 11.2.1](https://datatracker.ietf.org/doc/html/rfc3903#section-11.2.1)]
+    /// 412 Conditional Request Failed
+    [412 => CONDITIONAL_REQUEST_FAILED, "Conditional Request Failed"];
+
     /// [[RFC3621, Section 21.4.11](https://tools.ietf.org/html/rfc3261#section-21.4.11)]
     /// 413 Request Entity Too Large
     [413 => REQUEST_ENTITY_TOO_LARGE, "Request Entity Too Large"];
@@ -1,6 +1,6 @@
-use nom::error::VerboseError;
-
 use super::name::Name;
+use internal::ParseError;
+use nom::error::VerboseError;
 use std::error;
 use std::fmt;
 
@@ -17,6 +17,7 @@ pub struct HeaderError {
 enum Repr {
     Missing,
     Malformed(VerboseError<String>),
+    MalformedParse(Box<ParseError>),
     MalformedAdhoc(&'static str),
 }
 
@@ -35,6 +36,14 @@ impl HeaderError {
         }
     }
 
+    /// Create an error for a malformed header from the [`ParseError`] of its value
+    pub fn malformed_parse(name: Name, error: ParseError) -> Self {
+        HeaderError {
+            name,
+            repr: Repr::MalformedParse(Box::new(error)),
+        }
+    }
+
     pub fn malformed_adhoc(name: Name, error: &'static str) -> Self {
         HeaderError {
             name,
@@ -45,6 +54,14 @@ impl HeaderError {
     pub const fn is_missing(&self) -> bool {
         matches!(&self.repr, Repr::Missing)
     }
+
+    /// Returns the parser error if the header was malformed
+    pub fn parse_error(&self) -> Option<&ParseError> {
+        match &self.repr {
+            Repr::MalformedParse(err) => Some(err),
+            Repr::Missing | Repr::Malformed(_) | Repr::MalformedAdhoc(_) => None,
+        }
+    }
 }
 
 impl fmt::Display for HeaderError {
@@ -56,6 +73,11 @@ impl fmt::Display for HeaderError {
                 "header {:?} was found but is malformed: {}",
                 self.name, err
             ),
+            Repr::MalformedParse(err) => write!(
+                f,
+                "header {:?} was found but is malformed: {}",
+                self.name, err
+            ),
             Repr::MalformedAdhoc(err) => write!(
                 f,
                 "header {:?} was found but is malformed: {}",
@@ -3,10 +3,10 @@ use crate::header::name::Name;
 use crate::parse::Parser;
 use crate::print::{AppendCtx, Print, PrintCtx};
 use bytesstr::BytesStr;
-use internal::verbose_error_to_owned;
+use internal::ParseError;
 use nom::Finish;
-use std::iter::{once, FromIterator};
-use std::mem::take;
+use std::iter::FromIterator;
+use std::mem::{self, take};
 use std::{fmt, slice};
 
 /// Headers is simple container for SIP-Message headers.
@@ -69,7 +69,15 @@ impl Headers {
         let ctx = PrintCtx::default();
 
         if let Some(Entry { values, .. }) = self.entry_mut(&name) {
-            header.extend_values(ctx, values);
+            // Existing values are placed after the new ones, e.g. when a proxy adds its Via
+            let existing = mem::replace(values, header.create_values(ctx));
+
+            match existing {
+                OneOrMore::One(value) => values.push(value),
+                OneOrMore::More(existing) => {
+                    existing.into_iter().for_each(|value| values.push(value))
+                }
+            }
         } else {
             self.entries.insert(
                 0,
@@ -388,12 +396,30 @@ impl OneOrMore {
     }
 
     fn decode<H: DecodeValues>(&self, name: Name, parser: Parser) -> Result<H, HeaderError> {
-        match &self {
-            OneOrMore::One(v) => H::decode(parser, &mut once(v)).finish(),
-            OneOrMore::More(v) => H::decode(parser, &mut v.iter()).finish(),
-        }
-        .map(|(_, h)| h)
-        .map_err(|err| HeaderError::malformed(name, verbose_error_to_owned(err)))
+        let values: &[BytesStr] = match &self {
+            OneOrMore::One(v) => slice::from_ref(v),
+            OneOrMore::More(v) => v,
+        };
+
+        H::decode(parser, &mut values.iter())
+            .finish()
+            .map(|(_, h)| h)
+            .map_err(|err| {
+                // Report the position relative to the header value the error occurred in
+                let value = err
+                    .errors
+                    .first()
+                    .and_then(|(i, _)| {
+                        values.iter().find(|v| {
+                            let range = v.as_bytes().as_ptr_range();
+                            range.contains(&i.as_ptr()) || range.end == i.as_ptr()
+                        })
+                    })
+                    .map(|v| v.as_ref())
+                    .unwrap_or_default();
+
+                HeaderError::malformed_parse(name, ParseError::new(value, err))
+            })
     }
 }
 
@@ -471,6 +497,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn header_insert_front_existing() {
+        let mut headers = Headers::new();
+
+        headers.insert_named(&MaxForwards(70));
+        headers.insert_named_front(&MaxForwards(69));
+
+        assert_eq!(headers.entries.len(), 1);
+        assert_eq!(
+            headers.entries[0].values,
+            OneOrMore::More(vec![
+                BytesStr::from_static("69"),
+                BytesStr::from_static("70")
+            ])
+        );
+    }
+
     #[test]
     fn header_remove() {
         let mut headers = Headers::new();
@@ -12,8 +12,10 @@ mod error;
 pub mod headers;
 pub mod multiple;
 pub(crate) mod name;
+mod vendor;
 
 pub use error::HeaderError;
+pub use vendor::HeaderParamValue;
 
 // ==== PARSE TRAITS ====
 
@@ -175,4 +177,111 @@ macro_rules! from_str_header {
     }
 }
 
+/// Define a header consisting of `;` separated parameters, e.g. vendor specific headers like
+/// `X-Device: model=ab-12;firmware=3;secure`.
+///
+/// Each field is mapped to a parameter and its type must implement [`HeaderParamValue`].
+/// Fields of type `Option<T>` are optional, `bool` fields are flag parameters without value.
+/// Attributes are passed through to the generated struct, so additional derives (e.g. serde) can be added.
+///
+/// The header name is created using [`Name::custom`] and doesn't have to be registered elsewhere.
+///
+/// # Examples
+///
+/// ```
+/// use ezk_sip_types::vendor_header;
+/// use ezk_sip_types::Headers;
+/// use bytesstr::BytesStr;
+///
+/// vendor_header! {
+///     /// Custom header describing a device
+///     pub struct XDevice: "X-Device" {
+///         pub model: BytesStr = "model",
+///         pub firmware: Option<u32> = "firmware",
+///         pub secure: bool = "secure",
+///     }
+/// }
+///
+/// let mut headers = Headers::new();
+/// headers.insert_named(&XDevice {
+///     model: "ab-12".into(),
+///     firmware: Some(3),
+///     secure: true,
+/// });
+///
+/// assert_eq!(headers.to_string(), "X-Device: model=ab-12;firmware=3;secure\r\n");
+///
+/// let device: XDevice = headers.get_named().unwrap();
+/// assert_eq!(device.firmware, Some(3));
+/// ```
+#[macro_export]
+macro_rules! vendor_header {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $struct_name:ident: $header_name:literal {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $field_ty:ty = $param:literal
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        $vis struct $struct_name {
+            $(
+            $(#[$field_meta])*
+            $field_vis $field: $field_ty,
+            )*
+        }
+
+        impl $crate::header::ConstNamed for $struct_name {
+            const NAME: $crate::Name = $crate::Name::custom($header_name, &[$header_name]);
+        }
+
+        impl $crate::header::HeaderParse for $struct_name {
+            fn parse<'i>(ctx: $crate::parse::ParseCtx, i: &'i str) -> $crate::_private_reexport::IResult<&'i str, Self> {
+                use $crate::_private_reexport::nom;
+                use $crate::header::HeaderParamValue;
+                use $crate::uri::params::{Params, VPS};
+                use nom::combinator::map_opt;
+
+                map_opt(Params::<VPS>::parse(ctx), |mut params| {
+                    Some(Self {
+                        $(
+                        $field: <$field_ty as HeaderParamValue>::from_param(params.remove($param))?,
+                        )*
+                    })
+                })(i)
+            }
+        }
+
+        impl $crate::header::ExtendValues for $struct_name {
+            fn extend_values(&self, ctx: $crate::print::PrintCtx<'_>, values: &mut $crate::header::headers::OneOrMore) {
+                *values = self.create_values(ctx)
+            }
+
+            fn create_values(&self, _: $crate::print::PrintCtx<'_>) -> $crate::header::headers::OneOrMore {
+                $crate::header::headers::OneOrMore::One(self.to_string().into())
+            }
+        }
+
+        impl ::std::fmt::Display for $struct_name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                use $crate::header::HeaderParamValue;
+                use $crate::uri::params::{Params, VPS};
+
+                let mut params = Params::<VPS>::new();
+
+                $(
+                if let Some(param) = HeaderParamValue::to_param(&self.$field, $param) {
+                    params.push(param);
+                }
+                )*
+
+                ::std::fmt::Display::fmt(&params, f)
+            }
+        }
+    };
+}
+
 pub mod typed;
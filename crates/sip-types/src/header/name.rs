@@ -220,12 +220,24 @@ header_names! {
     /// [[RFC7315, Section 4.6](https://datatracker.ietf.org/doc/html/rfc7315#section-4.6)]
     "P-Charging-Vector", PChargingVector, ["p-charging-vector"], P_CHARGING_VECTOR;
 
+    /// [[RFC3325, Section 9.1](https://datatracker.ietf.org/doc/html/rfc3325#section-9.1)]
+    "P-Asserted-Identity",  PAssertedIdentity,  ["p-asserted-identity"],    P_ASSERTED_IDENTITY;
+
+    /// [[RFC3325, Section 9.2](https://datatracker.ietf.org/doc/html/rfc3325#section-9.2)]
+    "P-Preferred-Identity", PPreferredIdentity, ["p-preferred-identity"],   P_PREFERRED_IDENTITY;
+
     /// [[RFC3621, Section 20.26](https://tools.ietf.org/html/rfc3261#section-20.26)]
     "Priority",             Priority,           ["priority"],               PRIORITY;
 
+    /// [[RFC3323, Section 4.2](https://datatracker.ietf.org/doc/html/rfc3323#section-4.2)]
+    "Privacy",              Privacy,            ["privacy"],                PRIVACY;
+
     /// [[RFC3621, Section 20.27](https://tools.ietf.org/html/rfc3261#section-20.27)]
     "Proxy-Authenticate",   ProxyAuthenticate,  ["proxy-authenticate"],     PROXY_AUTHENTICATE;
 
+    /// [[RFC7615, Section 4](https://datatracker.ietf.org/doc/html/rfc7615#section-4)]
+    "Proxy-Authentication-Info", ProxyAuthenticationInfo, ["proxy-authentication-info"], PROXY_AUTHENTICATION_INFO;
+
     /// [[RFC3621, Section 20.28](https://tools.ietf.org/html/rfc3261#section-20.28)]
     "Proxy-Authorization",  ProxyAuthorization, ["proxy-authorization"],    PROXY_AUTHORIZATION;
 
@@ -235,9 +247,15 @@ header_names! {
      /// [[RFC3262, Section 20.34](https://datatracker.ietf.org/doc/html/rfc3262#section-7.2)]
     "RAck",                 RAck,               ["rack"],                   RACK;
 
+    /// [[RFC3326, Section 2](https://datatracker.ietf.org/doc/html/rfc3326#section-2)]
+    "Reason",               Reason,             ["reason"],                 REASON;
+
     /// [[RFC3621, Section 20.30](https://tools.ietf.org/html/rfc3261#section-20.30)]
     "Record-Route",         RecordRoute,        ["record-route"],           RECORD_ROUTE;
 
+    /// [[RFC3515, Section 2.1](https://datatracker.ietf.org/doc/html/rfc3515#section-2.1)]
+    "Refer-To",             ReferTo,            ["refer-to", "r"],          REFER_TO;
+
     /// [[RFC3891, Section 6.1](https://datatracker.ietf.org/doc/html/rfc3891#section-6.1)]
     "Replaces",             Replaces,           ["replaces"],               REPLACES;
 
@@ -271,6 +289,12 @@ header_names! {
     /// [[RFC4028, Section 20.35](https://datatracker.ietf.org/doc/html/rfc4028#section-4)]
     "Session-Expires",      SessionExpires,     ["session-expires", "x"],        SESSION_EXPIRES;
 
+    /// [[RFC3903, Section 11.3.1](https://datatracker.ietf.org/doc/html/rfc3903#section-11.3.1)]
+    "SIP-ETag",             SipETag,            ["sip-etag"],               SIP_ETAG;
+
+    /// [[RFC3903, Section 11.3.2](https://datatracker.ietf.org/doc/html/rfc3903#section-11.3.2)]
+    "SIP-If-Match",         SipIfMatch,         ["sip-if-match"],           SIP_IF_MATCH;
+
     /// [[RFC3621, Section 20.36](https://tools.ietf.org/html/rfc3261#section-20.36)]
     "Subject",              Subject,            ["subject", "s"],           SUBJECT;
 
@@ -1,23 +1,21 @@
 use crate::header::headers::OneOrMore;
 use crate::header::{ExtendValues, HeaderParse};
-use crate::parse::{parse_quoted, token, whitespace, ParseCtx};
-use crate::print::{AppendCtx, Print, PrintCtx};
+use crate::parse::{parse_quoted, token, unescape_quoted, whitespace, ParseCtx};
+use crate::print::{AppendCtx, Print, PrintCtx, Quoted};
 use anyhow::{bail, Context};
 use bytesstr::BytesStr;
 use internal::ws;
 use internal::IResult;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take_while, take_while1};
-use nom::combinator::{map, map_res};
+use nom::combinator::{eof, map, map_res, peek, recognize};
 use nom::multi::many0;
-use nom::sequence::{preceded, tuple};
+use nom::sequence::{preceded, terminated, tuple};
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use std::borrow::Cow;
 use std::fmt;
 use std::fmt::{Display, Write};
 
-// TODO: auth info header (https://datatracker.ietf.org/doc/html/rfc2617#section-3.2.3)
-
 /// Param contained inside [Auth].
 ///
 /// Has some special printing rules. Might not be hardcoded in the future.
@@ -29,7 +27,7 @@ pub struct AuthParam {
 
 impl fmt::Display for AuthParam {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, r#"{}="{}""#, self.name, self.value)
+        write!(f, "{}={}", self.name, Quoted(&self.value))
     }
 }
 
@@ -40,11 +38,16 @@ impl AuthParam {
                 ws((
                     take_while(token),
                     tag("="),
-                    alt((parse_quoted, take_while(token))),
+                    alt((
+                        map(parse_quoted, |value| unescape_quoted(ctx.src, value)),
+                        map(take_while(token), |value| {
+                            BytesStr::from_parse(ctx.src, value)
+                        }),
+                    )),
                 )),
                 move |(name, _, value)| AuthParam {
                     name: BytesStr::from_parse(ctx.src, name),
-                    value: BytesStr::from_parse(ctx.src, value),
+                    value,
                 },
             )(i)
         }
@@ -52,6 +55,7 @@ impl AuthParam {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum AuthChallenge {
     Digest(DigestChallenge),
     Other(Auth),
@@ -61,10 +65,14 @@ impl HeaderParse for AuthChallenge {
     fn parse<'i>(ctx: ParseCtx, i: &'i str) -> IResult<&'i str, Self> {
         map_res(
             parse_auth_params(ctx),
-            |(scheme, params)| -> anyhow::Result<Self> {
+            |(scheme, token, params)| -> anyhow::Result<Self> {
                 match scheme.as_ref() {
                     "Digest" => Ok(Self::Digest(DigestChallenge::from_auth_params(params)?)),
-                    _ => Ok(Self::Other(Auth { scheme, params })),
+                    _ => Ok(Self::Other(Auth {
+                        scheme,
+                        token,
+                        params,
+                    })),
                 }
             },
         )(i)
@@ -154,16 +162,17 @@ impl Print for DigestChallenge {
     fn print(&self, f: &mut fmt::Formatter<'_>, _ctx: PrintCtx<'_>) -> fmt::Result {
         write!(
             f,
-            r#"Digest realm="{}", nonce="{}""#,
-            self.realm, self.nonce,
+            "Digest realm={}, nonce={}",
+            Quoted(&self.realm),
+            Quoted(&self.nonce),
         )?;
 
         if let Some(domain) = &self.domain {
-            write!(f, r#", domain="{}""#, domain)?;
+            write!(f, ", domain={}", Quoted(domain))?;
         }
 
         if let Some(opaque) = &self.opaque {
-            write!(f, r#", opaque="{}""#, opaque)?;
+            write!(f, ", opaque={}", Quoted(opaque))?;
         }
 
         if self.stale {
@@ -212,10 +221,14 @@ impl HeaderParse for AuthResponse {
     fn parse<'i>(ctx: ParseCtx, i: &'i str) -> IResult<&'i str, Self> {
         map_res(
             parse_auth_params(ctx),
-            |(scheme, params)| -> anyhow::Result<Self> {
+            |(scheme, token, params)| -> anyhow::Result<Self> {
                 match scheme.as_ref() {
                     "Digest" => Ok(Self::Digest(DigestResponse::from_auth_params(params)?)),
-                    _ => Ok(Self::Other(Auth { scheme, params })),
+                    _ => Ok(Self::Other(Auth {
+                        scheme,
+                        token,
+                        params,
+                    })),
                 }
             },
         )(i)
@@ -283,7 +296,7 @@ impl Display for Username {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Username::Username(username) => {
-                write!(f, r#"username="{}""#, username)
+                write!(f, "username={}", Quoted(username))
             }
             Username::UsernameNonASCII(username_non_ascii) => {
                 write!(f, r#"username*={}"#, username_non_ascii)
@@ -392,8 +405,12 @@ impl Print for DigestResponse {
     fn print(&self, f: &mut fmt::Formatter<'_>, _ctx: PrintCtx<'_>) -> fmt::Result {
         write!(
             f,
-            r#"Digest {}, realm="{}", nonce="{}", uri="{}", response="{}""#,
-            self.username, self.realm, self.nonce, self.uri, self.response
+            "Digest {}, realm={}, nonce={}, uri={}, response={}",
+            self.username,
+            Quoted(&self.realm),
+            Quoted(&self.nonce),
+            Quoted(&self.uri),
+            Quoted(&self.response)
         )?;
 
         if !matches!(
@@ -404,14 +421,16 @@ impl Print for DigestResponse {
         }
 
         if let Some(opaque) = &self.opaque {
-            write!(f, r#", opaque="{}""#, opaque)?;
+            write!(f, ", opaque={}", Quoted(opaque))?;
         }
 
         if let Some(qop_response) = &self.qop_response {
             write!(
                 f,
-                r#", qop="{}", cnonce="{}", nc={:08X}"#,
-                qop_response.qop, qop_response.cnonce, qop_response.nc
+                r#", qop="{}", cnonce={}, nc={:08X}"#,
+                qop_response.qop,
+                Quoted(&qop_response.cnonce),
+                qop_response.nc
             )?;
         }
 
@@ -427,6 +446,103 @@ impl Print for DigestResponse {
     }
 }
 
+/// `Authentication-Info` or `Proxy-Authentication-Info` header sent in a successful response to
+/// an authorized request ([RFC2617 Section 3.2.3](https://datatracker.ietf.org/doc/html/rfc2617#section-3.2.3))
+#[derive(Debug, Clone, Default)]
+pub struct AuthenticationInfo {
+    /// Nonce the client should use for the next request
+    pub nextnonce: Option<BytesStr>,
+    pub qop: Option<QopOption>,
+    /// Response digest which proves that the server knows the user's secret
+    pub rspauth: Option<BytesStr>,
+    pub cnonce: Option<BytesStr>,
+    pub nc: Option<u32>,
+    /// Remaining fields
+    pub other: Vec<AuthParam>,
+}
+
+impl HeaderParse for AuthenticationInfo {
+    fn parse<'i>(ctx: ParseCtx, i: &'i str) -> IResult<&'i str, Self> {
+        map_res(
+            tuple((
+                AuthParam::parse(ctx),
+                many0(map(ws((tag(","), AuthParam::parse(ctx))), |(_, param)| {
+                    param
+                })),
+            )),
+            |(first, params)| -> anyhow::Result<Self> {
+                let mut info = AuthenticationInfo::default();
+
+                for param in std::iter::once(first).chain(params) {
+                    match param.name.as_ref() {
+                        "nextnonce" => info.nextnonce = Some(param.value),
+                        "qop" => info.qop = Some(QopOption::from(param.value)),
+                        "rspauth" => info.rspauth = Some(param.value),
+                        "cnonce" => info.cnonce = Some(param.value),
+                        "nc" => {
+                            info.nc = Some(
+                                u32::from_str_radix(param.value.as_ref(), 16)
+                                    .context("Failed to parse nc value")?,
+                            )
+                        }
+                        _ => info.other.push(param),
+                    }
+                }
+
+                Ok(info)
+            },
+        )(i)
+    }
+}
+
+impl ExtendValues for AuthenticationInfo {
+    fn extend_values(&self, ctx: PrintCtx<'_>, values: &mut OneOrMore) {
+        *values = self.create_values(ctx)
+    }
+
+    fn create_values(&self, ctx: PrintCtx<'_>) -> OneOrMore {
+        OneOrMore::One(self.print_ctx(ctx).to_string().into())
+    }
+}
+
+impl Print for AuthenticationInfo {
+    fn print(&self, f: &mut fmt::Formatter<'_>, _ctx: PrintCtx<'_>) -> fmt::Result {
+        let mut separator = "";
+
+        let mut write_param = |f: &mut fmt::Formatter<'_>, param: fmt::Arguments<'_>| {
+            let result = write!(f, "{separator}{param}");
+            separator = ", ";
+            result
+        };
+
+        if let Some(nextnonce) = &self.nextnonce {
+            write_param(f, format_args!("nextnonce={}", Quoted(nextnonce)))?;
+        }
+
+        if let Some(qop) = &self.qop {
+            write_param(f, format_args!("qop={}", qop))?;
+        }
+
+        if let Some(rspauth) = &self.rspauth {
+            write_param(f, format_args!("rspauth={}", Quoted(rspauth)))?;
+        }
+
+        if let Some(cnonce) = &self.cnonce {
+            write_param(f, format_args!("cnonce={}", Quoted(cnonce)))?;
+        }
+
+        if let Some(nc) = self.nc {
+            write_param(f, format_args!("nc={:08X}", nc))?;
+        }
+
+        for param in &self.other {
+            write_param(f, format_args!("{}", param))?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct QopResponse {
     pub qop: QopOption,
@@ -574,37 +690,60 @@ impl fmt::Display for AlgorithmValue {
     }
 }
 
-fn parse_auth_params(
-    ctx: ParseCtx<'_>,
-) -> impl Fn(&str) -> IResult<&str, (BytesStr, Vec<AuthParam>)> + '_ {
+type AuthParts = (BytesStr, Option<BytesStr>, Vec<AuthParam>);
+
+fn parse_auth_params(ctx: ParseCtx<'_>) -> impl Fn(&str) -> IResult<&str, AuthParts> + '_ {
     move |i| {
-        tuple((
-            map(take_while1(|c| !whitespace(c)), |scheme| {
-                BytesStr::from_parse(ctx.src, scheme)
-            }),
-            preceded(
-                take_while(whitespace),
-                map(
-                    tuple((
-                        AuthParam::parse(ctx),
-                        many0(map(ws((tag(","), AuthParam::parse(ctx))), |(_, scheme)| {
-                            scheme
-                        })),
+        map(
+            tuple((
+                map(take_while1(|c| !whitespace(c)), |scheme| {
+                    BytesStr::from_parse(ctx.src, scheme)
+                }),
+                preceded(
+                    take_while(whitespace),
+                    alt((
+                        map(parse_token68, |token| {
+                            (Some(BytesStr::from_parse(ctx.src, token)), vec![])
+                        }),
+                        map(
+                            tuple((
+                                AuthParam::parse(ctx),
+                                many0(map(ws((tag(","), AuthParam::parse(ctx))), |(_, scheme)| {
+                                    scheme
+                                })),
+                            )),
+                            |(first_param, mut v)| {
+                                v.insert(0, first_param);
+                                (None, v)
+                            },
+                        ),
                     )),
-                    |(first_param, mut v)| {
-                        v.insert(0, first_param);
-                        v
-                    },
                 ),
-            ),
-        ))(i)
+            )),
+            |(scheme, (token, params))| (scheme, token, params),
+        )(i)
     }
 }
 
+/// Parse token68 credentials (e.g. a Bearer token) which must be followed by the end of the value or a comma
+fn parse_token68(i: &str) -> IResult<&str, &str> {
+    terminated(
+        recognize(tuple((
+            take_while1(|c: char| {
+                c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~' | '+' | '/')
+            }),
+            take_while(|c| c == '='),
+        ))),
+        peek(preceded(take_while(whitespace), alt((eof, tag(","))))),
+    )(i)
+}
+
 /// Implementation for all Auth kind headers.
 #[derive(Debug, Clone)]
 pub struct Auth {
     pub scheme: BytesStr,
+    /// token68 credentials (e.g. `Bearer <token>`), used instead of params
+    pub token: Option<BytesStr>,
     pub params: Vec<AuthParam>,
 }
 
@@ -612,6 +751,10 @@ impl Print for Auth {
     fn print(&self, f: &mut fmt::Formatter<'_>, _: PrintCtx<'_>) -> fmt::Result {
         write!(f, "{} ", self.scheme)?;
 
+        if let Some(token) = &self.token {
+            return f.write_str(token);
+        }
+
         let mut params = self.params.iter();
 
         if let Some(param) = params.next() {
@@ -666,6 +809,71 @@ mod test {
         assert_eq!(rem, "");
     }
 
+    #[test]
+    fn authentication_info() {
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::AUTHENTICATION_INFO,
+            r#"nextnonce="47364c23432d2e131a5fb210812c", qop=auth, rspauth="6629fae49393a05397450978507c4ef1", cnonce="0a4f113b", nc=00000001"#,
+        );
+
+        let info: AuthenticationInfo = headers.get(Name::AUTHENTICATION_INFO).unwrap();
+
+        assert_eq!(
+            info.nextnonce.as_deref(),
+            Some("47364c23432d2e131a5fb210812c")
+        );
+        assert_eq!(info.qop, Some(QopOption::Auth));
+        assert_eq!(
+            info.rspauth.as_deref(),
+            Some("6629fae49393a05397450978507c4ef1")
+        );
+        assert_eq!(info.cnonce.as_deref(), Some("0a4f113b"));
+        assert_eq!(info.nc, Some(1));
+        assert!(info.other.is_empty());
+
+        assert_eq!(
+            info.default_print_ctx().to_string(),
+            r#"nextnonce="47364c23432d2e131a5fb210812c", qop=auth, rspauth="6629fae49393a05397450978507c4ef1", cnonce="0a4f113b", nc=00000001"#
+        );
+    }
+
+    #[test]
+    fn bearer_token() {
+        let mut headers = Headers::new();
+        headers.insert(Name::AUTHORIZATION, "Bearer dGVzdC50b2tlbg==");
+        headers.insert(
+            Name::WWW_AUTHENTICATE,
+            r#"Bearer realm="atlanta.com", scope="sip:register""#,
+        );
+
+        let AuthResponse::Other(authorization) =
+            headers.get::<AuthResponse>(Name::AUTHORIZATION).unwrap()
+        else {
+            panic!()
+        };
+
+        assert_eq!(authorization.scheme, "Bearer");
+        assert_eq!(authorization.token.as_deref(), Some("dGVzdC50b2tlbg=="));
+        assert!(authorization.params.is_empty());
+        assert_eq!(
+            authorization.default_print_ctx().to_string(),
+            "Bearer dGVzdC50b2tlbg=="
+        );
+
+        let AuthChallenge::Other(challenge) = headers
+            .get::<AuthChallenge>(Name::WWW_AUTHENTICATE)
+            .unwrap()
+        else {
+            panic!()
+        };
+
+        assert_eq!(challenge.scheme, "Bearer");
+        assert_eq!(challenge.token, None);
+        assert_eq!(challenge.params[0].name, "realm");
+        assert_eq!(challenge.params[1].value, "sip:register");
+    }
+
     #[test]
     fn print_simple_digest_challenge() {
         let challenge = AuthChallenge::Digest(DigestChallenge {
@@ -1015,7 +1223,7 @@ mod test {
         }
 
         match &www_vec[2] {
-            AuthChallenge::Other(Auth { scheme, params }) => {
+            AuthChallenge::Other(Auth { scheme, params, .. }) => {
                 assert_eq!(scheme, "OAuth");
                 assert_eq!(params[0].name, "some-field");
                 assert_eq!(params[0].value, "oauth_field");
@@ -18,6 +18,13 @@ from_str_header! {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ContentType(pub BytesStr);
 
+impl ContentType {
+    /// Returns the media type (`type/subtype`) without any parameters
+    pub fn media_type(&self) -> &str {
+        self.0.split(';').next().unwrap_or_default().trim()
+    }
+}
+
 impl ConstNamed for ContentType {
     const NAME: Name = Name::CONTENT_TYPE;
 }
@@ -80,4 +87,13 @@ mod test {
         let ctype: ContentType = headers.get_named().unwrap();
         assert_eq!(ctype.0, "application/sdp");
     }
+
+    #[test]
+    fn content_type_media_type() {
+        let content_type = ContentType(BytesStr::from_static("text/plain ; charset=UTF-8"));
+        assert_eq!(content_type.media_type(), "text/plain");
+
+        let content_type = ContentType(BytesStr::from_static("application/sdp"));
+        assert_eq!(content_type.media_type(), "application/sdp");
+    }
 }
@@ -0,0 +1,73 @@
+use crate::header::headers::OneOrMore;
+use crate::header::name::Name;
+use crate::header::{ConstNamed, ExtendValues, HeaderParse};
+use crate::parse::ParseCtx;
+use crate::print::PrintCtx;
+use bytesstr::BytesStr;
+use internal::{identity, IResult};
+use nom::combinator::map;
+
+macro_rules! etag_header {
+    ($(#[$meta:meta])* $struct_name:ident, $header_name:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $struct_name(pub BytesStr);
+
+        impl ConstNamed for $struct_name {
+            const NAME: Name = $header_name;
+        }
+
+        impl HeaderParse for $struct_name {
+            fn parse<'i>(ctx: ParseCtx, i: &'i str) -> IResult<&'i str, Self> {
+                map(identity(), |i| {
+                    Self(BytesStr::from_parse(ctx.src, i.trim()))
+                })(i)
+            }
+        }
+
+        impl ExtendValues for $struct_name {
+            fn extend_values(&self, ctx: PrintCtx<'_>, values: &mut OneOrMore) {
+                *values = self.create_values(ctx)
+            }
+
+            fn create_values(&self, _: PrintCtx<'_>) -> OneOrMore {
+                OneOrMore::One(self.0.as_str().into())
+            }
+        }
+    };
+}
+
+etag_header! {
+    /// `SIP-ETag` header, entity-tag assigned to published event state by the event state compositor
+    SipETag,
+    Name::SIP_ETAG
+}
+
+etag_header! {
+    /// `SIP-If-Match` header, entity-tag of the event state a PUBLISH refreshes, modifies or removes
+    SipIfMatch,
+    Name::SIP_IF_MATCH
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Headers;
+
+    #[test]
+    fn parse_etag() {
+        let mut headers = Headers::new();
+        headers.insert(Name::SIP_ETAG, "dx200xyz");
+
+        let etag: SipETag = headers.get_named().unwrap();
+        assert_eq!(etag.0, "dx200xyz");
+    }
+
+    #[test]
+    fn print_if_match() {
+        let mut headers = Headers::new();
+        headers.insert_named(&SipIfMatch(BytesStr::from_static("dx200xyz")));
+
+        assert_eq!(headers.to_string(), "SIP-If-Match: dx200xyz\r\n");
+    }
+}
@@ -0,0 +1,106 @@
+//! [RFC3325](https://datatracker.ietf.org/doc/html/rfc3325)
+
+use crate::header::headers::OneOrMore;
+use crate::header::name::Name;
+use crate::header::{ConstNamed, ExtendValues, HeaderParse};
+use crate::parse::ParseCtx;
+use crate::print::{AppendCtx, Print, PrintCtx, UriContext};
+use crate::uri::NameAddr;
+use internal::IResult;
+use nom::combinator::map;
+use std::fmt;
+
+macro_rules! identity_header {
+    ($(#[$meta:meta])* $ident:ident, $name:expr) => {
+        $(#[$meta])*
+        ///
+        /// May contain multiple identities (e.g. a sip and a tel URI), use `Vec<_>` to get all of them.
+        #[derive(Debug, Clone)]
+        pub struct $ident(pub NameAddr);
+
+        impl ConstNamed for $ident {
+            const NAME: Name = $name;
+        }
+
+        impl HeaderParse for $ident {
+            fn parse<'i>(ctx: ParseCtx<'_>, i: &'i str) -> IResult<&'i str, Self> {
+                map(NameAddr::parse(ctx), Self)(i)
+            }
+        }
+
+        impl ExtendValues for $ident {
+            fn extend_values(&self, ctx: PrintCtx<'_>, values: &mut OneOrMore) {
+                let value = match values {
+                    OneOrMore::One(value) => value,
+                    OneOrMore::More(values) => {
+                        values.last_mut().expect("empty OneOrMore::More variant")
+                    }
+                };
+
+                *value = format!("{}, {}", value, self.print_ctx(ctx)).into();
+            }
+
+            fn create_values(&self, ctx: PrintCtx<'_>) -> OneOrMore {
+                OneOrMore::One(self.print_ctx(ctx).to_string().into())
+            }
+        }
+
+        impl Print for $ident {
+            fn print(&self, f: &mut fmt::Formatter<'_>, mut ctx: PrintCtx<'_>) -> fmt::Result {
+                ctx.uri = Some(UriContext::FromTo);
+                self.0.print(f, ctx)
+            }
+        }
+    };
+}
+
+identity_header! {
+    /// `P-Asserted-Identity` header, identity of the user asserted by a trusted entity
+    PAssertedIdentity, Name::P_ASSERTED_IDENTITY
+}
+
+identity_header! {
+    /// `P-Preferred-Identity` header, identity the user would like to be asserted
+    PPreferredIdentity, Name::P_PREFERRED_IDENTITY
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::uri::sip::SipUri;
+    use crate::uri::tel::TelUri;
+    use crate::Headers;
+
+    #[test]
+    fn parse_multiple() {
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::P_ASSERTED_IDENTITY,
+            "\"Cullen Jennings\" <sip:fluffy@cisco.com>, tel:+14085264000",
+        );
+
+        let identities: Vec<PAssertedIdentity> = headers.get_named().unwrap();
+        assert_eq!(identities.len(), 2);
+
+        assert_eq!(identities[0].0.name.as_deref(), Some("Cullen Jennings"));
+        assert!(identities[0].0.uri.downcast_ref::<SipUri>().is_some());
+
+        let tel: &TelUri = identities[1].0.uri.downcast_ref().unwrap();
+        assert_eq!(tel.number, "+14085264000");
+    }
+
+    #[test]
+    fn print_multiple() {
+        let sip: SipUri = "sip:alice@example.com".parse().unwrap();
+        let tel: TelUri = "tel:+1-201-555-0123".parse().unwrap();
+
+        let mut headers = Headers::new();
+        headers.insert_named(&PPreferredIdentity(NameAddr::uri(sip)));
+        headers.insert_named(&PPreferredIdentity(NameAddr::uri(tel)));
+
+        assert_eq!(
+            headers.to_string(),
+            "P-Preferred-Identity: <sip:alice@example.com>, <tel:+12015550123>\r\n"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! `Call-Info` & `Alert-Info` headers ([RFC3261 Section 20.4](https://datatracker.ietf.org/doc/html/rfc3261#section-20.4),
+//! [RFC3261 Section 20.9](https://datatracker.ietf.org/doc/html/rfc3261#section-20.9))
+
+use crate::header::headers::OneOrMore;
+use crate::header::name::Name;
+use crate::header::{ConstNamed, ExtendValues, HeaderParse};
+use crate::parse::ParseCtx;
+use crate::print::PrintCtx;
+use crate::uri::params::{Params, CPS};
+use bytesstr::BytesStr;
+use internal::{ws, IResult};
+use nom::bytes::complete::take_while1;
+use nom::character::complete::char;
+use nom::combinator::map;
+use std::fmt;
+
+macro_rules! info_header {
+    ($(#[$meta:meta])* $ident:ident, $name:expr) => {
+        $(#[$meta])*
+        ///
+        /// The URI is kept as string since it is usually not a SIP URI (e.g. `http:` or `urn:`).
+        /// May occur multiple times, use `Vec<_>` to get all of them.
+        #[derive(Debug, Clone)]
+        pub struct $ident {
+            pub uri: BytesStr,
+            pub params: Params<CPS>,
+        }
+
+        impl $ident {
+            pub fn new<U: Into<BytesStr>>(uri: U) -> Self {
+                Self {
+                    uri: uri.into(),
+                    params: Params::new(),
+                }
+            }
+
+            impl_with_params!(params, with_key, with_value);
+        }
+
+        impl ConstNamed for $ident {
+            const NAME: Name = $name;
+        }
+
+        impl HeaderParse for $ident {
+            fn parse<'i>(ctx: ParseCtx<'_>, i: &'i str) -> IResult<&'i str, Self> {
+                map(
+                    ws((
+                        char('<'),
+                        take_while1(|c| c != '>'),
+                        char('>'),
+                        Params::<CPS>::parse(ctx),
+                    )),
+                    |(_, uri, _, params)| Self {
+                        uri: BytesStr::from_parse(ctx.src, uri.trim()),
+                        params,
+                    },
+                )(i)
+            }
+        }
+
+        impl ExtendValues for $ident {
+            fn extend_values(&self, _: PrintCtx<'_>, values: &mut OneOrMore) {
+                let value = match values {
+                    OneOrMore::One(value) => value,
+                    OneOrMore::More(values) => {
+                        values.last_mut().expect("empty OneOrMore::More variant")
+                    }
+                };
+
+                *value = format!("{}, {}", value, self).into();
+            }
+
+            fn create_values(&self, _: PrintCtx<'_>) -> OneOrMore {
+                OneOrMore::One(self.to_string().into())
+            }
+        }
+
+        impl fmt::Display for $ident {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "<{}>{}", self.uri, self.params)
+            }
+        }
+    };
+}
+
+info_header! {
+    /// `Call-Info` header, additional information about the caller or callee
+    CallInfo, Name::CALL_INFO
+}
+
+info_header! {
+    /// `Alert-Info` header, specifies an alternative ring tone or alerting behavior
+    AlertInfo, Name::ALERT_INFO
+}
+
+impl CallInfo {
+    /// Returns the `purpose` parameter (e.g. `icon`, `info` or `card`)
+    pub fn purpose(&self) -> Option<&BytesStr> {
+        self.params.get_val("purpose")
+    }
+
+    /// Returns the `appearance` parameter used by shared line appearances
+    pub fn appearance(&self) -> Option<u32> {
+        self.params.get_val("appearance")?.parse().ok()
+    }
+
+    /// Returns the seconds after which the call should be answered automatically,
+    /// from the `answer-after` parameter
+    pub fn answer_after(&self) -> Option<u32> {
+        self.params.get_val("answer-after")?.parse().ok()
+    }
+}
+
+impl AlertInfo {
+    /// Returns the `info` parameter (e.g. `alert-autoanswer` or a distinctive ring name)
+    pub fn info(&self) -> Option<&BytesStr> {
+        self.params.get_val("info")
+    }
+
+    /// Returns if the callee is asked to answer the call automatically (`info=alert-autoanswer`)
+    pub fn is_auto_answer(&self) -> bool {
+        self.info()
+            .is_some_and(|info| info.eq_ignore_ascii_case("alert-autoanswer"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Headers;
+
+    #[test]
+    fn parse_call_info() {
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::CALL_INFO,
+            "<http://wwww.example.com/alice/photo.jpg> ;purpose=icon, <sip:alice@example.com>;answer-after=0",
+        );
+
+        let call_info: Vec<CallInfo> = headers.get_named().unwrap();
+
+        assert_eq!(call_info.len(), 2);
+        assert_eq!(call_info[0].uri, "http://wwww.example.com/alice/photo.jpg");
+        assert_eq!(call_info[0].purpose().unwrap(), "icon");
+        assert_eq!(call_info[0].answer_after(), None);
+        assert_eq!(call_info[1].answer_after(), Some(0));
+    }
+
+    #[test]
+    fn parse_alert_info() {
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::ALERT_INFO,
+            "<http://www.example.com/sounds/moo.wav>;info=alert-autoanswer",
+        );
+
+        let alert_info: AlertInfo = headers.get_named().unwrap();
+
+        assert_eq!(alert_info.uri, "http://www.example.com/sounds/moo.wav");
+        assert!(alert_info.is_auto_answer());
+    }
+
+    #[test]
+    fn print_info() {
+        let mut headers = Headers::new();
+        headers.insert_named(&AlertInfo::new("urn:alert:service:call-waiting"));
+        headers
+            .insert_named(&CallInfo::new("sip:alice@example.com").with_value("answer-after", "5"));
+
+        assert_eq!(
+            headers.to_string(),
+            "Alert-Info: <urn:alert:service:call-waiting>\r\n\
+            Call-Info: <sip:alice@example.com>;answer-after=5\r\n"
+        );
+    }
+}
@@ -8,18 +8,25 @@ mod call_id;
 mod contact;
 mod content;
 mod cseq;
+mod etag;
 mod event;
 mod expires;
 mod extensions;
 mod from_to;
+mod identity;
+mod info;
 mod max_fwd;
 mod prack;
+mod privacy;
+mod reason;
+mod refer_to;
 mod replaces;
 mod retry_after;
 mod routing;
 mod subscription_state;
 mod timer;
 mod via;
+mod warning;
 
 pub use accept::Accept;
 pub use allow::Allow;
@@ -29,15 +36,22 @@ pub use call_id::CallID;
 pub use contact::Contact;
 pub use content::{ContentLength, ContentType};
 pub use cseq::CSeq;
+pub use etag::{SipETag, SipIfMatch};
 pub use event::Event;
 pub use expires::{Expires, MinExpires};
-pub use extensions::{Require, Supported};
+pub use extensions::{Require, Supported, Unsupported};
 pub use from_to::FromTo;
+pub use identity::{PAssertedIdentity, PPreferredIdentity};
+pub use info::{AlertInfo, CallInfo};
 pub use max_fwd::MaxForwards;
 pub use prack::{RAck, RSeq};
+pub use privacy::{Privacy, PrivacyValue};
+pub use reason::Reason;
+pub use refer_to::ReferTo;
 pub use replaces::Replaces;
 pub use retry_after::RetryAfter;
 pub use routing::Routing;
 pub use subscription_state::{EventReasonValue, SubStateValue, SubscriptionState};
 pub use timer::{MinSe, Refresher, SessionExpires};
 pub use via::Via;
+pub use warning::Warning;
@@ -0,0 +1,153 @@
+//! [RFC3323](https://datatracker.ietf.org/doc/html/rfc3323) & [RFC3325](https://datatracker.ietf.org/doc/html/rfc3325)
+
+use crate::header::headers::OneOrMore;
+use crate::header::name::Name;
+use crate::header::{ConstNamed, ExtendValues, HeaderParse};
+use crate::parse::{token, ParseCtx};
+use crate::print::PrintCtx;
+use bytesstr::BytesStr;
+use internal::{ws, IResult};
+use nom::bytes::complete::{tag, take_while1};
+use nom::combinator::map;
+use nom::multi::separated_list1;
+use std::fmt;
+
+/// priv-value defined in [RFC3323](https://datatracker.ietf.org/doc/html/rfc3323#section-4.2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivacyValue {
+    /// Hide headers which could identify the user
+    Header,
+
+    /// Provide anonymity for the session (media)
+    Session,
+
+    /// User level privacy, only requested by intermediaries
+    User,
+
+    /// No privacy is requested
+    None,
+
+    /// The requested privacy must be provided, otherwise the request must be rejected
+    Critical,
+
+    /// Hide the P-Asserted-Identity ([RFC3325](https://datatracker.ietf.org/doc/html/rfc3325#section-9.3))
+    Id,
+
+    Other(BytesStr),
+}
+
+impl PrivacyValue {
+    fn from_parse(ctx: ParseCtx<'_>, value: &str) -> Self {
+        match value {
+            _ if value.eq_ignore_ascii_case("header") => Self::Header,
+            _ if value.eq_ignore_ascii_case("session") => Self::Session,
+            _ if value.eq_ignore_ascii_case("user") => Self::User,
+            _ if value.eq_ignore_ascii_case("none") => Self::None,
+            _ if value.eq_ignore_ascii_case("critical") => Self::Critical,
+            _ if value.eq_ignore_ascii_case("id") => Self::Id,
+            _ => Self::Other(BytesStr::from_parse(ctx.src, value)),
+        }
+    }
+}
+
+impl fmt::Display for PrivacyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrivacyValue::Header => f.write_str("header"),
+            PrivacyValue::Session => f.write_str("session"),
+            PrivacyValue::User => f.write_str("user"),
+            PrivacyValue::None => f.write_str("none"),
+            PrivacyValue::Critical => f.write_str("critical"),
+            PrivacyValue::Id => f.write_str("id"),
+            PrivacyValue::Other(other) => f.write_str(other),
+        }
+    }
+}
+
+/// `Privacy` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Privacy(pub Vec<PrivacyValue>);
+
+impl Privacy {
+    /// Returns if the header contains the given value
+    pub fn contains(&self, value: &PrivacyValue) -> bool {
+        self.0.contains(value)
+    }
+}
+
+impl ConstNamed for Privacy {
+    const NAME: Name = Name::PRIVACY;
+}
+
+impl HeaderParse for Privacy {
+    fn parse<'i>(ctx: ParseCtx<'_>, i: &'i str) -> IResult<&'i str, Self> {
+        map(
+            separated_list1(
+                ws((tag(";"),)),
+                map(ws((take_while1(token),)), |(value,)| {
+                    PrivacyValue::from_parse(ctx, value)
+                }),
+            ),
+            Privacy,
+        )(i)
+    }
+}
+
+impl ExtendValues for Privacy {
+    fn extend_values(&self, ctx: PrintCtx<'_>, values: &mut OneOrMore) {
+        *values = self.create_values(ctx)
+    }
+
+    fn create_values(&self, _: PrintCtx<'_>) -> OneOrMore {
+        OneOrMore::One(self.to_string().into())
+    }
+}
+
+impl fmt::Display for Privacy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut values = self.0.iter();
+
+        if let Some(first) = values.next() {
+            write!(f, "{}", first)?;
+        }
+
+        for value in values {
+            write!(f, ";{}", value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Headers;
+
+    #[test]
+    fn privacy() {
+        let input = BytesStr::from_static("id; critical;history");
+
+        let (rem, privacy) = Privacy::parse(ParseCtx::default(&input), &input).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(
+            privacy.0,
+            [
+                PrivacyValue::Id,
+                PrivacyValue::Critical,
+                PrivacyValue::Other("history".into())
+            ]
+        );
+        assert!(privacy.contains(&PrivacyValue::Id));
+        assert!(!privacy.contains(&PrivacyValue::None));
+    }
+
+    #[test]
+    fn print_privacy() {
+        let mut headers = Headers::new();
+        headers.insert_named(&Privacy(vec![PrivacyValue::Id, PrivacyValue::Header]));
+
+        assert_eq!(headers.to_string(), "Privacy: id;header\r\n");
+    }
+}
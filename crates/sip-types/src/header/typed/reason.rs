@@ -0,0 +1,165 @@
+//! [RFC3326](https://datatracker.ietf.org/doc/html/rfc3326)
+
+use crate::header::headers::OneOrMore;
+use crate::header::{ConstNamed, ExtendValues, HeaderParse};
+use crate::parse::{token, ParseCtx};
+use crate::print::{PrintCtx, Quoted};
+use crate::uri::params::{Params, CPS};
+use crate::Name;
+use bytesstr::BytesStr;
+use internal::{ws, IResult};
+use nom::bytes::complete::take_while1;
+use nom::combinator::map;
+use std::fmt;
+
+/// `Reason` header, carries the cause of a request (e.g. why a call was terminated)
+///
+/// Multiple reasons with different protocols can be contained in a message,
+/// use `Vec<Reason>` to get all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reason {
+    /// Protocol of the cause, e.g. `SIP` or `Q.850`
+    pub protocol: BytesStr,
+
+    /// Cause value, a status code for `SIP` or cause code for `Q.850`
+    pub cause: Option<u16>,
+
+    /// Human readable description of the cause
+    pub text: Option<BytesStr>,
+}
+
+impl Reason {
+    pub const SIP: &'static str = "SIP";
+    pub const Q850: &'static str = "Q.850";
+
+    pub fn new<P: Into<BytesStr>>(protocol: P, cause: u16) -> Self {
+        Self {
+            protocol: protocol.into(),
+            cause: Some(cause),
+            text: None,
+        }
+    }
+
+    /// Create a reason with a SIP status code
+    pub fn sip(code: u16) -> Self {
+        Self::new(Self::SIP, code)
+    }
+
+    /// Create a reason with a Q.850 cause code
+    pub fn q850(cause: u16) -> Self {
+        Self::new(Self::Q850, cause)
+    }
+
+    pub fn with_text<T: Into<BytesStr>>(mut self, text: T) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Returns the cause if the protocol is Q.850
+    pub fn q850_cause(&self) -> Option<u16> {
+        if self.protocol.eq_ignore_ascii_case(Self::Q850) {
+            self.cause
+        } else {
+            None
+        }
+    }
+}
+
+impl ConstNamed for Reason {
+    const NAME: Name = Name::REASON;
+}
+
+impl HeaderParse for Reason {
+    fn parse<'i>(ctx: ParseCtx<'_>, i: &'i str) -> IResult<&'i str, Self> {
+        map(
+            ws((take_while1(token), Params::<CPS>::parse(ctx))),
+            |(protocol, mut params)| Self {
+                protocol: BytesStr::from_parse(ctx.src, protocol),
+                cause: params.take("cause").and_then(|cause| cause.parse().ok()),
+                text: params.take("text"),
+            },
+        )(i)
+    }
+}
+
+impl ExtendValues for Reason {
+    fn extend_values(&self, _: PrintCtx<'_>, values: &mut OneOrMore) {
+        let value = match values {
+            OneOrMore::One(value) => value,
+            OneOrMore::More(values) => values.last_mut().expect("empty OneOrMore::More variant"),
+        };
+
+        *value = format!("{}, {}", value, self).into();
+    }
+
+    fn create_values(&self, _: PrintCtx<'_>) -> OneOrMore {
+        OneOrMore::One(self.to_string().into())
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.protocol)?;
+
+        if let Some(cause) = self.cause {
+            write!(f, ";cause={}", cause)?;
+        }
+
+        if let Some(text) = &self.text {
+            write!(f, ";text={}", Quoted(text))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Headers;
+
+    #[test]
+    fn reason() {
+        let input = BytesStr::from_static("Q.850 ;cause=16 ;text=\"Terminated\"");
+
+        let (rem, reason) = Reason::parse(ParseCtx::default(&input), &input).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(reason.protocol, "Q.850");
+        assert_eq!(reason.cause, Some(16));
+        assert_eq!(reason.text.as_deref(), Some("Terminated"));
+        assert_eq!(reason.q850_cause(), Some(16));
+    }
+
+    #[test]
+    fn reason_multiple() {
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::REASON,
+            "SIP;cause=200;text=\"Call completed elsewhere\", Q.850;cause=31",
+        );
+
+        let reasons: Vec<Reason> = headers.get_named().unwrap();
+
+        assert_eq!(
+            reasons,
+            [
+                Reason::sip(200).with_text("Call completed elsewhere"),
+                Reason::q850(31)
+            ]
+        );
+        assert_eq!(reasons[0].q850_cause(), None);
+    }
+
+    #[test]
+    fn print_reason() {
+        let mut headers = Headers::new();
+        headers.insert_named(&Reason::q850(16).with_text("Normal call clearing"));
+        headers.insert_named(&Reason::sip(487));
+
+        assert_eq!(
+            headers.to_string(),
+            "Reason: Q.850;cause=16;text=\"Normal call clearing\", SIP;cause=487\r\n"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! [RFC3515](https://datatracker.ietf.org/doc/html/rfc3515)
+
+use crate::header::headers::OneOrMore;
+use crate::header::name::Name;
+use crate::header::{ConstNamed, ExtendValues, HeaderParse};
+use crate::parse::ParseCtx;
+use crate::print::{AppendCtx, Print, PrintCtx};
+use crate::uri::NameAddr;
+use internal::IResult;
+use nom::combinator::map;
+use std::fmt;
+
+/// `Refer-To` header, the URI the recipient of a REFER request is asked to contact
+///
+/// URI headers (e.g. `Replaces`) are kept, as they must be applied to the request sent to the URI.
+#[derive(Debug, Clone)]
+pub struct ReferTo(pub NameAddr);
+
+impl ConstNamed for ReferTo {
+    const NAME: Name = Name::REFER_TO;
+}
+
+impl HeaderParse for ReferTo {
+    fn parse<'i>(ctx: ParseCtx<'_>, i: &'i str) -> IResult<&'i str, Self> {
+        map(NameAddr::parse(ctx), Self)(i)
+    }
+}
+
+impl ExtendValues for ReferTo {
+    fn extend_values(&self, ctx: PrintCtx<'_>, values: &mut OneOrMore) {
+        *values = self.create_values(ctx)
+    }
+
+    fn create_values(&self, ctx: PrintCtx<'_>) -> OneOrMore {
+        OneOrMore::One(self.print_ctx(ctx).to_string().into())
+    }
+}
+
+impl Print for ReferTo {
+    fn print(&self, f: &mut fmt::Formatter<'_>, mut ctx: PrintCtx<'_>) -> fmt::Result {
+        // No URI context, to print the URI's header parameters
+        ctx.uri = None;
+        self.0.print(f, ctx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::uri::params::Param;
+    use crate::uri::sip::SipUri;
+    use crate::Headers;
+
+    #[test]
+    fn parse_refer_to() {
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::REFER_TO,
+            "<sip:dave@example.org?Replaces=12345%40192.168.118.3%3Bto-tag%3D12345%3Bfrom-tag%3D5FFE-3994>",
+        );
+
+        let refer_to: ReferTo = headers.get_named().unwrap();
+        let uri: &SipUri = refer_to.0.uri.downcast_ref().unwrap();
+
+        assert_eq!(
+            uri.header_params.get_val("Replaces").unwrap(),
+            "12345@192.168.118.3;to-tag=12345;from-tag=5FFE-3994"
+        );
+    }
+
+    #[test]
+    fn print_refer_to() {
+        let mut uri: SipUri = "sip:dave@example.org".parse().unwrap();
+        uri.header_params
+            .push(Param::value("Replaces", "abc@host;to-tag=1;from-tag=2"));
+
+        let mut headers = Headers::new();
+        headers.insert_named(&ReferTo(NameAddr::uri(uri)));
+
+        assert_eq!(
+            headers.to_string(),
+            "Refer-To: <sip:dave@example.org?Replaces=abc%40host%3Bto-tag%3D1%3Bfrom-tag%3D2>\r\n"
+        );
+    }
+}
@@ -12,6 +12,7 @@ use nom::combinator::{map, map_res, opt};
 use nom::sequence::{delimited, preceded, tuple};
 use std::fmt;
 use std::str::FromStr;
+use std::time::Duration;
 
 // TODO: Support Date
 
@@ -35,6 +36,24 @@ impl RetryAfter {
         }
     }
 
+    /// Set the `duration` parameter, the time the callee will be available for
+    pub fn with_duration(mut self, duration: u32) -> Self {
+        self.params.push_or_edit("duration", duration.to_string());
+        self
+    }
+
+    /// Time to wait before retrying the request
+    pub fn retry_in(&self) -> Duration {
+        Duration::from_secs(self.value.into())
+    }
+
+    /// Value of the `duration` parameter, the time the callee will be available for after [`RetryAfter::retry_in`]
+    pub fn duration(&self) -> Option<Duration> {
+        let duration = self.params.get_val("duration")?.parse().ok()?;
+
+        Some(Duration::from_secs(duration))
+    }
+
     pub fn with_comment<S>(mut self, comment: S) -> Self
     where
         S: Into<BytesStr>,
@@ -122,6 +141,9 @@ mod test {
         assert_eq!(duration, "60");
 
         assert!(retry_after.comment.is_none());
+
+        assert_eq!(retry_after.retry_in(), Duration::from_secs(120));
+        assert_eq!(retry_after.duration(), Some(Duration::from_secs(60)));
     }
 
     #[test]
@@ -149,6 +171,16 @@ mod test {
         assert_eq!(retry_after.default_print_ctx().to_string(), "120");
     }
 
+    #[test]
+    fn retry_after_duration_print() {
+        let retry_after = RetryAfter::new(120).with_duration(60);
+
+        assert_eq!(
+            retry_after.default_print_ctx().to_string(),
+            "120;duration=60"
+        );
+    }
+
     #[test]
     fn retry_after_comment_print() {
         let retry_after = RetryAfter::new(120).with_comment("Some Comment");
@@ -3,6 +3,7 @@ use crate::header::{ExtendValues, HeaderParse};
 use crate::parse::ParseCtx;
 use crate::print::{AppendCtx, Print, PrintCtx, UriContext};
 use crate::uri::params::{Params, CPS};
+use crate::uri::sip::SipUri;
 use crate::uri::NameAddr;
 use internal::IResult;
 use nom::combinator::map;
@@ -16,6 +17,16 @@ pub struct Routing {
     pub params: Params<CPS>,
 }
 
+impl Routing {
+    /// Returns if the route's URI contains the `lr` parameter, marking the element as loose router
+    pub fn is_loose(&self) -> bool {
+        self.uri
+            .uri
+            .downcast_ref::<SipUri>()
+            .is_some_and(|uri| uri.uri_params.get("lr").is_some())
+    }
+}
+
 impl HeaderParse for Routing {
     fn parse<'i>(ctx: ParseCtx<'_>, i: &'i str) -> IResult<&'i str, Self> {
         map(
@@ -50,7 +61,6 @@ impl Print for Routing {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::uri::sip::SipUri;
     use crate::{Headers, Name};
 
     fn test_routing() -> Routing {
@@ -62,6 +72,16 @@ mod test {
         }
     }
 
+    #[test]
+    fn loose_routing() {
+        let mut headers = Headers::new();
+        headers.insert(Name::ROUTE, "<sip:proxy.example.org;lr>, <sip:example.org>");
+
+        let routes: Vec<Routing> = headers.get(Name::ROUTE).unwrap();
+        assert!(routes[0].is_loose());
+        assert!(!routes[1].is_loose());
+    }
+
     #[test]
     fn print_routing_single() {
         let mut headers = Headers::new();
@@ -1,7 +1,7 @@
 use crate::header::headers::OneOrMore;
 use crate::header::name::Name;
 use crate::header::{ConstNamed, ExtendValues, HeaderParse};
-use crate::host::HostPort;
+use crate::host::{Host, HostPort};
 use crate::parse::{token, whitespace, ParseCtx};
 use crate::print::{AppendCtx, Print, PrintCtx};
 use crate::uri::params::{Param, Params, CPS};
@@ -12,6 +12,7 @@ use nom::bytes::complete::{tag, take_while};
 use nom::combinator::map;
 use nom::sequence::{delimited, preceded, tuple};
 use std::fmt;
+use std::net::{IpAddr, SocketAddr};
 
 /// `Via` header
 #[derive(Debug, Clone)]
@@ -39,6 +40,37 @@ impl Via {
             params: Params::new().with(Param::value("branch", branch)),
         }
     }
+
+    /// Returns the address the request was received from, as reported back by the server
+    /// using the `received` and `rport` parameters ([RFC3581](https://datatracker.ietf.org/doc/html/rfc3581)).
+    ///
+    /// Returns `None` if neither parameter carries a value.
+    pub fn public_address(&self) -> Option<SocketAddr> {
+        let received = self
+            .params
+            .get_val("received")
+            .and_then(|received| received.trim_matches(['[', ']']).parse::<IpAddr>().ok());
+
+        let rport = self
+            .params
+            .get_val("rport")
+            .and_then(|rport| rport.parse::<u16>().ok());
+
+        if received.is_none() && rport.is_none() {
+            return None;
+        }
+
+        let ip = match (received, &self.sent_by.host) {
+            (Some(ip), _) => ip,
+            (None, Host::IP4(ip)) => IpAddr::V4(*ip),
+            (None, Host::IP6(ip)) => IpAddr::V6(*ip),
+            (None, Host::Name(_)) => return None,
+        };
+
+        let port = rport.or(self.sent_by.port).unwrap_or(5060);
+
+        Some(SocketAddr::new(ip, port))
+    }
 }
 
 impl ConstNamed for Via {
@@ -95,9 +127,7 @@ impl Print for Via {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::host::Host;
     use std::net::Ipv4Addr;
-    use std::net::SocketAddr;
 
     #[test]
     fn via() {
@@ -130,4 +160,22 @@ mod test {
             "SIP/2.0/TCP 192.168.123.222:53983;branch=abc123"
         );
     }
+
+    #[test]
+    fn via_public_address() {
+        let input = BytesStr::from_static(
+            "SIP/2.0/UDP 192.168.1.2:5060;branch=abc123;received=203.0.113.7;rport=40000",
+        );
+        let (_, via) = Via::parse(ParseCtx::default(&input), &input).unwrap();
+
+        assert_eq!(
+            via.public_address(),
+            Some(SocketAddr::new(Ipv4Addr::new(203, 0, 113, 7).into(), 40000))
+        );
+
+        let input = BytesStr::from_static("SIP/2.0/UDP 192.168.1.2:5060;branch=abc123;rport");
+        let (_, via) = Via::parse(ParseCtx::default(&input), &input).unwrap();
+
+        assert_eq!(via.public_address(), None);
+    }
 }
@@ -0,0 +1,149 @@
+use crate::header::headers::OneOrMore;
+use crate::header::name::Name;
+use crate::header::{ConstNamed, ExtendValues, HeaderParse};
+use crate::parse::{parse_quoted, unescape_quoted, whitespace, ParseCtx};
+use crate::print::{PrintCtx, Quoted};
+use bytesstr::BytesStr;
+use internal::{ws, IResult};
+use nom::bytes::complete::take_while1;
+use nom::character::complete::digit1;
+use nom::combinator::{map, map_res};
+use std::fmt;
+use std::str::FromStr;
+
+/// `Warning` header ([RFC3261 Section 20.43](https://datatracker.ietf.org/doc/html/rfc3261#section-20.43))
+///
+/// Multiple warnings can be contained in a message, use `Vec<Warning>` to get all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Warning code, the `3xx` codes describe problems with the session description
+    pub code: u16,
+
+    /// Host or pseudonym of the entity adding the warning
+    pub agent: BytesStr,
+
+    /// Human readable description of the warning
+    pub text: BytesStr,
+}
+
+impl Warning {
+    pub const INCOMPATIBLE_NETWORK_PROTOCOL: u16 = 300;
+    pub const INCOMPATIBLE_NETWORK_ADDRESS_FORMATS: u16 = 301;
+    pub const INCOMPATIBLE_TRANSPORT_PROTOCOL: u16 = 302;
+    pub const INCOMPATIBLE_BANDWIDTH_UNITS: u16 = 303;
+    pub const MEDIA_TYPE_NOT_AVAILABLE: u16 = 304;
+    pub const INCOMPATIBLE_MEDIA_FORMAT: u16 = 305;
+    pub const ATTRIBUTE_NOT_UNDERSTOOD: u16 = 306;
+    pub const SESSION_DESCRIPTION_PARAMETER_NOT_UNDERSTOOD: u16 = 307;
+    pub const MULTICAST_NOT_AVAILABLE: u16 = 330;
+    pub const UNICAST_NOT_AVAILABLE: u16 = 331;
+    pub const INSUFFICIENT_BANDWIDTH: u16 = 370;
+    pub const MISCELLANEOUS_WARNING: u16 = 399;
+
+    pub fn new<A, T>(code: u16, agent: A, text: T) -> Self
+    where
+        A: Into<BytesStr>,
+        T: Into<BytesStr>,
+    {
+        Self {
+            code,
+            agent: agent.into(),
+            text: text.into(),
+        }
+    }
+}
+
+impl ConstNamed for Warning {
+    const NAME: Name = Name::WARNING;
+}
+
+impl HeaderParse for Warning {
+    fn parse<'i>(ctx: ParseCtx<'_>, i: &'i str) -> IResult<&'i str, Self> {
+        map(
+            ws((
+                map_res(digit1, FromStr::from_str),
+                take_while1(|c: char| !whitespace(c)),
+                parse_quoted,
+            )),
+            |(code, agent, text)| Self {
+                code,
+                agent: BytesStr::from_parse(ctx.src, agent),
+                text: unescape_quoted(ctx.src, text),
+            },
+        )(i)
+    }
+}
+
+impl ExtendValues for Warning {
+    fn extend_values(&self, _: PrintCtx<'_>, values: &mut OneOrMore) {
+        let value = match values {
+            OneOrMore::One(value) => value,
+            OneOrMore::More(values) => values.last_mut().expect("empty OneOrMore::More variant"),
+        };
+
+        *value = format!("{}, {}", value, self).into();
+    }
+
+    fn create_values(&self, _: PrintCtx<'_>) -> OneOrMore {
+        OneOrMore::One(self.to_string().into())
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:03} {} {}", self.code, self.agent, Quoted(&self.text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Headers;
+
+    #[test]
+    fn warning() {
+        let input = BytesStr::from_static("307 isi.edu \"Session parameter 'foo' not understood\"");
+
+        let (rem, warning) = Warning::parse(ParseCtx::default(&input), &input).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(
+            warning,
+            Warning::new(
+                Warning::SESSION_DESCRIPTION_PARAMETER_NOT_UNDERSTOOD,
+                "isi.edu",
+                "Session parameter 'foo' not understood"
+            )
+        );
+    }
+
+    #[test]
+    fn warning_multiple() {
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::WARNING,
+            "301 isi.edu \"Incompatible network address type 'E.164'\", 399 example.com \"Other\"",
+        );
+
+        let warnings: Vec<Warning> = headers.get_named().unwrap();
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].code, 301);
+        assert_eq!(warnings[1].agent, "example.com");
+    }
+
+    #[test]
+    fn print_warning() {
+        let mut headers = Headers::new();
+        headers.insert_named(&Warning::new(
+            Warning::INCOMPATIBLE_MEDIA_FORMAT,
+            "example.com",
+            "Incompatible media format",
+        ));
+
+        assert_eq!(
+            headers.to_string(),
+            "Warning: 305 example.com \"Incompatible media format\"\r\n"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Support for headers defined using [`vendor_header!`](crate::vendor_header)
+
+use crate::uri::params::Param;
+use bytesstr::BytesStr;
+
+/// Type of a field inside a header defined by [`vendor_header!`](crate::vendor_header).
+///
+/// Implemented for strings, integers, `bool` (flag parameters without value) and `Option<T>`
+/// for optional parameters.
+pub trait HeaderParamValue: Sized {
+    /// Create the value from the parameter with the field's name, `None` if it is missing.
+    ///
+    /// Returns `None` if the value is missing or invalid, which fails parsing the header.
+    fn from_param(param: Option<Param>) -> Option<Self>;
+
+    /// Create the parameter to print, `None` if it should be omitted
+    fn to_param(&self, name: &'static str) -> Option<Param>;
+}
+
+impl<T: HeaderParamValue> HeaderParamValue for Option<T> {
+    fn from_param(param: Option<Param>) -> Option<Self> {
+        match param {
+            Some(param) => T::from_param(Some(param)).map(Some),
+            None => Some(None),
+        }
+    }
+
+    fn to_param(&self, name: &'static str) -> Option<Param> {
+        self.as_ref()?.to_param(name)
+    }
+}
+
+impl HeaderParamValue for bool {
+    fn from_param(param: Option<Param>) -> Option<Self> {
+        Some(param.is_some())
+    }
+
+    fn to_param(&self, name: &'static str) -> Option<Param> {
+        self.then(|| Param::name(name))
+    }
+}
+
+impl HeaderParamValue for BytesStr {
+    fn from_param(param: Option<Param>) -> Option<Self> {
+        param?.value
+    }
+
+    fn to_param(&self, name: &'static str) -> Option<Param> {
+        Some(Param::value(name, self.clone()))
+    }
+}
+
+macro_rules! from_str_param_value {
+    ($($ty:ty),*) => {
+        $(
+        impl HeaderParamValue for $ty {
+            fn from_param(param: Option<Param>) -> Option<Self> {
+                param?.value?.parse().ok()
+            }
+
+            fn to_param(&self, name: &'static str) -> Option<Param> {
+                Some(Param::value(name, self.to_string()))
+            }
+        }
+        )*
+    };
+}
+
+from_str_param_value!(String, u8, u16, u32, u64, i8, i16, i32, i64);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Headers, Name};
+
+    crate::vendor_header! {
+        struct XGbVer: "X-GB-Ver" {
+            version: BytesStr = "ver",
+            minor: Option<u8> = "minor",
+        }
+    }
+
+    #[test]
+    fn parse_vendor_header() {
+        let mut headers = Headers::new();
+        headers.insert(Name::custom("X-GB-Ver", &["X-GB-Ver"]), "ver=3.0 ; minor=1");
+
+        let ver: XGbVer = headers.get_named().unwrap();
+        assert_eq!(ver.version, "3.0");
+        assert_eq!(ver.minor, Some(1));
+    }
+
+    #[test]
+    fn parse_vendor_header_missing_param() {
+        let mut headers = Headers::new();
+        headers.insert(Name::custom("X-GB-Ver", &["X-GB-Ver"]), "minor=1");
+
+        assert!(headers.get_named::<XGbVer>().is_err());
+    }
+
+    #[test]
+    fn print_vendor_header() {
+        let mut headers = Headers::new();
+        headers.insert_named(&XGbVer {
+            version: "2.0".into(),
+            minor: None,
+        });
+
+        assert_eq!(headers.to_string(), "X-GB-Ver: ver=2.0\r\n");
+    }
+}
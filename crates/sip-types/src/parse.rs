@@ -2,17 +2,40 @@
 //! Parsing utilities for SIP message components
 
 use crate::uri::sip::SipUri;
+use crate::uri::tel::TelUri;
 use crate::uri::Uri;
 use bytes::Bytes;
+use bytesstr::BytesStr;
 use internal::IResult;
 use nom::branch::alt;
 use nom::bytes::complete::{escaped, is_not};
-use nom::character::complete::char;
+use nom::character::complete::{anychar, char};
 use nom::combinator::map;
 use nom::sequence::delimited;
 
+/// Parse a quoted-string, returns its content which may still contain escape sequences
 pub(crate) fn parse_quoted(i: &str) -> IResult<&str, &str> {
-    delimited(char('"'), escaped(is_not("\""), '\\', char('"')), char('"'))(i)
+    delimited(char('"'), escaped(is_not("\"\\"), '\\', anychar), char('"'))(i)
+}
+
+/// Remove all escape sequences from the content of a quoted-string returned by [`parse_quoted`]
+pub(crate) fn unescape_quoted(src: &Bytes, quoted: &str) -> BytesStr {
+    if !quoted.contains('\\') {
+        return BytesStr::from_parse(src, quoted);
+    }
+
+    let mut unescaped = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.extend(chars.next());
+        } else {
+            unescaped.push(c);
+        }
+    }
+
+    unescaped.into()
 }
 
 pub(crate) fn whitespace(c: char) -> bool {
@@ -25,7 +48,8 @@ pub(crate) fn token(c: char) -> bool {
 
 /// Can be used to extend the parsing capabilities of this library.
 ///
-/// Currently this can be used to register nom parsers for custom URI types
+/// Currently this can be used to register nom parsers for custom URI types.
+/// They are tried after the built-in SIP URI parser but before the tel URI parser.
 #[derive(Copy, Clone)]
 pub struct Parser {
     pub parse_other_uri: fn(&str) -> IResult<&str, Box<dyn Uri>>,
@@ -72,6 +96,7 @@ impl<'p> ParseCtx<'p> {
             alt((
                 map(SipUri::parse(self), |uri| -> Box<dyn Uri> { Box::new(uri) }),
                 self.parser.parse_other_uri,
+                map(TelUri::parse(self), |uri| -> Box<dyn Uri> { Box::new(uri) }),
             ))(i)
         }
     }
@@ -83,6 +108,9 @@ impl<'p> ParseCtx<'p> {
                     Box::new(uri)
                 }),
                 self.parser.parse_other_uri_no_params,
+                map(TelUri::parse_no_params(self), |uri| -> Box<dyn Uri> {
+                    Box::new(uri)
+                }),
             ))(i)
         }
     }
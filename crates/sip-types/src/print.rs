@@ -81,6 +81,27 @@ impl<T: fmt::Display> Print for T {
     }
 }
 
+/// Prints a string as quoted-string, escaping all quotes and backslashes
+pub struct Quoted<'s>(pub &'s str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write;
+
+        f.write_char('"')?;
+
+        for c in self.0.chars() {
+            if matches!(c, '"' | '\\') {
+                f.write_char('\\')?;
+            }
+
+            f.write_char(c)?;
+        }
+
+        f.write_char('"')
+    }
+}
+
 /// Implements std::fmt::Debug for byte-slices.
 /// Useful to print ascii with special characters escaped
 // taken from bytes crate with some small changes
@@ -1,6 +1,6 @@
-//! Contains the URI trait, SIP and NameAddr implementation
+//! Contains the URI trait, SIP, tel and NameAddr implementation
 
-use crate::host::HostPort;
+use crate::host::{Host, HostPort};
 use crate::print::{Print, PrintCtx};
 use crate::uri::sip::SipUri;
 use downcast_rs::Downcast;
@@ -11,6 +11,7 @@ use std::fmt;
 pub mod params;
 mod name_addr;
 pub mod sip;
+pub mod tel;
 
 pub use name_addr::NameAddr;
 
@@ -86,10 +87,17 @@ pub struct UriInfo<'i> {
     pub secure: bool,
 
     /// [`HostPort`] part of the uri
+    ///
+    /// URIs without a host (e.g. global tel URIs) use an empty host name, see [`UriInfo::has_host`].
     pub host_port: HostPort,
 }
 
 impl UriInfo<'_> {
+    /// Returns if the URI contains a host which requests can be sent to
+    pub fn has_host(&self) -> bool {
+        !matches!(&self.host_port.host, Host::Name(name) if name.is_empty())
+    }
+
     pub fn allows_security_level(&self, secure: bool) -> bool {
         if self.secure {
             secure
@@ -1,5 +1,5 @@
-use crate::parse::{parse_quoted, whitespace, ParseCtx};
-use crate::print::{AppendCtx, Print, PrintCtx};
+use crate::parse::{parse_quoted, unescape_quoted, whitespace, ParseCtx};
+use crate::print::{AppendCtx, Print, PrintCtx, Quoted};
 use crate::uri::Uri;
 use bytesstr::BytesStr;
 use internal::IResult;
@@ -47,16 +47,13 @@ impl NameAddr {
             map(
                 alt((
                     tuple((
-                        opt(alt((parse_quoted, take_while1(display)))),
+                        opt(display_name(ctx)),
                         take_while(whitespace),
                         delimited(tag("<"), ctx.parse_uri(), tag(">")),
                     )),
                     map(ctx.parse_uri(), |uri| (None, "", uri)),
                 )),
-                move |(name, _, uri)| Self {
-                    name: name.map(|name| BytesStr::from_parse(ctx.src, name.trim())),
-                    uri,
-                },
+                move |(name, _, uri)| Self { name, uri },
             )(i)
         }
     }
@@ -66,16 +63,13 @@ impl NameAddr {
             map(
                 alt((
                     tuple((
-                        opt(alt((parse_quoted, take_while1(display)))),
+                        opt(display_name(ctx)),
                         take_while(whitespace),
                         delimited(tag("<"), ctx.parse_uri(), tag(">")),
                     )),
                     map(ctx.parse_uri_no_params(), |uri| (None, "", uri)),
                 )),
-                move |(name, _, uri)| Self {
-                    name: name.map(|name| BytesStr::from_parse(ctx.src, name.trim())),
-                    uri,
-                },
+                move |(name, _, uri)| Self { name, uri },
             )(i)
         }
     }
@@ -84,13 +78,25 @@ impl NameAddr {
 impl Print for NameAddr {
     fn print(&self, f: &mut fmt::Formatter<'_>, ctx: PrintCtx<'_>) -> fmt::Result {
         if let Some(name) = &self.name {
-            write!(f, "\"{}\"", name)?;
+            write!(f, "{}", Quoted(name))?;
         }
 
         write!(f, "<{}>", self.uri.print_ctx(ctx))
     }
 }
 
+/// Display name, either quoted or a sequence of tokens
+fn display_name(ctx: ParseCtx<'_>) -> impl Fn(&str) -> IResult<&str, BytesStr> + '_ {
+    move |i| {
+        alt((
+            map(parse_quoted, |name| unescape_quoted(ctx.src, name)),
+            map(take_while1(display), |name: &str| {
+                BytesStr::from_parse(ctx.src, name.trim())
+            }),
+        ))(i)
+    }
+}
+
 fn display(c: char) -> bool {
     !lookup_table!(c => ':', '\r', '\n', '<')
 }
@@ -121,4 +127,40 @@ mod test {
         assert!(sip_uri.host_port.port.is_none());
         assert!(matches!(&sip_uri.host_port.host,  Host::Name(name) if name == "example.com"));
     }
+
+    #[test]
+    fn name_addr_escaped_name() {
+        let input = BytesStr::from_static(r#""Bob \"B\" \\ Müller" <sip:bob@example.com>"#);
+
+        let (rem, name_addr) = NameAddr::parse(ParseCtx::default(&input))(&input).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(
+            name_addr.name.as_ref().map(BytesStr::as_ref),
+            Some(r#"Bob "B" \ Müller"#)
+        );
+        assert_eq!(
+            name_addr.default_print_ctx().to_string(),
+            r#""Bob \"B\" \\ Müller"<sip:bob@example.com>"#
+        );
+    }
+
+    #[test]
+    fn name_addr_escaped_user() {
+        let input = BytesStr::from_static("<sip:b%40b:p%3Aw@example.com>");
+
+        let (rem, name_addr) = NameAddr::parse(ParseCtx::default(&input))(&input).unwrap();
+
+        assert!(rem.is_empty());
+
+        let sip_uri: &SipUri = name_addr.uri.downcast_ref().unwrap();
+
+        assert!(
+            matches!(&sip_uri.user_part, UserPart::UserPw(pw) if pw.user == "b@b" && pw.password == "p:w")
+        );
+        assert_eq!(
+            name_addr.default_print_ctx().to_string(),
+            "<sip:b%40b:p%3Aw@example.com>"
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use crate::parse::{parse_quoted, ParseCtx};
+use crate::parse::{parse_quoted, unescape_quoted, ParseCtx};
+use crate::print::Quoted;
 use bytes::Bytes;
 use bytesstr::BytesStr;
 use internal::ws;
@@ -86,6 +87,17 @@ impl<S: ParamsSpec> Params<S> {
         self.params.remove(pos).value
     }
 
+    /// Remove the first parameter with the given name
+    #[inline]
+    pub fn remove<N>(&mut self, name: N) -> Option<Param>
+    where
+        N: AsRef<str>,
+    {
+        let pos = self.params.iter().position(|p| p.name == name.as_ref())?;
+
+        Some(self.params.remove(pos))
+    }
+
     #[inline]
     pub fn push_or_edit<N, V>(&mut self, name: N, value: V)
     where
@@ -215,7 +227,8 @@ encode_set!(header_char, HPS_SET);
 impl ParamsSpec for HPS {
     const FIRST_DELIMITER: &'static str = "?";
     const DELIMITER: &'static str = "&";
-    const CHAR_SPEC: fn(char) -> bool = header_char;
+    // Accept escaped characters when parsing, but keep encoding '%' when printing
+    const CHAR_SPEC: fn(char) -> bool = |c| c == '%' || header_char(c);
     const ENCODE_SET: fn() -> &'static AsciiSet = || &HPS_SET;
 }
 
@@ -235,11 +248,24 @@ impl ParamsSpec for CPS {
     const ENCODE_SET: fn() -> &'static AsciiSet = || &CPS_SET;
 }
 
+/// Vendor header Param Specification (some=value;other=value), used by [`vendor_header!`](crate::vendor_header)
+pub enum VPS {}
+
+impl ParamsSpec for VPS {
+    const FIRST_DELIMITER: &'static str = "";
+    const DELIMITER: &'static str = ";";
+    const CHAR_SPEC: fn(char) -> bool = param_char;
+    const ENCODE_SET: fn() -> &'static AsciiSet = || &CPS_SET;
+}
+
 /// Represents a Parameter `name[=(value|"value")]`
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Param {
     pub name: BytesStr,
     pub value: Option<BytesStr>,
+
+    /// The value is printed as quoted-string instead of being percent-encoded
+    quoted: bool,
 }
 
 impl Param {
@@ -251,6 +277,7 @@ impl Param {
         Param {
             name: name.into(),
             value: None,
+            quoted: false,
         }
     }
 
@@ -263,12 +290,39 @@ impl Param {
         Param {
             name: name.into(),
             value: Some(value.into()),
+            quoted: false,
+        }
+    }
+
+    /// Create a parameter with a value which is printed as quoted-string
+    #[inline]
+    pub fn quoted<N, V>(name: N, value: V) -> Param
+    where
+        N: Into<BytesStr>,
+        V: Into<BytesStr>,
+    {
+        Param {
+            name: name.into(),
+            value: Some(value.into()),
+            quoted: true,
         }
     }
 
+    /// Returns if the value was parsed from or is printed as quoted-string
+    #[inline]
+    pub fn is_quoted(&self) -> bool {
+        self.quoted
+    }
+
     pub(crate) fn write(&self, f: &mut fmt::Formatter<'_>, set: &'static AsciiSet) -> fmt::Result {
         match (&self.name, &self.value) {
             (name, None) => write!(f, "{}", percent_encode(name.as_bytes(), set)),
+            (name, Some(value)) if self.quoted => write!(
+                f,
+                "{}={}",
+                percent_encode(name.as_bytes(), set),
+                Quoted(value)
+            ),
             (name, Some(value)) => write!(
                 f,
                 "{}={}",
@@ -286,7 +340,13 @@ impl Param {
             map_res(
                 ws((
                     take_while(spec),
-                    opt(ws((tag("="), alt((parse_quoted, take_while(spec)))))),
+                    opt(ws((
+                        tag("="),
+                        alt((
+                            map(parse_quoted, |value| (value, true)),
+                            map(take_while(spec), |value| (value, false)),
+                        )),
+                    ))),
                 )),
                 move |(name, value)| -> Result<_, Utf8Error> {
                     Ok(Param {
@@ -296,13 +356,15 @@ impl Param {
                         },
                         value: match value {
                             None => None,
-                            Some((_, value)) => {
+                            Some((_, (value, true))) => Some(unescape_quoted(src, value)),
+                            Some((_, (value, false))) => {
                                 Some(match percent_decode(value.as_bytes()).decode_utf8()? {
                                     Cow::Borrowed(slice) => BytesStr::from_parse(src, slice),
                                     Cow::Owned(owned) => BytesStr::from(owned),
                                 })
                             }
                         },
+                        quoted: matches!(value, Some((_, (_, true)))),
                     })
                 },
             )(i)
@@ -390,6 +452,20 @@ mod test {
         assert_eq!(value, "😀");
     }
 
+    #[test]
+    fn common_params_quoted() {
+        let input = BytesStr::from_static(r#";+sip.instance="<urn:uuid:1234>";text="a \"b\" c""#);
+
+        let (rem, params) = Params::<CPS>::parse(ParseCtx::default(&input))(&input).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(params.get_val("+sip.instance").unwrap(), "<urn:uuid:1234>");
+        assert_eq!(params.get_val("text").unwrap(), r#"a "b" c"#);
+        assert!(params.params[1].is_quoted());
+
+        assert_eq!(params.to_string(), input.as_ref());
+    }
+
     #[test]
     fn header_params_parse() {
         let input = BytesStr::from_static("?some_single_key&some_key=with_value");
@@ -105,7 +105,7 @@ impl Print for SipUri {
                     f,
                     "{}:{}@",
                     percent_encode(user_pw.user.as_ref(), &USER_SET),
-                    user_pw.password
+                    percent_encode(user_pw.password.as_ref(), &PASSWORD_SET)
                 )?;
             }
         }
@@ -138,6 +138,7 @@ impl Print for SipUri {
 }
 
 encode_set!(user, USER_SET);
+encode_set!(password, PASSWORD_SET);
 
 fn user(c: char) -> bool {
     lookup_table!(c => alpha; num; '-', '_', '.', '!', '~', '*', '\'', '(', ')', '%', '&', '=', '+', '$', ',', ';', '?', '/')
@@ -195,15 +196,12 @@ impl SipUri {
 
 fn user_part(src: &Bytes, user_pw: Option<(&str, Option<&str>)>) -> Result<UserPart, Utf8Error> {
     if let Some((user, password)) = user_pw {
-        let user = match percent_decode_str(user).decode_utf8()? {
-            Cow::Borrowed(slice) => BytesStr::from_parse(src, slice),
-            Cow::Owned(owned) => BytesStr::from(owned),
-        };
+        let user = percent_decode(src, user)?;
 
         if let Some(pw) = password {
             Ok(UserPart::UserPw(Box::new(UserPw {
                 user,
-                password: BytesStr::from_parse(src, pw),
+                password: percent_decode(src, pw)?,
             })))
         } else {
             Ok(UserPart::User(user))
@@ -213,6 +211,13 @@ fn user_part(src: &Bytes, user_pw: Option<(&str, Option<&str>)>) -> Result<UserP
     }
 }
 
+fn percent_decode(src: &Bytes, i: &str) -> Result<BytesStr, Utf8Error> {
+    match percent_decode_str(i).decode_utf8()? {
+        Cow::Borrowed(slice) => Ok(BytesStr::from_parse(src, slice)),
+        Cow::Owned(owned) => Ok(BytesStr::from(owned)),
+    }
+}
+
 fn parse_scheme(i: &str) -> IResult<&str, bool> {
     alt((
         map(tag_no_case("sip:"), |_| false),
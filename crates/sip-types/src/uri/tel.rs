@@ -0,0 +1,355 @@
+//! tel URI implementation ([RFC3966](https://datatracker.ietf.org/doc/html/rfc3966))
+
+use crate::host::{Host, HostPort};
+use crate::parse::ParseCtx;
+use crate::print::{AppendCtx, Print, PrintCtx};
+use crate::uri::params::{Params, CPS};
+use crate::uri::sip::{SipUri, UserPart};
+use crate::uri::{Uri, UriInfo};
+use bytesstr::BytesStr;
+use internal::IResult;
+use nom::bytes::complete::{tag_no_case, take_while1};
+use nom::combinator::{map, verify};
+use nom::sequence::{preceded, tuple};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// tel URI, representing a telephone number either globally unique (`tel:+1-201-555-0123`)
+/// or only valid in a context (`tel:7042;phone-context=example.com`)
+#[derive(Clone)]
+pub struct TelUri {
+    /// The number without visual separators, global numbers start with `+`
+    pub number: BytesStr,
+
+    /// Parameters like `phone-context` or `ext`
+    pub params: Params<CPS>,
+}
+
+impl TelUri {
+    /// Create a new tel URI, visual separators are removed from the number
+    pub fn new<N: AsRef<str>>(number: N) -> Self {
+        Self {
+            number: remove_visual_separators(number.as_ref()).into(),
+            params: Params::new(),
+        }
+    }
+
+    impl_with_params!(params, param_key, param_value);
+
+    /// Returns if the number is globally unique (starts with `+`)
+    pub fn is_global(&self) -> bool {
+        self.number.starts_with('+')
+    }
+
+    /// Returns the `phone-context` parameter, required for local numbers
+    pub fn phone_context(&self) -> Option<&BytesStr> {
+        self.params.get_val("phone-context")
+    }
+
+    /// Convert the tel URI to a SIP URI with the given host, using the number
+    /// and its parameters as user part ([RFC3261 Section 19.1.6](https://datatracker.ietf.org/doc/html/rfc3261#section-19.1.6))
+    pub fn to_sip_uri(&self, host_port: HostPort) -> SipUri {
+        let user = format!("{}{}", self.number, self.params);
+
+        SipUri::new(host_port)
+            .user(user.into())
+            .uri_param_value("user", "phone")
+    }
+
+    /// Extract the tel URI from the user part of a SIP URI, which must contain the `user=phone` parameter
+    pub fn from_sip_uri(uri: &SipUri) -> Option<Self> {
+        if !uri
+            .uri_params
+            .get_val("user")
+            .is_some_and(|user| user.eq_ignore_ascii_case("phone"))
+        {
+            return None;
+        }
+
+        let user = match &uri.user_part {
+            UserPart::Empty => return None,
+            UserPart::User(user) => user,
+            UserPart::UserPw(user_pw) => &user_pw.user,
+        };
+
+        format!("tel:{user}").parse().ok()
+    }
+
+    pub fn compare(&self, other: &Self) -> bool {
+        let param_eq = |name| match (self.params.get_val(name), other.params.get_val(name)) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            (None, None) => true,
+            _ => false,
+        };
+
+        self.number.eq_ignore_ascii_case(&other.number)
+            && param_eq("phone-context")
+            && param_eq("ext")
+            && param_eq("isub")
+    }
+
+    pub fn parse(ctx: ParseCtx<'_>) -> impl Fn(&str) -> IResult<&str, Self> + '_ {
+        move |i| {
+            verify(
+                map(
+                    tuple((parse_number, Params::<CPS>::parse(ctx))),
+                    |(number, params)| Self {
+                        number: number_to_bytesstr(ctx, number),
+                        params,
+                    },
+                ),
+                Self::is_valid,
+            )(i)
+        }
+    }
+
+    /// Parse the tel URI without any parameters, used where parameters would belong to the header
+    ///
+    /// Local numbers are rejected, as they require the `phone-context` parameter.
+    pub fn parse_no_params(ctx: ParseCtx<'_>) -> impl Fn(&str) -> IResult<&str, Self> + '_ {
+        move |i| {
+            verify(
+                map(parse_number, |number| Self {
+                    number: number_to_bytesstr(ctx, number),
+                    params: Params::new(),
+                }),
+                Self::is_valid,
+            )(i)
+        }
+    }
+
+    /// Local numbers must specify the context they are valid in
+    fn is_valid(&self) -> bool {
+        self.is_global() || self.phone_context().is_some()
+    }
+}
+
+fn parse_number(i: &str) -> IResult<&str, &str> {
+    preceded(
+        tag_no_case("tel:"),
+        verify(take_while1(number_char), |number: &str| {
+            match number.strip_prefix('+') {
+                Some(global) => {
+                    global.chars().any(|c| c.is_ascii_digit())
+                        && global
+                            .chars()
+                            .all(|c| c.is_ascii_digit() || is_visual_separator(c))
+                }
+                None => number.chars().any(|c| !is_visual_separator(c)),
+            }
+        }),
+    )(i)
+}
+
+fn number_to_bytesstr(ctx: ParseCtx<'_>, number: &str) -> BytesStr {
+    if number.contains(is_visual_separator) {
+        remove_visual_separators(number).into()
+    } else {
+        BytesStr::from_parse(ctx.src, number)
+    }
+}
+
+fn number_char(c: char) -> bool {
+    c.is_ascii_hexdigit() || matches!(c, '+' | '*' | '#') || is_visual_separator(c)
+}
+
+fn is_visual_separator(c: char) -> bool {
+    matches!(c, '-' | '.' | '(' | ')')
+}
+
+fn remove_visual_separators(number: &str) -> String {
+    number
+        .chars()
+        .filter(|c| !is_visual_separator(*c))
+        .collect()
+}
+
+impl fmt::Debug for TelUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.print_ctx(PrintCtx::default()))
+    }
+}
+
+impl Print for TelUri {
+    fn print(&self, f: &mut fmt::Formatter<'_>, _: PrintCtx<'_>) -> fmt::Result {
+        write!(f, "tel:{}{}", self.number, self.params)
+    }
+}
+
+impl Uri for TelUri {
+    /// tel URIs have no host, the domain of the `phone-context` is used if available.
+    /// Otherwise [`UriInfo::has_host`] returns false and requests to the URI must be
+    /// sent using an outbound proxy.
+    fn info(&self) -> UriInfo<'_> {
+        let host = match self.phone_context() {
+            Some(context) if !context.starts_with('+') => Host::Name(context.clone()),
+            _ => Host::Name(BytesStr::from_static("")),
+        };
+
+        UriInfo {
+            transport: None,
+            secure: false,
+            host_port: HostPort { host, port: None },
+        }
+    }
+
+    fn compare(&self, other: &dyn Uri) -> bool {
+        if let Some(other) = other.downcast_ref::<Self>() {
+            self.compare(other)
+        } else {
+            false
+        }
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Uri> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid tel uri")]
+pub struct InvalidTelUri(());
+
+impl FromStr for TelUri {
+    type Err = InvalidTelUri;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = BytesStr::from(s);
+
+        let ctx = ParseCtx::default(&s);
+
+        let res = Self::parse(ctx)(s.as_ref())
+            .map(|(_, uri)| uri)
+            .map_err(|_| InvalidTelUri(()));
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::uri::NameAddr;
+
+    #[test]
+    fn global_number() {
+        let uri: TelUri = "tel:+1-201-555-0123".parse().unwrap();
+
+        assert!(uri.is_global());
+        assert_eq!(uri.number, "+12015550123");
+        assert!(uri.phone_context().is_none());
+        assert_eq!(uri.default_print_ctx().to_string(), "tel:+12015550123");
+    }
+
+    #[test]
+    fn local_number() {
+        let uri: TelUri = "tel:7042;phone-context=example.com".parse().unwrap();
+
+        assert!(!uri.is_global());
+        assert_eq!(uri.number, "7042");
+        assert_eq!(uri.phone_context().unwrap(), "example.com");
+        assert_eq!(
+            uri.default_print_ctx().to_string(),
+            "tel:7042;phone-context=example.com"
+        );
+    }
+
+    #[test]
+    fn local_number_requires_context() {
+        assert!("tel:7042".parse::<TelUri>().is_err());
+        assert!("tel:+".parse::<TelUri>().is_err());
+        assert!("tel:+12a4".parse::<TelUri>().is_err());
+    }
+
+    #[test]
+    fn compare() {
+        let a: TelUri = "tel:+1-201-555-0123".parse().unwrap();
+        let b: TelUri = "tel:+1.201.555.0123".parse().unwrap();
+        let c: TelUri = "tel:+1-201-555-0123;ext=22".parse().unwrap();
+
+        assert!(a.compare(&b));
+        assert!(!a.compare(&c));
+    }
+
+    #[test]
+    fn sip_uri_conversion() {
+        let uri: TelUri = "tel:7042;phone-context=example.com".parse().unwrap();
+
+        let sip_uri = uri.to_sip_uri(HostPort {
+            host: Host::Name("gateway.example.com".into()),
+            port: None,
+        });
+
+        assert_eq!(
+            sip_uri.default_print_ctx().to_string(),
+            "sip:7042;phone-context=example.com@gateway.example.com;user=phone"
+        );
+
+        let converted = TelUri::from_sip_uri(&sip_uri).unwrap();
+        assert!(converted.compare(&uri));
+
+        let no_phone: SipUri = "sip:7042@example.com".parse().unwrap();
+        assert!(TelUri::from_sip_uri(&no_phone).is_none());
+    }
+
+    #[test]
+    fn parse_no_params() {
+        let input = BytesStr::from_static("tel:+1-201-555-0123;tag=abc");
+        let (rem, uri) = TelUri::parse_no_params(ParseCtx::default(&input))(&input).unwrap();
+
+        assert_eq!(uri.number, "+12015550123");
+        assert_eq!(rem, ";tag=abc");
+
+        let input = BytesStr::from_static("tel:7042;phone-context=example.com");
+        assert!(TelUri::parse_no_params(ParseCtx::default(&input))(&input).is_err());
+    }
+
+    #[test]
+    fn info_without_host() {
+        let global: TelUri = "tel:+1-201-555-0123".parse().unwrap();
+        assert!(!global.info().has_host());
+
+        let local: TelUri = "tel:7042;phone-context=example.com".parse().unwrap();
+        let info = local.info();
+
+        assert!(info.has_host());
+        assert_eq!(info.host_port.host, Host::Name("example.com".into()));
+
+        let local: TelUri = "tel:7042;phone-context=+1-201".parse().unwrap();
+        assert!(!local.info().has_host());
+    }
+
+    #[test]
+    fn custom_parser_precedes_tel() {
+        fn parse_custom(i: &str) -> IResult<&str, Box<dyn Uri>> {
+            map(tag_no_case("tel:+1"), |_| -> Box<dyn Uri> {
+                Box::new(SipUri::from_str("sip:custom@example.com").unwrap())
+            })(i)
+        }
+
+        let parser = crate::parse::Parser {
+            parse_other_uri: parse_custom,
+            ..Default::default()
+        };
+
+        let input = BytesStr::from_static("tel:+1");
+        let (_, uri) = ParseCtx::new(input.as_ref(), parser).parse_uri()(&input).unwrap();
+        assert!(uri.downcast_ref::<SipUri>().is_some());
+
+        let input = BytesStr::from_static("tel:+49");
+        let (_, uri) = ParseCtx::new(input.as_ref(), parser).parse_uri()(&input).unwrap();
+        assert!(uri.downcast_ref::<TelUri>().is_some());
+    }
+
+    #[test]
+    fn name_addr() {
+        let input = BytesStr::from_static("\"Alice\" <tel:+1-201-555-0123>");
+
+        let (rem, name_addr) = NameAddr::parse(ParseCtx::default(&input))(&input).unwrap();
+        assert!(rem.is_empty());
+
+        let uri: &TelUri = name_addr.uri.downcast_ref().unwrap();
+        assert_eq!(uri.number, "+12015550123");
+    }
+}
@@ -0,0 +1,285 @@
+//! Building block for back-to-back user agents, bridging two INVITE sessions
+//!
+//! Each leg keeps its own dialog, so CSeq numbers, tags and identities are never shared between the legs.
+
+use crate::dialog::Dialog;
+use crate::invite::session::{Event, Session};
+use bytes::Bytes;
+use bytesstr::BytesStr;
+use sip_core::transaction::TsxResponse;
+use sip_core::transport::OutgoingResponse;
+use sip_core::{IncomingRequest, Request, Result};
+use sip_types::header::typed::{ContentType, Warning};
+use sip_types::uri::sip::SipUri;
+use sip_types::{Code, CodeKind, Headers, Method};
+use tokio::select;
+
+const SDP_CONTENT_TYPE: &str = "application/sdp";
+
+/// Warnings added to `488 Not Acceptable Here` responses
+const MISSING_SDP: (u16, &str) = (
+    Warning::MISCELLANEOUS_WARNING,
+    "Missing session description",
+);
+const INCOMPATIBLE_SDP: (u16, &str) = (
+    Warning::INCOMPATIBLE_MEDIA_FORMAT,
+    "Incompatible media format",
+);
+
+/// One of the two legs of a [`B2bua`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leg {
+    A,
+    B,
+}
+
+impl Leg {
+    pub fn other(self) -> Self {
+        match self {
+            Leg::A => Leg::B,
+            Leg::B => Leg::A,
+        }
+    }
+}
+
+/// Maps session descriptions between the legs of a [`B2bua`]
+///
+/// Can be used to anchor media, e.g. by replacing the connection addresses with the ones of
+/// a media relay or transcoder.
+pub trait SdpMapper: Send {
+    /// Map an offer or answer received on leg `from` before it is sent to the other leg
+    ///
+    /// Returning `None` rejects the offer with `488 Not Acceptable Here` and a
+    /// `305 Incompatible media format` warning.
+    fn map(&mut self, from: Leg, sdp: Bytes) -> Option<Bytes>;
+}
+
+/// [`SdpMapper`] which forwards all session descriptions unchanged
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PassThrough;
+
+impl SdpMapper for PassThrough {
+    fn map(&mut self, _from: Leg, sdp: Bytes) -> Option<Bytes> {
+        Some(sdp)
+    }
+}
+
+/// Bridges two established INVITE sessions
+///
+/// Re-INVITEs and INFO requests received on one leg are relayed to the other leg,
+/// their responses are relayed back. Session descriptions are passed through the [`SdpMapper`].
+///
+/// While a re-INVITE is relayed, a colliding re-INVITE received on the other leg is rejected with
+/// `491 Request Pending`, other requests of that leg are handled once the relayed re-INVITE completed.
+/// If the other leg rejects the relayed re-INVITE with `491 Request Pending` or its peer's re-INVITE takes
+/// precedence, the original re-INVITE is rejected with `491 Request Pending`.
+///
+/// Re-INVITEs without offer are rejected with `488 Not Acceptable Here`, as the answer to the offer of the
+/// other leg would have to be relayed in the ACK request. All `488` responses created by the B2BUA contain
+/// a `Warning` header describing why the session description was not accepted.
+pub struct B2bua<M> {
+    pub a: Session,
+    pub b: Session,
+    mapper: M,
+}
+
+impl<M: SdpMapper> B2bua<M> {
+    pub fn new(a: Session, b: Session, mapper: M) -> Self {
+        Self { a, b, mapper }
+    }
+
+    /// Drive both sessions until one of them is terminated, the other session is then terminated using a BYE
+    ///
+    /// Returns the leg which ended the call.
+    pub async fn run(&mut self) -> Result<Leg> {
+        loop {
+            let ended = select! {
+                event = self.a.drive() => relay(event?, Leg::A, &mut self.b, &mut self.mapper).await?,
+                event = self.b.drive() => relay(event?, Leg::B, &mut self.a, &mut self.mapper).await?,
+            };
+
+            if let Some(leg) = ended {
+                return Ok(leg);
+            }
+        }
+    }
+}
+
+/// Handle an event of the session on leg `from`, returns `Some(from)` if the session ended
+async fn relay<M: SdpMapper>(
+    event: Event<'_>,
+    from: Leg,
+    other: &mut Session,
+    mapper: &mut M,
+) -> Result<Option<Leg>> {
+    match event {
+        Event::RefreshNeeded(event) => {
+            event.process_default().await?;
+        }
+        Event::ReInviteReceived(event) => {
+            let offer = if event.invite.body.is_empty() {
+                Err(MISSING_SDP)
+            } else {
+                mapper
+                    .map(from, event.invite.body.clone())
+                    .ok_or(INCOMPATIBLE_SDP)
+            };
+
+            let offer = match offer {
+                Ok(offer) => offer,
+                Err(warning) => {
+                    let response = not_acceptable(&event.session.dialog, &event.invite, warning)?;
+
+                    event.transaction.respond_failure(response).await?;
+                    return Ok(None);
+                }
+            };
+
+            let other_response = other
+                .reinvite(move |dialog| {
+                    let mut invite = dialog.create_request(Method::INVITE);
+                    set_sdp_body(&mut invite.headers, &mut invite.body, offer.clone());
+                    invite
+                })
+                .await?;
+
+            // The re-INVITE of the other leg's peer takes precedence, the offering peer has to retry
+            let Some(other_response) = other_response else {
+                other.cancel_queued_reinvite();
+
+                let response = event.session.dialog.create_response(
+                    &event.invite,
+                    Code::REQUEST_PENDING,
+                    None,
+                )?;
+
+                event.transaction.respond_failure(response).await?;
+                return Ok(None);
+            };
+            let code = other_response.line.code;
+
+            // A 2XX response to an offer must contain the answer
+            let answer = if code.kind() != CodeKind::Success {
+                None
+            } else if other_response.body.is_empty() {
+                Some(Err(MISSING_SDP))
+            } else {
+                Some(
+                    mapper
+                        .map(from.other(), other_response.body.clone())
+                        .ok_or(INCOMPATIBLE_SDP),
+                )
+            };
+
+            match answer {
+                Some(Ok(answer)) => {
+                    let mut response =
+                        event
+                            .session
+                            .dialog
+                            .create_response(&event.invite, code, None)?;
+                    set_sdp_body(&mut response.msg.headers, &mut response.msg.body, answer);
+
+                    event.respond_success(response).await?;
+                }
+                Some(Err(warning)) => {
+                    let response = not_acceptable(&event.session.dialog, &event.invite, warning)?;
+
+                    event.transaction.respond_failure(response).await?;
+                }
+                None => {
+                    let response =
+                        event
+                            .session
+                            .dialog
+                            .create_response(&event.invite, code, None)?;
+
+                    event.transaction.respond_failure(response).await?;
+                }
+            }
+        }
+        Event::Info(event) => {
+            let mut info = other.dialog.create_request(Method::INFO);
+
+            if let Ok(content_type) = event.info.headers.get_named::<ContentType>() {
+                info.headers.insert_named(&content_type);
+            }
+            info.body = event.info.body.clone();
+
+            let other_response = send_request(other, info).await?;
+
+            let response = event.session.dialog.create_response(
+                &event.info,
+                other_response.line.code,
+                None,
+            )?;
+
+            event.transaction.respond(response).await?;
+        }
+        Event::ReInviteResponse(_) => {
+            // Relayed re-INVITEs are never queued
+        }
+        Event::Bye(event) => {
+            event.process_default().await?;
+            terminate(other).await;
+
+            return Ok(Some(from));
+        }
+        Event::Terminated => {
+            terminate(other).await;
+
+            return Ok(Some(from));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Create a `488 Not Acceptable Here` response to `invite` with the warning code and text of `warning`
+/// ([RFC 3261 Section 20.43](https://datatracker.ietf.org/doc/html/rfc3261#section-20.43))
+fn not_acceptable(
+    dialog: &Dialog,
+    invite: &IncomingRequest,
+    (warn_code, text): (u16, &'static str),
+) -> Result<OutgoingResponse> {
+    // The warn-agent is the host of the B2BUA, fall back to a pseudonym for non SIP contacts
+    let agent = dialog
+        .local_contact
+        .uri
+        .uri
+        .downcast_ref::<SipUri>()
+        .map(|uri| uri.host_port.host.to_string())
+        .unwrap_or_else(|| "b2bua".into());
+
+    let mut response = dialog.create_response(invite, Code::NOT_ACCEPTABLE_HERE, None)?;
+    response
+        .msg
+        .headers
+        .insert_named(&Warning::new(warn_code, agent, text));
+
+    Ok(response)
+}
+
+fn set_sdp_body(headers: &mut Headers, body: &mut Bytes, sdp: Bytes) {
+    headers.insert_named(&ContentType(BytesStr::from_static(SDP_CONTENT_TYPE)));
+    *body = sdp;
+}
+
+async fn send_request(session: &Session, request: Request) -> Result<TsxResponse> {
+    let mut target_tp_info = session.dialog.target_tp_info.lock().await;
+
+    let mut transaction = session
+        .endpoint
+        .send_request(request, &mut target_tp_info)
+        .await?;
+
+    drop(target_tp_info);
+
+    transaction.receive_final().await
+}
+
+async fn terminate(session: &mut Session) {
+    if let Err(e) = session.terminate().await {
+        log::warn!("Failed to terminate the other leg of the B2BUA, {e}");
+    }
+}
@@ -1,6 +1,6 @@
 use super::{Dialog, DialogLayer};
 use crate::dialog::layer::DialogEntry;
-use crate::util::{random_sequence_number, random_string};
+use crate::util::{random_sequence_number, RequestHook};
 use bytes::Bytes;
 use sip_core::transaction::TsxResponse;
 use sip_core::transport::TargetTransportInfo;
@@ -24,6 +24,9 @@ pub struct ClientDialogBuilder {
     pub target: Box<dyn Uri>,
     pub secure: bool,
     pub target_tp_info: TargetTransportInfo,
+
+    /// Called with every request created by the builder, passed on to all dialogs created by it
+    pub request_hook: Option<RequestHook>,
 }
 
 impl ClientDialogBuilder {
@@ -34,17 +37,21 @@ impl ClientDialogBuilder {
         local_contact: Contact,
         target: Box<dyn Uri>,
     ) -> Self {
+        let local_tag = endpoint.generate_tag();
+        let call_id = endpoint.generate_call_id();
+
         Self {
             endpoint,
             dialog_layer,
             local_cseq: random_sequence_number(),
-            local_fromto: FromTo::new(local_addr, Some(random_string())),
+            local_fromto: FromTo::new(local_addr, Some(local_tag)),
             peer_fromto: FromTo::new(NameAddr::uri(target.clone()), None),
             local_contact,
-            call_id: CallID(random_string()),
+            call_id,
             secure: target.info().secure,
             target,
             target_tp_info: TargetTransportInfo::default(),
+            request_hook: None,
         }
     }
 
@@ -61,14 +68,22 @@ impl ClientDialogBuilder {
         });
         headers.insert_named(&self.local_contact);
 
-        Request {
+        let mut request = Request {
             line: RequestLine {
                 method,
                 uri: self.target.clone(),
             },
             headers,
             body: Bytes::new(),
+        };
+
+        self.endpoint.add_outbound_proxy_route(&mut request);
+
+        if let Some(request_hook) = &self.request_hook {
+            request_hook.apply(&mut request);
         }
+
+        request
     }
 
     pub fn create_dialog_from_response(
@@ -80,7 +95,8 @@ impl ClientDialogBuilder {
         let dialog = Dialog {
             endpoint: self.endpoint.clone(),
             dialog_layer: self.dialog_layer,
-            local_cseq: self.local_cseq.into(),
+            // The first request inside the dialog must use a CSeq higher than the initial request
+            local_cseq: (self.local_cseq + 1).into(),
             local_fromto: self.local_fromto.clone(),
             peer_fromto: response.base_headers.to.clone(),
             local_contact: self.local_contact.clone(),
@@ -89,6 +105,7 @@ impl ClientDialogBuilder {
             route_set: response.headers.get(Name::RECORD_ROUTE).unwrap_or_default(),
             secure: self.secure,
             target_tp_info: Mutex::new(self.target_tp_info.clone()),
+            request_hook: self.request_hook.clone(),
         };
 
         let entry = DialogEntry::new(None);
@@ -100,3 +117,56 @@ impl ClientDialogBuilder {
         Ok(dialog)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sip_types::header::typed::Routing;
+    use sip_types::uri::sip::SipUri;
+
+    fn sip_uri(uri: &str) -> SipUri {
+        uri.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn outbound_proxy_is_only_used_outside_of_dialogs() {
+        let mut builder = Endpoint::builder();
+        let dialog_layer = builder.add_layer(DialogLayer::default());
+        builder.set_outbound_proxy(sip_uri("sip:proxy.example.com"));
+        let endpoint = builder.build();
+
+        let mut dialog_builder = ClientDialogBuilder::new(
+            endpoint.clone(),
+            dialog_layer,
+            NameAddr::uri(sip_uri("sip:alice@example.com")),
+            Contact::new(NameAddr::uri(sip_uri("sip:alice@192.0.2.1"))),
+            Box::new(sip_uri("sip:bob@example.com")),
+        );
+
+        let invite = dialog_builder.create_request(Method::INVITE);
+        let route: Routing = invite.headers.get(Name::ROUTE).unwrap();
+        assert!(route.is_loose());
+        assert!(route.uri.uri.compare(&sip_uri("sip:proxy.example.com;lr")));
+
+        let dialog = Dialog {
+            endpoint,
+            dialog_layer,
+            local_cseq: 1.into(),
+            local_fromto: dialog_builder.local_fromto.clone(),
+            peer_fromto: FromTo::new(
+                NameAddr::uri(sip_uri("sip:bob@example.com")),
+                Some("peer-tag".into()),
+            ),
+            local_contact: dialog_builder.local_contact.clone(),
+            peer_contact: Contact::new(NameAddr::uri(sip_uri("sip:bob@192.0.2.2"))),
+            call_id: dialog_builder.call_id.clone(),
+            route_set: vec![],
+            secure: false,
+            target_tp_info: Mutex::default(),
+            request_hook: None,
+        };
+
+        let bye = dialog.create_request(Method::BYE);
+        assert!(!bye.headers.contains(&Name::ROUTE));
+    }
+}
@@ -1,4 +1,5 @@
 use super::key::DialogKey;
+use bytesstr::BytesStr;
 use parking_lot::Mutex;
 use sip_core::{Endpoint, EndpointBuilder, IncomingRequest, Layer, LayerKey, MayTake, Result};
 use sip_types::{Code, Method};
@@ -48,7 +49,7 @@ impl Layer for DialogLayer {
     }
 
     async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
-        let key = match DialogKey::from_incoming(&request) {
+        let mut key = match DialogKey::from_incoming(&request) {
             Some(key) => key,
             None => {
                 // No dialog key, we don't care
@@ -59,6 +60,12 @@ impl Layer for DialogLayer {
         let (usages, requests) = {
             let mut dialogs = self.dialogs.lock();
 
+            // Requests may arrive before the response creating the dialog,
+            // match them to an early usage registered without peer tag
+            if !dialogs.contains_key(&key) {
+                key.peer_tag = None;
+            }
+
             if let Some(dialog_entry) = dialogs.get_mut(&key) {
                 let request_cseq = request.base_headers.cseq.cseq;
 
@@ -190,6 +197,11 @@ impl Drop for UsageGuard {
         if let Some(dialog_entry) = dialogs.get_mut(&self.dialog_key) {
             let usage = dialog_entry.usages.remove(self.usage_key);
 
+            // Entries without peer tag only exist for early usages
+            if self.dialog_key.peer_tag.is_none() && dialog_entry.usages.is_empty() {
+                dialogs.remove(&self.dialog_key);
+            }
+
             // Make sure to release the lock before dropping the usage to avoid potential deadlocks
             drop(dialogs);
             drop(usage);
@@ -225,3 +237,42 @@ where
         usage_key,
     })
 }
+
+/// Register the given `usage` for a dialog which is not established yet, identified
+/// by its `call_id` and `local_tag`
+///
+/// Receives requests of the peer until a dialog matching their From-tag exists, e.g. a
+/// NOTIFY arriving before the 2xx response to the SUBSCRIBE which creates the dialog.
+pub fn register_early_usage<U>(
+    endpoint: Endpoint,
+    dialog_layer: LayerKey<DialogLayer>,
+    call_id: BytesStr,
+    local_tag: BytesStr,
+    usage: U,
+) -> UsageGuard
+where
+    U: Usage,
+{
+    let dialog_key = DialogKey {
+        call_id,
+        peer_tag: None,
+        local_tag,
+    };
+
+    let mut dialogs = endpoint[dialog_layer].dialogs.lock();
+
+    let usage_key = dialogs
+        .entry(dialog_key.clone())
+        .or_insert_with(|| DialogEntry::new(None))
+        .usages
+        .insert(Arc::new(usage));
+
+    drop(dialogs);
+
+    UsageGuard {
+        endpoint,
+        dialog_layer,
+        dialog_key,
+        usage_key,
+    }
+}
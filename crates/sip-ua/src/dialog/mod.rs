@@ -1,10 +1,11 @@
 use self::layer::DialogEntry;
-use crate::util::{random_sequence_number, random_string};
+use crate::util::{random_sequence_number, RequestHook};
 use bytesstr::BytesStr;
 use sip_core::transport::{OutgoingResponse, TargetTransportInfo};
 use sip_core::{Endpoint, Error, IncomingRequest, LayerKey, Request, Result};
 use sip_types::header::typed::{CSeq, CallID, Contact, FromTo, MaxForwards, Routing};
 use sip_types::header::HeaderError;
+use sip_types::uri::NameAddr;
 use sip_types::{Code, Method, Name};
 use std::sync::atomic::{AtomicU32, Ordering};
 
@@ -14,7 +15,7 @@ mod layer;
 
 pub use client_builder::ClientDialogBuilder;
 pub use key::DialogKey;
-pub use layer::{register_usage, DialogLayer, Usage, UsageGuard};
+pub use layer::{register_early_usage, register_usage, DialogLayer, Usage, UsageGuard};
 use tokio::sync::Mutex;
 
 #[derive(Debug)]
@@ -56,6 +57,9 @@ pub struct Dialog {
 
     /// Target of the dialog peer
     pub target_tp_info: Mutex<TargetTransportInfo>,
+
+    /// Called with every request created using [`Dialog::create_request`]
+    pub request_hook: Option<RequestHook>,
 }
 
 impl Dialog {
@@ -88,9 +92,10 @@ impl Dialog {
             // TODO check how this works exactly
             secure: request.line.uri.info().secure,
             target_tp_info: Default::default(),
+            request_hook: None,
         };
 
-        dialog.local_fromto.tag = Some(random_string());
+        dialog.local_fromto.tag = Some(dialog.endpoint.generate_tag());
 
         let entry = DialogEntry::new(Some(request.base_headers.cseq.cseq));
         dialog.endpoint[dialog_layer]
@@ -117,8 +122,27 @@ impl Dialog {
 
     pub fn create_request(&self, method: Method) -> Request {
         let mut request = Request::new(method.clone(), self.peer_contact.uri.uri.clone());
+        let mut route_set = self.route_set.clone();
+
+        // Strict router, it expects the request URI to be its own URI and the remote target as last Route
+        // (RFC3261 Section 12.2.1.1)
+        if let Some(first) = route_set.first().filter(|route| !route.is_loose()) {
+            request.line.uri = first.uri.uri.clone();
+            route_set.remove(0);
+            route_set.push(Routing {
+                uri: NameAddr::uri(self.peer_contact.uri.uri.clone()),
+                params: Default::default(),
+            });
+        }
+
+        // ACK requests take the CSeq number of the INVITE they acknowledge and must not consume a new one
+        let cseq = if method == Method::ACK {
+            self.local_cseq.load(Ordering::Relaxed)
+        } else {
+            self.local_cseq.fetch_add(1, Ordering::Relaxed)
+        };
 
-        let cseq = CSeq::new(self.local_cseq.fetch_add(1, Ordering::Relaxed), method);
+        let cseq = CSeq::new(cseq, method);
 
         request.headers.insert_type(Name::FROM, &self.local_fromto);
         request.headers.insert_type(Name::TO, &self.peer_fromto);
@@ -126,8 +150,12 @@ impl Dialog {
         request.headers.insert_named(&self.call_id);
         request.headers.insert_named(&cseq);
 
-        if !self.route_set.is_empty() {
-            request.headers.insert_type(Name::ROUTE, &self.route_set);
+        if !route_set.is_empty() {
+            request.headers.insert_type(Name::ROUTE, &route_set);
+        }
+
+        if let Some(request_hook) = &self.request_hook {
+            request_hook.apply(&mut request);
         }
 
         request
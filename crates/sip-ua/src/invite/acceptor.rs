@@ -13,6 +13,7 @@ use sip_core::{Endpoint, IncomingRequest, LayerKey, Result};
 use sip_types::header::typed::{RSeq, Require, Supported};
 use sip_types::{Code, Method};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{mpsc, oneshot, Mutex};
 use tokio::time::timeout;
 
@@ -37,6 +38,9 @@ pub struct Acceptor {
 
     /// Configuration for `timer` extension
     timer_config: AcceptorTimerConfig,
+
+    /// RSeq of the next reliable provisional response
+    next_rseq: u32,
 }
 
 impl Drop for Acceptor {
@@ -126,6 +130,7 @@ impl Acceptor {
             usage_guard: Some(usage_guard),
             cancellable_key,
             timer_config: AcceptorTimerConfig::default(),
+            next_rseq: random_sequence_number(),
         })
     }
 
@@ -186,7 +191,8 @@ impl Acceptor {
         let mut state = self.inner.state.lock().await;
 
         if let InviteSessionState::UasProvisional { tsx, invite, .. } = &mut *state {
-            let rack = random_sequence_number();
+            let rack = self.next_rseq;
+            self.next_rseq = self.next_rseq.wrapping_add(1);
 
             response.msg.headers.insert_named(&Require("100rel".into()));
             response.msg.headers.insert_named(&RSeq(rack));
@@ -201,26 +207,29 @@ impl Acceptor {
 
             tsx.respond_provisional(&mut response).await?;
 
-            let mut prack = None;
+            // Retransmit with exponential backoff until PRACKed or 64*T1 passed (RFC3262 Section 3)
             let mut delta = T1;
+            let mut elapsed = Duration::ZERO;
 
-            for _ in 1..6 {
+            while elapsed < T1 * 64 {
                 match timeout(delta, &mut prack_recv).await {
                     Ok(res) => {
                         // Unwrap is safe as no other function sets `awaiting_prack`
                         // which means the channel will not be dropped
-                        prack = Some(res.unwrap());
-                        break;
+                        return Ok(res.unwrap());
                     }
                     Err(_) => {
                         // retransmit on timeout
                         tsx.respond_provisional(&mut response).await?;
-                        delta = T1 * 2;
+                        elapsed += delta;
+                        delta *= 2;
                     }
                 }
             }
 
-            prack.ok_or(Error::RequestTerminated)
+            self.inner.awaited_prack.lock().take();
+
+            Err(Error::RequestTerminated)
         } else {
             Err(Error::RequestTerminated)
         }
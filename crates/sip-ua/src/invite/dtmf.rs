@@ -0,0 +1,150 @@
+use bytes::Bytes;
+use std::str::from_utf8;
+use std::time::Duration;
+
+/// Content-Type of INFO requests carrying a [`DtmfRelay`] body
+pub const DTMF_RELAY_CONTENT_TYPE: &str = "application/dtmf-relay";
+
+/// Duration assumed when the `Duration` line is missing from a dtmf-relay body
+const DEFAULT_DURATION: Duration = Duration::from_millis(250);
+
+/// DTMF tone sent inside the body of an INFO request (`application/dtmf-relay`)
+///
+/// Used as a fallback when no telephone-event payload has been negotiated for the media session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DtmfRelay {
+    /// One of `0-9`, `*`, `#` or `A-D`
+    pub digit: char,
+    pub duration: Duration,
+}
+
+impl DtmfRelay {
+    /// Create a new DTMF event, returns `None` if `digit` is not a valid DTMF digit
+    pub fn new(digit: char, duration: Duration) -> Option<Self> {
+        let digit = digit.to_ascii_uppercase();
+
+        is_dtmf_digit(digit).then_some(Self { digit, duration })
+    }
+
+    /// Parse the body of an INFO request
+    pub fn parse(body: &[u8]) -> Option<Self> {
+        let body = from_utf8(body).ok()?;
+
+        let mut digit = None;
+        let mut duration = DEFAULT_DURATION;
+
+        for line in body.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            let value = value.trim();
+
+            match key.trim().to_ascii_lowercase().as_str() {
+                "signal" => {
+                    digit = Some(parse_signal(value)?);
+                }
+                "duration" => {
+                    duration = Duration::from_millis(value.parse().ok()?);
+                }
+                _ => {}
+            }
+        }
+
+        Self::new(digit?, duration)
+    }
+
+    /// Create the body of an INFO request
+    pub fn to_body(&self) -> Bytes {
+        format!(
+            "Signal={}\r\nDuration={}\r\n",
+            self.digit,
+            self.duration.as_millis()
+        )
+        .into()
+    }
+}
+
+/// Parse the value of the `Signal` line, either a single character or an event code (RFC 4733)
+fn parse_signal(value: &str) -> Option<char> {
+    // Some implementations send the event code instead of the character
+    let digit = match value {
+        "10" => '*',
+        "11" => '#',
+        "12" => 'A',
+        "13" => 'B',
+        "14" => 'C',
+        "15" => 'D',
+        _ => {
+            let mut chars = value.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return None,
+            }
+        }
+    };
+
+    Some(digit)
+}
+
+fn is_dtmf_digit(c: char) -> bool {
+    matches!(c, '0'..='9' | '*' | '#' | 'A'..='D')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_digit() {
+        let dtmf = DtmfRelay::parse(b"Signal=5\r\nDuration=160\r\n").unwrap();
+
+        assert_eq!(dtmf.digit, '5');
+        assert_eq!(dtmf.duration, Duration::from_millis(160));
+    }
+
+    #[test]
+    fn parse_default_duration() {
+        let dtmf = DtmfRelay::parse(b"Signal=#\r\n").unwrap();
+
+        assert_eq!(dtmf.digit, '#');
+        assert_eq!(dtmf.duration, DEFAULT_DURATION);
+    }
+
+    #[test]
+    fn parse_event_codes() {
+        for (code, digit) in [
+            ("10", '*'),
+            ("11", '#'),
+            ("12", 'A'),
+            ("13", 'B'),
+            ("14", 'C'),
+            ("15", 'D'),
+        ] {
+            let body = format!("Signal={code}\r\nDuration=100\r\n");
+
+            assert_eq!(DtmfRelay::parse(body.as_bytes()).unwrap().digit, digit);
+        }
+    }
+
+    #[test]
+    fn parse_lowercase_letter() {
+        assert_eq!(DtmfRelay::parse(b"Signal=a\r\n").unwrap().digit, 'A');
+    }
+
+    #[test]
+    fn reject_invalid_signal() {
+        assert!(DtmfRelay::parse(b"Signal=16\r\n").is_none());
+        assert!(DtmfRelay::parse(b"Signal=1x\r\n").is_none());
+        assert!(DtmfRelay::parse(b"Signal=E\r\n").is_none());
+        assert!(DtmfRelay::parse(b"Duration=100\r\n").is_none());
+    }
+
+    #[test]
+    fn body_roundtrip() {
+        let dtmf = DtmfRelay::new('b', Duration::from_millis(200)).unwrap();
+
+        assert_eq!(DtmfRelay::parse(&dtmf.to_body()), Some(dtmf));
+    }
+}
@@ -1,27 +1,40 @@
 // TODO: remove clippy allow
 #![allow(clippy::large_enum_variant)]
 
-use super::prack::get_rseq;
+use super::prack::{create_prack, get_rseq, send_prack_detached};
 use super::session::{Role, Session};
 use super::timer::InitiatorTimerConfig;
-use super::{Inner, InviteLayer, InviteSessionState, InviteUsage};
+use super::{create_ack, Inner, InviteLayer, InviteSessionState, InviteUsage};
 use crate::dialog::{ClientDialogBuilder, Dialog, DialogLayer};
+use crate::redirect::RedirectPolicy;
+use crate::util::{retry_after, RequestHook};
 use bytesstr::BytesStr;
 use parking_lot as pl;
 use sip_core::transaction::{ClientInvTsx, TsxResponse};
+use sip_core::transport::{OutgoingRequest, TargetTransportInfo};
 use sip_core::{Endpoint, Error, LayerKey, Request};
-use sip_types::header::typed::{Contact, RSeq, Refresher, Supported};
+use sip_types::header::typed::{
+    Contact, PAssertedIdentity, PPreferredIdentity, Privacy, PrivacyValue, RSeq, Refresher,
+    Supported,
+};
 use sip_types::header::HeaderError;
+use sip_types::host::{Host, HostPort};
+use sip_types::uri::sip::SipUri;
 use sip_types::uri::{NameAddr, Uri};
 use sip_types::{Method, Name};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 
 #[derive(Debug)]
 pub enum Response {
     Provisional(TsxResponse),
     Failure(TsxResponse),
+    /// A provisional response created a new early dialog.
+    ///
+    /// If the INVITE was forked, one early dialog is created for each peer responding.
     Early(Early, TsxResponse, Option<RSeq>),
+    /// A success response established the session and was acknowledged
     Session(Session, TsxResponse),
     Finished,
 }
@@ -40,9 +53,35 @@ pub struct Initiator {
     /// will be forwarded using the channel.
     early_list: Vec<(BytesStr, mpsc::Sender<EarlyEvent>)>,
 
+    /// To-tag of the first success response, which established the session
+    ///
+    /// Success responses of other forks are acknowledged and terminated immediately.
+    established_tag: Option<BytesStr>,
+
+    /// ACK sent for the success response which established the session, resent on its retransmissions
+    established_ack: Option<OutgoingRequest>,
+
+    /// Backoff requested by the peer using the Retry-After header of the failure response
+    retry_after: Option<Duration>,
+
+    /// Number of redirects followed since the INVITE was sent
+    redirects: u32,
+
+    /// Policy used to follow redirect (3xx) responses
+    pub redirect_policy: RedirectPolicy,
+
     pub support_timer: bool,
     pub support_100rel: bool,
 
+    /// Identities added as P-Asserted-Identity headers to the INVITE, set when acting as trusted entity
+    pub asserted_identities: Vec<NameAddr>,
+
+    /// Identities added as P-Preferred-Identity headers to the INVITE
+    pub preferred_identities: Vec<NameAddr>,
+
+    /// Privacy header added to the INVITE, see [`Initiator::set_anonymous`]
+    pub privacy: Option<Privacy>,
+
     pub timer_config: InitiatorTimerConfig,
 
     invite_layer: LayerKey<InviteLayer>,
@@ -64,8 +103,16 @@ impl Initiator {
             dialog_builder: dialog,
             transaction: None,
             early_list: vec![],
+            established_tag: None,
+            established_ack: None,
+            retry_after: None,
+            redirects: 0,
+            redirect_policy: RedirectPolicy::default(),
             support_timer: true,
             support_100rel: true,
+            asserted_identities: vec![],
+            preferred_identities: vec![],
+            privacy: None,
             timer_config: InitiatorTimerConfig {
                 expires_secs: None,
                 refresher: Refresher::Unspecified,
@@ -75,6 +122,29 @@ impl Initiator {
         }
     }
 
+    /// Make an anonymous call ([RFC3323](https://datatracker.ietf.org/doc/html/rfc3323#section-4.1.1.3)).
+    ///
+    /// Replaces the From header with an anonymous identity and requests the P-Asserted-Identity to be
+    /// withheld from the peer using `Privacy: id`. The real identity can still be provided to a trusted
+    /// proxy using [`Initiator::preferred_identities`]. Must be called before the INVITE is created.
+    pub fn set_anonymous(&mut self) {
+        let anonymous = SipUri::new(HostPort {
+            host: Host::Name(BytesStr::from_static("anonymous.invalid")),
+            port: None,
+        })
+        .user(BytesStr::from_static("anonymous"));
+
+        self.dialog_builder.local_fromto.uri =
+            NameAddr::new(BytesStr::from_static("Anonymous"), anonymous);
+        self.privacy = Some(Privacy(vec![PrivacyValue::Id]));
+    }
+
+    /// Set a hook which is called with every request created for this call, including all requests
+    /// inside the dialogs created from responses to the INVITE
+    pub fn set_request_hook(&mut self, hook: RequestHook) {
+        self.dialog_builder.request_hook = Some(hook);
+    }
+
     pub fn create_invite(&mut self) -> Request {
         let mut request = self.dialog_builder.create_request(Method::INVITE);
 
@@ -90,6 +160,22 @@ impl Initiator {
             self.timer_config.populate_request(&mut request);
         }
 
+        for identity in &self.asserted_identities {
+            request
+                .headers
+                .insert_named(&PAssertedIdentity(identity.clone()));
+        }
+
+        for identity in &self.preferred_identities {
+            request
+                .headers
+                .insert_named(&PPreferredIdentity(identity.clone()));
+        }
+
+        if let Some(privacy) = &self.privacy {
+            request.headers.insert_named(privacy);
+        }
+
         request
     }
 
@@ -101,6 +187,7 @@ impl Initiator {
             .await?;
 
         self.transaction = Some(transaction);
+        self.redirects = 0;
 
         Ok(())
     }
@@ -109,16 +196,26 @@ impl Initiator {
         self.transaction.as_ref()
     }
 
-    pub async fn receive(&mut self) -> Result<Response, Error> {
-        let transaction = self
-            .transaction
-            .as_mut()
-            .expect("must send invite before calling receive");
+    /// Returns the time to wait before calling again, if the peer included a Retry-After header
+    /// in the failure response (e.g. `486 Busy Here` or `503 Service Unavailable`)
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
 
+    pub async fn receive(&mut self) -> Result<Response, Error> {
         loop {
+            let transaction = self
+                .transaction
+                .as_mut()
+                .expect("must send invite before calling receive");
+
             let response = match transaction.receive().await? {
                 Some(response) => response,
-                None => return Ok(Response::Finished),
+                None => {
+                    self.terminate_early_dialogs().await;
+
+                    return Ok(Response::Finished);
+                }
             };
 
             let code = response.line.code.into_u16();
@@ -129,14 +226,15 @@ impl Initiator {
             }
 
             if code >= 300 {
-                for (_, early) in self.early_list.drain(..) {
-                    if early.send(EarlyEvent::Terminate).await.is_err() {
-                        log::warn!(
-                            "failed to forward termination event, receiver of early dropped"
-                        );
-                    }
+                self.terminate_early_dialogs().await;
+
+                if let Some(target) = self.redirect_policy.target(&response, self.redirects) {
+                    self.follow_redirect(target).await?;
+                    continue;
                 }
 
+                self.retry_after = retry_after(&response.headers);
+
                 return Ok(Response::Failure(response));
             }
 
@@ -146,6 +244,31 @@ impl Initiator {
                 continue;
             };
 
+            if (200..300).contains(&code) {
+                match &self.established_tag {
+                    Some(established_tag) if established_tag == to_tag => {
+                        // Retransmission of the success response which established the session
+                        if let Some(ack) = &mut self.established_ack {
+                            if let Err(e) = self
+                                .dialog_builder
+                                .endpoint
+                                .send_outgoing_request(ack)
+                                .await
+                            {
+                                log::warn!("Failed to retransmit ACK, {e}");
+                            }
+                        }
+
+                        continue;
+                    }
+                    Some(_) => {
+                        self.terminate_fork(&response).await?;
+                        continue;
+                    }
+                    None => self.established_tag = Some(to_tag.clone()),
+                }
+            }
+
             // Check if the response is part of any early dialog
             if let Some((_, tx)) = self.early_list.iter().find(|(tag, _)| tag == to_tag) {
                 // Found a early dialog for the tag, forward
@@ -163,15 +286,27 @@ impl Initiator {
                         return Ok(Response::Provisional(response));
                     }
 
-                    let early = self.create_early_dialog(&response)?;
+                    let mut early = self.create_early_dialog(&response)?;
 
                     let rseq = get_rseq(&response);
 
+                    if let Some(rseq) = &rseq {
+                        early.acknowledge_reliable(&response, rseq.0).await?;
+                    }
+
                     return Ok(Response::Early(early, response, rseq));
                 }
                 200..=299 => {
                     let session = self.create_session(&response)?;
 
+                    let mut ack =
+                        create_ack(&session.dialog, response.base_headers.cseq.cseq).await?;
+                    self.dialog_builder
+                        .endpoint
+                        .send_outgoing_request(&mut ack)
+                        .await?;
+                    self.established_ack = Some(ack);
+
                     return Ok(Response::Session(session, response));
                 }
                 _ => unreachable!(),
@@ -179,6 +314,100 @@ impl Initiator {
         }
     }
 
+    /// Send the INVITE again to the redirect `target`, keeping the body of the previous INVITE
+    async fn follow_redirect(&mut self, target: Box<dyn Uri>) -> Result<(), Error> {
+        log::debug!("following redirect to {target:?}");
+
+        let previous = self
+            .transaction
+            .take()
+            .expect("redirect must be received in a transaction");
+
+        self.redirects += 1;
+        self.established_tag = None;
+        self.established_ack = None;
+
+        // The new INVITE uses a new target but keeps Call-ID, From and To (RFC3261 Section 8.1.3.4)
+        self.dialog_builder.secure = target.info().secure;
+        self.dialog_builder.target = target;
+        self.dialog_builder.target_tp_info = TargetTransportInfo::default();
+        self.dialog_builder.local_cseq += 1;
+
+        let mut request = self.create_invite();
+
+        let previous = &previous.request().msg;
+        let _ = previous
+            .headers
+            .clone_into(&mut request.headers, Name::CONTENT_TYPE);
+        request.body = previous.body.clone();
+
+        let transaction = self
+            .dialog_builder
+            .endpoint
+            .send_invite(request, &mut self.dialog_builder.target_tp_info)
+            .await?;
+
+        self.transaction = Some(transaction);
+
+        Ok(())
+    }
+
+    /// Send a termination event to all early dialogs
+    async fn terminate_early_dialogs(&mut self) {
+        for (_, early) in self.early_list.drain(..) {
+            if early.send(EarlyEvent::Terminate).await.is_err() {
+                log::warn!("failed to forward termination event, receiver of early dropped");
+            }
+        }
+    }
+
+    /// Acknowledge and immediately terminate the success response of a fork,
+    /// as the session has already been established with another peer
+    async fn terminate_fork(&mut self, response: &TsxResponse) -> Result<(), Error> {
+        let to_tag = response.base_headers.to.tag.as_ref();
+
+        if let Some(i) = self
+            .early_list
+            .iter()
+            .position(|(tag, _)| Some(tag) == to_tag)
+        {
+            let (_, early) = self.early_list.remove(i);
+
+            if early.send(EarlyEvent::Terminate).await.is_err() {
+                log::warn!("failed to forward termination event, receiver of early dropped");
+            }
+        }
+
+        let dialog = self.dialog_builder.create_dialog_from_response(response)?;
+
+        let mut ack = create_ack(&dialog, response.base_headers.cseq.cseq).await?;
+        self.dialog_builder
+            .endpoint
+            .send_outgoing_request(&mut ack)
+            .await?;
+
+        let bye = dialog.create_request(Method::BYE);
+
+        let mut target_tp_info = dialog.target_tp_info.lock().await;
+        let mut transaction = self
+            .dialog_builder
+            .endpoint
+            .send_request(bye, &mut target_tp_info)
+            .await?;
+        drop(target_tp_info);
+
+        // Keep the dialog alive until the BYE transaction is completed
+        tokio::spawn(async move {
+            if let Err(e) = transaction.receive_final().await {
+                log::warn!("Failed to terminate forked dialog, {e}");
+            }
+
+            drop(dialog);
+        });
+
+        Ok(())
+    }
+
     fn create_early_dialog(&mut self, response: &TsxResponse) -> Result<Early, HeaderError> {
         let dialog = self.dialog_builder.create_dialog_from_response(response)?;
 
@@ -191,6 +420,7 @@ impl Initiator {
         Ok(Early {
             endpoint: self.dialog_builder.endpoint.clone(),
             dialog: Some(dialog),
+            last_rseq: None,
             response_rx,
             timer_config: self.timer_config,
             invite_layer: self.invite_layer,
@@ -248,6 +478,9 @@ pub struct Early {
     endpoint: Endpoint,
     dialog: Option<Dialog>,
 
+    /// RSeq of the last reliable provisional response acknowledged with a PRACK
+    last_rseq: Option<u32>,
+
     response_rx: mpsc::Receiver<EarlyEvent>,
 
     timer_config: InitiatorTimerConfig,
@@ -263,17 +496,32 @@ pub enum EarlyResponse {
 }
 
 impl Early {
+    /// Receive the next response in the early dialog.
+    ///
+    /// Reliable provisional responses (`100rel`) are acknowledged using a PRACK request
+    /// before being returned, retransmissions of them are discarded.
     pub async fn receive(&mut self) -> Result<EarlyResponse, Error> {
-        let dialog = self.dialog.as_mut().unwrap();
+        loop {
+            let response = match self.response_rx.recv().await.expect("dropped initiator") {
+                EarlyEvent::Response(response) => response,
+                EarlyEvent::Terminate => return Ok(EarlyResponse::Terminated),
+            };
 
-        match self.response_rx.recv().await.expect("dropped initiator") {
-            EarlyEvent::Response(response) => match response.line.code.into_u16() {
+            match response.line.code.into_u16() {
                 101..=199 => {
                     let rseq = get_rseq(&response);
 
-                    Ok(EarlyResponse::Provisional(response, rseq))
+                    if let Some(rseq) = &rseq {
+                        if !self.acknowledge_reliable(&response, rseq.0).await? {
+                            continue;
+                        }
+                    }
+
+                    return Ok(EarlyResponse::Provisional(response, rseq));
                 }
                 200..=299 => {
+                    let dialog = self.dialog.as_mut().unwrap();
+
                     let (evt_sink, usage_events) = mpsc::channel(4);
 
                     let supported = response
@@ -309,11 +557,141 @@ impl Early {
                         self.dialog.take().unwrap(),
                     );
 
-                    Ok(EarlyResponse::Success(session, response))
+                    return Ok(EarlyResponse::Success(session, response));
                 }
                 _ => unreachable!("initiator only forwards messages with 101..=299 status"),
-            },
-            EarlyEvent::Terminate => Ok(EarlyResponse::Terminated),
+            }
+        }
+    }
+
+    /// Send a PRACK for a reliable provisional response.
+    ///
+    /// Returns `false` if the response is a retransmission or out of order and must be discarded.
+    async fn acknowledge_reliable(
+        &mut self,
+        response: &TsxResponse,
+        rseq: u32,
+    ) -> Result<bool, Error> {
+        if let Some(last_rseq) = self.last_rseq {
+            if rseq != last_rseq.wrapping_add(1) {
+                return Ok(false);
+            }
         }
+
+        let dialog = self.dialog.as_ref().unwrap();
+
+        let prack = create_prack(dialog, response, rseq);
+        send_prack_detached(dialog, prack).await?;
+
+        self.last_rseq = Some(rseq);
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sip_core::transport::udp::Udp;
+    use sip_core::{IncomingRequest, Layer, MayTake};
+    use sip_types::header::typed::FromTo;
+    use sip_types::Code;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Peer which sends the success response to an INVITE twice and counts the received ACKs
+    #[derive(Default)]
+    struct Uas {
+        acks: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Layer for Uas {
+        fn name(&self) -> &'static str {
+            "test-uas"
+        }
+
+        async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+            if request.line.method == Method::ACK {
+                self.acks.fetch_add(1, Ordering::SeqCst);
+                return;
+            }
+
+            if request.line.method != Method::INVITE {
+                return;
+            }
+
+            let invite = request.take();
+
+            let mut response = endpoint.create_response(&invite, Code::OK, None);
+            response
+                .msg
+                .headers
+                .edit(Name::TO, |to: &mut FromTo| {
+                    to.tag = Some(BytesStr::from_static("uas-tag"))
+                })
+                .unwrap();
+            response
+                .msg
+                .headers
+                .insert_named(&Contact::new(NameAddr::uri(
+                    "sip:bob@127.0.0.1".parse::<SipUri>().unwrap(),
+                )));
+
+            for _ in 0..2 {
+                endpoint
+                    .send_outgoing_response(&mut response)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    async fn spawn_uas() -> (Endpoint, SocketAddr, Arc<AtomicUsize>) {
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+
+        let uas = Uas::default();
+        let acks = uas.acks.clone();
+        builder.add_layer(uas);
+
+        (builder.build(), transport.bound(), acks)
+    }
+
+    #[tokio::test]
+    async fn retransmitted_success_is_acknowledged() {
+        let (_uas, uas_addr, acks) = spawn_uas().await;
+
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let dialog_layer = builder.add_layer(DialogLayer::default());
+        let invite_layer = builder.add_layer(InviteLayer::default());
+        let endpoint = builder.build();
+
+        let id: SipUri = "sip:alice@example.com".parse().unwrap();
+        let contact: SipUri = format!("sip:alice@{}", transport.bound()).parse().unwrap();
+        let target: SipUri = format!("sip:bob@{uas_addr}").parse().unwrap();
+
+        let mut initiator = Initiator::new(
+            endpoint,
+            dialog_layer,
+            invite_layer,
+            NameAddr::uri(id),
+            Contact::new(NameAddr::uri(contact)),
+            Box::new(target),
+        );
+
+        let invite = initiator.create_invite();
+        initiator.send_invite(invite).await.unwrap();
+
+        let Response::Session(_session, _) = initiator.receive().await.unwrap() else {
+            panic!("expected session");
+        };
+
+        // The retransmission is acknowledged without creating another session
+        let receive = tokio::time::timeout(Duration::from_secs(1), initiator.receive()).await;
+        assert!(receive.is_err());
+
+        assert_eq!(acks.load(Ordering::SeqCst), 2);
     }
 }
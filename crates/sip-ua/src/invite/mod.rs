@@ -4,7 +4,7 @@ use parking_lot as pl;
 use prack::AwaitedPrack;
 use session::UsageEvent;
 use sip_core::transaction::consts::{T1, T2};
-use sip_core::transaction::{Accepted, ServerInvTsx, TsxKey};
+use sip_core::transaction::{Accepted, ServerInvTsx};
 use sip_core::transport::OutgoingRequest;
 use sip_core::{
     Endpoint, EndpointBuilder, Error, IncomingRequest, Layer, LayerKey, MayTake, Result,
@@ -19,10 +19,12 @@ use tokio::sync::{mpsc, oneshot, Mutex};
 use tokio::time::timeout;
 
 pub mod acceptor;
+pub mod dtmf;
 pub mod initiator;
 pub mod prack;
 pub mod session;
 mod timer;
+pub mod transfer;
 
 #[derive(Debug)]
 struct AwaitedAck {
@@ -135,6 +137,7 @@ impl Layer for InviteLayer {
         endpoint.add_allow(Method::ACK);
         endpoint.add_allow(Method::CANCEL);
         endpoint.add_allow(Method::PRACK);
+        endpoint.add_allow(Method::INFO);
 
         endpoint.add_supported("100rel");
         endpoint.add_supported("timer");
@@ -227,6 +230,19 @@ impl Usage for InviteUsage {
                     }
                 }
             }
+            Method::INFO => {
+                let state = self.inner.state.lock().await;
+
+                if let InviteSessionState::Established { evt_sink } = &*state {
+                    let info = request.inner().take().unwrap();
+
+                    if let Err(SendError(UsageEvent::Info(info))) =
+                        evt_sink.send(UsageEvent::Info(info)).await
+                    {
+                        *request.inner() = Some(info);
+                    }
+                }
+            }
             Method::ACK => {
                 let mut awaited_ack_opt = self.inner.awaited_ack.lock();
 
@@ -336,7 +352,7 @@ pub async fn create_ack(dialog: &Dialog, cseq_num: u32) -> Result<OutgoingReques
 
     // Create temporary transaction key to create Via, but never register it
     // as we don't need to receive responses
-    let tsx_key = TsxKey::client(&Method::ACK);
+    let tsx_key = dialog.endpoint.create_client_tsx_key(&Method::ACK);
     let via = dialog.endpoint.create_via(
         // wrap
         &ack.parts.transport,
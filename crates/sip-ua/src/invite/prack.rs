@@ -3,7 +3,7 @@ use crate::dialog::Dialog;
 use sip_core::transaction::TsxResponse;
 use sip_core::{Endpoint, IncomingRequest, MayTake, Request, Result};
 use sip_types::header::typed::{RAck, RSeq, Require};
-use sip_types::{Code, Method};
+use sip_types::{Code, CodeKind, Method};
 use tokio::sync::oneshot;
 
 #[derive(Debug)]
@@ -62,7 +62,7 @@ pub fn get_rseq(response: &TsxResponse) -> Option<RSeq> {
     None
 }
 
-pub fn create_prack(dialog: &Dialog, response: &mut TsxResponse, rack: u32) -> Request {
+pub fn create_prack(dialog: &Dialog, response: &TsxResponse, rack: u32) -> Request {
     let mut request = dialog.create_request(Method::PRACK);
 
     request.headers.insert_named(&RAck {
@@ -86,3 +86,28 @@ pub async fn send_prack(dialog: &Dialog, request: Request) -> Result<TsxResponse
 
     transaction.receive_final().await
 }
+
+/// Send a PRACK request without waiting for its final response.
+///
+/// The transaction is driven in the background, so receiving further responses
+/// of the INVITE is not blocked by the PRACK.
+pub(super) async fn send_prack_detached(dialog: &Dialog, request: Request) -> Result<()> {
+    let mut target_tp_info = dialog.target_tp_info.lock().await;
+
+    let mut transaction = dialog
+        .endpoint
+        .send_request(request, &mut target_tp_info)
+        .await?;
+
+    drop(target_tp_info);
+
+    tokio::spawn(async move {
+        match transaction.receive_final().await {
+            Ok(response) if response.line.code.kind() == CodeKind::Success => {}
+            Ok(response) => log::warn!("PRACK was rejected with {:?}", response.line.code),
+            Err(e) => log::warn!("Failed to send PRACK, {e}"),
+        }
+    });
+
+    Ok(())
+}
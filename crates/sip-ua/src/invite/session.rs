@@ -1,16 +1,33 @@
+use super::dtmf::{DtmfRelay, DTMF_RELAY_CONTENT_TYPE};
 use super::timer::SessionTimer;
 use super::Inner;
 use crate::dialog::{Dialog, UsageGuard};
 use crate::invite::AwaitedAck;
+use crate::util::glare_retry_delay;
 use sip_core::transaction::{ServerInvTsx, ServerTsx, TsxResponse};
 use sip_core::transport::OutgoingResponse;
-use sip_core::{Endpoint, IncomingRequest, Result};
-use sip_types::header::typed::Refresher;
+use sip_core::{Endpoint, Error, IncomingRequest, Request, Result};
+use sip_types::header::typed::{ContentType, Reason, Refresher};
 use sip_types::{Code, CodeKind, Method};
+use std::collections::VecDeque;
+use std::fmt;
 use std::sync::Arc;
 use tokio::select;
 use tokio::sync::mpsc::{self, Receiver};
 use tokio::sync::oneshot;
+use tokio::time::{sleep_until, Instant};
+
+/// Function creating a re-INVITE, called again for every retry of the re-INVITE
+type CreateReInvite = Box<dyn FnMut(&Dialog) -> Request + Send>;
+
+/// Re-INVITE which is sent once the peer's re-INVITE which took precedence has been answered
+struct QueuedReInvite(CreateReInvite);
+
+impl fmt::Debug for QueuedReInvite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueuedReInvite").finish_non_exhaustive()
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum Role {
@@ -28,6 +45,12 @@ pub struct Session {
     /// Receiver side of dialog-usage events
     usage_events: Receiver<UsageEvent>,
 
+    /// Usage events received while sending a re-INVITE, returned by the next calls to [`Session::drive`]
+    pending_events: VecDeque<UsageEvent>,
+
+    /// Re-INVITE postponed by [`Session::reinvite`] because of a re-INVITE of the peer, sent by [`Session::drive`]
+    queued_reinvite: Option<QueuedReInvite>,
+
     session_timer: SessionTimer,
 
     // drop usage before dialog
@@ -41,42 +64,10 @@ pub struct RefreshNeeded<'s> {
 
 impl RefreshNeeded<'_> {
     pub async fn process_default(self) -> Result<()> {
-        let invite = self.session.dialog.create_request(Method::INVITE);
-
-        let mut target_tp_info = self.session.dialog.target_tp_info.lock().await;
-
-        let mut transaction = self
-            .session
-            .endpoint
-            .send_invite(invite, &mut target_tp_info)
+        self.session
+            .reinvite(|dialog| dialog.create_request(Method::INVITE))
             .await?;
 
-        drop(target_tp_info);
-
-        let mut ack = None;
-
-        while let Some(response) = transaction.receive().await? {
-            match response.line.code.kind() {
-                CodeKind::Provisional => { /* ignore */ }
-                CodeKind::Success => {
-                    let ack = if let Some(ack) = &mut ack {
-                        ack
-                    } else {
-                        let ack_req = super::create_ack(
-                            &self.session.dialog,
-                            response.base_headers.cseq.cseq,
-                        )
-                        .await?;
-
-                        ack.insert(ack_req)
-                    };
-
-                    self.session.endpoint.send_outgoing_request(ack).await?;
-                }
-                _ => { /* TODO: how to correctly handle responses here */ }
-            }
-        }
-
         Ok(())
     }
 }
@@ -107,9 +98,17 @@ pub struct ByeEvent<'s> {
     pub session: &'s mut Session,
     pub bye: IncomingRequest,
     pub transaction: ServerTsx,
+
+    /// Reasons for the termination found in the BYE's Reason headers
+    pub reasons: Vec<Reason>,
 }
 
 impl ByeEvent<'_> {
+    /// Returns the Q.850 cause code of the termination, if the peer provided one
+    pub fn q850_cause(&self) -> Option<u16> {
+        self.reasons.iter().find_map(Reason::q850_cause)
+    }
+
     /// Process the BYE as one would expect, respond with a 200 OK
     pub async fn process_default(self) -> Result<()> {
         let response = self
@@ -121,11 +120,35 @@ impl ByeEvent<'_> {
     }
 }
 
+pub struct InfoEvent<'s> {
+    pub session: &'s mut Session,
+    pub info: IncomingRequest,
+    pub transaction: ServerTsx,
+
+    /// DTMF tone sent by the peer, if the INFO contains an `application/dtmf-relay` body
+    pub dtmf: Option<DtmfRelay>,
+}
+
+impl InfoEvent<'_> {
+    /// Process the INFO as one would expect, respond with a 200 OK
+    pub async fn process_default(self) -> Result<()> {
+        let response = self
+            .session
+            .dialog
+            .create_response(&self.info, Code::OK, None)?;
+
+        self.transaction.respond(response).await
+    }
+}
+
 #[allow(clippy::large_enum_variant)] // TODO address this
 pub enum Event<'s> {
     RefreshNeeded(RefreshNeeded<'s>),
     ReInviteReceived(ReInviteReceived<'s>),
     Bye(ByeEvent<'s>),
+    Info(InfoEvent<'s>),
+    /// Final response to a re-INVITE which was queued by [`Session::reinvite`]
+    ReInviteResponse(TsxResponse),
     Terminated,
 }
 
@@ -144,6 +167,8 @@ impl Session {
             inner,
             role,
             usage_events,
+            pending_events: VecDeque::new(),
+            queued_reinvite: None,
             session_timer,
             _usage_guard: usage_guard,
             dialog: Arc::new(dialog),
@@ -151,6 +176,19 @@ impl Session {
     }
 
     pub async fn drive(&mut self) -> Result<Event<'_>> {
+        // A queued re-INVITE is sent once the pending re-INVITE of the peer has been answered
+        if self.pending_events.is_empty() {
+            if let Some(QueuedReInvite(create_request)) = self.queued_reinvite.take() {
+                if let Some(response) = self.reinvite_boxed(create_request).await? {
+                    return Ok(Event::ReInviteResponse(response));
+                }
+            }
+        }
+
+        if let Some(evt) = self.pending_events.pop_front() {
+            return self.handle_usage_event(Some(evt));
+        }
+
         select! {
             _ = self.session_timer.wait() => {
                self.handle_session_timer().await
@@ -161,6 +199,153 @@ impl Session {
         }
     }
 
+    /// Send a re-INVITE created by `create_request` and return its final response. 2XX responses are acknowledged.
+    ///
+    /// If the re-INVITE collides with a re-INVITE of the peer and is rejected with `491 Request Pending`,
+    /// it is retried after the randomized interval of [RFC 3261 Section 14.1](https://datatracker.ietf.org/doc/html/rfc3261#section-14.1).
+    /// `create_request` is called again for every retry, so the offer can be created from the current session state.
+    ///
+    /// Re-INVITEs of the peer received while the request is pending are rejected with `491 Request Pending`.
+    /// If the peer's re-INVITE arrives while waiting to retry, it takes precedence: `None` is returned and the
+    /// peer's re-INVITE is returned by the next call to [`Session::drive`]. Once it has been answered, the
+    /// re-INVITE is sent by [`Session::drive`], which returns its final response as [`Event::ReInviteResponse`].
+    pub async fn reinvite<F>(&mut self, create_request: F) -> Result<Option<TsxResponse>>
+    where
+        F: FnMut(&Dialog) -> Request + Send + 'static,
+    {
+        self.reinvite_boxed(Box::new(create_request)).await
+    }
+
+    /// Discard the re-INVITE queued by [`Session::reinvite`], returns if there was one
+    pub fn cancel_queued_reinvite(&mut self) -> bool {
+        self.queued_reinvite.take().is_some()
+    }
+
+    async fn reinvite_boxed(
+        &mut self,
+        mut create_request: CreateReInvite,
+    ) -> Result<Option<TsxResponse>> {
+        loop {
+            if self.has_pending_reinvite() {
+                self.queued_reinvite = Some(QueuedReInvite(create_request));
+                return Ok(None);
+            }
+
+            let invite = create_request(&self.dialog);
+            let response = self.send_reinvite(invite).await?;
+
+            if response.line.code != Code::REQUEST_PENDING {
+                return Ok(Some(response));
+            }
+
+            let owns_call_id = matches!(self.role, Role::Uac);
+            let retry_at = Instant::now() + glare_retry_delay(owns_call_id);
+
+            self.wait_for_retry(retry_at).await;
+        }
+    }
+
+    /// Returns if a re-INVITE of the peer is waiting to be returned by [`Session::drive`]
+    fn has_pending_reinvite(&self) -> bool {
+        self.pending_events
+            .iter()
+            .any(|evt| matches!(evt, UsageEvent::ReInvite(..)))
+    }
+
+    /// Wait until `retry_at` while collecting usage events, stops early when the peer sends a re-INVITE
+    async fn wait_for_retry(&mut self, retry_at: Instant) {
+        loop {
+            select! {
+                _ = sleep_until(retry_at) => return,
+                evt = self.usage_events.recv() => {
+                    let Some(evt) = evt else {
+                        sleep_until(retry_at).await;
+                        return;
+                    };
+
+                    let is_reinvite = matches!(evt, UsageEvent::ReInvite(..));
+                    self.pending_events.push_back(evt);
+
+                    if is_reinvite {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn send_reinvite(&mut self, invite: Request) -> Result<TsxResponse> {
+        let mut target_tp_info = self.dialog.target_tp_info.lock().await;
+
+        let mut transaction = self
+            .endpoint
+            .send_invite(invite, &mut target_tp_info)
+            .await?;
+
+        drop(target_tp_info);
+
+        let mut usage_events_closed = false;
+
+        loop {
+            let response = select! {
+                response = transaction.receive() => response?,
+                evt = self.usage_events.recv(), if !usage_events_closed => {
+                    match evt {
+                        Some(UsageEvent::ReInvite(invite)) => self.reject_glare(invite)?,
+                        Some(evt) => self.pending_events.push_back(evt),
+                        None => usage_events_closed = true,
+                    }
+
+                    continue;
+                }
+            };
+
+            let Some(response) = response else {
+                return Err(Error::RequestTimedOut);
+            };
+
+            match response.line.code.kind() {
+                CodeKind::Provisional => {}
+                CodeKind::Success => {
+                    let mut ack =
+                        super::create_ack(&self.dialog, response.base_headers.cseq.cseq).await?;
+                    self.endpoint.send_outgoing_request(&mut ack).await?;
+
+                    let endpoint = self.endpoint.clone();
+
+                    // Acknowledge retransmissions of the 2XX response
+                    tokio::spawn(async move {
+                        while let Ok(Some(_)) = transaction.receive().await {
+                            if let Err(e) = endpoint.send_outgoing_request(&mut ack).await {
+                                log::warn!("Failed to retransmit ACK, {e}");
+                            }
+                        }
+                    });
+
+                    return Ok(response);
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    /// Respond to a re-INVITE received while our own re-INVITE is pending
+    /// ([RFC 3261 Section 14.2](https://datatracker.ietf.org/doc/html/rfc3261#section-14.2))
+    fn reject_glare(&mut self, mut invite: IncomingRequest) -> Result<()> {
+        let transaction = self.endpoint.create_server_inv_tsx(&mut invite);
+        let response = self
+            .dialog
+            .create_response(&invite, Code::REQUEST_PENDING, None)?;
+
+        tokio::spawn(async move {
+            if let Err(e) = transaction.respond_failure(response).await {
+                log::warn!("Failed to respond to colliding re-INVITE, {e}");
+            }
+        });
+
+        Ok(())
+    }
+
     pub async fn terminate(&mut self) -> Result<TsxResponse> {
         let mut state = self.inner.state.lock().await;
         state.set_terminated();
@@ -179,6 +364,29 @@ impl Session {
         transaction.receive_final().await
     }
 
+    /// Send a DTMF tone using an INFO request with an `application/dtmf-relay` body
+    ///
+    /// Should only be used when no telephone-event payload has been negotiated for the media session.
+    pub async fn send_dtmf_info(&self, dtmf: DtmfRelay) -> Result<TsxResponse> {
+        let mut request = self.dialog.create_request(Method::INFO);
+
+        request
+            .headers
+            .insert_named(&ContentType(DTMF_RELAY_CONTENT_TYPE.into()));
+        request.body = dtmf.to_body();
+
+        let mut target_tp_info = self.dialog.target_tp_info.lock().await;
+
+        let mut transaction = self
+            .endpoint
+            .send_request(request, &mut target_tp_info)
+            .await?;
+
+        drop(target_tp_info);
+
+        transaction.receive_final().await
+    }
+
     fn handle_usage_event(&mut self, evt: Option<UsageEvent>) -> Result<Event<'_>> {
         let evt = if let Some(evt) = evt {
             evt
@@ -192,10 +400,45 @@ impl Session {
             UsageEvent::Bye(mut request) => {
                 let transaction = self.endpoint.create_server_tsx(&mut request);
 
+                let reasons = match request.headers.try_get_named::<Vec<Reason>>() {
+                    Some(Ok(reasons)) => reasons,
+                    Some(Err(e)) => {
+                        log::warn!("Failed to parse Reason header of BYE, {e}");
+                        vec![]
+                    }
+                    None => vec![],
+                };
+
                 Ok(Event::Bye(ByeEvent {
                     session: self,
                     bye: request,
                     transaction,
+                    reasons,
+                }))
+            }
+            UsageEvent::Info(mut info) => {
+                let transaction = self.endpoint.create_server_tsx(&mut info);
+
+                let is_dtmf_relay =
+                    info.headers
+                        .get_named::<ContentType>()
+                        .is_ok_and(|content_type| {
+                            content_type
+                                .media_type()
+                                .eq_ignore_ascii_case(DTMF_RELAY_CONTENT_TYPE)
+                        });
+
+                let dtmf = if is_dtmf_relay {
+                    DtmfRelay::parse(&info.body)
+                } else {
+                    None
+                };
+
+                Ok(Event::Info(InfoEvent {
+                    session: self,
+                    info,
+                    transaction,
+                    dtmf,
                 }))
             }
             UsageEvent::ReInvite(mut invite) => {
@@ -232,7 +475,196 @@ impl Session {
     }
 }
 
+#[derive(Debug)]
 pub(super) enum UsageEvent {
     ReInvite(IncomingRequest),
     Bye(IncomingRequest),
+    Info(IncomingRequest),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dialog::DialogLayer;
+    use crate::invite::acceptor::Acceptor;
+    use crate::invite::initiator::{Initiator, Response};
+    use crate::invite::InviteLayer;
+    use sip_core::transport::udp::Udp;
+    use sip_core::{Layer, LayerKey, MayTake};
+    use sip_types::header::typed::Contact;
+    use sip_types::uri::sip::SipUri;
+    use sip_types::uri::NameAddr;
+    use std::time::Duration;
+
+    /// Accepts incoming INVITEs and hands the created sessions to the test
+    struct Accept {
+        dialog_layer: LayerKey<DialogLayer>,
+        invite_layer: LayerKey<InviteLayer>,
+        contact: Contact,
+        sessions: mpsc::Sender<Session>,
+    }
+
+    #[async_trait::async_trait]
+    impl Layer for Accept {
+        fn name(&self) -> &'static str {
+            "test-accept"
+        }
+
+        async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+            if request.line.method != Method::INVITE {
+                return;
+            }
+
+            let invite = request.take();
+
+            let dialog = Dialog::new_server(
+                endpoint.clone(),
+                self.dialog_layer,
+                &invite,
+                self.contact.clone(),
+            )
+            .unwrap();
+
+            let acceptor = Acceptor::new(dialog, self.invite_layer, invite).unwrap();
+            let response = acceptor.create_response(Code::OK, None).await.unwrap();
+            let (session, _ack) = acceptor.respond_success(response).await.unwrap();
+
+            self.sessions.send(session).await.unwrap();
+        }
+    }
+
+    /// Establish a session between two endpoints, returns the sessions of the UAC and UAS
+    async fn establish() -> (Session, Session) {
+        let (sessions, mut accepted) = mpsc::channel(1);
+
+        let mut builder = Endpoint::builder();
+        let uas_transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let dialog_layer = builder.add_layer(DialogLayer::default());
+        let invite_layer = builder.add_layer(InviteLayer::default());
+        let contact: SipUri = format!("sip:bob@{}", uas_transport.bound())
+            .parse()
+            .unwrap();
+        builder.add_layer(Accept {
+            dialog_layer,
+            invite_layer,
+            contact: Contact::new(NameAddr::uri(contact)),
+            sessions,
+        });
+        let _uas = builder.build();
+
+        let mut builder = Endpoint::builder();
+        let uac_transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let dialog_layer = builder.add_layer(DialogLayer::default());
+        let invite_layer = builder.add_layer(InviteLayer::default());
+        let endpoint = builder.build();
+
+        let id: SipUri = "sip:alice@example.com".parse().unwrap();
+        let contact: SipUri = format!("sip:alice@{}", uac_transport.bound())
+            .parse()
+            .unwrap();
+        let target: SipUri = format!("sip:bob@{}", uas_transport.bound())
+            .parse()
+            .unwrap();
+
+        let mut initiator = Initiator::new(
+            endpoint,
+            dialog_layer,
+            invite_layer,
+            NameAddr::uri(id),
+            Contact::new(NameAddr::uri(contact)),
+            Box::new(target),
+        );
+
+        let invite = initiator.create_invite();
+        initiator.send_invite(invite).await.unwrap();
+
+        let Response::Session(uac, _) = initiator.receive().await.unwrap() else {
+            panic!("expected session");
+        };
+
+        (uac, accepted.recv().await.unwrap())
+    }
+
+    /// Respond to the next re-INVITE received by `session` with `code`
+    async fn respond_reinvite(session: &mut Session, code: Code) {
+        let Event::ReInviteReceived(event) = session.drive().await.unwrap() else {
+            panic!("expected re-INVITE");
+        };
+
+        let response = event
+            .session
+            .dialog
+            .create_response(&event.invite, code, None)
+            .unwrap();
+
+        if code.kind() == CodeKind::Success {
+            event.respond_success(response).await.unwrap();
+        } else {
+            event.transaction.respond_failure(response).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn reinvite_is_retried_after_491() {
+        let (mut uac, mut uas) = establish().await;
+
+        let peer = tokio::spawn(async move {
+            respond_reinvite(&mut uac, Code::REQUEST_PENDING).await;
+            respond_reinvite(&mut uac, Code::OK).await;
+            uac
+        });
+
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            uas.reinvite(|dialog| dialog.create_request(Method::INVITE)),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(response.line.code, Code::OK);
+        peer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reinvite_of_peer_takes_precedence() {
+        let (mut uac, mut uas) = establish().await;
+
+        let peer = tokio::spawn(async move {
+            respond_reinvite(&mut uas, Code::REQUEST_PENDING).await;
+
+            // Send a re-INVITE while the UAC waits to retry
+            let response = uas
+                .reinvite(|dialog| dialog.create_request(Method::INVITE))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(response.line.code, Code::OK);
+
+            respond_reinvite(&mut uas, Code::OK).await;
+            uas
+        });
+
+        // The re-INVITE is queued until the peer's re-INVITE has been answered
+        let response = uac
+            .reinvite(|dialog| dialog.create_request(Method::INVITE))
+            .await
+            .unwrap();
+        assert!(response.is_none());
+
+        respond_reinvite(&mut uac, Code::OK).await;
+
+        let event = tokio::time::timeout(Duration::from_secs(5), uac.drive())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let Event::ReInviteResponse(response) = event else {
+            panic!("expected response to the queued re-INVITE");
+        };
+
+        assert_eq!(response.line.code, Code::OK);
+        peer.await.unwrap();
+    }
 }
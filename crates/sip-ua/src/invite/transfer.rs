@@ -0,0 +1,273 @@
+//! Call transfer using REFER requests ([RFC 5589](https://datatracker.ietf.org/doc/html/rfc5589))
+
+use super::session::Session;
+use crate::dialog::Dialog;
+use crate::subscription::refer::Refer;
+use crate::subscription::{Subscription, SubscriptionEvent};
+use sip_core::transaction::TsxResponse;
+use sip_core::{Error, Result};
+use sip_types::header::typed::{CSeq, ReferTo, Replaces};
+use sip_types::header::HeaderError;
+use sip_types::msg::StatusLine;
+use sip_types::uri::params::Param;
+use sip_types::uri::sip::SipUri;
+use sip_types::uri::NameAddr;
+use sip_types::{Code, CodeKind, Method, Name};
+use std::time::Duration;
+
+/// Duration requested when refreshing the implicit subscription of a transfer
+const REFER_EXPIRES: Duration = Duration::from_secs(60);
+
+/// Result of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferOutcome {
+    /// The transferee successfully contacted the transfer target
+    Succeeded,
+
+    /// The REFER or the request of the transferee to the transfer target was rejected with the given code
+    Failed(Code),
+
+    /// The subscription was terminated before the transferee reported a final response
+    Unknown,
+}
+
+/// A transfer started using [`Session::transfer_blind`] or [`Session::transfer_attended`]
+#[derive(Debug)]
+pub struct Transfer {
+    /// Final response to the REFER request
+    pub response: TsxResponse,
+
+    subscription: Option<Subscription<Refer>>,
+}
+
+impl Transfer {
+    /// Implicit subscription reporting the progress of the transfer, `None` if the REFER was rejected
+    pub fn subscription(&mut self) -> Option<&mut Subscription<Refer>> {
+        self.subscription.as_mut()
+    }
+
+    /// Wait for the final response the transferee received from the transfer target
+    ///
+    /// Processes the NOTIFY requests of the subscription until one reports a final response or
+    /// the subscription is terminated.
+    pub async fn outcome(&mut self) -> Result<TransferOutcome> {
+        let Some(subscription) = &mut self.subscription else {
+            return Ok(TransferOutcome::Failed(self.response.line.code));
+        };
+
+        loop {
+            if let Some(outcome) = event_outcome(subscription.receive().await?) {
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+/// Outcome reported by an event of the implicit subscription, `None` while the transfer is in progress
+fn event_outcome(event: SubscriptionEvent<StatusLine>) -> Option<TransferOutcome> {
+    match event {
+        SubscriptionEvent::Notify {
+            notification: Some(status),
+            ..
+        } => match status.code.kind() {
+            CodeKind::Provisional => None,
+            CodeKind::Success => Some(TransferOutcome::Succeeded),
+            _ => Some(TransferOutcome::Failed(status.code)),
+        },
+        SubscriptionEvent::Notify { .. } => None,
+        SubscriptionEvent::Terminated(_) => Some(TransferOutcome::Unknown),
+    }
+}
+
+/// Remote target of `dialog` with a `Replaces` header parameter identifying `dialog`
+/// as seen by its peer ([RFC 3891 Section 3](https://datatracker.ietf.org/doc/html/rfc3891#section-3))
+fn replacing_target(dialog: &Dialog) -> Result<SipUri> {
+    let Some(target) = dialog.peer_contact.uri.uri.downcast_ref::<SipUri>() else {
+        return Err(Error::Header(HeaderError::malformed_adhoc(
+            Name::CONTACT,
+            "transfer target is not a SIP URI",
+        )));
+    };
+
+    let replaces = Replaces {
+        call_id: dialog.call_id.0.clone(),
+        from_tag: dialog.local_fromto.tag.clone().unwrap_or_default(),
+        to_tag: dialog.peer_fromto.tag.clone().unwrap_or_default(),
+        early_only: false,
+    };
+
+    let mut target = target.clone();
+    target
+        .header_params
+        .push(Param::value("Replaces", replaces.to_string()));
+
+    Ok(target)
+}
+
+impl Session {
+    /// Ask the peer to call `target` (blind transfer,
+    /// [RFC 5589 Section 6](https://datatracker.ietf.org/doc/html/rfc5589#section-6))
+    ///
+    /// The session stays active, it should be terminated once the transfer succeeded.
+    pub async fn transfer_blind(&self, target: NameAddr) -> Result<Transfer> {
+        self.send_refer(ReferTo(target)).await
+    }
+
+    /// Ask the peer to call the peer of `other`, replacing the dialog of `other` (attended transfer,
+    /// [RFC 5589 Section 7](https://datatracker.ietf.org/doc/html/rfc5589#section-7))
+    ///
+    /// The transfer target is addressed using the remote target of `other`, which must be a SIP URI.
+    /// Both sessions stay active, they should be terminated once the transfer succeeded.
+    pub async fn transfer_attended(&self, other: &Session) -> Result<Transfer> {
+        let target = replacing_target(&other.dialog)?;
+
+        self.send_refer(ReferTo(NameAddr::uri(target))).await
+    }
+
+    async fn send_refer(&self, refer_to: ReferTo) -> Result<Transfer> {
+        let mut request = self.dialog.create_request(Method::REFER);
+        request.headers.insert_named(&refer_to);
+
+        let cseq = request.headers.get_named::<CSeq>()?.cseq;
+
+        // Listen for NOTIFY requests before sending the REFER, they may arrive before its response
+        let subscription = Subscription::implicit(
+            self.endpoint.clone(),
+            Refer,
+            self.dialog.clone(),
+            cseq,
+            REFER_EXPIRES,
+        );
+
+        let mut target_tp_info = self.dialog.target_tp_info.lock().await;
+
+        let mut transaction = self
+            .endpoint
+            .send_request(request, &mut target_tp_info)
+            .await?;
+
+        drop(target_tp_info);
+
+        let response = transaction.receive_final().await?;
+
+        let subscription = (response.line.code.kind() == CodeKind::Success).then_some(subscription);
+
+        Ok(Transfer {
+            response,
+            subscription,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dialog::DialogLayer;
+    use crate::subscription::TerminationReason;
+    use sip_core::Endpoint;
+    use sip_types::header::typed::{
+        CallID, Contact, EventReasonValue, FromTo, SubStateValue, SubscriptionState,
+    };
+    use sip_types::Headers;
+    use std::sync::atomic::AtomicU32;
+    use tokio::sync::Mutex;
+
+    fn notify(code: Code) -> SubscriptionEvent<StatusLine> {
+        SubscriptionEvent::Notify {
+            state: SubscriptionState::new(SubStateValue::Active),
+            notification: Some(StatusLine { code, reason: None }),
+        }
+    }
+
+    #[test]
+    fn outcome_of_notifications() {
+        assert_eq!(event_outcome(notify(Code::TRYING)), None);
+        assert_eq!(event_outcome(notify(Code::RINGING)), None);
+        assert_eq!(
+            event_outcome(notify(Code::OK)),
+            Some(TransferOutcome::Succeeded)
+        );
+        assert_eq!(
+            event_outcome(notify(Code::BUSY_HERE)),
+            Some(TransferOutcome::Failed(Code::BUSY_HERE))
+        );
+    }
+
+    #[test]
+    fn outcome_of_termination() {
+        let notify_without_body = SubscriptionEvent::Notify {
+            state: SubscriptionState::new(SubStateValue::Active),
+            notification: None,
+        };
+
+        assert_eq!(event_outcome(notify_without_body), None);
+
+        let terminated = SubscriptionEvent::Terminated(TerminationReason::Notifier {
+            reason: Some(EventReasonValue::NoResource),
+            retry_after: None,
+        });
+
+        assert_eq!(event_outcome(terminated), Some(TransferOutcome::Unknown));
+    }
+
+    fn dialog(peer_contact: &str) -> Dialog {
+        let mut builder = Endpoint::builder();
+        let dialog_layer = builder.add_layer(DialogLayer::default());
+        let endpoint = builder.build();
+
+        Dialog {
+            endpoint,
+            dialog_layer,
+            local_cseq: AtomicU32::new(1),
+            local_fromto: FromTo::new(
+                NameAddr::uri("sip:alice@example.org".parse::<SipUri>().unwrap()),
+                Some("local-tag".into()),
+            ),
+            peer_fromto: FromTo::new(
+                NameAddr::uri("sip:carol@example.org".parse::<SipUri>().unwrap()),
+                Some("peer-tag".into()),
+            ),
+            local_contact: Contact::new(NameAddr::uri(
+                "sip:alice@192.0.2.1".parse::<SipUri>().unwrap(),
+            )),
+            peer_contact: Contact::new(NameAddr::uri(peer_contact.parse::<SipUri>().unwrap())),
+            call_id: CallID::new("abc@192.0.2.1"),
+            route_set: vec![],
+            secure: false,
+            target_tp_info: Mutex::default(),
+            request_hook: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn replaces_identifies_dialog_of_peer() {
+        let dialog = dialog("sip:carol@192.0.2.3");
+
+        let target = replacing_target(&dialog).unwrap();
+
+        let mut headers = Headers::new();
+        headers.insert(
+            Name::REPLACES,
+            target.header_params.get_val("Replaces").unwrap().clone(),
+        );
+        let replaces: Replaces = headers.get_named().unwrap();
+
+        assert_eq!(replaces.call_id, "abc@192.0.2.1");
+        assert_eq!(replaces.to_tag, "peer-tag");
+        assert_eq!(replaces.from_tag, "local-tag");
+        assert!(!replaces.early_only);
+    }
+
+    #[tokio::test]
+    async fn replaces_is_escaped_in_refer_to() {
+        let dialog = dialog("sip:carol@192.0.2.3");
+
+        let mut headers = Headers::new();
+        headers.insert_named(&ReferTo(NameAddr::uri(replacing_target(&dialog).unwrap())));
+
+        assert_eq!(
+            headers.to_string(),
+            "Refer-To: <sip:carol@192.0.2.3?Replaces=abc%40192.0.2.1%3Bfrom-tag%3Dlocal-tag%3Bto-tag%3Dpeer-tag>\r\n"
+        );
+    }
+}
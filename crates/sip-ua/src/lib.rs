@@ -1,4 +1,9 @@
+pub mod b2bua;
 pub mod dialog;
 pub mod invite;
+pub mod message;
+pub mod redirect;
 pub mod register;
+pub mod registrar;
+pub mod subscription;
 pub mod util;
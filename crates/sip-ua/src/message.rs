@@ -0,0 +1,148 @@
+//! Pager-mode instant messages using MESSAGE requests ([RFC 3428](https://datatracker.ietf.org/doc/html/rfc3428))
+
+use crate::util::random_sequence_number;
+use bytes::Bytes;
+use bytesstr::BytesStr;
+use sip_core::transaction::TsxResponse;
+use sip_core::transport::TargetTransportInfo;
+use sip_core::{Endpoint, EndpointBuilder, IncomingRequest, Layer, MayTake, Request, Result};
+use sip_types::header::typed::{CSeq, ContentType, FromTo, MaxForwards};
+use sip_types::uri::NameAddr;
+use sip_types::{Code, CodeKind, Method, Name};
+
+/// Outcome of sending a MESSAGE request, derived from its final response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryReport {
+    /// The message was accepted (2xx). `202 Accepted` means that it was accepted
+    /// for later delivery, e.g. by a store-and-forward server.
+    Delivered { code: Code },
+
+    /// The message was rejected or could not be delivered (3xx-6xx)
+    Failed { code: Code },
+}
+
+impl DeliveryReport {
+    pub fn from_response(response: &TsxResponse) -> Self {
+        let code = response.line.code;
+
+        if code.kind() == CodeKind::Success {
+            Self::Delivered { code }
+        } else {
+            Self::Failed { code }
+        }
+    }
+
+    pub fn is_delivered(&self) -> bool {
+        matches!(self, Self::Delivered { .. })
+    }
+}
+
+/// Create a MESSAGE request outside of a dialog
+///
+/// Authorization headers can be added to the returned request before sending it using [`send_message`].
+pub fn create_message(
+    endpoint: &Endpoint,
+    from: NameAddr,
+    to: NameAddr,
+    content_type: BytesStr,
+    body: Bytes,
+) -> Request {
+    let mut request = Request::new(Method::MESSAGE, to.uri.clone());
+
+    request.headers.insert_named(&MaxForwards(70));
+    request.headers.insert_type(
+        Name::FROM,
+        &FromTo::new(from, Some(endpoint.generate_tag())),
+    );
+    request
+        .headers
+        .insert_type(Name::TO, &FromTo::new(to, None));
+    request.headers.insert_named(&endpoint.generate_call_id());
+    request
+        .headers
+        .insert_named(&CSeq::new(random_sequence_number(), Method::MESSAGE));
+    request.headers.insert_named(&ContentType(content_type));
+
+    endpoint.add_outbound_proxy_route(&mut request);
+
+    request.body = body;
+    request
+}
+
+/// Send a MESSAGE request and wait for the delivery report
+pub async fn send_message(endpoint: &Endpoint, request: Request) -> Result<DeliveryReport> {
+    let mut target = TargetTransportInfo::default();
+
+    let mut transaction = endpoint.send_request(request, &mut target).await?;
+    let response = transaction.receive_final().await?;
+
+    Ok(DeliveryReport::from_response(&response))
+}
+
+/// MESSAGE request received outside of a dialog
+#[derive(Debug, Clone)]
+pub struct ReceivedMessage {
+    pub from: FromTo,
+    pub to: FromTo,
+    pub content_type: Option<BytesStr>,
+    pub body: Bytes,
+}
+
+type MessageHandler = Box<dyn Fn(ReceivedMessage) -> Code + Send + Sync>;
+
+/// Layer which receives MESSAGE requests outside of dialogs and passes them to an application callback
+///
+/// The request is answered with the status code returned by the callback, which must be a final code
+/// (e.g. `200 OK` or `415 Unsupported Media Type`).
+pub struct MessageLayer {
+    handler: MessageHandler,
+}
+
+impl MessageLayer {
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(ReceivedMessage) -> Code + Send + Sync + 'static,
+    {
+        Self {
+            handler: Box::new(handler),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Layer for MessageLayer {
+    fn name(&self) -> &'static str {
+        "message"
+    }
+
+    fn init(&mut self, endpoint: &mut EndpointBuilder) {
+        endpoint.add_allow(Method::MESSAGE);
+    }
+
+    async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+        // Requests inside dialogs are handled by their dialog usages
+        if request.line.method != Method::MESSAGE || request.base_headers.to.tag.is_some() {
+            return;
+        }
+
+        let mut request = request.take();
+        let transaction = endpoint.create_server_tsx(&mut request);
+
+        let code = (self.handler)(ReceivedMessage {
+            from: request.base_headers.from.clone(),
+            to: request.base_headers.to.clone(),
+            content_type: request
+                .headers
+                .get_named::<ContentType>()
+                .ok()
+                .map(|content_type| content_type.0),
+            body: request.body.clone(),
+        });
+
+        let response = endpoint.create_response(&request, code, None);
+
+        if let Err(e) = transaction.respond(response).await {
+            log::warn!("Failed to respond to MESSAGE request, {e}");
+        }
+    }
+}
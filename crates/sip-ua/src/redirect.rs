@@ -0,0 +1,227 @@
+use sip_core::transaction::TsxResponse;
+use sip_types::header::typed::Contact;
+use sip_types::uri::sip::SipUri;
+use sip_types::uri::tel::TelUri;
+use sip_types::uri::Uri;
+use sip_types::{Code, Headers};
+
+/// Policy deciding which 3xx redirect responses are followed
+#[derive(Debug, Clone)]
+pub struct RedirectPolicy {
+    /// Maximum number of redirects followed for a single request. Defaults to 5
+    pub max_redirects: u32,
+
+    /// URI schemes of targets which may be redirected to. Defaults to `sip` and `sips`
+    pub allowed_schemes: Vec<&'static str>,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max_redirects: 5,
+            allowed_schemes: vec!["sip", "sips"],
+        }
+    }
+}
+
+impl RedirectPolicy {
+    /// Policy which never follows redirects
+    pub fn none() -> Self {
+        Self {
+            max_redirects: 0,
+            allowed_schemes: vec![],
+        }
+    }
+
+    /// Returns the target to redirect to, if the `response` is a redirect which may be followed
+    /// after `redirects` redirects have already been followed.
+    ///
+    /// Uses the allowed Contact with the highest q-value.
+    pub fn target(&self, response: &TsxResponse, redirects: u32) -> Option<Box<dyn Uri>> {
+        self.select_target(response.line.code, &response.headers, redirects)
+    }
+
+    fn select_target(&self, code: Code, headers: &Headers, redirects: u32) -> Option<Box<dyn Uri>> {
+        if redirects >= self.max_redirects {
+            return None;
+        }
+
+        if !matches!(
+            code,
+            Code::MULTIPLE_CHOICES | Code::MOVED_PERMANENTLY | Code::MOVED_TEMPORARILY
+        ) {
+            return None;
+        }
+
+        let contacts = headers.get_named::<Vec<Contact>>().unwrap_or_default();
+
+        contacts
+            .into_iter()
+            .filter(|contact| {
+                uri_scheme(&*contact.uri.uri)
+                    .is_some_and(|scheme| self.allowed_schemes.contains(&scheme))
+            })
+            // Keep the first contact when multiple contacts have the same q-value
+            .rev()
+            .max_by(|a, b| q_value(a).total_cmp(&q_value(b)))
+            .map(|contact| contact.uri.uri)
+    }
+}
+
+fn uri_scheme(uri: &dyn Uri) -> Option<&'static str> {
+    if let Some(sip_uri) = uri.downcast_ref::<SipUri>() {
+        Some(if sip_uri.sips { "sips" } else { "sip" })
+    } else if uri.downcast_ref::<TelUri>().is_some() {
+        Some("tel")
+    } else {
+        None
+    }
+}
+
+fn q_value(contact: &Contact) -> f32 {
+    contact
+        .params
+        .get_val("q")
+        .and_then(|q| q.parse().ok())
+        .unwrap_or(1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sip_types::uri::sip::UserPart;
+    use sip_types::Name;
+
+    fn contacts(contacts: &[&str]) -> Headers {
+        let mut headers = Headers::new();
+
+        for contact in contacts {
+            headers.insert(Name::CONTACT, *contact);
+        }
+
+        headers
+    }
+
+    /// Scheme and user of the selected target
+    fn target(policy: &RedirectPolicy, code: Code, contacts_: &[&str]) -> Option<String> {
+        let uri = policy.select_target(code, &contacts(contacts_), 0)?;
+        let sip_uri = uri.downcast_ref::<SipUri>().unwrap();
+
+        let UserPart::User(user) = &sip_uri.user_part else {
+            panic!("target without user");
+        };
+
+        Some(format!("{}:{user}", uri_scheme(&*uri).unwrap()))
+    }
+
+    #[test]
+    fn highest_q_value_wins() {
+        let target = target(
+            &RedirectPolicy::default(),
+            Code::MOVED_TEMPORARILY,
+            &[
+                "<sip:a@example.com>;q=0.5",
+                "<sip:b@example.com>;q=0.9",
+                "<sip:c@example.com>;q=0.1",
+            ],
+        );
+
+        assert_eq!(target, Some("sip:b".into()));
+    }
+
+    #[test]
+    fn first_contact_wins_on_equal_q_value() {
+        let policy = RedirectPolicy::default();
+
+        let target_ = target(
+            &policy,
+            Code::MULTIPLE_CHOICES,
+            &[
+                "<sip:a@example.com>;q=0.5",
+                "<sip:b@example.com>;q=0.7",
+                "<sip:c@example.com>;q=0.7",
+            ],
+        );
+        assert_eq!(target_, Some("sip:b".into()));
+
+        // Contacts without q-value have the highest priority
+        let target_ = target(
+            &policy,
+            Code::MOVED_PERMANENTLY,
+            &["<sip:a@example.com>", "<sip:b@example.com>"],
+        );
+        assert_eq!(target_, Some("sip:a".into()));
+    }
+
+    #[test]
+    fn disallowed_schemes_are_filtered() {
+        let contacts_ = [
+            "<tel:+4930123456>;q=1.0",
+            "<sips:b@example.com>;q=0.3",
+            "<sip:c@example.com>;q=0.2",
+        ];
+
+        let target_ = target(
+            &RedirectPolicy::default(),
+            Code::MOVED_TEMPORARILY,
+            &contacts_,
+        );
+        assert_eq!(target_, Some("sips:b".into()));
+
+        let policy = RedirectPolicy {
+            allowed_schemes: vec!["sip"],
+            ..RedirectPolicy::default()
+        };
+        let target_ = target(&policy, Code::MOVED_TEMPORARILY, &contacts_);
+        assert_eq!(target_, Some("sip:c".into()));
+
+        let policy = RedirectPolicy {
+            allowed_schemes: vec!["tel"],
+            ..RedirectPolicy::default()
+        };
+        let target_ = policy
+            .select_target(Code::MOVED_TEMPORARILY, &contacts(&contacts_), 0)
+            .unwrap();
+        assert!(target_.downcast_ref::<TelUri>().is_some());
+
+        assert_eq!(
+            target(&RedirectPolicy::none(), Code::MOVED_TEMPORARILY, &contacts_),
+            None
+        );
+    }
+
+    #[test]
+    fn max_redirects_is_honoured() {
+        let policy = RedirectPolicy {
+            max_redirects: 2,
+            ..RedirectPolicy::default()
+        };
+        let headers = contacts(&["<sip:a@example.com>"]);
+
+        assert!(policy
+            .select_target(Code::MOVED_TEMPORARILY, &headers, 1)
+            .is_some());
+        assert!(policy
+            .select_target(Code::MOVED_TEMPORARILY, &headers, 2)
+            .is_none());
+    }
+
+    #[test]
+    fn other_codes_are_ignored() {
+        let policy = RedirectPolicy::default();
+        let contacts_ = ["<sip:a@example.com>"];
+
+        for code in [
+            Code::OK,
+            Code::USE_PROXY,
+            Code::ALTERNATIVE_SERVICE,
+            Code::NOT_FOUND,
+            Code::SERVICE_UNAVAILABLE,
+        ] {
+            assert_eq!(target(&policy, code, &contacts_), None, "{code:?}");
+        }
+
+        // Redirects without usable contacts cannot be followed
+        assert_eq!(target(&policy, Code::MOVED_TEMPORARILY, &[]), None);
+    }
+}
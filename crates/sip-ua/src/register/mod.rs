@@ -1,15 +1,55 @@
-use crate::util::{random_sequence_number, random_string};
+use crate::redirect::RedirectPolicy;
+use crate::util::{random_sequence_number, retry_after, RequestHook};
+use bytesstr::BytesStr;
+use rand::Rng;
 use sip_core::transaction::TsxResponse;
-use sip_core::Request;
-use sip_types::header::typed::{CSeq, CallID, Contact, Expires, FromTo, MinExpires};
+use sip_core::transport::TargetTransportInfo;
+use sip_core::{Endpoint, Error, Request, Result};
+use sip_types::header::typed::{
+    CSeq, CallID, Contact, Expires, FromTo, MinExpires, Routing, Supported, Via,
+};
+use sip_types::uri::params::Param;
+use sip_types::uri::sip::SipUri;
 use sip_types::uri::{NameAddr, Uri};
-use sip_types::{CodeKind, Method, Name};
+use sip_types::{Code, CodeKind, Method, Name};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
 use std::time::Duration;
-use tokio::time::{interval_at, Instant, Interval};
+use tokio::sync::watch;
+use tokio::time::{interval_at, sleep, Instant, Interval};
+
+/// Backoff after the first failed registration attempt, doubled with each following failure
+const MIN_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Upper limit of the backoff between failed registration attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Base and upper limit of the backoff after an outbound flow failed (RFC5626 Section 4.5)
+const FLOW_MIN_BACKOFF: Duration = Duration::from_secs(30);
+const FLOW_MAX_BACKOFF: Duration = Duration::from_secs(1800);
+
+/// State of a [`Registration`], see [`Registration::subscribe`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistrationState {
+    /// No binding has been registered yet, or it was removed
+    Unregistered,
+
+    /// The binding is registered with the registrar
+    Registered { expires: Duration },
+
+    /// The last registration attempts failed, the registration will be retried after a backoff
+    Failed { failures: u32 },
+}
 
 pub struct Registration {
     registrar: Box<dyn Uri>,
 
+    /// Registrars to fail over to when all targets of the current registrar failed
+    backup_registrars: VecDeque<Box<dyn Uri>>,
+
+    /// Route of the endpoint's outbound proxy, all requests are sent to it instead of the registrar
+    outbound_proxy: Option<Routing>,
+
     to: FromTo,
     from: FromTo,
 
@@ -22,20 +62,361 @@ pub struct Registration {
 
     /// Re-registration interval, is set to `expires - 10`
     register_interval: Interval,
+
+    /// Transport and address the registrar is currently reached with
+    target: TargetTransportInfo,
+
+    /// Registrar addresses which failed to respond since the last successful request
+    failed_targets: Vec<SocketAddr>,
+
+    /// Public address learned from the registrar's `received`/`rport` Via parameters
+    public_address: Option<SocketAddr>,
+
+    /// Contact was changed to the public address and must be registered
+    contact_changed: bool,
+
+    /// Backoff requested by the registrar using the Retry-After header of an error response
+    retry_after: Option<Duration>,
+
+    /// Policy used to follow 3xx responses of the registrar
+    redirect_policy: RedirectPolicy,
+
+    /// Number of consecutive failed registration attempts
+    failures: u32,
+
+    /// The binding is registered as outbound flow (RFC5626)
+    outbound: bool,
+
+    state: watch::Sender<RegistrationState>,
+
+    request_hook: Option<RequestHook>,
 }
 
 impl Registration {
-    pub fn new(id: NameAddr, contact: NameAddr, registrar: Box<dyn Uri>, expiry: Duration) -> Self {
+    pub fn new(
+        endpoint: &Endpoint,
+        id: NameAddr,
+        contact: NameAddr,
+        registrar: Box<dyn Uri>,
+        expiry: Duration,
+    ) -> Self {
         Self {
             registrar,
+            backup_registrars: VecDeque::new(),
+            outbound_proxy: endpoint.outbound_proxy().cloned(),
             to: FromTo::new(id.clone(), None),
-            from: FromTo::new(id, Some(random_string())),
+            from: FromTo::new(id, Some(endpoint.generate_tag())),
             cseq: random_sequence_number(),
-            call_id: CallID::new(random_string()),
+            call_id: endpoint.generate_call_id(),
             contact: Contact::new(contact),
 
             expires: expiry,
             register_interval: create_reg_interval(expiry),
+
+            target: TargetTransportInfo::default(),
+            failed_targets: vec![],
+
+            public_address: None,
+            contact_changed: false,
+
+            retry_after: None,
+
+            redirect_policy: RedirectPolicy::default(),
+
+            failures: 0,
+            outbound: false,
+            state: watch::Sender::new(RegistrationState::Unregistered),
+            request_hook: None,
+        }
+    }
+
+    /// Set a hook which is called with every REGISTER request created by [`Self::create_register`]
+    pub fn set_request_hook(&mut self, hook: RequestHook) {
+        self.request_hook = Some(hook);
+    }
+
+    /// Add a registrar to fail over to (e.g. from DNS SRV results), when all targets of the current
+    /// registrar fail to respond or respond with a server failure.
+    ///
+    /// Registrars are tried in the order they were added, the previous registrar becomes the last backup.
+    pub fn add_backup_registrar(&mut self, registrar: Box<dyn Uri>) {
+        self.backup_registrars.push_back(registrar);
+    }
+
+    /// Register the binding as an outbound flow ([RFC5626](https://datatracker.ietf.org/doc/html/rfc5626))
+    ///
+    /// `instance_id` is the URN identifying this user agent instance (e.g. `urn:uuid:...`) and must be
+    /// the same for all registrations of the instance. One flow is maintained for each outbound proxy
+    /// by using a separate `Registration` with a distinct `reg_id` per proxy.
+    ///
+    /// A failed flow (failed REGISTER or [`Self::probe_registrar`]) is re-registered after the backoff
+    /// described in RFC5626 Section 4.5.
+    pub fn set_outbound_flow(&mut self, instance_id: &str, reg_id: u32) {
+        self.contact.params.remove("+sip.instance");
+        self.contact
+            .params
+            .push(Param::quoted("+sip.instance", format!("<{instance_id}>")));
+        self.contact
+            .params
+            .push_or_edit("reg-id", reg_id.to_string());
+
+        self.outbound = true;
+    }
+
+    /// Returns the current state of the registration
+    pub fn state(&self) -> RegistrationState {
+        self.state.borrow().clone()
+    }
+
+    /// Subscribe to changes of the registration's state
+    pub fn subscribe(&self) -> watch::Receiver<RegistrationState> {
+        self.state.subscribe()
+    }
+
+    /// Set the policy used to follow redirect (3xx) responses of the registrar
+    pub fn set_redirect_policy(&mut self, policy: RedirectPolicy) {
+        self.redirect_policy = policy;
+    }
+
+    /// Returns the public address of this client as seen by the registrar.
+    ///
+    /// Only available when the endpoint requests symmetric response routing
+    /// (see `EndpointBuilder::set_rport`) and the registrar supports it.
+    pub fn public_address(&self) -> Option<SocketAddr> {
+        self.public_address
+    }
+
+    /// The contact which is registered with the registrar
+    pub fn contact(&self) -> &Contact {
+        &self.contact
+    }
+
+    /// Returns the address of the registrar target currently in use, if one has been selected yet
+    pub fn current_target(&self) -> Option<SocketAddr> {
+        self.target
+            .transport
+            .as_ref()
+            .map(|(_, destination)| *destination)
+    }
+
+    /// Transport info of the registrar target currently in use.
+    ///
+    /// Can be cloned to send subsequent requests to the same target.
+    pub fn target_tp_info(&self) -> &TargetTransportInfo {
+        &self.target
+    }
+
+    /// Create and send a REGISTER request, returning the final response.
+    ///
+    /// If the registrar resolves to multiple targets, timeouts, transport errors and
+    /// server failure (5xx) responses cause the request to be retried with the next target.
+    /// The last working target is remembered and used for all following requests.
+    ///
+    /// If the registrar responds with `423 Interval Too Brief`, the expiry is raised to the
+    /// registrar's Min-Expires value and the REGISTER request is sent again.
+    ///
+    /// Redirect responses allowed by the redirect policy change the registrar to the
+    /// redirect target, which is used for this and all following requests.
+    ///
+    /// See [`Self::create_register`] for the meaning of `remove_binding`.
+    pub async fn send_register(
+        &mut self,
+        endpoint: &Endpoint,
+        remove_binding: bool,
+    ) -> Result<TsxResponse> {
+        let mut redirects = 0;
+        let mut failovers = 0;
+
+        loop {
+            if self.target.transport.is_none() {
+                let selected = endpoint
+                    .select_transport_excluding(self.next_hop(), &self.failed_targets)
+                    .await;
+
+                match selected {
+                    Ok(transport) => self.target.transport = Some(transport),
+                    Err(e) => {
+                        self.failed_targets.clear();
+
+                        if failovers < self.backup_registrars.len() {
+                            failovers += 1;
+                            self.fail_over();
+                            continue;
+                        }
+
+                        self.set_failed();
+                        return Err(e);
+                    }
+                }
+            }
+
+            let request = self.create_register(remove_binding);
+
+            let result = match endpoint.send_request(request, &mut self.target).await {
+                Ok(mut transaction) => transaction.receive_final().await,
+                Err(e) => Err(e),
+            };
+
+            if let Ok(response) = &result {
+                if !remove_binding && self.raise_expiry_to_minimum(response) {
+                    continue;
+                }
+
+                if let Some(target) = self.redirect_policy.target(response, redirects) {
+                    log::debug!("registrar redirected to {target:?}");
+
+                    redirects += 1;
+
+                    self.registrar = target;
+                    self.target = TargetTransportInfo::default();
+                    self.failed_targets.clear();
+                    continue;
+                }
+            }
+
+            let failed = match &result {
+                Ok(response) => response.line.code.kind() == CodeKind::ServerFailure,
+                Err(Error::Io(_) | Error::RequestTimedOut) => true,
+                Err(Error::Header(_)) => false,
+            };
+
+            if !failed {
+                self.failed_targets.clear();
+                return result;
+            }
+
+            if self.try_next_target(endpoint).await {
+                continue;
+            }
+
+            if failovers < self.backup_registrars.len() {
+                failovers += 1;
+                self.fail_over();
+                continue;
+            }
+
+            if result.is_err() {
+                self.set_failed();
+            }
+
+            return result;
+        }
+    }
+
+    /// Switch to the next backup registrar, the current one becomes the last backup
+    fn fail_over(&mut self) {
+        let Some(next) = self.backup_registrars.pop_front() else {
+            return;
+        };
+
+        log::warn!("all targets of registrar failed, failing over to {next:?}");
+
+        let previous = std::mem::replace(&mut self.registrar, next);
+        self.backup_registrars.push_back(previous);
+
+        self.target = TargetTransportInfo::default();
+        self.failed_targets.clear();
+    }
+
+    /// Record a failed registration attempt
+    fn set_failed(&mut self) {
+        self.failures += 1;
+        self.state.send_replace(RegistrationState::Failed {
+            failures: self.failures,
+        });
+    }
+
+    /// Exponential backoff with jitter, based on the number of consecutive failures
+    fn backoff(&self) -> Duration {
+        let backoff = max_backoff(self.failures, self.outbound);
+
+        // Randomize between 50% and 100% to avoid all clients retrying at the same time
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
+    /// URI the requests are sent to, the outbound proxy if configured, the registrar otherwise
+    fn next_hop(&self) -> &dyn Uri {
+        match &self.outbound_proxy {
+            Some(outbound_proxy) => &*outbound_proxy.uri.uri,
+            None => &*self.registrar,
+        }
+    }
+
+    /// Mark the current target as failed and select the next one.
+    ///
+    /// Returns `false` if there's no target left to try.
+    async fn try_next_target(&mut self, endpoint: &Endpoint) -> bool {
+        if let Some((_, destination)) = self.target.transport.take() {
+            log::warn!("registrar target {destination} failed, trying next target");
+            self.failed_targets.push(destination);
+        }
+
+        match endpoint
+            .select_transport_excluding(self.next_hop(), &self.failed_targets)
+            .await
+        {
+            Ok(transport) => {
+                self.target.transport = Some(transport);
+                true
+            }
+            Err(_) => {
+                // All targets failed, start from the beginning next time
+                self.failed_targets.clear();
+                false
+            }
+        }
+    }
+
+    /// Check if the registrar is still reachable by sending an OPTIONS request to the current target.
+    ///
+    /// Any response counts as reachable, as registrars may reject OPTIONS requests. If the request
+    /// times out or fails to send, the target is marked as failed and the next target is selected,
+    /// so the following REGISTER request is sent to a backup registrar if one exists.
+    pub async fn probe_registrar(&mut self, endpoint: &Endpoint) -> bool {
+        if self.target.transport.is_none() {
+            match endpoint
+                .select_transport_excluding(self.next_hop(), &self.failed_targets)
+                .await
+            {
+                Ok(transport) => self.target.transport = Some(transport),
+                Err(_) => return false,
+            }
+        }
+
+        let mut request = Request::new(Method::OPTIONS, self.registrar.clone());
+
+        request.headers.insert_type(Name::FROM, &self.from);
+        request.headers.insert_type(Name::TO, &self.to);
+        request.headers.insert_named(&endpoint.generate_call_id());
+        request
+            .headers
+            .insert_named(&CSeq::new(random_sequence_number(), Method::OPTIONS));
+
+        if let Some(outbound_proxy) = &self.outbound_proxy {
+            request.headers.insert_type(Name::ROUTE, outbound_proxy);
+        }
+
+        let result = match endpoint.send_request(request, &mut self.target).await {
+            Ok(mut transaction) => transaction.receive_final().await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!("registrar is unreachable, {e}");
+
+                if !self.try_next_target(endpoint).await {
+                    self.fail_over();
+                }
+
+                if self.outbound {
+                    // The flow failed and must be registered again
+                    self.set_failed();
+                }
+
+                false
+            }
         }
     }
 
@@ -64,6 +445,20 @@ impl Registration {
         request.headers.insert_named(&expires);
         request.headers.insert_named(&self.contact);
 
+        if self.outbound {
+            request
+                .headers
+                .insert_named(&Supported(BytesStr::from_static("outbound")));
+        }
+
+        if let Some(outbound_proxy) = &self.outbound_proxy {
+            request.headers.insert_type(Name::ROUTE, outbound_proxy);
+        }
+
+        if let Some(request_hook) = &self.request_hook {
+            request_hook.apply(&mut request);
+        }
+
         request
     }
 
@@ -74,44 +469,150 @@ impl Registration {
     pub fn receive_success_response(&mut self, response: TsxResponse) {
         assert_eq!(response.line.code.kind(), CodeKind::Success);
 
-        if let Ok(expires) = response.headers.get_named::<Expires>() {
-            let expires = Duration::from_secs(expires.0 as _);
+        let expires = response
+            .headers
+            .get_named::<Expires>()
+            .map(|expires| Duration::from_secs(expires.0 as _));
 
-            if self.expires != expires {
+        if let Ok(expires) = expires {
+            if self.expires != expires && !expires.is_zero() {
                 self.register_interval = create_reg_interval(expires);
                 self.expires = expires;
             }
         }
 
+        self.failures = 0;
+
+        if expires.is_ok_and(|expires| expires.is_zero()) {
+            self.state.send_replace(RegistrationState::Unregistered);
+        } else {
+            self.state.send_replace(RegistrationState::Registered {
+                expires: self.expires,
+            });
+        }
+
         if self.to.tag.is_none() {
             self.to.tag = response.base_headers.to.tag;
         }
+
+        if let Some(via) = response.base_headers.via.first() {
+            self.learn_public_address(via);
+        }
+    }
+
+    /// Update the contact with the public address reported by the registrar.
+    ///
+    /// Only done if the registrar responded to an `rport` parameter, so the address
+    /// includes the public port of the NAT binding.
+    fn learn_public_address(&mut self, via: &Via) {
+        if via.params.get_val("rport").is_none() {
+            return;
+        }
+
+        let Some(public_address) = via.public_address() else {
+            return;
+        };
+
+        self.public_address = Some(public_address);
+
+        let Some(contact_uri) = self.contact.uri.uri.downcast_mut::<SipUri>() else {
+            return;
+        };
+
+        if contact_uri.host_port != public_address.into() {
+            log::debug!("learned public address {public_address}, updating contact");
+
+            contact_uri.host_port = public_address.into();
+            self.contact_changed = true;
+        }
     }
 
     /// Handle an error response received from a registrar
     ///
-    /// Returns whether or not to retry the registration
+    /// Returns whether or not to retry the registration. If the registrar asked to retry later
+    /// using the Retry-After header, [`Self::wait_for_expiry`] waits for the requested time.
     pub fn receive_error_response(&mut self, response: TsxResponse) -> bool {
-        if !matches!(response.line.code.kind(), CodeKind::RequestFailure) {
+        if self.raise_expiry_to_minimum(&response) {
+            return true;
+        }
+
+        self.set_failed();
+
+        if let Some(retry_after) = retry_after(&response.headers) {
+            log::debug!("registrar asked to retry after {retry_after:?}");
+
+            self.retry_after = Some(retry_after);
+            return true;
+        }
+
+        false
+    }
+
+    /// Handle a `423 Interval Too Brief` response by raising the expiry to the registrar's Min-Expires value.
+    ///
+    /// Returns `false` if the response is no 423 or the expiry is already large enough,
+    /// to avoid sending the same REGISTER again.
+    fn raise_expiry_to_minimum(&mut self, response: &TsxResponse) -> bool {
+        if response.line.code != Code::INTERVAL_TOO_BRIEF {
             return false;
         }
 
-        let Ok(expires) = response.headers.get_named::<MinExpires>() else {
+        let Ok(min_expires) = response.headers.get_named::<MinExpires>() else {
             return false;
         };
 
-        self.expires = Duration::from_secs(expires.0 as _);
+        let min_expires = Duration::from_secs(min_expires.0.into());
+
+        if min_expires <= self.expires {
+            return false;
+        }
+
+        log::debug!("registrar requires a minimum expiry of {min_expires:?}");
+
+        self.expires = min_expires;
         self.register_interval = create_reg_interval(self.expires);
 
         true
     }
 
     /// Returns when a new REGISTER request must be sent to refresh the binding on the registrar.
+    ///
+    /// Returns immediately if the contact was updated with a newly learned public address.
+    /// After an error response with a Retry-After header, returns once the requested time has passed.
+    /// After other failed attempts, returns after an exponential backoff.
     pub async fn wait_for_expiry(&mut self) {
+        if let Some(retry_after) = self.retry_after.take() {
+            sleep(retry_after).await;
+            return;
+        }
+
+        if self.failures > 0 {
+            sleep(self.backoff()).await;
+            return;
+        }
+
+        if self.contact_changed {
+            self.contact_changed = false;
+            return;
+        }
+
         self.register_interval.tick().await;
     }
 }
 
+/// Backoff after `failures` consecutive failures before applying the jitter
+fn max_backoff(failures: u32, outbound: bool) -> Duration {
+    let (min_backoff, max_backoff) = if outbound {
+        (FLOW_MIN_BACKOFF, FLOW_MAX_BACKOFF)
+    } else {
+        (MIN_BACKOFF, MAX_BACKOFF)
+    };
+
+    let exponent = failures.saturating_sub(1).min(16);
+
+    (min_backoff * 2u32.pow(exponent)).min(max_backoff)
+}
+
 fn create_reg_interval(period: Duration) -> Interval {
     // Avoid underflow and zero duration intervals by limiting `period` to be at least 20s
     let period = period.max(Duration::from_secs(20));
@@ -122,3 +623,140 @@ fn create_reg_interval(period: Duration) -> Interval {
     register_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
     register_interval
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sip_core::transport::udp::Udp;
+    use sip_core::{IncomingRequest, Layer, LayerKey, MayTake};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Registrar responding to every REGISTER with `code`
+    struct Responder {
+        code: Code,
+        received: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Layer for Responder {
+        fn name(&self) -> &'static str {
+            "test-registrar"
+        }
+
+        async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+            let mut request = request.take();
+
+            self.received.fetch_add(1, Ordering::SeqCst);
+
+            let response = endpoint.create_response(&request, self.code, None);
+            let tsx = endpoint.create_server_tsx(&mut request);
+            tsx.respond(response).await.unwrap();
+        }
+    }
+
+    async fn spawn_registrar(code: Code) -> (Endpoint, LayerKey<Responder>, SocketAddr) {
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let key = builder.add_layer(Responder {
+            code,
+            received: AtomicUsize::new(0),
+        });
+
+        (builder.build(), key, transport.bound())
+    }
+
+    fn sip_uri(addr: SocketAddr) -> SipUri {
+        format!("sip:{addr}").parse().unwrap()
+    }
+
+    async fn spawn_client(registrar: SipUri) -> (Endpoint, Registration) {
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let endpoint = builder.build();
+
+        let id: SipUri = "sip:alice@example.com".parse().unwrap();
+        let contact: SipUri = format!("sip:alice@{}", transport.bound()).parse().unwrap();
+
+        let registration = Registration::new(
+            &endpoint,
+            NameAddr::uri(id),
+            NameAddr::uri(contact),
+            Box::new(registrar),
+            Duration::from_secs(300),
+        );
+
+        (endpoint, registration)
+    }
+
+    #[test]
+    fn backoff_doubles_until_limit() {
+        let backoffs: Vec<u64> = (1..=10)
+            .map(|failures| max_backoff(failures, false).as_secs())
+            .collect();
+
+        assert_eq!(backoffs, [2, 4, 8, 16, 32, 64, 128, 256, 300, 300]);
+
+        // Large failure counts must not overflow
+        assert_eq!(max_backoff(u32::MAX, false), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn outbound_flow_backoff() {
+        let backoffs: Vec<u64> = (1..=8)
+            .map(|failures| max_backoff(failures, true).as_secs())
+            .collect();
+
+        assert_eq!(backoffs, [30, 60, 120, 240, 480, 960, 1800, 1800]);
+    }
+
+    #[tokio::test]
+    async fn backoff_jitter() {
+        let (_endpoint, mut registration) =
+            spawn_client(sip_uri("127.0.0.1:5060".parse().unwrap())).await;
+        registration.failures = 4;
+
+        let limit = max_backoff(4, false);
+
+        for _ in 0..100 {
+            let backoff = registration.backoff();
+
+            assert!(backoff >= limit / 2 && backoff <= limit, "{backoff:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_over_to_backup_registrar() {
+        let (primary, primary_key, primary_addr) = spawn_registrar(Code::SERVICE_UNAVAILABLE).await;
+        let (backup, backup_key, backup_addr) = spawn_registrar(Code::OK).await;
+
+        let (endpoint, mut registration) = spawn_client(sip_uri(primary_addr)).await;
+        registration.add_backup_registrar(Box::new(sip_uri(backup_addr)));
+
+        let response = registration.send_register(&endpoint, false).await.unwrap();
+        assert_eq!(response.line.code, Code::OK);
+        assert_eq!(registration.current_target(), Some(backup_addr));
+
+        // The working registrar is kept for following requests
+        let response = registration.send_register(&endpoint, false).await.unwrap();
+        assert_eq!(response.line.code, Code::OK);
+
+        assert_eq!(primary[primary_key].received.load(Ordering::SeqCst), 1);
+        assert_eq!(backup[backup_key].received.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn all_registrars_failed() {
+        let (primary, primary_key, primary_addr) = spawn_registrar(Code::SERVICE_UNAVAILABLE).await;
+        let (backup, backup_key, backup_addr) = spawn_registrar(Code::SERVER_INTERNAL_ERROR).await;
+
+        let (endpoint, mut registration) = spawn_client(sip_uri(primary_addr)).await;
+        registration.add_backup_registrar(Box::new(sip_uri(backup_addr)));
+
+        // Each registrar is tried once, the last failure response is returned
+        let response = registration.send_register(&endpoint, false).await.unwrap();
+        assert_eq!(response.line.code, Code::SERVER_INTERNAL_ERROR);
+
+        assert_eq!(primary[primary_key].received.load(Ordering::SeqCst), 1);
+        assert_eq!(backup[backup_key].received.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,632 @@
+//! Registrar server ([RFC 3261 Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3))
+
+use sip_auth::{CredentialLookup, RequestParts, UasAuthenticator, Verification};
+use sip_core::transport::OutgoingResponse;
+use sip_core::{Endpoint, EndpointBuilder, IncomingRequest, Layer, MayTake, Result};
+use sip_types::header::typed::{CallID, Contact, Expires, MinExpires};
+use sip_types::host::Host;
+use sip_types::print::AppendCtx;
+use sip_types::uri::params::Params;
+use sip_types::uri::sip::{SipUri, UserPart};
+use sip_types::uri::Uri;
+use sip_types::{Code, Headers, Method, Name};
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+
+mod store;
+
+pub use store::{Binding, BindingStore, InMemoryBindingStore};
+
+/// Object safe wrapper around [`UasAuthenticator`], to avoid making the layer generic over the credential lookup
+trait Authenticate: Send + Sync {
+    fn verify(&self, request_parts: RequestParts<'_>) -> Verification;
+    fn challenge(&self, headers: &mut Headers, stale: bool);
+}
+
+impl<L: CredentialLookup> Authenticate for UasAuthenticator<L> {
+    fn verify(&self, request_parts: RequestParts<'_>) -> Verification {
+        UasAuthenticator::verify(self, request_parts, false)
+    }
+
+    fn challenge(&self, headers: &mut Headers, stale: bool) {
+        UasAuthenticator::challenge(self, headers, false, stale)
+    }
+}
+
+/// Decides if an authenticated user may modify the bindings of an address of record
+/// ([RFC 3261 Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3) step 3)
+///
+/// Implemented for closures taking the authenticated username and the address of record.
+pub trait RegistrationAuthorizer: Send + Sync + 'static {
+    fn is_authorized(&self, username: &str, aor: &dyn Uri) -> bool;
+}
+
+impl<F> RegistrationAuthorizer for F
+where
+    F: Fn(&str, &dyn Uri) -> bool + Send + Sync + 'static,
+{
+    fn is_authorized(&self, username: &str, aor: &dyn Uri) -> bool {
+        self(username, aor)
+    }
+}
+
+/// Default [`RegistrationAuthorizer`], only allows users to register the address of record with their username as user part
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SameUser;
+
+impl RegistrationAuthorizer for SameUser {
+    fn is_authorized(&self, username: &str, aor: &dyn Uri) -> bool {
+        let Some(aor) = aor.downcast_ref::<SipUri>() else {
+            return false;
+        };
+
+        match &aor.user_part {
+            UserPart::Empty => false,
+            UserPart::User(user) => user == username,
+            UserPart::UserPw(user_pw) => user_pw.user == username,
+        }
+    }
+}
+
+/// Result of applying a REGISTER request to the bindings of its address of record
+#[derive(Debug)]
+enum Update {
+    /// The request was applied, contains all active bindings of the address of record
+    Bindings(Vec<Binding>),
+
+    /// The request must be rejected with the given code
+    Rejected(Code),
+}
+
+/// Layer which accepts REGISTER requests and stores the bindings in a [`BindingStore`]
+///
+/// Requests are challenged using digest authentication if an authenticator is set.
+/// Registered contacts of an address of record can be queried using [`RegistrarLayer::lookup`].
+pub struct RegistrarLayer {
+    store: Box<dyn BindingStore>,
+    authenticator: Option<Box<dyn Authenticate>>,
+    authorizer: Box<dyn RegistrationAuthorizer>,
+
+    /// Serializes modifications of the bindings
+    update_lock: Mutex<()>,
+
+    /// Registrations with a shorter duration are rejected with `423 Interval Too Brief`. Defaults to 60 seconds
+    pub min_expires: Duration,
+
+    /// Registrations with a longer duration are shortened to this value. Defaults to 2 hours
+    pub max_expires: Duration,
+
+    /// Duration used when the REGISTER contains no expiry. Defaults to 1 hour
+    pub default_expires: Duration,
+}
+
+impl RegistrarLayer {
+    pub fn new<S: BindingStore>(store: S) -> Self {
+        Self {
+            store: Box::new(store),
+            authenticator: None,
+            authorizer: Box::new(SameUser),
+            update_lock: Mutex::new(()),
+            min_expires: Duration::from_secs(60),
+            max_expires: Duration::from_secs(7200),
+            default_expires: Duration::from_secs(3600),
+        }
+    }
+
+    /// Challenge all REGISTER requests using the `authenticator`
+    ///
+    /// Authenticated users must also be authorized to modify the bindings of the address of record,
+    /// by default using [`SameUser`]. Unauthorized requests are rejected with `403 Forbidden`.
+    pub fn with_authenticator<L>(mut self, authenticator: UasAuthenticator<L>) -> Self
+    where
+        L: CredentialLookup + 'static,
+    {
+        self.authenticator = Some(Box::new(authenticator));
+        self
+    }
+
+    /// Set the [`RegistrationAuthorizer`] used for authenticated requests (default [`SameUser`])
+    pub fn with_authorizer<A: RegistrationAuthorizer>(mut self, authorizer: A) -> Self {
+        self.authorizer = Box::new(authorizer);
+        self
+    }
+
+    /// Returns all active bindings of the address of record
+    pub async fn lookup(&self, aor: &dyn Uri) -> Vec<Binding> {
+        let now = SystemTime::now();
+
+        let mut bindings = self.store.bindings(&aor_key(aor)).await;
+        bindings.retain(|binding| !binding.is_expired(now));
+        bindings
+    }
+
+    async fn handle_register(
+        &self,
+        endpoint: &Endpoint,
+        mut request: IncomingRequest,
+    ) -> Result<()> {
+        let transaction = endpoint.create_server_tsx(&mut request);
+
+        if let Some(authenticator) = &self.authenticator {
+            let request_parts = RequestParts {
+                line: &request.line,
+                headers: &request.headers,
+                body: &request.body,
+            };
+
+            let stale = match authenticator.verify(request_parts) {
+                Verification::Authenticated(username) => {
+                    if !self
+                        .authorizer
+                        .is_authorized(&username, &*request.base_headers.to.uri.uri)
+                    {
+                        let response = endpoint.create_response(&request, Code::FORBIDDEN, None);
+                        return transaction.respond(response).await;
+                    }
+
+                    None
+                }
+                Verification::Missing => Some(false),
+                Verification::Stale => Some(true),
+                Verification::Invalid => {
+                    let response = endpoint.create_response(&request, Code::FORBIDDEN, None);
+                    return transaction.respond(response).await;
+                }
+            };
+
+            if let Some(stale) = stale {
+                let mut response = endpoint.create_response(&request, Code::UNAUTHORIZED, None);
+                authenticator.challenge(&mut response.msg.headers, stale);
+
+                return transaction.respond(response).await;
+            }
+        }
+
+        let now = SystemTime::now();
+
+        let update = self
+            .update_bindings(
+                &aor_key(&*request.base_headers.to.uri.uri),
+                &request.base_headers.call_id,
+                request.base_headers.cseq.cseq,
+                &request.headers,
+                now,
+            )
+            .await;
+
+        let response = self.create_response(endpoint, &request, update, now);
+
+        transaction.respond(response).await
+    }
+
+    fn create_response(
+        &self,
+        endpoint: &Endpoint,
+        request: &IncomingRequest,
+        update: Update,
+        now: SystemTime,
+    ) -> OutgoingResponse {
+        let bindings = match update {
+            Update::Bindings(bindings) => bindings,
+            Update::Rejected(code) => {
+                let mut response = endpoint.create_response(request, code, None);
+
+                if code == Code::INTERVAL_TOO_BRIEF {
+                    response
+                        .msg
+                        .headers
+                        .insert_named(&MinExpires(self.min_expires.as_secs() as u32));
+                }
+
+                return response;
+            }
+        };
+
+        let mut response = endpoint.create_response(request, Code::OK, None);
+
+        for binding in bindings {
+            let remaining = binding
+                .expires_at
+                .duration_since(now)
+                .unwrap_or_default()
+                .as_secs();
+
+            let mut contact = binding.contact;
+            contact
+                .params
+                .push_or_edit("expires", remaining.to_string());
+
+            response.msg.headers.insert_named(&contact);
+        }
+
+        response
+    }
+
+    /// Apply the Contact and Expires headers of a REGISTER request to the bindings of `aor`
+    /// ([RFC 3261 Section 10.3](https://datatracker.ietf.org/doc/html/rfc3261#section-10.3) steps 6 to 8)
+    async fn update_bindings(
+        &self,
+        aor: &str,
+        call_id: &CallID,
+        cseq: u32,
+        headers: &Headers,
+        now: SystemTime,
+    ) -> Update {
+        let contact_values: Vec<&str> = headers
+            .iter()
+            .filter(|(name, _)| **name == Name::CONTACT)
+            .map(|(_, value)| value.trim())
+            .collect();
+
+        let remove_all = contact_values.contains(&"*");
+
+        let contacts = match headers.try_get_named::<Vec<Contact>>() {
+            _ if remove_all => vec![],
+            Some(Ok(contacts)) => contacts,
+            Some(Err(e)) => {
+                log::debug!("Failed to parse Contact headers of REGISTER, {e}");
+                return Update::Rejected(Code::BAD_REQUEST);
+            }
+            None => vec![],
+        };
+
+        let expires = headers
+            .get_named::<Expires>()
+            .ok()
+            .map(|expires| Duration::from_secs(expires.0.into()));
+
+        let _update_lock = self.update_lock.lock().await;
+
+        let mut bindings = self.store.bindings(aor).await;
+        bindings.retain(|binding| !binding.is_expired(now));
+
+        if remove_all {
+            // Contact: * is only allowed as the only contact with an expiry of zero
+            if contact_values.len() != 1 || expires != Some(Duration::ZERO) {
+                return Update::Rejected(Code::BAD_REQUEST);
+            }
+
+            let reordered = bindings
+                .iter()
+                .any(|binding| binding.call_id == *call_id && binding.cseq >= cseq);
+
+            if reordered {
+                // Out of order request, RFC 3261 Section 10.3 step 6
+                return Update::Rejected(Code::SERVER_INTERNAL_ERROR);
+            }
+
+            bindings.clear();
+        }
+
+        for contact in contacts {
+            let contact_expires = contact
+                .params
+                .get_val("expires")
+                .and_then(|expires| expires.parse().ok())
+                .map(Duration::from_secs)
+                .or(expires)
+                .unwrap_or(self.default_expires);
+
+            if !contact_expires.is_zero() && contact_expires < self.min_expires {
+                return Update::Rejected(Code::INTERVAL_TOO_BRIEF);
+            }
+
+            let existing = bindings
+                .iter()
+                .position(|binding| binding.contact.uri.uri.compare(&*contact.uri.uri));
+
+            if let Some(existing) = existing {
+                let binding = &bindings[existing];
+
+                if binding.call_id == *call_id && binding.cseq >= cseq {
+                    // Out of order request, RFC 3261 Section 10.3 step 7
+                    return Update::Rejected(Code::SERVER_INTERNAL_ERROR);
+                }
+
+                bindings.remove(existing);
+            }
+
+            if contact_expires.is_zero() {
+                continue;
+            }
+
+            let mut contact = contact;
+            contact.params.remove("expires");
+
+            bindings.push(Binding {
+                contact,
+                call_id: call_id.clone(),
+                cseq,
+                expires_at: now + contact_expires.min(self.max_expires),
+            });
+        }
+
+        self.store.set_bindings(aor, bindings.clone()).await;
+
+        Update::Bindings(bindings)
+    }
+}
+
+#[async_trait::async_trait]
+impl Layer for RegistrarLayer {
+    fn name(&self) -> &'static str {
+        "registrar"
+    }
+
+    fn init(&mut self, endpoint: &mut EndpointBuilder) {
+        endpoint.add_allow(Method::REGISTER);
+    }
+
+    async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+        if request.line.method != Method::REGISTER {
+            return;
+        }
+
+        if let Err(e) = self.handle_register(endpoint, request.take()).await {
+            log::warn!("Failed to respond to REGISTER request, {e}");
+        }
+    }
+}
+
+/// Create the key of an address of record, URI parameters are not part of the address of record
+///
+/// The host is compared case-insensitive and the default port is equal to no port
+/// ([RFC 3261 Section 19.1.4](https://datatracker.ietf.org/doc/html/rfc3261#section-19.1.4)).
+fn aor_key(uri: &dyn Uri) -> String {
+    if let Some(sip_uri) = uri.downcast_ref::<SipUri>() {
+        let mut sip_uri = sip_uri.clone();
+        sip_uri.uri_params = Params::new();
+        sip_uri.header_params = Params::new();
+
+        if let Host::Name(name) = &sip_uri.host_port.host {
+            sip_uri.host_port.host = Host::Name(name.to_ascii_lowercase().into());
+        }
+
+        let default_port = if sip_uri.sips { 5061 } else { 5060 };
+
+        if sip_uri.host_port.port == Some(default_port) {
+            sip_uri.host_port.port = None;
+        }
+
+        sip_uri.default_print_ctx().to_string()
+    } else {
+        uri.clone_boxed().default_print_ctx().to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    const AOR: &str = "sip:alice@example.com";
+
+    fn headers(contacts: &[&str], expires: Option<u32>) -> Headers {
+        let mut headers = Headers::new();
+
+        for contact in contacts {
+            headers.insert(Name::CONTACT, *contact);
+        }
+
+        if let Some(expires) = expires {
+            headers.insert_named(&Expires(expires));
+        }
+
+        headers
+    }
+
+    fn bindings(update: Update) -> Vec<Binding> {
+        match update {
+            Update::Bindings(bindings) => bindings,
+            Update::Rejected(code) => panic!("unexpected rejection {code:?}"),
+        }
+    }
+
+    fn rejected(update: Update) -> Code {
+        match update {
+            Update::Bindings(_) => panic!("expected rejection"),
+            Update::Rejected(code) => code,
+        }
+    }
+
+    #[tokio::test]
+    async fn add_and_query_bindings() {
+        let registrar = RegistrarLayer::new(InMemoryBindingStore::new());
+        let call_id = CallID::new("a");
+        let now = SystemTime::now();
+
+        let update = registrar
+            .update_bindings(
+                AOR,
+                &call_id,
+                1,
+                &headers(
+                    &["<sip:alice@10.0.0.1>", "<sip:alice@10.0.0.2>;expires=120"],
+                    None,
+                ),
+                now,
+            )
+            .await;
+
+        let added = bindings(update);
+        assert_eq!(added.len(), 2);
+        assert_eq!(added[0].expires_at, now + registrar.default_expires);
+        assert_eq!(added[1].expires_at, now + Duration::from_secs(120));
+        assert!(added[1].contact.params.get_val("expires").is_none());
+
+        // A REGISTER without Contact headers queries the current bindings
+        let update = registrar
+            .update_bindings(AOR, &CallID::new("b"), 1, &headers(&[], None), now)
+            .await;
+
+        let queried = bindings(update);
+        assert_eq!(queried.len(), 2);
+        assert!(queried[0]
+            .contact
+            .uri
+            .uri
+            .compare(&*added[0].contact.uri.uri));
+        assert!(queried[1]
+            .contact
+            .uri
+            .uri
+            .compare(&*added[1].contact.uri.uri));
+    }
+
+    #[tokio::test]
+    async fn remove_all_bindings() {
+        let registrar = RegistrarLayer::new(InMemoryBindingStore::new());
+        let call_id = CallID::new("a");
+        let now = SystemTime::now();
+
+        registrar
+            .update_bindings(
+                AOR,
+                &call_id,
+                1,
+                &headers(&["<sip:alice@10.0.0.1>"], None),
+                now,
+            )
+            .await;
+
+        let update = registrar
+            .update_bindings(AOR, &call_id, 2, &headers(&["*"], Some(0)), now)
+            .await;
+
+        assert!(bindings(update).is_empty());
+        assert!(registrar.store.bindings(AOR).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reordered_remove_all() {
+        let registrar = RegistrarLayer::new(InMemoryBindingStore::new());
+        let call_id = CallID::new("a");
+        let now = SystemTime::now();
+
+        bindings(
+            registrar
+                .update_bindings(
+                    AOR,
+                    &call_id,
+                    2,
+                    &headers(&["<sip:alice@10.0.0.1>"], None),
+                    now,
+                )
+                .await,
+        );
+
+        let update = registrar
+            .update_bindings(AOR, &call_id, 1, &headers(&["*"], Some(0)), now)
+            .await;
+
+        assert_eq!(rejected(update), Code::SERVER_INTERNAL_ERROR);
+        assert_eq!(registrar.store.bindings(AOR).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn remove_all_requires_zero_expires() {
+        let registrar = RegistrarLayer::new(InMemoryBindingStore::new());
+        let now = SystemTime::now();
+
+        let update = registrar
+            .update_bindings(AOR, &CallID::new("a"), 1, &headers(&["*"], None), now)
+            .await;
+        assert_eq!(rejected(update), Code::BAD_REQUEST);
+
+        let update = registrar
+            .update_bindings(AOR, &CallID::new("a"), 1, &headers(&["*"], Some(60)), now)
+            .await;
+        assert_eq!(rejected(update), Code::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn interval_too_brief() {
+        let registrar = RegistrarLayer::new(InMemoryBindingStore::new());
+
+        let update = registrar
+            .update_bindings(
+                AOR,
+                &CallID::new("a"),
+                1,
+                &headers(&["<sip:alice@10.0.0.1>"], Some(30)),
+                SystemTime::now(),
+            )
+            .await;
+
+        assert_eq!(rejected(update), Code::INTERVAL_TOO_BRIEF);
+        assert!(registrar.store.bindings(AOR).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn out_of_order_request() {
+        let registrar = RegistrarLayer::new(InMemoryBindingStore::new());
+        let call_id = CallID::new("a");
+        let now = SystemTime::now();
+        let contact = headers(&["<sip:alice@10.0.0.1>"], None);
+
+        bindings(
+            registrar
+                .update_bindings(AOR, &call_id, 2, &contact, now)
+                .await,
+        );
+
+        for cseq in [1, 2] {
+            let update = registrar
+                .update_bindings(AOR, &call_id, cseq, &contact, now)
+                .await;
+
+            assert_eq!(rejected(update), Code::SERVER_INTERNAL_ERROR);
+        }
+
+        // Other clients use a different Call-ID and are not affected by the CSeq
+        let update = registrar
+            .update_bindings(AOR, &CallID::new("b"), 1, &contact, now)
+            .await;
+
+        assert_eq!(bindings(update).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn expires_is_capped() {
+        let registrar = RegistrarLayer::new(InMemoryBindingStore::new());
+        let now = SystemTime::now();
+
+        let update = registrar
+            .update_bindings(
+                AOR,
+                &CallID::new("a"),
+                1,
+                &headers(&["<sip:alice@10.0.0.1>"], Some(86400)),
+                now,
+            )
+            .await;
+
+        let bindings = bindings(update);
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0].expires_at, now + registrar.max_expires);
+    }
+
+    #[test]
+    fn aor_key_is_normalized() {
+        let key = |uri: &str| aor_key(&SipUri::from_str(uri).unwrap());
+
+        assert_eq!(key("SIP:alice@Example.COM"), AOR);
+        assert_eq!(key("sip:alice@example.com:5060"), AOR);
+        assert_eq!(key("sip:alice@example.com;transport=tcp"), AOR);
+        assert_eq!(key("sips:alice@example.com:5061"), "sips:alice@example.com");
+
+        assert_ne!(key("sip:Alice@example.com"), AOR);
+        assert_ne!(key("sip:alice@example.com:5061"), AOR);
+        assert_ne!(key("sips:alice@example.com:5060"), "sips:alice@example.com");
+    }
+
+    #[test]
+    fn same_user_authorization() {
+        let aor = SipUri::from_str(AOR).unwrap();
+        let anonymous = SipUri::from_str("sip:example.com").unwrap();
+
+        assert!(SameUser.is_authorized("alice", &aor));
+        assert!(!SameUser.is_authorized("bob", &aor));
+        assert!(!SameUser.is_authorized("alice", &anonymous));
+    }
+}
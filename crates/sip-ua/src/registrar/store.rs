@@ -0,0 +1,74 @@
+use parking_lot::Mutex;
+use sip_types::header::typed::{CallID, Contact};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// A contact registered for an address of record
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub contact: Contact,
+
+    /// Call-ID and CSeq of the REGISTER request which last updated the binding,
+    /// used to detect reordered requests
+    pub call_id: CallID,
+    pub cseq: u32,
+
+    pub expires_at: SystemTime,
+}
+
+impl Binding {
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Storage of the bindings of a [`RegistrarLayer`](super::RegistrarLayer)
+///
+/// Implement this to persist bindings in a database or share them between multiple registrars.
+#[async_trait::async_trait]
+pub trait BindingStore: Send + Sync + 'static {
+    /// Returns all bindings of the address of record, expired bindings may be included
+    async fn bindings(&self, aor: &str) -> Vec<Binding>;
+
+    /// Replace all bindings of the address of record, removes the address of record if `bindings` is empty
+    async fn set_bindings(&self, aor: &str, bindings: Vec<Binding>);
+}
+
+/// [`BindingStore`] which keeps all bindings in memory
+#[derive(Debug, Default)]
+pub struct InMemoryBindingStore {
+    bindings: Mutex<HashMap<String, Vec<Binding>>>,
+}
+
+impl InMemoryBindingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove all expired bindings
+    pub fn remove_expired(&self) {
+        let now = SystemTime::now();
+
+        self.bindings.lock().retain(|_, bindings| {
+            bindings.retain(|binding| !binding.is_expired(now));
+            !bindings.is_empty()
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl BindingStore for InMemoryBindingStore {
+    async fn bindings(&self, aor: &str) -> Vec<Binding> {
+        self.bindings.lock().get(aor).cloned().unwrap_or_default()
+    }
+
+    async fn set_bindings(&self, aor: &str, bindings: Vec<Binding>) {
+        let mut map = self.bindings.lock();
+
+        if bindings.is_empty() {
+            map.remove(aor);
+        } else {
+            map.insert(aor.to_owned(), bindings);
+        }
+    }
+}
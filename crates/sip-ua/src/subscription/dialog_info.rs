@@ -0,0 +1,441 @@
+//! Dialog event package ([RFC 4235](https://datatracker.ietf.org/doc/html/rfc4235)), used to
+//! monitor the state of extensions (busy lamp field)
+
+use super::EventPackage;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::str::from_utf8;
+
+/// Content-Type of dialog-info documents
+pub const DIALOG_INFO_CONTENT_TYPE: &str = "application/dialog-info+xml";
+
+const DIALOG_INFO_NAMESPACE: &str = "urn:ietf:params:xml:ns:dialog-info";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DialogInfoError {
+    #[error(transparent)]
+    Xml(#[from] roxmltree::Error),
+    #[error("root element is not a dialog-info element")]
+    NotDialogInfo,
+    #[error("dialog-info element is missing the {0} attribute")]
+    MissingAttribute(&'static str),
+    #[error("dialog-info element has an invalid {0} attribute")]
+    InvalidAttribute(&'static str),
+}
+
+/// Whether a dialog-info document contains the full or only the changed state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentState {
+    Full,
+    Partial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogDirection {
+    Initiator,
+    Recipient,
+}
+
+/// State of a single dialog of the monitored extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogState {
+    Trying,
+    Proceeding,
+    Early,
+    Confirmed,
+    Terminated,
+}
+
+impl DialogState {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "trying" => Some(Self::Trying),
+            "proceeding" => Some(Self::Proceeding),
+            "early" => Some(Self::Early),
+            "confirmed" => Some(Self::Confirmed),
+            "terminated" => Some(Self::Terminated),
+            _ => None,
+        }
+    }
+}
+
+/// A `dialog` element of a dialog-info document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogElement {
+    pub id: String,
+    pub call_id: Option<String>,
+    pub local_tag: Option<String>,
+    pub remote_tag: Option<String>,
+    pub direction: Option<DialogDirection>,
+    pub state: DialogState,
+
+    /// Event which caused the state change, e.g. `rejected` or `replaced`
+    pub event: Option<String>,
+
+    /// Seconds since the dialog was created
+    pub duration: Option<u32>,
+
+    pub local_identity: Option<String>,
+    pub remote_identity: Option<String>,
+}
+
+/// Dialog information document (`application/dialog-info+xml`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogInfo {
+    /// Incremented by the notifier with every document sent inside a subscription
+    pub version: u32,
+    pub state: DocumentState,
+
+    /// URI of the monitored extension
+    pub entity: String,
+    pub dialogs: Vec<DialogElement>,
+}
+
+impl DialogInfo {
+    pub fn parse(xml: &str) -> Result<Self, DialogInfoError> {
+        let document = Document::parse(xml)?;
+        let dialog_info = document.root_element();
+
+        if !dialog_info.has_tag_name((DIALOG_INFO_NAMESPACE, "dialog-info")) {
+            return Err(DialogInfoError::NotDialogInfo);
+        }
+
+        let version = dialog_info
+            .attribute("version")
+            .ok_or(DialogInfoError::MissingAttribute("version"))?
+            .parse()
+            .map_err(|_| DialogInfoError::InvalidAttribute("version"))?;
+
+        let state = match dialog_info
+            .attribute("state")
+            .ok_or(DialogInfoError::MissingAttribute("state"))?
+        {
+            "full" => DocumentState::Full,
+            "partial" => DocumentState::Partial,
+            _ => return Err(DialogInfoError::InvalidAttribute("state")),
+        };
+
+        let entity = dialog_info
+            .attribute("entity")
+            .ok_or(DialogInfoError::MissingAttribute("entity"))?;
+
+        let dialogs = children(dialog_info, "dialog")
+            .filter_map(parse_dialog)
+            .collect();
+
+        Ok(Self {
+            version,
+            state,
+            entity: entity.into(),
+            dialogs,
+        })
+    }
+}
+
+/// Parse a dialog element, returns `None` if it is missing the mandatory id or state
+fn parse_dialog(dialog: Node<'_, '_>) -> Option<DialogElement> {
+    let state_element = children(dialog, "state").next()?;
+
+    let identity = |participant: &'static str| {
+        children(dialog, participant)
+            .flat_map(|participant| children(participant, "identity"))
+            .find_map(text)
+            .map(String::from)
+    };
+
+    Some(DialogElement {
+        id: dialog.attribute("id")?.into(),
+        call_id: dialog.attribute("call-id").map(Into::into),
+        local_tag: dialog.attribute("local-tag").map(Into::into),
+        remote_tag: dialog.attribute("remote-tag").map(Into::into),
+        direction: match dialog.attribute("direction") {
+            Some("initiator") => Some(DialogDirection::Initiator),
+            Some("recipient") => Some(DialogDirection::Recipient),
+            _ => None,
+        },
+        state: DialogState::parse(text(state_element)?)?,
+        event: state_element.attribute("event").map(Into::into),
+        duration: children(dialog, "duration")
+            .find_map(text)
+            .and_then(|duration| duration.parse().ok()),
+        local_identity: identity("local"),
+        remote_identity: identity("remote"),
+    })
+}
+
+fn children<'a, 'i>(node: Node<'a, 'i>, name: &'static str) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children()
+        .filter(move |child| child.has_tag_name((DIALOG_INFO_NAMESPACE, name)))
+}
+
+fn text<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.text().map(str::trim)
+}
+
+/// The `dialog` event package
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dialogs;
+
+impl EventPackage for Dialogs {
+    type Notification = DialogInfo;
+
+    fn event(&self) -> &'static str {
+        "dialog"
+    }
+
+    fn accept(&self) -> &'static [&'static str] {
+        &[DIALOG_INFO_CONTENT_TYPE]
+    }
+
+    fn parse(&self, content_type: &str, body: &[u8]) -> Option<DialogInfo> {
+        if !content_type.eq_ignore_ascii_case(DIALOG_INFO_CONTENT_TYPE) {
+            return None;
+        }
+
+        match DialogInfo::parse(from_utf8(body).ok()?) {
+            Ok(dialog_info) => Some(dialog_info),
+            Err(e) => {
+                log::warn!("Failed to parse dialog-info document, {e}");
+                None
+            }
+        }
+    }
+}
+
+/// State of a busy lamp field key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LampState {
+    /// The extension has no active dialogs
+    Idle,
+
+    /// The extension is being called
+    Ringing,
+
+    /// The extension is in a call or calling someone
+    Busy,
+}
+
+/// Tracks the dialogs of a monitored extension by applying full and partial dialog-info documents
+#[derive(Debug, Default)]
+pub struct DialogMonitor {
+    version: Option<u32>,
+    dialogs: HashMap<String, DialogElement>,
+}
+
+impl DialogMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a dialog-info document received in a NOTIFY
+    ///
+    /// Outdated documents are ignored. Returns `false` if a partial document was received after
+    /// a missed version, in which case the subscription must be refreshed to receive the full state
+    /// (RFC 4235 Section 4.1).
+    pub fn apply(&mut self, info: DialogInfo) -> bool {
+        match (info.state, self.version) {
+            (_, Some(version)) if info.version <= version => return true,
+            (DocumentState::Partial, Some(version)) if info.version != version + 1 => return false,
+            (DocumentState::Partial, None) => return false,
+            (DocumentState::Full, _) => self.dialogs.clear(),
+            (DocumentState::Partial, _) => {}
+        }
+
+        self.version = Some(info.version);
+
+        for dialog in info.dialogs {
+            if dialog.state == DialogState::Terminated {
+                self.dialogs.remove(&dialog.id);
+            } else {
+                self.dialogs.insert(dialog.id.clone(), dialog);
+            }
+        }
+
+        true
+    }
+
+    /// Forget the tracked state, e.g. when the subscription is re-created
+    pub fn reset(&mut self) {
+        self.version = None;
+        self.dialogs.clear();
+    }
+
+    /// All active dialogs of the extension
+    pub fn dialogs(&self) -> impl Iterator<Item = &DialogElement> {
+        self.dialogs.values()
+    }
+
+    pub fn lamp_state(&self) -> LampState {
+        let mut lamp_state = LampState::Idle;
+
+        for dialog in self.dialogs.values() {
+            match (dialog.state, dialog.direction) {
+                (DialogState::Confirmed, _) => return LampState::Busy,
+                (_, Some(DialogDirection::Recipient)) => lamp_state = LampState::Ringing,
+                _ if lamp_state == LampState::Idle => lamp_state = LampState::Busy,
+                _ => {}
+            }
+        }
+
+        lamp_state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn document(version: u32, state: &str, dialogs: &str) -> DialogInfo {
+        DialogInfo::parse(&format!(
+            r#"<?xml version="1.0"?>
+<dialog-info xmlns="urn:ietf:params:xml:ns:dialog-info" version="{version}" state="{state}" entity="sip:alice@example.com">
+{dialogs}
+</dialog-info>"#
+        ))
+        .unwrap()
+    }
+
+    fn dialog(id: &str, direction: &str, state: &str) -> String {
+        format!(r#"<dialog id="{id}" direction="{direction}"><state>{state}</state></dialog>"#)
+    }
+
+    #[test]
+    fn parse_dialog_info() {
+        let info = DialogInfo::parse(
+            r#"<?xml version="1.0"?>
+<dialog-info xmlns="urn:ietf:params:xml:ns:dialog-info" version="1" state="full" entity="sip:alice@example.com">
+  <dialog id="as7d900as8" call-id="a84b4c76e66710" local-tag="1928301774" remote-tag="456" direction="initiator">
+    <state event="replaced">confirmed</state>
+    <duration>274</duration>
+    <local><identity>sip:alice@example.com</identity></local>
+    <remote><identity display="Bob">sip:bob@example.org</identity></remote>
+  </dialog>
+  <dialog id="no-state"/>
+</dialog-info>"#,
+        )
+        .unwrap();
+
+        assert_eq!(info.version, 1);
+        assert_eq!(info.state, DocumentState::Full);
+        assert_eq!(info.entity, "sip:alice@example.com");
+        assert_eq!(info.dialogs.len(), 1);
+
+        let dialog = &info.dialogs[0];
+        assert_eq!(dialog.id, "as7d900as8");
+        assert_eq!(dialog.call_id.as_deref(), Some("a84b4c76e66710"));
+        assert_eq!(dialog.local_tag.as_deref(), Some("1928301774"));
+        assert_eq!(dialog.remote_tag.as_deref(), Some("456"));
+        assert_eq!(dialog.direction, Some(DialogDirection::Initiator));
+        assert_eq!(dialog.state, DialogState::Confirmed);
+        assert_eq!(dialog.event.as_deref(), Some("replaced"));
+        assert_eq!(dialog.duration, Some(274));
+        assert_eq!(
+            dialog.local_identity.as_deref(),
+            Some("sip:alice@example.com")
+        );
+        assert_eq!(
+            dialog.remote_identity.as_deref(),
+            Some("sip:bob@example.org")
+        );
+    }
+
+    #[test]
+    fn parse_invalid_dialog_info() {
+        assert!(matches!(
+            DialogInfo::parse(
+                r#"<presence xmlns="urn:ietf:params:xml:ns:pidf" entity="sip:a@b"/>"#
+            ),
+            Err(DialogInfoError::NotDialogInfo)
+        ));
+        assert!(matches!(
+            DialogInfo::parse(
+                r#"<dialog-info xmlns="urn:ietf:params:xml:ns:dialog-info" state="full" entity="sip:a@b"/>"#
+            ),
+            Err(DialogInfoError::MissingAttribute("version"))
+        ));
+        assert!(matches!(
+            DialogInfo::parse(
+                r#"<dialog-info xmlns="urn:ietf:params:xml:ns:dialog-info" version="1" state="some" entity="sip:a@b"/>"#
+            ),
+            Err(DialogInfoError::InvalidAttribute("state"))
+        ));
+    }
+
+    #[test]
+    fn outdated_document_is_ignored() {
+        let mut monitor = DialogMonitor::new();
+
+        assert!(monitor.apply(document(2, "full", &dialog("1", "recipient", "early"))));
+        assert!(monitor.apply(document(2, "full", "")));
+        assert!(monitor.apply(document(
+            1,
+            "partial",
+            &dialog("1", "recipient", "terminated")
+        )));
+
+        assert_eq!(monitor.dialogs().count(), 1);
+        assert_eq!(monitor.lamp_state(), LampState::Ringing);
+    }
+
+    #[test]
+    fn partial_document_after_gap() {
+        let mut monitor = DialogMonitor::new();
+
+        // Partial documents require the full state first
+        assert!(!monitor.apply(document(1, "partial", &dialog("1", "recipient", "early"))));
+        assert_eq!(monitor.dialogs().count(), 0);
+
+        assert!(monitor.apply(document(1, "full", "")));
+        assert!(monitor.apply(document(2, "partial", &dialog("1", "recipient", "early"))));
+        assert!(!monitor.apply(document(
+            4,
+            "partial",
+            &dialog("1", "recipient", "confirmed")
+        )));
+
+        assert_eq!(monitor.lamp_state(), LampState::Ringing);
+    }
+
+    #[test]
+    fn full_document_resets_state() {
+        let mut monitor = DialogMonitor::new();
+
+        let dialogs = dialog("1", "recipient", "early") + &dialog("2", "initiator", "confirmed");
+        assert!(monitor.apply(document(1, "full", &dialogs)));
+        assert_eq!(monitor.dialogs().count(), 2);
+
+        // A full document is accepted after a gap and replaces all dialogs
+        assert!(monitor.apply(document(5, "full", &dialog("3", "initiator", "trying"))));
+
+        let ids: Vec<_> = monitor.dialogs().map(|dialog| dialog.id.as_str()).collect();
+        assert_eq!(ids, ["3"]);
+
+        monitor.reset();
+        assert_eq!(monitor.dialogs().count(), 0);
+        assert!(!monitor.apply(document(6, "partial", "")));
+    }
+
+    #[test]
+    fn lamp_state_of_dialogs() {
+        let mut monitor = DialogMonitor::new();
+        assert_eq!(monitor.lamp_state(), LampState::Idle);
+
+        monitor.apply(document(1, "full", &dialog("1", "initiator", "early")));
+        assert_eq!(monitor.lamp_state(), LampState::Busy);
+
+        monitor.apply(document(2, "partial", &dialog("2", "recipient", "early")));
+        assert_eq!(monitor.lamp_state(), LampState::Ringing);
+
+        monitor.apply(document(
+            3,
+            "partial",
+            &dialog("2", "recipient", "confirmed"),
+        ));
+        assert_eq!(monitor.lamp_state(), LampState::Busy);
+
+        let terminated =
+            dialog("1", "initiator", "terminated") + &dialog("2", "recipient", "terminated");
+        monitor.apply(document(4, "partial", &terminated));
+        assert_eq!(monitor.lamp_state(), LampState::Idle);
+    }
+}
@@ -0,0 +1,264 @@
+//! Message summary event package for message waiting indication
+//! ([RFC 3842](https://datatracker.ietf.org/doc/html/rfc3842))
+
+use super::{EventPackage, Subscriber};
+use crate::dialog::DialogLayer;
+use sip_core::{Endpoint, LayerKey};
+use sip_types::header::typed::Contact;
+use sip_types::uri::NameAddr;
+use std::str::from_utf8;
+use std::time::Duration;
+
+/// Content-Type of message summary bodies
+pub const MESSAGE_SUMMARY_CONTENT_TYPE: &str = "application/simple-message-summary";
+
+/// Message counts of a single message context class (e.g. `Voice-Message`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageCounts {
+    /// Name of the message context class as found in the body, e.g. `Voice-Message`
+    pub class: String,
+    pub new: u32,
+    pub old: u32,
+    pub new_urgent: u32,
+    pub old_urgent: u32,
+}
+
+/// Body of a message-summary NOTIFY (`application/simple-message-summary`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageSummary {
+    pub messages_waiting: bool,
+
+    /// URI of the account the summary is for, if the notifier included it
+    pub account: Option<String>,
+
+    pub counts: Vec<MessageCounts>,
+}
+
+impl MessageSummary {
+    /// Parse a message summary body, returns `None` if the mandatory `Messages-Waiting` line is missing
+    pub fn parse(body: &str) -> Option<Self> {
+        let mut messages_waiting = None;
+        let mut account = None;
+        let mut counts = vec![];
+
+        for line in body.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+
+            let key = key.trim();
+            let value = value.trim();
+
+            if key.eq_ignore_ascii_case("Messages-Waiting") {
+                messages_waiting = Some(value.eq_ignore_ascii_case("yes"));
+            } else if key.eq_ignore_ascii_case("Message-Account") {
+                account = Some(value.to_owned());
+            } else if let Some(message_counts) = parse_counts(key, value) {
+                counts.push(message_counts);
+            }
+        }
+
+        Some(Self {
+            messages_waiting: messages_waiting?,
+            account,
+            counts,
+        })
+    }
+
+    /// Returns the counts of the message context class, `class` is compared case-insensitively
+    pub fn counts(&self, class: &str) -> Option<&MessageCounts> {
+        self.counts
+            .iter()
+            .find(|counts| counts.class.eq_ignore_ascii_case(class))
+    }
+
+    /// Returns the counts of voice messages (voicemail)
+    pub fn voice(&self) -> Option<&MessageCounts> {
+        self.counts("Voice-Message")
+    }
+}
+
+/// Parse a line in the format of `new/old (new_urgent/old_urgent)` where the urgent counts are optional
+fn parse_counts(class: &str, value: &str) -> Option<MessageCounts> {
+    fn pair(s: &str) -> Option<(u32, u32)> {
+        let (a, b) = s.split_once('/')?;
+
+        Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+    }
+
+    let (counts, urgent) = match value.split_once('(') {
+        Some((counts, urgent)) => (counts, Some(urgent.trim_end().strip_suffix(')')?)),
+        None => (value, None),
+    };
+
+    let (new, old) = pair(counts)?;
+    let (new_urgent, old_urgent) = match urgent {
+        Some(urgent) => pair(urgent)?,
+        None => (0, 0),
+    };
+
+    Some(MessageCounts {
+        class: class.to_owned(),
+        new,
+        old,
+        new_urgent,
+        old_urgent,
+    })
+}
+
+/// The `message-summary` event package, used to watch the voicemail box of an account
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessageWaiting;
+
+impl EventPackage for MessageWaiting {
+    type Notification = MessageSummary;
+
+    fn event(&self) -> &'static str {
+        "message-summary"
+    }
+
+    fn accept(&self) -> &'static [&'static str] {
+        &[MESSAGE_SUMMARY_CONTENT_TYPE]
+    }
+
+    fn parse(&self, content_type: &str, body: &[u8]) -> Option<MessageSummary> {
+        if !content_type.eq_ignore_ascii_case(MESSAGE_SUMMARY_CONTENT_TYPE) {
+            return None;
+        }
+
+        MessageSummary::parse(from_utf8(body).ok()?)
+    }
+}
+
+impl Subscriber<MessageWaiting> {
+    /// Create a subscriber watching the message counts of the account `id`
+    ///
+    /// The subscription is sent to the account's address of record, where it is
+    /// usually routed to the voicemail server by the registrar.
+    pub fn message_waiting(
+        endpoint: Endpoint,
+        dialog_layer: LayerKey<DialogLayer>,
+        id: NameAddr,
+        contact: Contact,
+        expires: Duration,
+    ) -> Self {
+        let target = id.uri.clone();
+
+        Self::new(
+            endpoint,
+            dialog_layer,
+            MessageWaiting,
+            id,
+            contact,
+            target,
+            expires,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_summary() {
+        let summary = MessageSummary::parse(
+            "Messages-Waiting: yes\r\n\
+             Message-Account: sip:alice@vmail.example.com\r\n\
+             Voice-Message: 4/8 (1/2)\r\n\
+             Fax-Message: 1/0\r\n",
+        )
+        .unwrap();
+
+        assert!(summary.messages_waiting);
+        assert_eq!(
+            summary.account.as_deref(),
+            Some("sip:alice@vmail.example.com")
+        );
+
+        assert_eq!(
+            summary.voice(),
+            Some(&MessageCounts {
+                class: "Voice-Message".into(),
+                new: 4,
+                old: 8,
+                new_urgent: 1,
+                old_urgent: 2,
+            })
+        );
+
+        // Urgent counts are optional
+        let fax = summary.counts("fax-message").unwrap();
+        assert_eq!((fax.new, fax.old), (1, 0));
+        assert_eq!((fax.new_urgent, fax.old_urgent), (0, 0));
+    }
+
+    #[test]
+    fn messages_waiting_is_required() {
+        assert!(MessageSummary::parse("Voice-Message: 1/0\r\n").is_none());
+        assert!(MessageSummary::parse("").is_none());
+
+        let summary = MessageSummary::parse("Messages-Waiting: no\r\n").unwrap();
+        assert!(!summary.messages_waiting);
+        assert!(summary.account.is_none());
+        assert!(summary.counts.is_empty());
+    }
+
+    #[test]
+    fn keys_are_case_insensitive() {
+        let summary = MessageSummary::parse(
+            "messages-waiting: YES\r\nmessage-account: sip:alice@example.com\r\nvoice-message: 2/3\r\n",
+        )
+        .unwrap();
+
+        assert!(summary.messages_waiting);
+        assert!(summary.account.is_some());
+        assert_eq!(summary.voice().unwrap().new, 2);
+    }
+
+    #[test]
+    fn malformed_counts_are_ignored() {
+        let summary = MessageSummary::parse(
+            "Messages-Waiting: yes\r\n\
+             Voice-Message: 2\r\n\
+             Fax-Message: a/b\r\n\
+             Pager-Message: 1/2 (3/4\r\n\
+             Multimedia-Message: 1/2 (x/4)\r\n\
+             Text-Message: 5 / 6 ( 7 / 8 )\r\n\
+             not a header line\r\n",
+        )
+        .unwrap();
+
+        assert_eq!(summary.counts.len(), 1);
+
+        let text = summary.counts("Text-Message").unwrap();
+        assert_eq!((text.new, text.old), (5, 6));
+        assert_eq!((text.new_urgent, text.old_urgent), (7, 8));
+    }
+
+    #[test]
+    fn parse_counts_line() {
+        assert_eq!(
+            parse_counts("Voice-Message", "0/0"),
+            Some(MessageCounts {
+                class: "Voice-Message".into(),
+                new: 0,
+                old: 0,
+                new_urgent: 0,
+                old_urgent: 0,
+            })
+        );
+        assert!(parse_counts("Voice-Message", "").is_none());
+        assert!(parse_counts("Voice-Message", "1/-1").is_none());
+    }
+
+    #[test]
+    fn package_checks_content_type() {
+        let body = b"Messages-Waiting: yes\r\n";
+
+        assert!(MessageWaiting
+            .parse("Application/Simple-Message-Summary", body)
+            .is_some());
+        assert!(MessageWaiting.parse("text/plain", body).is_none());
+    }
+}
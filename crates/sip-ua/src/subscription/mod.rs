@@ -0,0 +1,653 @@
+//! Client side of SIP-specific event notification ([RFC 6665](https://datatracker.ietf.org/doc/html/rfc6665))
+
+use crate::dialog::{
+    register_early_usage, ClientDialogBuilder, Dialog, DialogLayer, Usage, UsageGuard,
+};
+use sip_core::transaction::TsxResponse;
+use sip_core::{Endpoint, IncomingRequest, LayerKey, MayTake, Request, Result};
+use sip_types::header::typed::{
+    Accept, Contact, ContentType, Event, EventReasonValue, Expires, SubStateValue,
+    SubscriptionState,
+};
+use sip_types::header::HeaderError;
+use sip_types::uri::{NameAddr, Uri};
+use sip_types::{Code, CodeKind, Method};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::time::{sleep_until, Instant};
+
+pub mod dialog_info;
+pub mod message_summary;
+pub mod presence;
+pub mod refer;
+
+/// Event package which defines the `Event` header value and the NOTIFY bodies of a [`Subscription`]
+pub trait EventPackage: Send + Sync + 'static {
+    /// Typed content of NOTIFY bodies
+    type Notification: Send;
+
+    /// Name of the event package, used as value of the `Event` header
+    fn event(&self) -> &'static str;
+
+    /// Content types of NOTIFY bodies understood by the package, sent in the `Accept` header
+    fn accept(&self) -> &'static [&'static str];
+
+    /// Parse the body of a NOTIFY request, returns `None` if the body is not understood
+    fn parse(&self, content_type: &str, body: &[u8]) -> Option<Self::Notification>;
+}
+
+/// Reason why a [`Subscription`] was terminated
+#[derive(Debug, Clone, PartialEq)]
+pub enum TerminationReason {
+    /// The notifier terminated the subscription using a NOTIFY with the `terminated` state
+    Notifier {
+        reason: Option<EventReasonValue>,
+        retry_after: Option<Duration>,
+    },
+
+    /// A refresh of the subscription was rejected with the given status code
+    RefreshRejected(Code),
+}
+
+pub enum SubscriptionEvent<N> {
+    /// A NOTIFY request has been received and responded to
+    Notify {
+        state: SubscriptionState,
+
+        /// Parsed body of the NOTIFY, `None` if it has no body or it couldn't be parsed
+        notification: Option<N>,
+    },
+
+    /// The subscription has been terminated, no more NOTIFY requests will be received
+    Terminated(TerminationReason),
+}
+
+/// Creates a [`Subscription`] by sending a SUBSCRIBE request outside of a dialog
+#[derive(Debug)]
+pub struct Subscriber<P> {
+    pub dialog_builder: ClientDialogBuilder,
+    package: P,
+    expires: Duration,
+
+    notify_sink: Sender<IncomingRequest>,
+    notify_events: Receiver<IncomingRequest>,
+
+    // receives NOTIFY requests until the dialog is created
+    _early_usage_guard: UsageGuard,
+}
+
+impl<P: EventPackage> Subscriber<P> {
+    pub fn new(
+        endpoint: Endpoint,
+        dialog_layer: LayerKey<DialogLayer>,
+        package: P,
+        local_addr: NameAddr,
+        local_contact: Contact,
+        target: Box<dyn Uri>,
+        expires: Duration,
+    ) -> Self {
+        let dialog_builder =
+            ClientDialogBuilder::new(endpoint, dialog_layer, local_addr, local_contact, target);
+
+        let (notify_sink, notify_events) = mpsc::channel(4);
+
+        // A NOTIFY may arrive before the response to the SUBSCRIBE
+        // (RFC 6665 Section 4.1.2.4), listen for it before sending any request
+        let early_usage_guard = register_early_usage(
+            dialog_builder.endpoint.clone(),
+            dialog_layer,
+            dialog_builder.call_id.0.clone(),
+            dialog_builder
+                .local_fromto
+                .tag
+                .clone()
+                .expect("builder sets local tag"),
+            SubscriptionUsage {
+                event: package.event(),
+                id: None,
+                notify_sink: notify_sink.clone(),
+            },
+        );
+
+        Self {
+            dialog_builder,
+            package,
+            expires,
+            notify_sink,
+            notify_events,
+            _early_usage_guard: early_usage_guard,
+        }
+    }
+
+    /// Create a new SUBSCRIBE request
+    ///
+    /// Every call increments the CSeq, so it can also be used to retry the request with authorization.
+    pub fn create_subscribe(&mut self) -> Request {
+        self.dialog_builder.local_cseq += 1;
+
+        let mut request = self.dialog_builder.create_request(Method::SUBSCRIBE);
+        add_subscribe_headers(&mut request, &self.package, self.expires);
+        request
+    }
+
+    /// Send the SUBSCRIBE request and wait for its final response
+    pub async fn send_subscribe(&mut self, request: Request) -> Result<TsxResponse> {
+        let mut transaction = self
+            .dialog_builder
+            .endpoint
+            .send_request(request, &mut self.dialog_builder.target_tp_info)
+            .await?;
+
+        transaction.receive_final().await
+    }
+
+    /// Create the subscription from the successful response to the SUBSCRIBE request
+    ///
+    /// NOTIFY requests which arrived before the response are returned by [`Subscription::receive`].
+    pub fn create_subscription(
+        mut self,
+        response: &TsxResponse,
+    ) -> Result<Subscription<P>, HeaderError> {
+        assert_eq!(response.line.code.kind(), CodeKind::Success);
+
+        let dialog = self.dialog_builder.create_dialog_from_response(response)?;
+
+        // The notifier may shorten the duration of the subscription
+        let expires = response
+            .headers
+            .get_named::<Expires>()
+            .map(|expires| Duration::from_secs(expires.0.into()))
+            .unwrap_or(self.expires);
+
+        let usage_guard = dialog.register_usage(SubscriptionUsage {
+            event: self.package.event(),
+            id: None,
+            notify_sink: self.notify_sink,
+        });
+
+        Ok(Subscription {
+            endpoint: self.dialog_builder.endpoint,
+            package: self.package,
+            notify_events: self.notify_events,
+            expires: self.expires,
+            refresh_at: Some(Instant::now() + refresh_interval(expires)),
+            terminated: None,
+            _usage_guard: usage_guard,
+            dialog: Arc::new(dialog),
+        })
+    }
+}
+
+/// An established subscription to an event package
+///
+/// Must be driven using [`Subscription::receive`] to process NOTIFY requests and refresh the subscription.
+#[derive(Debug)]
+pub struct Subscription<P> {
+    pub endpoint: Endpoint,
+    package: P,
+
+    notify_events: Receiver<IncomingRequest>,
+
+    /// Duration requested when refreshing the subscription
+    expires: Duration,
+
+    /// Time at which the subscription must be refreshed, `None` after unsubscribing
+    refresh_at: Option<Instant>,
+
+    terminated: Option<TerminationReason>,
+
+    // drop usage before dialog
+    _usage_guard: UsageGuard,
+    pub dialog: Arc<Dialog>,
+}
+
+impl<P: EventPackage> Subscription<P> {
+    /// Create a subscription which is implicitly created by a request inside an existing dialog
+    /// (e.g. a REFER request, [RFC 6665 Section 4.2](https://datatracker.ietf.org/doc/html/rfc6665#section-4.2))
+    ///
+    /// Must be created before sending the request, so no NOTIFY is missed. NOTIFY requests are only
+    /// accepted if the `id` parameter of their Event header is absent or equal to `id`. The subscription
+    /// is not refreshed automatically, [`Subscription::refresh`] requests a duration of `expires`.
+    pub(crate) fn implicit(
+        endpoint: Endpoint,
+        package: P,
+        dialog: Arc<Dialog>,
+        id: u32,
+        expires: Duration,
+    ) -> Self {
+        let (notify_sink, notify_events) = mpsc::channel(4);
+
+        let usage_guard = dialog.register_usage(SubscriptionUsage {
+            event: package.event(),
+            id: Some(id),
+            notify_sink,
+        });
+
+        Self {
+            endpoint,
+            package,
+            notify_events,
+            expires,
+            refresh_at: None,
+            terminated: None,
+            _usage_guard: usage_guard,
+            dialog,
+        }
+    }
+
+    /// Wait for the next event of the subscription, refreshes the subscription before it expires
+    ///
+    /// After a NOTIFY with the `terminated` state has been returned, every following call
+    /// returns [`SubscriptionEvent::Terminated`].
+    pub async fn receive(&mut self) -> Result<SubscriptionEvent<P::Notification>> {
+        loop {
+            if let Some(reason) = &self.terminated {
+                return Ok(SubscriptionEvent::Terminated(reason.clone()));
+            }
+
+            let refresh = async {
+                match self.refresh_at {
+                    Some(refresh_at) => sleep_until(refresh_at).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            select! {
+                _ = refresh => {
+                    let response = self.refresh().await?;
+
+                    if response.line.code.kind() != CodeKind::Success {
+                        self.terminated = Some(TerminationReason::RefreshRejected(response.line.code));
+                    }
+                }
+                notify = self.notify_events.recv() => {
+                    let notify = notify.expect("usage is registered as long as the subscription exists");
+
+                    if let Some(event) = self.handle_notify(notify).await? {
+                        return Ok(event);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Refresh the subscription by sending a SUBSCRIBE request inside the dialog
+    pub async fn refresh(&mut self) -> Result<TsxResponse> {
+        // Do not retry immediately when the request fails
+        self.refresh_at = Some(Instant::now() + self.expires);
+
+        let response = self.send_subscribe(self.expires).await?;
+
+        if response.line.code.kind() == CodeKind::Success {
+            let expires = response
+                .headers
+                .get_named::<Expires>()
+                .map(|expires| Duration::from_secs(expires.0.into()))
+                .unwrap_or(self.expires);
+
+            self.refresh_at = Some(Instant::now() + refresh_interval(expires));
+        }
+
+        Ok(response)
+    }
+
+    /// Terminate the subscription by sending a SUBSCRIBE with `Expires: 0`
+    ///
+    /// The notifier responds with a final NOTIFY, which is returned by [`Subscription::receive`].
+    pub async fn unsubscribe(&mut self) -> Result<TsxResponse> {
+        self.refresh_at = None;
+
+        self.send_subscribe(Duration::ZERO).await
+    }
+
+    async fn send_subscribe(&self, expires: Duration) -> Result<TsxResponse> {
+        let mut request = self.dialog.create_request(Method::SUBSCRIBE);
+        add_subscribe_headers(&mut request, &self.package, expires);
+
+        let mut target_tp_info = self.dialog.target_tp_info.lock().await;
+
+        let mut transaction = self
+            .endpoint
+            .send_request(request, &mut target_tp_info)
+            .await?;
+
+        drop(target_tp_info);
+
+        transaction.receive_final().await
+    }
+
+    async fn handle_notify(
+        &mut self,
+        mut notify: IncomingRequest,
+    ) -> Result<Option<SubscriptionEvent<P::Notification>>> {
+        let transaction = self.endpoint.create_server_tsx(&mut notify);
+
+        let state = match notify.headers.get_named::<SubscriptionState>() {
+            Ok(state) => state,
+            Err(e) => {
+                log::warn!("Received NOTIFY with invalid Subscription-State header, {e}");
+
+                let response = self
+                    .dialog
+                    .create_response(&notify, Code::BAD_REQUEST, None)?;
+                transaction.respond(response).await?;
+
+                return Ok(None);
+            }
+        };
+
+        let response = self.dialog.create_response(&notify, Code::OK, None)?;
+        transaction.respond(response).await?;
+
+        match state.state {
+            SubStateValue::Active | SubStateValue::Pending => {
+                if let Some(expires) = state.expires {
+                    let expires = Duration::from_secs(expires.into());
+
+                    if let Some(refresh_at) = &mut self.refresh_at {
+                        *refresh_at = (*refresh_at).min(Instant::now() + refresh_interval(expires));
+                    }
+                }
+            }
+            SubStateValue::Terminated => {
+                self.refresh_at = None;
+                self.terminated = Some(TerminationReason::Notifier {
+                    reason: state.reason.clone(),
+                    retry_after: state
+                        .retry_after
+                        .map(|retry_after| Duration::from_secs(retry_after.into())),
+                });
+            }
+        }
+
+        let notification = self.parse_body(&notify);
+
+        Ok(Some(SubscriptionEvent::Notify {
+            state,
+            notification,
+        }))
+    }
+
+    fn parse_body(&self, notify: &IncomingRequest) -> Option<P::Notification> {
+        if notify.body.is_empty() {
+            return None;
+        }
+
+        let content_type = notify.headers.get_named::<ContentType>().ok()?;
+        let notification = self.package.parse(&content_type.0, &notify.body);
+
+        if notification.is_none() {
+            log::warn!(
+                "Failed to parse {} body of {} NOTIFY",
+                content_type.0,
+                self.package.event()
+            );
+        }
+
+        notification
+    }
+}
+
+struct SubscriptionUsage {
+    event: &'static str,
+
+    /// Value of the Event header's `id` parameter identifying the subscription, if any
+    id: Option<u32>,
+
+    notify_sink: Sender<IncomingRequest>,
+}
+
+impl SubscriptionUsage {
+    /// Returns if the Event header of a NOTIFY belongs to this subscription
+    fn matches(&self, event: &Event) -> bool {
+        let mut parts = event.0.split(';').map(str::trim);
+
+        let package = parts.next().unwrap_or_default();
+
+        let id = parts.find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            name.trim().eq_ignore_ascii_case("id").then(|| value.trim())
+        });
+
+        let id_matches = match (id, self.id) {
+            (None, _) => true,
+            (Some(id), Some(own_id)) => id.parse() == Ok(own_id),
+            (Some(_), None) => false,
+        };
+
+        package.eq_ignore_ascii_case(self.event) && id_matches
+    }
+}
+
+#[async_trait::async_trait]
+impl Usage for SubscriptionUsage {
+    fn name(&self) -> &'static str {
+        "subscription-usage"
+    }
+
+    async fn receive(&self, _endpoint: &Endpoint, mut request: MayTake<'_, IncomingRequest>) {
+        if request.line.method != Method::NOTIFY {
+            return;
+        }
+
+        let matches_event = request
+            .headers
+            .get_named::<Event>()
+            .is_ok_and(|event| self.matches(&event));
+
+        if !matches_event {
+            return;
+        }
+
+        let notify = request.inner().take().unwrap();
+
+        if let Err(SendError(notify)) = self.notify_sink.send(notify).await {
+            *request.inner() = Some(notify);
+        }
+    }
+}
+
+fn add_subscribe_headers<P: EventPackage>(request: &mut Request, package: &P, expires: Duration) {
+    request.headers.insert_named(&Event::new(package.event()));
+    request
+        .headers
+        .insert_named(&Expires(expires.as_secs() as u32));
+
+    let accept: Vec<Accept> = package
+        .accept()
+        .iter()
+        .map(|content_type| Accept((*content_type).into()))
+        .collect();
+
+    if !accept.is_empty() {
+        request.headers.insert_named(&accept);
+    }
+}
+
+/// Interval after which a subscription with the given duration must be refreshed
+///
+/// Leaves enough time for the refresh to complete before the subscription expires.
+fn refresh_interval(expires: Duration) -> Duration {
+    if expires > Duration::from_secs(64) {
+        expires - Duration::from_secs(32)
+    } else {
+        expires / 2
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes::Bytes;
+    use bytesstr::BytesStr;
+    use sip_core::transaction::ClientTsx;
+    use sip_core::transport::udp::Udp;
+    use sip_core::transport::TargetTransportInfo;
+    use sip_core::Layer;
+    use sip_types::header::typed::FromTo;
+    use sip_types::uri::sip::SipUri;
+    use sip_types::Name;
+    use std::net::SocketAddr;
+
+    fn usage(id: Option<u32>) -> SubscriptionUsage {
+        SubscriptionUsage {
+            event: "refer",
+            id,
+            notify_sink: mpsc::channel(1).0,
+        }
+    }
+
+    #[test]
+    fn event_matches_package() {
+        assert!(usage(None).matches(&Event::new("refer")));
+        assert!(usage(None).matches(&Event::new("Refer")));
+        assert!(!usage(None).matches(&Event::new("presence")));
+        assert!(!usage(None).matches(&Event::new("refer;id=1")));
+    }
+
+    #[test]
+    fn event_matches_id() {
+        assert!(usage(Some(93809824)).matches(&Event::new("refer;id=93809824")));
+        assert!(usage(Some(93809824)).matches(&Event::new("refer")));
+        assert!(!usage(Some(93809824)).matches(&Event::new("refer;id=93809825")));
+    }
+
+    struct TestPackage;
+
+    impl EventPackage for TestPackage {
+        type Notification = String;
+
+        fn event(&self) -> &'static str {
+            "test"
+        }
+
+        fn accept(&self) -> &'static [&'static str] {
+            &["text/plain"]
+        }
+
+        fn parse(&self, _content_type: &str, body: &[u8]) -> Option<String> {
+            String::from_utf8(body.to_vec()).ok()
+        }
+    }
+
+    /// Notifier which sends the initial NOTIFY before responding to the SUBSCRIBE
+    struct Notifier {
+        dialog_layer: LayerKey<DialogLayer>,
+        state: parking_lot::Mutex<Vec<(Dialog, ClientTsx)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Layer for Notifier {
+        fn name(&self) -> &'static str {
+            "test-notifier"
+        }
+
+        async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+            if request.line.method != Method::SUBSCRIBE {
+                return;
+            }
+
+            let mut subscribe = request.take();
+
+            let contact: SipUri = "sip:notifier@127.0.0.1".parse().unwrap();
+            let dialog = Dialog::new_server(
+                endpoint.clone(),
+                self.dialog_layer,
+                &subscribe,
+                Contact::new(NameAddr::uri(contact)),
+            )
+            .unwrap();
+
+            let mut notify = dialog.create_request(Method::NOTIFY);
+            notify.headers.insert_named(&Event::new("test"));
+            notify
+                .headers
+                .insert_named(&SubscriptionState::new(SubStateValue::Active).with_expires(300));
+            notify
+                .headers
+                .insert_named(&ContentType(BytesStr::from_static("text/plain")));
+            notify.body = Bytes::from_static(b"initial state");
+
+            let notify_tsx = endpoint
+                .send_request(notify, &mut TargetTransportInfo::default())
+                .await
+                .unwrap();
+
+            let mut response = endpoint.create_response(&subscribe, Code::OK, None);
+            response
+                .msg
+                .headers
+                .edit(Name::TO, |to: &mut FromTo| {
+                    to.tag.clone_from(&dialog.local_fromto.tag)
+                })
+                .unwrap();
+            response.msg.headers.insert_named(&dialog.local_contact);
+
+            let tsx = endpoint.create_server_tsx(&mut subscribe);
+            tsx.respond(response).await.unwrap();
+
+            self.state.lock().push((dialog, notify_tsx));
+        }
+    }
+
+    async fn spawn_notifier() -> (Endpoint, SocketAddr) {
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let dialog_layer = builder.add_layer(DialogLayer::default());
+        builder.add_layer(Notifier {
+            dialog_layer,
+            state: Default::default(),
+        });
+
+        (builder.build(), transport.bound())
+    }
+
+    #[tokio::test]
+    async fn notify_before_subscribe_response() {
+        let (_notifier, notifier_addr) = spawn_notifier().await;
+
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let dialog_layer = builder.add_layer(DialogLayer::default());
+        let endpoint = builder.build();
+
+        let id: SipUri = "sip:alice@example.com".parse().unwrap();
+        let contact: SipUri = format!("sip:alice@{}", transport.bound()).parse().unwrap();
+        let target: SipUri = format!("sip:notifier@{notifier_addr}").parse().unwrap();
+
+        let mut subscriber = Subscriber::new(
+            endpoint,
+            dialog_layer,
+            TestPackage,
+            NameAddr::uri(id),
+            Contact::new(NameAddr::uri(contact)),
+            Box::new(target),
+            Duration::from_secs(300),
+        );
+
+        let subscribe = subscriber.create_subscribe();
+        let response = subscriber.send_subscribe(subscribe).await.unwrap();
+        assert_eq!(response.line.code, Code::OK);
+
+        let mut subscription = subscriber.create_subscription(&response).unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), subscription.receive())
+            .await
+            .unwrap()
+            .unwrap();
+
+        match event {
+            SubscriptionEvent::Notify {
+                state,
+                notification,
+            } => {
+                assert_eq!(state.state, SubStateValue::Active);
+                assert_eq!(notification.as_deref(), Some("initial state"));
+            }
+            SubscriptionEvent::Terminated(_) => panic!("expected NOTIFY"),
+        }
+    }
+}
@@ -0,0 +1,674 @@
+//! Presence event package ([RFC 3856](https://datatracker.ietf.org/doc/html/rfc3856)) using
+//! PIDF documents ([RFC 3863](https://datatracker.ietf.org/doc/html/rfc3863))
+
+use super::{EventPackage, Subscription, SubscriptionEvent};
+use crate::util::random_sequence_number;
+use bytesstr::BytesStr;
+use roxmltree::{Document, Node};
+use sip_core::transaction::TsxResponse;
+use sip_core::{Endpoint, Request, Result};
+use sip_types::header::typed::{
+    CSeq, CallID, ContentType, Event, Expires, FromTo, MinExpires, Routing, SipETag, SipIfMatch,
+};
+use sip_types::uri::{NameAddr, Uri};
+use sip_types::{Code, CodeKind, Method, Name};
+use std::fmt::Write;
+use std::str::from_utf8;
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// Content-Type of PIDF documents
+pub const PIDF_CONTENT_TYPE: &str = "application/pidf+xml";
+
+const PIDF_NAMESPACE: &str = "urn:ietf:params:xml:ns:pidf";
+
+#[derive(Debug, thiserror::Error)]
+pub enum PidfError {
+    #[error(transparent)]
+    Xml(#[from] roxmltree::Error),
+    #[error("root element is not a PIDF presence element")]
+    NotPresence,
+    #[error("presence element is missing the entity attribute")]
+    MissingEntity,
+}
+
+/// Basic status of a presence tuple
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BasicStatus {
+    Open,
+    Closed,
+}
+
+/// A single `tuple` of a PIDF document, usually describing one device or service of the presentity
+#[derive(Debug, Clone, PartialEq)]
+pub struct PidfTuple {
+    pub id: String,
+    pub status: Option<BasicStatus>,
+    pub contact: Option<String>,
+    pub note: Option<String>,
+}
+
+impl PidfTuple {
+    pub fn new(id: impl Into<String>, status: BasicStatus) -> Self {
+        Self {
+            id: id.into(),
+            status: Some(status),
+            contact: None,
+            note: None,
+        }
+    }
+}
+
+/// Presence Information Data Format document (`application/pidf+xml`)
+///
+/// Only the elements defined by RFC 3863 are supported, extensions are ignored when parsing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pidf {
+    /// URI of the presentity
+    pub entity: String,
+    pub tuples: Vec<PidfTuple>,
+    pub notes: Vec<String>,
+}
+
+impl Pidf {
+    pub fn new(entity: impl Into<String>) -> Self {
+        Self {
+            entity: entity.into(),
+            tuples: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// Returns if any tuple of the presentity has the `open` status
+    pub fn is_open(&self) -> bool {
+        self.tuples
+            .iter()
+            .any(|tuple| tuple.status == Some(BasicStatus::Open))
+    }
+
+    pub fn parse(xml: &str) -> Result<Self, PidfError> {
+        let document = Document::parse(xml)?;
+        let presence = document.root_element();
+
+        if !presence.has_tag_name((PIDF_NAMESPACE, "presence")) {
+            return Err(PidfError::NotPresence);
+        }
+
+        let entity = presence
+            .attribute("entity")
+            .ok_or(PidfError::MissingEntity)?;
+
+        let tuples = pidf_children(presence, "tuple")
+            .map(|tuple| PidfTuple {
+                id: tuple.attribute("id").unwrap_or_default().into(),
+                status: pidf_children(tuple, "status")
+                    .flat_map(|status| pidf_children(status, "basic"))
+                    .find_map(|basic| match basic.text()?.trim() {
+                        "open" => Some(BasicStatus::Open),
+                        "closed" => Some(BasicStatus::Closed),
+                        _ => None,
+                    }),
+                contact: pidf_children(tuple, "contact")
+                    .find_map(text)
+                    .map(Into::into),
+                note: pidf_children(tuple, "note").find_map(text).map(Into::into),
+            })
+            .collect();
+
+        let notes = pidf_children(presence, "note")
+            .filter_map(text)
+            .map(Into::into)
+            .collect();
+
+        Ok(Self {
+            entity: entity.into(),
+            tuples,
+            notes,
+        })
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+
+        // Writing to a String cannot fail
+        let _ = self.write_xml(&mut xml);
+
+        xml
+    }
+
+    fn write_xml(&self, xml: &mut String) -> std::fmt::Result {
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            xml,
+            r#"<presence xmlns="{PIDF_NAMESPACE}" entity="{}">"#,
+            escape(&self.entity)
+        )?;
+
+        for tuple in &self.tuples {
+            writeln!(xml, r#"  <tuple id="{}">"#, escape(&tuple.id))?;
+
+            if let Some(status) = tuple.status {
+                let status = match status {
+                    BasicStatus::Open => "open",
+                    BasicStatus::Closed => "closed",
+                };
+
+                writeln!(xml, "    <status><basic>{status}</basic></status>")?;
+            }
+
+            if let Some(contact) = &tuple.contact {
+                writeln!(xml, "    <contact>{}</contact>", escape(contact))?;
+            }
+
+            if let Some(note) = &tuple.note {
+                writeln!(xml, "    <note>{}</note>", escape(note))?;
+            }
+
+            writeln!(xml, "  </tuple>")?;
+        }
+
+        for note in &self.notes {
+            writeln!(xml, "  <note>{}</note>", escape(note))?;
+        }
+
+        writeln!(xml, "</presence>")
+    }
+}
+
+fn pidf_children<'a, 'i>(
+    node: Node<'a, 'i>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children()
+        .filter(move |child| child.has_tag_name((PIDF_NAMESPACE, name)))
+}
+
+fn text<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.text().map(str::trim)
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// The `presence` event package
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Presence;
+
+impl EventPackage for Presence {
+    type Notification = Pidf;
+
+    fn event(&self) -> &'static str {
+        "presence"
+    }
+
+    fn accept(&self) -> &'static [&'static str] {
+        &[PIDF_CONTENT_TYPE]
+    }
+
+    fn parse(&self, content_type: &str, body: &[u8]) -> Option<Pidf> {
+        if !content_type.eq_ignore_ascii_case(PIDF_CONTENT_TYPE) {
+            return None;
+        }
+
+        match Pidf::parse(from_utf8(body).ok()?) {
+            Ok(pidf) => Some(pidf),
+            Err(e) => {
+                log::warn!("Failed to parse PIDF document, {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Watches the presence of a single presentity, e.g. an entry of a buddy list
+#[derive(Debug)]
+pub struct PresenceWatcher {
+    pub subscription: Subscription<Presence>,
+    presence: Option<Pidf>,
+}
+
+impl PresenceWatcher {
+    pub fn new(subscription: Subscription<Presence>) -> Self {
+        Self {
+            subscription,
+            presence: None,
+        }
+    }
+
+    /// The last presence document received from the presentity
+    pub fn presence(&self) -> Option<&Pidf> {
+        self.presence.as_ref()
+    }
+
+    /// Returns if the presentity is known to be online
+    pub fn is_open(&self) -> bool {
+        self.presence.as_ref().is_some_and(Pidf::is_open)
+    }
+
+    /// Wait until a new presence document has been received
+    ///
+    /// Returns `None` once the subscription has been terminated, the reason can be retrieved
+    /// using [`Subscription::receive`].
+    pub async fn changed(&mut self) -> Result<Option<&Pidf>> {
+        loop {
+            match self.subscription.receive().await? {
+                SubscriptionEvent::Notify {
+                    notification: Some(pidf),
+                    ..
+                } => {
+                    return Ok(Some(self.presence.insert(pidf)));
+                }
+                SubscriptionEvent::Notify { .. } => {}
+                SubscriptionEvent::Terminated(_) => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Publishes the presence of a user using PUBLISH requests ([RFC 3903](https://datatracker.ietf.org/doc/html/rfc3903))
+///
+/// Like [`Registration`](crate::register::Registration) this only creates the requests
+/// and handles their responses, sending them is up to the user.
+#[derive(Debug)]
+pub struct PresencePublisher {
+    presentity: Box<dyn Uri>,
+
+    /// Route of the endpoint's outbound proxy
+    outbound_proxy: Option<Routing>,
+
+    to: FromTo,
+    from: FromTo,
+
+    cseq: u32,
+    call_id: CallID,
+
+    /// Requested lifetime of the published state
+    expires: Duration,
+
+    /// Entity-tag of the published state assigned by the presence server
+    etag: Option<BytesStr>,
+
+    /// Time at which the published state must be refreshed
+    refresh_at: Option<Instant>,
+}
+
+impl PresencePublisher {
+    pub fn new(endpoint: &Endpoint, id: NameAddr, expires: Duration) -> Self {
+        Self {
+            presentity: id.uri.clone(),
+            outbound_proxy: endpoint.outbound_proxy().cloned(),
+            to: FromTo::new(id.clone(), None),
+            from: FromTo::new(id, Some(endpoint.generate_tag())),
+            cseq: random_sequence_number(),
+            call_id: endpoint.generate_call_id(),
+            expires,
+            etag: None,
+            refresh_at: None,
+        }
+    }
+
+    /// Returns if presence state has been published and not yet removed
+    pub fn is_published(&self) -> bool {
+        self.etag.is_some()
+    }
+
+    /// Create a PUBLISH request
+    ///
+    /// With `document` set the published state is replaced. Without, the previously
+    /// published state is refreshed, which requires a prior successful publication.
+    pub fn create_publish(&mut self, document: Option<&Pidf>) -> Request {
+        let mut request = self.create_request(self.expires);
+
+        if let Some(document) = document {
+            request
+                .headers
+                .insert_named(&ContentType(BytesStr::from_static(PIDF_CONTENT_TYPE)));
+            request.body = document.to_xml().into();
+        }
+
+        request
+    }
+
+    /// Create a PUBLISH request which removes the published state
+    pub fn create_remove(&mut self) -> Request {
+        self.create_request(Duration::ZERO)
+    }
+
+    fn create_request(&mut self, expires: Duration) -> Request {
+        let mut request = Request::new(Method::PUBLISH, self.presentity.clone());
+
+        request.headers.insert_type(Name::FROM, &self.from);
+        request.headers.insert_type(Name::TO, &self.to);
+        request.headers.insert_named(&self.call_id);
+
+        self.cseq += 1;
+        request
+            .headers
+            .insert_named(&CSeq::new(self.cseq, Method::PUBLISH));
+
+        request
+            .headers
+            .insert_named(&Event(BytesStr::from_static("presence")));
+        request
+            .headers
+            .insert_named(&Expires(expires.as_secs() as u32));
+
+        if let Some(etag) = &self.etag {
+            request.headers.insert_named(&SipIfMatch(etag.clone()));
+        }
+
+        if let Some(outbound_proxy) = &self.outbound_proxy {
+            request.headers.insert_type(Name::ROUTE, outbound_proxy);
+        }
+
+        request
+    }
+
+    /// Handle the success response to a PUBLISH request
+    ///
+    /// [`Self::wait_for_refresh`] should be used to wait until the published state must be refreshed.
+    pub fn receive_success_response(&mut self, response: &TsxResponse) {
+        assert_eq!(response.line.code.kind(), CodeKind::Success);
+
+        let expires = response
+            .headers
+            .get_named::<Expires>()
+            .map(|expires| Duration::from_secs(expires.0.into()))
+            .unwrap_or(self.expires);
+
+        if expires.is_zero() {
+            self.etag = None;
+            self.refresh_at = None;
+            return;
+        }
+
+        self.etag = response
+            .headers
+            .get_named::<SipETag>()
+            .ok()
+            .map(|etag| etag.0);
+
+        // Refresh shortly before the state expires, but not more often than every 10 seconds
+        let refresh_in = expires
+            .saturating_sub(Duration::from_secs(10))
+            .max(Duration::from_secs(10));
+
+        self.refresh_at = Some(Instant::now() + refresh_in);
+    }
+
+    /// Handle an error response to a PUBLISH request
+    ///
+    /// Returns `true` if the full presence state must be published again using
+    /// [`Self::create_publish`] with a document.
+    pub fn receive_error_response(&mut self, response: &TsxResponse) -> bool {
+        match response.line.code {
+            Code::CONDITIONAL_REQUEST_FAILED => {
+                // The published state is unknown to the server (e.g. it expired)
+                self.etag = None;
+                self.refresh_at = None;
+                true
+            }
+            Code::INTERVAL_TOO_BRIEF => {
+                if let Ok(min_expires) = response.headers.get_named::<MinExpires>() {
+                    self.expires = Duration::from_secs(min_expires.0.into());
+                }
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns when the published state must be refreshed using [`Self::create_publish`] without document
+    ///
+    /// Never returns while no state is published.
+    pub async fn wait_for_refresh(&mut self) {
+        match self.refresh_at {
+            Some(refresh_at) => sleep_until(refresh_at).await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sip_core::transport::udp::Udp;
+    use sip_core::transport::TargetTransportInfo;
+    use sip_core::{IncomingRequest, Layer, LayerKey, MayTake};
+    use sip_types::uri::sip::SipUri;
+    use std::collections::VecDeque;
+    use std::net::SocketAddr;
+
+    #[test]
+    fn parse_pidf() {
+        let pidf = Pidf::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<presence xmlns="urn:ietf:params:xml:ns:pidf"
+    xmlns:im="urn:ietf:params:xml:ns:pidf:im"
+    entity="pres:someone@example.com">
+  <tuple id="sg89ae">
+    <status>
+      <basic>open</basic>
+      <im:im>busy</im:im>
+    </status>
+    <contact priority="0.8">tel:+09012345678</contact>
+  </tuple>
+  <tuple id="x">
+    <status><basic>unknown</basic></status>
+  </tuple>
+  <note xml:lang="en">Don't Disturb Please!</note>
+</presence>"#,
+        )
+        .unwrap();
+
+        assert_eq!(pidf.entity, "pres:someone@example.com");
+        assert_eq!(pidf.notes, ["Don't Disturb Please!"]);
+        assert_eq!(pidf.tuples.len(), 2);
+
+        assert_eq!(pidf.tuples[0].id, "sg89ae");
+        assert_eq!(pidf.tuples[0].status, Some(BasicStatus::Open));
+        assert_eq!(pidf.tuples[0].contact.as_deref(), Some("tel:+09012345678"));
+        assert_eq!(pidf.tuples[1].status, None);
+        assert!(pidf.is_open());
+    }
+
+    #[test]
+    fn parse_invalid_pidf() {
+        assert!(matches!(
+            Pidf::parse(r#"<presence entity="sip:alice@example.com"/>"#),
+            Err(PidfError::NotPresence)
+        ));
+        assert!(matches!(
+            Pidf::parse(r#"<presence xmlns="urn:ietf:params:xml:ns:pidf"/>"#),
+            Err(PidfError::MissingEntity)
+        ));
+        assert!(matches!(Pidf::parse("<presence"), Err(PidfError::Xml(_))));
+    }
+
+    #[test]
+    fn pidf_round_trip() {
+        let mut pidf = Pidf::new("sip:alice@example.com;tag=\"<&>\"");
+
+        let mut tuple = PidfTuple::new("t'1", BasicStatus::Closed);
+        tuple.contact = Some("sip:alice@192.0.2.1?subject=a&b".into());
+        tuple.note = Some("in a <meeting>".into());
+        pidf.tuples.push(tuple);
+        pidf.tuples.push(PidfTuple::new("t2", BasicStatus::Open));
+        pidf.notes.push("Tom & Jerry's".into());
+
+        let xml = pidf.to_xml();
+
+        assert!(xml.contains(r#"entity="sip:alice@example.com;tag=&quot;&lt;&amp;&gt;&quot;""#));
+        assert!(xml.contains(r#"<tuple id="t&apos;1">"#));
+        assert!(xml.contains("<note>in a &lt;meeting&gt;</note>"));
+
+        assert_eq!(Pidf::parse(&xml).unwrap(), pidf);
+    }
+
+    /// Status code and headers of a response sent by the presence server
+    type TestResponse = (Code, Vec<(Name, &'static str)>);
+
+    /// Presence server responding to PUBLISH requests with the queued responses
+    #[derive(Default)]
+    struct PresenceServer {
+        responses: parking_lot::Mutex<VecDeque<TestResponse>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Layer for PresenceServer {
+        fn name(&self) -> &'static str {
+            "test-presence-server"
+        }
+
+        async fn receive(&self, endpoint: &Endpoint, request: MayTake<'_, IncomingRequest>) {
+            let mut request = request.take();
+
+            let (code, headers) = self.responses.lock().pop_front().unwrap();
+
+            let mut response = endpoint.create_response(&request, code, None);
+
+            for (name, value) in headers {
+                response.msg.headers.insert(name, value);
+            }
+
+            let tsx = endpoint.create_server_tsx(&mut request);
+            tsx.respond(response).await.unwrap();
+        }
+    }
+
+    async fn spawn_server() -> (Endpoint, LayerKey<PresenceServer>, SocketAddr) {
+        let mut builder = Endpoint::builder();
+        let transport = Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let key = builder.add_layer(PresenceServer::default());
+
+        (builder.build(), key, transport.bound())
+    }
+
+    async fn spawn_publisher(server: SocketAddr) -> (Endpoint, PresencePublisher) {
+        let mut builder = Endpoint::builder();
+        Udp::spawn(&mut builder, "127.0.0.1:0").await.unwrap();
+        let endpoint = builder.build();
+
+        let id: SipUri = format!("sip:alice@{server}").parse().unwrap();
+        let publisher =
+            PresencePublisher::new(&endpoint, NameAddr::uri(id), Duration::from_secs(300));
+
+        (endpoint, publisher)
+    }
+
+    async fn send(
+        endpoint: &Endpoint,
+        server: &Endpoint,
+        key: LayerKey<PresenceServer>,
+        request: Request,
+        response: TestResponse,
+    ) -> TsxResponse {
+        server[key].responses.lock().push_back(response);
+
+        let mut transaction = endpoint
+            .send_request(request, &mut TargetTransportInfo::default())
+            .await
+            .unwrap();
+
+        transaction.receive_final().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn publish_uses_etag() {
+        let (server, key, addr) = spawn_server().await;
+        let (endpoint, mut publisher) = spawn_publisher(addr).await;
+
+        let document = Pidf::new("sip:alice@example.com");
+        let request = publisher.create_publish(Some(&document));
+
+        assert!(!request.headers.contains(&Name::SIP_IF_MATCH));
+        assert_eq!(request.headers.get_named::<Expires>().unwrap().0, 300);
+        assert_eq!(
+            request.headers.get_named::<ContentType>().unwrap().0,
+            PIDF_CONTENT_TYPE
+        );
+
+        let ok = (
+            Code::OK,
+            vec![(Name::SIP_ETAG, "dx200xyz"), (Name::EXPIRES, "120")],
+        );
+        let response = send(&endpoint, &server, key, request, ok).await;
+        publisher.receive_success_response(&response);
+        assert!(publisher.is_published());
+
+        // Refreshes carry the entity-tag but no document
+        let refresh = publisher.create_publish(None);
+        assert_eq!(
+            refresh.headers.get_named::<SipIfMatch>().unwrap().0,
+            "dx200xyz"
+        );
+        assert!(refresh.body.is_empty());
+
+        let remove = publisher.create_remove();
+        assert_eq!(remove.headers.get_named::<Expires>().unwrap().0, 0);
+
+        let removed = (Code::OK, vec![(Name::EXPIRES, "0")]);
+        let response = send(&endpoint, &server, key, remove, removed).await;
+        publisher.receive_success_response(&response);
+        assert!(!publisher.is_published());
+    }
+
+    #[tokio::test]
+    async fn publish_again_after_412() {
+        let (server, key, addr) = spawn_server().await;
+        let (endpoint, mut publisher) = spawn_publisher(addr).await;
+
+        let document = Pidf::new("sip:alice@example.com");
+        let request = publisher.create_publish(Some(&document));
+        let ok = (Code::OK, vec![(Name::SIP_ETAG, "dx200xyz")]);
+        let response = send(&endpoint, &server, key, request, ok).await;
+        publisher.receive_success_response(&response);
+
+        let refresh = publisher.create_publish(None);
+        let failed = (Code::CONDITIONAL_REQUEST_FAILED, vec![]);
+        let response = send(&endpoint, &server, key, refresh, failed).await;
+
+        assert!(publisher.receive_error_response(&response));
+        assert!(!publisher.is_published());
+
+        // The state is published again without entity-tag
+        let request = publisher.create_publish(Some(&document));
+        assert!(!request.headers.contains(&Name::SIP_IF_MATCH));
+    }
+
+    #[tokio::test]
+    async fn publish_again_after_423() {
+        let (server, key, addr) = spawn_server().await;
+        let (endpoint, mut publisher) = spawn_publisher(addr).await;
+
+        let document = Pidf::new("sip:alice@example.com");
+        let request = publisher.create_publish(Some(&document));
+        let too_brief = (Code::INTERVAL_TOO_BRIEF, vec![(Name::MIN_EXPIRES, "3600")]);
+        let response = send(&endpoint, &server, key, request, too_brief).await;
+
+        assert!(publisher.receive_error_response(&response));
+
+        let request = publisher.create_publish(Some(&document));
+        assert_eq!(request.headers.get_named::<Expires>().unwrap().0, 3600);
+
+        // Other failures are not retried
+        let forbidden = (Code::FORBIDDEN, vec![]);
+        let response = send(&endpoint, &server, key, request, forbidden).await;
+        assert!(!publisher.receive_error_response(&response));
+    }
+}
@@ -0,0 +1,105 @@
+//! Implicit subscription created by a REFER request
+//! ([RFC 3515](https://datatracker.ietf.org/doc/html/rfc3515))
+
+use super::EventPackage;
+use bytesstr::BytesStr;
+use sip_types::msg::StatusLine;
+use sip_types::Code;
+use std::str::from_utf8;
+
+/// Content-Type of NOTIFY bodies of the refer event package
+pub const SIPFRAG_CONTENT_TYPE: &str = "message/sipfrag";
+
+/// The `refer` event package, reports the progress of the request sent by the recipient of a REFER
+///
+/// NOTIFY bodies contain the status line of the latest response received for that request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Refer;
+
+impl EventPackage for Refer {
+    type Notification = StatusLine;
+
+    fn event(&self) -> &'static str {
+        "refer"
+    }
+
+    fn accept(&self) -> &'static [&'static str] {
+        &[SIPFRAG_CONTENT_TYPE]
+    }
+
+    fn parse(&self, content_type: &str, body: &[u8]) -> Option<StatusLine> {
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+
+        if !media_type.eq_ignore_ascii_case(SIPFRAG_CONTENT_TYPE) {
+            return None;
+        }
+
+        parse_sipfrag_status(from_utf8(body).ok()?)
+    }
+}
+
+/// Parse the status line of a `message/sipfrag` body, headers following the status line are ignored
+pub fn parse_sipfrag_status(body: &str) -> Option<StatusLine> {
+    let line = body.lines().next()?;
+
+    let rest = line.strip_prefix("SIP/2.0")?.trim_start();
+    let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+
+    let code: u16 = code.parse().ok()?;
+
+    if !(100..=699).contains(&code) {
+        return None;
+    }
+
+    let reason = reason.trim();
+
+    Some(StatusLine {
+        code: Code::from(code),
+        reason: (!reason.is_empty()).then(|| BytesStr::from(reason)),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_status() {
+        let status = parse_sipfrag_status("SIP/2.0 200 OK\r\n").unwrap();
+
+        assert_eq!(status.code, Code::OK);
+        assert_eq!(status.reason.as_deref(), Some("OK"));
+    }
+
+    #[test]
+    fn parse_status_with_headers() {
+        let status =
+            parse_sipfrag_status("SIP/2.0 603 Declined\r\nContent-Length: 0\r\n\r\n").unwrap();
+
+        assert_eq!(status.code, Code::DECLINE);
+    }
+
+    #[test]
+    fn parse_status_without_reason() {
+        let status = parse_sipfrag_status("SIP/2.0 100").unwrap();
+
+        assert_eq!(status.code, Code::TRYING);
+        assert!(status.reason.is_none());
+    }
+
+    #[test]
+    fn reject_invalid_status() {
+        assert!(parse_sipfrag_status("INVITE sip:bob@example.com SIP/2.0").is_none());
+        assert!(parse_sipfrag_status("SIP/2.0 abc").is_none());
+        assert!(parse_sipfrag_status("SIP/2.0 999 Unknown").is_none());
+    }
+
+    #[test]
+    fn package_ignores_content_type_params() {
+        let status = Refer
+            .parse("message/sipfrag;version=2.0", b"SIP/2.0 180 Ringing")
+            .unwrap();
+
+        assert_eq!(status.code, Code::RINGING);
+    }
+}
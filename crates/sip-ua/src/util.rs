@@ -1,6 +1,40 @@
 use bytesstr::BytesStr;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
+use sip_core::Request;
+use sip_types::header::typed::RetryAfter;
+use sip_types::Headers;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Hook which is called with every request created by a [`Registration`](crate::register::Registration),
+/// [`ClientDialogBuilder`](crate::dialog::ClientDialogBuilder) or [`Dialog`](crate::dialog::Dialog)
+///
+/// Can be used to add or modify headers (e.g. `P-Access-Network-Info`) of all requests without
+/// re-implementing the flows creating them. Headers specific to a request (e.g. Supported of an INVITE)
+/// may be added after the hook was called.
+#[derive(Clone)]
+pub struct RequestHook(Arc<dyn Fn(&mut Request) + Send + Sync>);
+
+impl RequestHook {
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&mut Request) + Send + Sync + 'static,
+    {
+        Self(Arc::new(hook))
+    }
+
+    pub fn apply(&self, request: &mut Request) {
+        (self.0)(request)
+    }
+}
+
+impl fmt::Debug for RequestHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestHook").finish_non_exhaustive()
+    }
+}
 
 pub fn random_string() -> BytesStr {
     thread_rng()
@@ -14,3 +48,45 @@ pub fn random_string() -> BytesStr {
 pub fn random_sequence_number() -> u32 {
     rand::thread_rng().gen_range(0..(u32::MAX >> 1))
 }
+
+/// Returns the time to wait before retrying a re-INVITE rejected with `491 Request Pending`
+/// ([RFC 3261 Section 14.1](https://datatracker.ietf.org/doc/html/rfc3261#section-14.1))
+///
+/// The owner of the dialog's Call-ID (the UAC of the initial INVITE) waits longer, so both sides
+/// do not retry at the same time again.
+pub fn glare_retry_delay(owns_call_id: bool) -> Duration {
+    // Chosen in units of 10ms
+    let units = if owns_call_id {
+        thread_rng().gen_range(210..=400)
+    } else {
+        thread_rng().gen_range(0..=200)
+    };
+
+    Duration::from_millis(units * 10)
+}
+
+/// Returns the time to wait before retrying a failed request, if the response contains a Retry-After header
+pub fn retry_after(headers: &Headers) -> Option<Duration> {
+    headers
+        .get_named::<RetryAfter>()
+        .ok()
+        .map(|retry_after| retry_after.retry_in())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glare_retry_delay_ranges() {
+        for _ in 0..100 {
+            let owner = glare_retry_delay(true);
+            assert!(owner >= Duration::from_millis(2100) && owner <= Duration::from_secs(4));
+            assert_eq!(owner.as_millis() % 10, 0);
+
+            let other = glare_retry_delay(false);
+            assert!(other <= Duration::from_secs(2));
+            assert_eq!(other.as_millis() % 10, 0);
+        }
+    }
+}
@@ -92,18 +92,29 @@ impl Attribute<'_> for XorRelayedAddress {
 }
 
 /// [RFC5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.6)
+///
+/// Contains the value of the R bit, which requests to reserve the next higher port.
 pub struct EvenPort(pub bool);
 
+impl EvenPort {
+    /// The R bit is the most significant bit of the attribute's single byte value
+    const RESERVE_BIT: u8 = 0x80;
+}
+
 impl Attribute<'_> for EvenPort {
     type Context = ();
     const TYPE: u16 = 0x0018;
 
     fn decode(_: Self::Context, msg: &mut Message, attr: AttrSpan) -> Result<Self, Error> {
-        Ok(Self(attr.get_value(msg.buffer()).read_u8()? == 1))
+        Ok(Self(
+            attr.get_value(msg.buffer()).read_u8()? & Self::RESERVE_BIT != 0,
+        ))
     }
 
     fn encode(&self, _: Self::Context, builder: &mut MessageBuilder) {
-        builder.buffer().put_u8(if self.0 { 1 } else { 0 });
+        builder
+            .buffer()
+            .put_u8(if self.0 { Self::RESERVE_BIT } else { 0 });
     }
 
     fn encode_len(&self) -> Result<u16, Error> {
@@ -177,3 +188,73 @@ impl Attribute<'_> for ReservationToken {
         Ok(8)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header::{Class, Method};
+    use crate::TransactionId;
+
+    #[test]
+    fn allocate_attributes() {
+        let mut builder = MessageBuilder::new(
+            Class::Request,
+            Method::Allocate,
+            TransactionId::new([3; 12]),
+        );
+
+        builder.add_attr(RequestedTransport {
+            protocol_number: 17,
+        });
+        builder.add_attr(Lifetime(600));
+        builder.add_attr(EvenPort(true));
+        builder.add_attr(DontFragment);
+        builder.add_attr(ChannelNumber(0x4001));
+
+        let mut msg = Message::parse(builder.finish()).unwrap();
+
+        let transport = msg.attribute::<RequestedTransport>().unwrap().unwrap();
+        assert_eq!(transport.protocol_number, 17);
+        assert_eq!(msg.attribute::<Lifetime>().unwrap().unwrap().0, 600);
+        assert!(msg.attribute::<EvenPort>().unwrap().unwrap().0);
+        assert!(msg.attribute::<DontFragment>().unwrap().is_ok());
+        assert_eq!(msg.attribute::<ChannelNumber>().unwrap().unwrap().0, 0x4001);
+    }
+
+    #[test]
+    fn even_port_uses_r_bit() {
+        let mut builder = MessageBuilder::new(
+            Class::Request,
+            Method::Allocate,
+            TransactionId::new([3; 12]),
+        );
+        builder.add_attr(EvenPort(true));
+
+        let bytes = builder.finish();
+
+        // header (20) + attribute type & length (4)
+        assert_eq!(bytes[24], 0x80);
+    }
+
+    #[test]
+    fn addresses_and_data() {
+        let peer: SocketAddr = "192.0.2.1:4000".parse().unwrap();
+        let relayed: SocketAddr = "[2001:db8::1]:50000".parse().unwrap();
+
+        let mut builder =
+            MessageBuilder::new(Class::Indication, Method::Send, TransactionId::new([4; 12]));
+
+        builder.add_attr(XorPeerAddress(peer));
+        builder.add_attr(XorRelayedAddress(relayed));
+        builder.add_attr(Data::new(b"hello"));
+
+        let mut msg = Message::parse(builder.finish()).unwrap();
+
+        assert_eq!(msg.attribute::<XorPeerAddress>().unwrap().unwrap().0, peer);
+        assert_eq!(
+            msg.attribute::<XorRelayedAddress>().unwrap().unwrap().0,
+            relayed
+        );
+        assert_eq!(msg.attribute::<Data>().unwrap().unwrap().0, b"hello");
+    }
+}
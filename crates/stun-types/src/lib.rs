@@ -9,10 +9,12 @@ pub mod attributes;
 mod builder;
 mod header;
 mod parse;
+mod transaction;
 
 pub use builder::MessageBuilder;
 pub use header::{Class, MessageHead, Method};
 pub use parse::{AttrSpan, Message};
+pub use transaction::{ClientTransaction, TransactionAction, TransactionConfig};
 
 type NE = byteorder::NetworkEndian;
 
@@ -127,3 +129,67 @@ pub fn is_stun_message(i: &[u8]) -> IsStunMessageInfo {
         }
     }
 }
+
+/// Take the first complete STUN message out of a buffer which is filled from a
+/// stream-oriented transport like TCP or TLS.
+///
+/// Over streams, STUN messages are delimited by the length field of the message header,
+/// see [RFC8489](https://datatracker.ietf.org/doc/html/rfc8489#section-6.2.2).
+///
+/// Returns `Ok(None)` if the buffer does not contain a complete message yet.
+/// On success the bytes of the message are removed from the buffer.
+pub fn take_framed_message(buffer: &mut Vec<u8>) -> Result<Option<Message>, Error> {
+    match is_stun_message(buffer) {
+        IsStunMessageInfo::TooShort | IsStunMessageInfo::YesIncomplete { .. } => Ok(None),
+        IsStunMessageInfo::No => Err(Error::InvalidData(
+            "stream does not start with a STUN message",
+        )),
+        IsStunMessageInfo::Yes { len } => {
+            let message: Vec<u8> = buffer.drain(..len).collect();
+
+            Message::parse(message).map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attributes::Software;
+
+    #[test]
+    fn framed_messages() {
+        let mut builder =
+            MessageBuilder::new(Class::Request, Method::Binding, TransactionId::new([1; 12]));
+        builder.add_attr(Software::new("ezk"));
+        let first = builder.finish();
+
+        let second =
+            MessageBuilder::new(Class::Request, Method::Binding, TransactionId::new([2; 12]))
+                .finish();
+
+        let mut stream = vec![];
+        stream.extend_from_slice(&first);
+        stream.extend_from_slice(&second[..10]);
+
+        let msg = take_framed_message(&mut stream).unwrap().unwrap();
+        assert_eq!(msg.transaction_id(), TransactionId::new([1; 12]));
+
+        // second message is incomplete
+        assert!(take_framed_message(&mut stream).unwrap().is_none());
+        assert_eq!(stream.len(), 10);
+
+        stream.extend_from_slice(&second[10..]);
+
+        let msg = take_framed_message(&mut stream).unwrap().unwrap();
+        assert_eq!(msg.transaction_id(), TransactionId::new([2; 12]));
+        assert!(stream.is_empty());
+    }
+
+    #[test]
+    fn framed_not_stun() {
+        let mut stream = b"OPTIONS sip:example.com SIP/2.0\r\n".to_vec();
+
+        assert!(take_framed_message(&mut stream).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+/// Retransmission parameters of a STUN client transaction
+///
+/// [RFC8489](https://datatracker.ietf.org/doc/html/rfc8489#section-6.2.1)
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionConfig {
+    /// Initial retransmission timeout (RTO)
+    pub rto: Duration,
+
+    /// Maximum number of requests sent over unreliable transports (Rc)
+    pub max_requests: u32,
+
+    /// Multiplier of the RTO used to wait for a response after the last request was sent (Rm)
+    pub last_wait_multiplier: u32,
+
+    /// Timeout of transactions over reliable transports (Ti)
+    pub reliable_timeout: Duration,
+}
+
+impl Default for TransactionConfig {
+    fn default() -> Self {
+        Self {
+            rto: Duration::from_millis(500),
+            max_requests: 7,
+            last_wait_multiplier: 16,
+            reliable_timeout: Duration::from_millis(39_500),
+        }
+    }
+}
+
+/// Action to be performed by the user of a [`ClientTransaction`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionAction {
+    /// The request must be sent (again)
+    Send,
+
+    /// Wait for a response until the given instant, then poll the transaction again
+    WaitUntil(Instant),
+
+    /// No response was received in time, the transaction failed
+    TimedOut,
+}
+
+/// Sans-io client transaction, implements the retransmission and timeout
+/// behavior for STUN requests.
+///
+/// The transaction does not know about responses, the user is responsible for
+/// matching them and discarding the transaction when one is received.
+#[derive(Debug)]
+pub struct ClientTransaction {
+    config: TransactionConfig,
+    reliable: bool,
+
+    /// Number of times the request has been sent
+    sent: u32,
+
+    /// Instant the current wait for a response ends
+    deadline: Option<Instant>,
+    timed_out: bool,
+}
+
+impl ClientTransaction {
+    /// Create a new transaction with the default [`TransactionConfig`]
+    pub fn new(reliable: bool) -> Self {
+        Self::with_config(reliable, TransactionConfig::default())
+    }
+
+    pub fn with_config(reliable: bool, config: TransactionConfig) -> Self {
+        Self {
+            config,
+            reliable,
+            sent: 0,
+            deadline: None,
+            timed_out: false,
+        }
+    }
+
+    /// Returns how often the request has been sent
+    pub fn sent(&self) -> u32 {
+        self.sent
+    }
+
+    /// Advance the transaction to `now`, returning what the user must do next
+    pub fn poll(&mut self, now: Instant) -> TransactionAction {
+        if self.timed_out {
+            return TransactionAction::TimedOut;
+        }
+
+        match self.deadline {
+            Some(deadline) if now < deadline => TransactionAction::WaitUntil(deadline),
+            Some(_) if self.reliable || self.sent >= self.config.max_requests => {
+                self.timed_out = true;
+                TransactionAction::TimedOut
+            }
+            _ => {
+                self.sent += 1;
+                self.deadline = Some(now + self.current_wait());
+                TransactionAction::Send
+            }
+        }
+    }
+
+    /// Duration to wait for a response after the request has been sent for the `sent`-th time
+    fn current_wait(&self) -> Duration {
+        if self.reliable {
+            self.config.reliable_timeout
+        } else if self.sent >= self.config.max_requests {
+            self.config.rto * self.config.last_wait_multiplier
+        } else {
+            self.config.rto * 2u32.saturating_pow(self.sent - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unreliable_retransmits() {
+        let start = Instant::now();
+        let mut tsx = ClientTransaction::new(false);
+
+        let mut now = start;
+        let mut sent_at = vec![];
+
+        loop {
+            match tsx.poll(now) {
+                TransactionAction::Send => sent_at.push(now - start),
+                TransactionAction::WaitUntil(deadline) => now = deadline,
+                TransactionAction::TimedOut => break,
+            }
+        }
+
+        let expected: Vec<Duration> = [0, 500, 1500, 3500, 7500, 15500, 31500]
+            .into_iter()
+            .map(Duration::from_millis)
+            .collect();
+
+        assert_eq!(sent_at, expected);
+        assert_eq!(tsx.sent(), 7);
+
+        // Last request waits Rm * RTO
+        assert_eq!(now - start, Duration::from_millis(39500));
+        assert_eq!(tsx.poll(now), TransactionAction::TimedOut);
+    }
+
+    #[test]
+    fn reliable_sends_once() {
+        let start = Instant::now();
+        let mut tsx = ClientTransaction::new(true);
+
+        assert_eq!(tsx.poll(start), TransactionAction::Send);
+
+        let deadline = start + Duration::from_millis(39_500);
+        assert_eq!(tsx.poll(start), TransactionAction::WaitUntil(deadline));
+        assert_eq!(tsx.poll(deadline), TransactionAction::TimedOut);
+        assert_eq!(tsx.sent(), 1);
+    }
+}
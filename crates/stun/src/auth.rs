@@ -1,9 +1,9 @@
 use stun_types::attributes::{
-    long_term_password_md5, long_term_password_sha256, MessageIntegrity, MessageIntegrityKey,
-    MessageIntegritySha256, MessageIntegritySha256Key, Nonce, PasswordAlgorithm, Realm, Username,
-    ALGORITHM_MD5, ALGORITHM_SHA256,
+    long_term_password_md5, long_term_password_sha256, ErrorCode, MessageIntegrity,
+    MessageIntegrityKey, MessageIntegritySha256, MessageIntegritySha256Key, Nonce,
+    PasswordAlgorithm, PasswordAlgorithms, Realm, Username, ALGORITHM_MD5, ALGORITHM_SHA256,
 };
-use stun_types::{Message, MessageBuilder};
+use stun_types::{Class, Message, MessageBuilder};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -11,8 +11,10 @@ pub enum Error {
     Parse(#[from] stun_types::Error),
     #[error("missing nonce in response")]
     MissingNonce,
-    #[error("password algorithm contained an unknown value")]
+    #[error("none of the offered password algorithms is supported")]
     UnknownAlgorithm,
+    #[error("missing realm in response")]
+    MissingRealm,
 }
 
 pub enum StunCredential {
@@ -27,50 +29,370 @@ pub enum StunCredential {
     },
 }
 
-impl StunCredential {
-    pub fn authenticate(
-        &mut self,
-        response: &mut Message,
-        mut msg: MessageBuilder,
-    ) -> Result<(), Error> {
-        match &*self {
-            StunCredential::ShortTerm { username, password } => {
-                msg.add_attr(Username::new(username));
+/// Error code of a 401 (Unauthenticated) error response
+const UNAUTHENTICATED: u32 = 401;
+
+/// Error code of a 438 (Stale Nonce) error response
+const STALE_NONCE: u32 = 438;
+
+/// Authenticates requests sent to a STUN/TURN server.
+///
+/// For long-term credentials the realm and nonce are learned from the server's 401 (Unauthenticated)
+/// response and updated on 438 (Stale Nonce) responses. See [`Authenticator::handle_error_response`].
+///
+/// If the server offers password algorithms ([RFC8489](https://datatracker.ietf.org/doc/html/rfc8489#section-9.2.4)),
+/// SHA-256 is preferred over MD5 and requests are additionally protected with MESSAGE-INTEGRITY-SHA256.
+pub struct Authenticator {
+    credential: StunCredential,
+    state: Option<LongTermState>,
+}
+
+/// Realm, nonce and key learned from the server
+struct LongTermState {
+    realm: String,
+    nonce: Vec<u8>,
+    key: Vec<u8>,
+
+    /// PASSWORD-ALGORITHMS offered by the server and the selected algorithm, echoed in every request
+    password_algorithms: Option<(OfferedAlgorithms, u16)>,
+}
+
+/// Algorithms and their parameters of a PASSWORD-ALGORITHMS attribute
+type OfferedAlgorithms = Vec<(u16, Vec<u8>)>;
+
+impl Authenticator {
+    pub fn new(credential: StunCredential) -> Self {
+        Self {
+            credential,
+            state: None,
+        }
+    }
 
+    /// Returns if the authenticator has everything needed to authenticate requests.
+    ///
+    /// Short-term credentials can always be used, long-term credentials require the
+    /// realm and nonce received in an error response first.
+    pub fn is_ready(&self) -> bool {
+        match &self.credential {
+            StunCredential::ShortTerm { .. } => true,
+            StunCredential::LongTerm { .. } => self.state.is_some(),
+        }
+    }
+
+    /// Add the authentication attributes to the request.
+    ///
+    /// Must be called after all other attributes have been added, except for the fingerprint.
+    /// Does nothing for long-term credentials until the server's realm and nonce are known.
+    pub fn authenticate(&self, msg: &mut MessageBuilder) {
+        match (&self.credential, &self.state) {
+            (StunCredential::ShortTerm { username, password }, _) => {
+                msg.add_attr(Username::new(username));
                 msg.add_attr_with(
                     MessageIntegritySha256,
                     MessageIntegritySha256Key::new(password),
                 );
                 msg.add_attr_with(MessageIntegrity, MessageIntegrityKey::new(password));
             }
-            StunCredential::LongTerm {
-                realm,
-                username,
-                password,
-            } => {
-                let key = if let Some(alg) = response.attribute::<PasswordAlgorithm>() {
-                    let alg = alg?;
-
-                    match alg.algorithm {
-                        ALGORITHM_MD5 => long_term_password_md5(username, realm, password),
-                        ALGORITHM_SHA256 => long_term_password_sha256(username, realm, password),
-                        _ => return Err(Error::UnknownAlgorithm),
-                    }
-                } else {
-                    long_term_password_md5(username, realm, password)
-                };
-
-                let nonce = response.attribute::<Nonce>().ok_or(Error::MissingNonce)??;
-
-                msg.add_attr(Nonce::new(nonce.0));
-                msg.add_attr(Realm::new(realm));
+            (StunCredential::LongTerm { username, .. }, Some(state)) => {
+                msg.add_attr(Nonce::new(&state.nonce));
+                msg.add_attr(Realm::new(&state.realm));
                 msg.add_attr(Username::new(username));
 
-                msg.add_attr_with(MessageIntegritySha256, MessageIntegritySha256Key::new(&key));
-                msg.add_attr_with(MessageIntegrity, MessageIntegrityKey::new(&key));
+                if let Some((algorithms, algorithm)) = &state.password_algorithms {
+                    msg.add_attr(PasswordAlgorithms {
+                        algorithms: algorithms
+                            .iter()
+                            .map(|(algorithm, params)| (*algorithm, params.as_slice()))
+                            .collect(),
+                    });
+                    msg.add_attr(PasswordAlgorithm {
+                        algorithm: *algorithm,
+                        params: &[],
+                    });
+                }
+
+                msg.add_attr_with(MessageIntegrity, MessageIntegrityKey::new(&state.key));
+
+                if state.password_algorithms.is_some() {
+                    msg.add_attr_with(
+                        MessageIntegritySha256,
+                        MessageIntegritySha256Key::new(&state.key),
+                    );
+                }
+            }
+            (StunCredential::LongTerm { .. }, None) => {}
+        }
+    }
+
+    /// Handle an error response to a request.
+    ///
+    /// Returns `true` if the request must be sent again (and authenticated using [`Authenticator::authenticate`]),
+    /// `false` if the error cannot be resolved by authenticating (again).
+    pub fn handle_error_response(&mut self, response: &mut Message) -> Result<bool, Error> {
+        if response.class() != Class::Error {
+            return Ok(false);
+        }
+
+        let StunCredential::LongTerm {
+            realm: configured_realm,
+            username,
+            password,
+        } = &self.credential
+        else {
+            return Ok(false);
+        };
+
+        let code = match response.attribute::<ErrorCode>() {
+            Some(code) => code?.number,
+            None => return Ok(false),
+        };
+
+        if !matches!(code, UNAUTHENTICATED | STALE_NONCE) {
+            return Ok(false);
+        }
+
+        let nonce = response
+            .attribute::<Nonce>()
+            .ok_or(Error::MissingNonce)??
+            .0
+            .to_vec();
+
+        if code == STALE_NONCE {
+            match &mut self.state {
+                Some(state) if state.nonce != nonce => {
+                    state.nonce = nonce;
+                    return Ok(true);
+                }
+                // Same nonce as before or no previous state, nothing to retry with
+                _ => return Ok(false),
+            }
+        }
+
+        let realm = match response.attribute::<Realm>() {
+            Some(realm) => realm?.0.to_string(),
+            None if !configured_realm.is_empty() => configured_realm.clone(),
+            None => return Err(Error::MissingRealm),
+        };
+
+        // Credentials were already sent with this nonce and realm and rejected, retrying won't help
+        if let Some(state) = &self.state {
+            if state.nonce == nonce && state.realm == realm {
+                return Ok(false);
             }
         }
 
-        Ok(())
+        let password_algorithms = match response.attribute::<PasswordAlgorithms>() {
+            Some(offered) => {
+                let offered: OfferedAlgorithms = offered?
+                    .algorithms
+                    .into_iter()
+                    .map(|(algorithm, params)| (algorithm, params.to_vec()))
+                    .collect();
+
+                let algorithm = [ALGORITHM_SHA256, ALGORITHM_MD5]
+                    .into_iter()
+                    .find(|supported| offered.iter().any(|(algorithm, _)| algorithm == supported))
+                    .ok_or(Error::UnknownAlgorithm)?;
+
+                Some((offered, algorithm))
+            }
+            None => None,
+        };
+
+        let key = match password_algorithms {
+            Some((_, ALGORITHM_SHA256)) => long_term_password_sha256(username, &realm, password),
+            _ => long_term_password_md5(username, &realm, password),
+        };
+
+        self.state = Some(LongTermState {
+            realm,
+            nonce,
+            key,
+            password_algorithms,
+        });
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stun_types::{Method, TransactionId};
+
+    fn error_response(code: u32, nonce: &[u8]) -> Message {
+        error_response_with_algorithms(code, nonce, None)
+    }
+
+    fn error_response_with_algorithms(
+        code: u32,
+        nonce: &[u8],
+        algorithms: Option<Vec<(u16, &[u8])>>,
+    ) -> Message {
+        let mut builder =
+            MessageBuilder::new(Class::Error, Method::Allocate, TransactionId::new([0; 12]));
+
+        builder.add_attr(ErrorCode {
+            number: code,
+            reason: "",
+        });
+        builder.add_attr(Realm::new("example.org"));
+        builder.add_attr(Nonce::new(nonce));
+
+        if let Some(algorithms) = algorithms {
+            builder.add_attr(PasswordAlgorithms { algorithms });
+        }
+
+        Message::parse(builder.finish()).unwrap()
+    }
+
+    fn authenticated_request(auth: &Authenticator) -> Message {
+        let mut request = MessageBuilder::new(
+            Class::Request,
+            Method::Allocate,
+            TransactionId::new([1; 12]),
+        );
+        auth.authenticate(&mut request);
+
+        Message::parse(request.finish()).unwrap()
+    }
+
+    fn long_term() -> Authenticator {
+        Authenticator::new(StunCredential::LongTerm {
+            realm: String::new(),
+            username: "user".into(),
+            password: "pass".into(),
+        })
+    }
+
+    #[test]
+    fn long_term_unauthenticated_then_authenticate() {
+        let mut auth = long_term();
+        assert!(!auth.is_ready());
+
+        assert!(auth
+            .handle_error_response(&mut error_response(401, b"nonce1"))
+            .unwrap());
+        assert!(auth.is_ready());
+
+        let mut request = MessageBuilder::new(
+            Class::Request,
+            Method::Allocate,
+            TransactionId::new([1; 12]),
+        );
+        auth.authenticate(&mut request);
+
+        let mut request = Message::parse(request.finish()).unwrap();
+
+        assert_eq!(request.attribute::<Nonce>().unwrap().unwrap().0, b"nonce1");
+        assert_eq!(
+            request.attribute::<Realm>().unwrap().unwrap().0,
+            "example.org"
+        );
+
+        let key = long_term_password_md5("user", "example.org", "pass");
+        request
+            .attribute_with::<MessageIntegrity>(MessageIntegrityKey::new(key))
+            .unwrap()
+            .unwrap();
+
+        // credentials were rejected
+        assert!(!auth
+            .handle_error_response(&mut error_response(401, b"nonce1"))
+            .unwrap());
+    }
+
+    #[test]
+    fn long_term_stale_nonce() {
+        let mut auth = long_term();
+
+        // stale nonce without prior state cannot be retried
+        assert!(!auth
+            .handle_error_response(&mut error_response(438, b"nonce1"))
+            .unwrap());
+
+        assert!(auth
+            .handle_error_response(&mut error_response(401, b"nonce1"))
+            .unwrap());
+        assert!(auth
+            .handle_error_response(&mut error_response(438, b"nonce2"))
+            .unwrap());
+        assert!(!auth
+            .handle_error_response(&mut error_response(438, b"nonce2"))
+            .unwrap());
+    }
+
+    #[test]
+    fn unrelated_error() {
+        let mut auth = long_term();
+
+        assert!(!auth
+            .handle_error_response(&mut error_response(400, b"nonce1"))
+            .unwrap());
+    }
+
+    #[test]
+    fn long_term_sha256() {
+        let mut auth = long_term();
+
+        assert!(auth
+            .handle_error_response(&mut error_response_with_algorithms(
+                401,
+                b"nonce1",
+                Some(vec![(ALGORITHM_MD5, &[]), (ALGORITHM_SHA256, &[])]),
+            ))
+            .unwrap());
+
+        let mut request = authenticated_request(&auth);
+
+        let algorithm = request.attribute::<PasswordAlgorithm>().unwrap().unwrap();
+        assert_eq!(algorithm.algorithm, ALGORITHM_SHA256);
+
+        let algorithms = request.attribute::<PasswordAlgorithms>().unwrap().unwrap();
+        assert_eq!(
+            algorithms.algorithms,
+            vec![(ALGORITHM_MD5, &[][..]), (ALGORITHM_SHA256, &[][..])]
+        );
+
+        let key = long_term_password_sha256("user", "example.org", "pass");
+        request
+            .attribute_with::<MessageIntegritySha256>(MessageIntegritySha256Key::new(&key))
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn long_term_unsupported_algorithms() {
+        let mut auth = long_term();
+
+        assert!(matches!(
+            auth.handle_error_response(&mut error_response_with_algorithms(
+                401,
+                b"nonce1",
+                Some(vec![(0x1234, &[])]),
+            )),
+            Err(Error::UnknownAlgorithm)
+        ));
+    }
+
+    #[test]
+    fn long_term_unauthenticated_with_new_nonce() {
+        let mut auth = long_term();
+
+        assert!(auth
+            .handle_error_response(&mut error_response(401, b"nonce1"))
+            .unwrap());
+
+        // the server issued a new nonce, retry once with it
+        assert!(auth
+            .handle_error_response(&mut error_response(401, b"nonce2"))
+            .unwrap());
+
+        let mut request = authenticated_request(&auth);
+        assert_eq!(request.attribute::<Nonce>().unwrap().unwrap().0, b"nonce2");
+
+        assert!(!auth
+            .handle_error_response(&mut error_response(401, b"nonce2"))
+            .unwrap());
     }
 }
@@ -2,10 +2,10 @@ use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::io;
 use std::net::SocketAddr;
-use std::time::Duration;
-use stun_types::{Message, TransactionId};
+use std::time::Instant;
+use stun_types::{ClientTransaction, Message, TransactionAction, TransactionId};
 use tokio::sync::oneshot;
-use tokio::time::timeout;
+use tokio::time::timeout_at;
 
 pub mod auth;
 
@@ -58,6 +58,17 @@ pub struct StunEndpoint<U: StunEndpointUser> {
 
 struct Transaction {
     sender: oneshot::Sender<Message>,
+
+    /// Remote address of requests sent over reliable transports,
+    /// used to abort the transaction when the connection is closed
+    reliable_target: Option<SocketAddr>,
+}
+
+fn connection_aborted() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "connection closed before receiving a response",
+    )
 }
 
 impl<U: StunEndpointUser> StunEndpoint<U> {
@@ -96,43 +107,55 @@ impl<U: StunEndpointUser> StunEndpoint<U> {
 
         let _guard = DropGuard(self, request.tsx_id);
 
-        let (tx, mut rx) = oneshot::channel();
-        self.transactions
-            .lock()
-            .insert(request.tsx_id, Transaction { sender: tx });
-
-        let mut delta = Duration::from_millis(500);
+        let reliable = request.transport.reliable();
 
-        if request.transport.reliable() {
-            match timeout(delta, &mut rx).await {
-                Ok(Ok(response)) => Ok(Some(response)),
-                Ok(Err(_)) => unreachable!(),
-                Err(_) => Ok(None),
-            }
-        } else {
-            for _ in 0..7 {
-                self.user
-                    .send_to(request.bytes, target, request.transport)
-                    .await?;
-
-                match timeout(delta, &mut rx).await {
-                    Ok(Ok(response)) => return Ok(Some(response)),
-                    Ok(Err(_)) => unreachable!(),
-                    Err(_) => {
-                        delta *= 2;
+        let (tx, mut rx) = oneshot::channel();
+        self.transactions.lock().insert(
+            request.tsx_id,
+            Transaction {
+                sender: tx,
+                reliable_target: reliable.then_some(target),
+            },
+        );
+
+        // Reliable transports handle retransmissions themselves, the
+        // transaction only sends the request once and waits until Ti expires
+        let mut transaction = ClientTransaction::new(reliable);
+
+        loop {
+            match transaction.poll(Instant::now()) {
+                TransactionAction::Send => {
+                    self.user
+                        .send_to(request.bytes, target, request.transport)
+                        .await?;
+                }
+                TransactionAction::WaitUntil(deadline) => {
+                    match timeout_at(deadline.into(), &mut rx).await {
+                        Ok(Ok(response)) => return Ok(Some(response)),
+                        Ok(Err(_)) => return Err(connection_aborted()),
+                        Err(_) => {}
                     }
                 }
+                TransactionAction::TimedOut => return Ok(None),
             }
-
-            Ok(None)
         }
     }
 
+    /// Notify the endpoint that the reliable connection to `remote` has been closed.
+    ///
+    /// All pending requests sent over the connection are aborted and return an error.
+    pub fn connection_closed(&self, remote: SocketAddr) {
+        self.transactions
+            .lock()
+            .retain(|_, tsx| tsx.reliable_target != Some(remote));
+    }
+
     /// Pass a received STUN message to the endpoint for further processing
     pub async fn receive(&self, message: Message, source: SocketAddr, transport: U::Transport) {
         {
             let mut transactions = self.transactions.lock();
-            if let Some(Transaction { sender }) = transactions.remove(&message.transaction_id()) {
+            if let Some(Transaction { sender, .. }) = transactions.remove(&message.transaction_id())
+            {
                 let _ = sender.send(message);
                 return;
             }
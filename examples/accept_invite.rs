@@ -56,9 +56,17 @@ impl Layer for InviteAcceptLayer {
 
                     event.respond_success(response).await.unwrap();
                 }
+                Event::ReInviteResponse(_) => {}
                 Event::Bye(event) => {
                     event.process_default().await.unwrap();
                 }
+                Event::Info(event) => {
+                    if let Some(dtmf) = event.dtmf {
+                        println!("received DTMF {}", dtmf.digit);
+                    }
+
+                    event.process_default().await.unwrap();
+                }
                 Event::Terminated => {
                     break;
                 }
@@ -1,6 +1,5 @@
 use sip_core::transport::tcp::TcpConnector;
 use sip_core::transport::udp::Udp;
-use sip_core::transport::TargetTransportInfo;
 use sip_core::{Endpoint, Result};
 use sip_types::uri::sip::SipUri;
 use sip_types::uri::NameAddr;
@@ -34,8 +33,8 @@ async fn main() -> Result<()> {
     let contact: SipUri = "sip:alice@192.168.178.2:5060".parse().unwrap();
     let registrar: SipUri = "sip:example.com".parse().unwrap();
 
-    let mut target = TargetTransportInfo::default();
     let mut registration = Registration::new(
+        &endpoint,
         NameAddr::uri(id),
         NameAddr::uri(contact),
         registrar.into(),
@@ -43,9 +42,8 @@ async fn main() -> Result<()> {
     );
 
     loop {
-        let request = registration.create_register(false);
-        let mut transaction = endpoint.send_request(request, &mut target).await?;
-        let response = transaction.receive_final().await?;
+        // Fails over to the next resolved registrar target on timeouts or 5xx responses
+        let response = registration.send_register(&endpoint, false).await?;
 
         match response.line.code.kind() {
             CodeKind::Success => {}